@@ -1,10 +1,508 @@
 extern crate fluid_mechanics_rust;
-//use fluid_mechanics_rust::prelude::*;
+use fluid_mechanics_rust::prelude::*;
+
+use crate::{TherminolFluidComponent, ComponentPressureBreakdown,
+    ComponentFlowDiagnostics, CIETSolverError};
+
+extern crate roots;
+use roots::find_root_brent;
+use roots::SimpleConvergency;
+use roots::SearchError;
 
 
 
 /// a branch is a collection of pipes and components
-pub trait TherminolSeriesComponents {}
+///
+/// the components can be read and their fluid temperatures set
+/// one at a time, which is what the non isothermal facility needs
+/// to give each component its own temperature
+pub trait TherminolSeriesComponents {
+
+    /// returns the components owned by the branch
+    /// in the order the fluid flows through them
+    fn get_therminol_components(&self) -> Vec<&dyn TherminolFluidComponent>;
+
+    /// sets the fluid temperature of the component at this index,
+    /// leaving the other components in the branch as they are
+    fn set_component_fluid_temperature(
+        &mut self,
+        component_index: usize,
+        fluid_temp: ThermodynamicTemperature);
+
+    /// sets the opening (0-100%) of the component at this index,
+    /// this does nothing unless the component is a valve
+    fn set_component_valve_opening_percent(
+        &mut self,
+        component_index: usize,
+        opening_percent: f64);
+
+    /// sets the opening (0-100%) of every valve in the branch,
+    /// and returns how many valves there were
+    fn set_valve_opening_percent(&mut self, opening_percent: f64) -> usize {
+
+        let valve_indices: Vec<usize> = self.get_therminol_components()
+            .iter()
+            .enumerate()
+            .filter(|(_, component)| component.get_valve_opening_percent().is_some())
+            .map(|(component_index, _)| component_index)
+            .collect();
+
+        for component_index in valve_indices.iter() {
+            self.set_component_valve_opening_percent(
+                *component_index, opening_percent);
+        }
+
+        return valve_indices.len();
+    }
+
+    /// returns the opening (0-100%) of the first valve in the branch,
+    /// or None if the branch has no valves
+    fn get_valve_opening_percent(&self) -> Option<f64> {
+        return self.get_therminol_components().iter()
+            .find_map(|component| component.get_valve_opening_percent());
+    }
+
+    /// returns the lowest and highest total pressure loss the
+    /// components can have at zero flow, this is zero to zero
+    /// unless the branch has check valves
+    fn get_zero_flow_pressure_loss_range(&self) -> (Pressure, Pressure) {
+
+        let zero_pressure = Pressure::new::<pascal>(0.0);
+
+        return self.get_therminol_components().iter()
+            .map(|component| component.get_zero_flow_pressure_loss_range())
+            .fold((zero_pressure, zero_pressure),
+                  |(lowest_sum, highest_sum), (lowest, highest)|
+                  (lowest_sum + lowest, highest_sum + highest));
+    }
+
+    /// returns the friction, form, hydrostatic and pump pressure
+    /// changes of each component at this mass flowrate, in the
+    /// order the fluid flows through them
+    fn get_component_pressure_breakdown(&self, mass_flowrate: MassRate)
+        -> Vec<ComponentPressureBreakdown> {
+
+        return self.get_therminol_components().iter()
+            .map(|component| ComponentPressureBreakdown::new(
+                    *component, mass_flowrate))
+            .collect();
+    }
+
+    /// returns the reynolds number, mean velocity and flow regime
+    /// of each component at this mass flowrate, in the order the
+    /// fluid flows through them
+    fn get_component_flow_diagnostics(&self, mass_flowrate: MassRate)
+        -> Vec<ComponentFlowDiagnostics> {
+
+        return self.get_therminol_components().iter()
+            .map(|component| ComponentFlowDiagnostics::new(
+                    *component, mass_flowrate))
+            .collect();
+    }
+
+    /// returns the pressure change across each component at this
+    /// mass flowrate, in the order the fluid flows through them
+    ///
+    /// these add up to the pressure change across the branch
+    fn get_component_pressure_changes(&self, mass_flowrate: MassRate)
+        -> Vec<Pressure> {
+
+        return self.get_component_pressure_breakdown(mass_flowrate).iter()
+            .map(|component| component.get_pressure_change())
+            .collect();
+    }
+
+    /// returns the index of the first component which can hold
+    /// a pressure difference at zero flow (eg. a check valve),
+    /// or None if there is no such component
+    fn get_first_check_valve_index(&self) -> Option<usize> {
+        return self.get_therminol_components().iter()
+            .position(|component| {
+                let (lowest_shut_pressure_loss, highest_shut_pressure_loss) =
+                    component.get_zero_flow_pressure_loss_range();
+                lowest_shut_pressure_loss != highest_shut_pressure_loss
+            });
+    }
+}
+
+/// These are the brent settings for finding the mass flowrate
+/// through a series branch at a given pressure change
+///
+/// they work the same way as the parallel branch solver settings,
+/// but on the mass flowrate rather than the pressure change
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SeriesBranchSolverSettings {
+
+    /// the mass flowrate is first searched for between
+    /// plus and minus this value
+    pub mass_flowrate_bracket_half_width: MassRate,
+
+    /// if the bracket does not contain a root, its width is doubled,
+    /// up to this many times before giving up
+    pub max_bracket_expansions: usize,
+
+    /// brent tolerance on the mass flowrate (in kg/s)
+    pub tolerance: f64,
+
+    /// maximum brent iterations
+    pub max_iter: usize,
+}
+
+impl SeriesBranchSolverSettings {
+
+    /// constructor, the bracket starts at plus or minus 1 kg/s
+    /// which is what ciet has always used,
+    /// and the tolerances are the same as ciet digital twin v1
+    pub fn new() -> Self {
+        return Self {
+            mass_flowrate_bracket_half_width:
+                MassRate::new::<kilogram_per_second>(1.0),
+            max_bracket_expansions: 10,
+            tolerance: 1e-9,
+            max_iter: 30,
+        };
+    }
+}
+
+/// finds the mass flowrate through a series branch
+/// at this pressure change across it
+///
+/// branch_pressure_change gives the pressure change across the
+/// branch at a mass flowrate, and zero_flow_pressure_loss_range 
+/// is the range of pressure losses the branch can have at zero flow
+/// (see TherminolSeriesComponents::get_zero_flow_pressure_loss_range)
+///
+/// without check valves, brent looks for the mass flowrate
+/// within the bracket in the solver settings (-1 to 1 kg/s
+/// by default), the bracket is doubled until it contains
+/// the mass flowrate, just like the parallel branch solver
+/// does with the pressure change
+///
+/// with check valves, the branch pressure change jumps at zero flow,
+/// which brent does not handle well, so
+/// (1) if the shut check valves can hold this pressure change,
+/// there is no flow
+/// (2) otherwise brent only looks on the side of zero flow
+/// (forward or reverse) where the answer is, where the
+/// pressure change is smooth
+///
+/// if the bracket still has no root after expanding, brent does
+/// not converge, or the branch gives NaN, an error is returned
+/// rather than panicking
+pub fn find_series_branch_mass_flowrate(
+    branch_pressure_change: &dyn Fn(MassRate) -> Pressure,
+    zero_flow_pressure_loss_range: (Pressure, Pressure),
+    pressure_change: Pressure,
+    solver_settings: &SeriesBranchSolverSettings)
+    -> Result<MassRate, CIETSolverError> {
+
+    let zero_flowrate = MassRate::new::<kilogram_per_second>(0.0);
+
+    // which sides of zero flow the bracket covers
+    let mut search_forward_flow = true;
+    let mut search_reverse_flow = true;
+
+    let (lowest_shut_pressure_loss, highest_shut_pressure_loss) =
+        zero_flow_pressure_loss_range;
+
+    if lowest_shut_pressure_loss != highest_shut_pressure_loss {
+
+        // every component has zero pressure loss at exactly zero flow,
+        // so the branch can have anywhere between these pressure
+        // changes at zero flow
+        let zero_flow_pressure_change = branch_pressure_change(zero_flowrate);
+
+        if zero_flow_pressure_change.value.is_nan() {
+            return Err(CIETSolverError::NotANumber);
+        }
+
+        let lowest_shut_pressure_change =
+            zero_flow_pressure_change - highest_shut_pressure_loss;
+        let highest_shut_pressure_change =
+            zero_flow_pressure_change - lowest_shut_pressure_loss;
+
+        if pressure_change >= lowest_shut_pressure_change
+            && pressure_change <= highest_shut_pressure_change {
+            return Ok(zero_flowrate);
+        }
+
+        // the pressure change drops as the mass flowrate goes up,
+        // so below what the shut valves can hold, the flow is forward
+        match pressure_change < lowest_shut_pressure_change {
+            true => search_reverse_flow = false,
+            false => search_forward_flow = false,
+        }
+    }
+
+    // now we have a function comparing the pressure change
+    // to the pressure change of the calculated value
+    let mass_flow_from_pressure_chg_root =
+        |mass_flow_kg_per_s: f64| -> f64 {
+
+        let mass_rate =
+            MassRate::new::<kilogram_per_second>(
+                mass_flow_kg_per_s);
+
+        let pressure_change_tested = branch_pressure_change(mass_rate);
+
+        // since we are finding root, then we must also
+        // subtract it from our pressure change value
+        let pressure_change_error: f64 =
+            pressure_change.value -
+            pressure_change_tested.value;
+
+        return pressure_change_error;
+    };
+
+    // expand the bracket until the pressure change error
+    // changes sign across it
+    let mut bracket_half_width = solver_settings.mass_flowrate_bracket_half_width;
+    let mut bracket_expansion_count: usize = 0;
+
+    let (lower_bound, upper_bound) = loop {
+
+        let upper_bound = match search_forward_flow {
+            true => bracket_half_width,
+            false => zero_flowrate,
+        };
+        let lower_bound = match search_reverse_flow {
+            true => -bracket_half_width,
+            false => zero_flowrate,
+        };
+
+        let upper_pressure_change_error =
+            mass_flow_from_pressure_chg_root(upper_bound.value);
+        let lower_pressure_change_error =
+            mass_flow_from_pressure_chg_root(lower_bound.value);
+
+        // no point expanding further if the branch can't
+        // give a pressure change at these flowrates
+        if upper_pressure_change_error.is_nan()
+            || lower_pressure_change_error.is_nan() {
+            return Err(CIETSolverError::NotANumber);
+        }
+
+        if upper_pressure_change_error * lower_pressure_change_error <= 0.0 {
+            break (lower_bound, upper_bound);
+        }
+
+        if bracket_expansion_count >= solver_settings.max_bracket_expansions {
+            return Err(CIETSolverError::MassFlowrateBracketFailure {
+                lower_bound, upper_bound });
+        }
+
+        bracket_half_width = bracket_half_width * 2.0;
+        bracket_expansion_count += 1;
+    };
+
+    let mut convergency = SimpleConvergency { 
+        eps: solver_settings.tolerance, 
+        max_iter: solver_settings.max_iter };
+
+    let mass_flowrate_result
+        = find_root_brent(
+            upper_bound.value,
+            lower_bound.value,
+            &mass_flow_from_pressure_chg_root,
+            &mut convergency);
+
+    let mass_flowrate_value: f64 = match mass_flowrate_result {
+        Ok(mass_flowrate_value) => mass_flowrate_value,
+        Err(SearchError::NoBracketing) => return Err(
+            CIETSolverError::MassFlowrateBracketFailure {
+                lower_bound, upper_bound }),
+        Err(_) => return Err(CIETSolverError::NonConvergence {
+            max_iter: solver_settings.max_iter }),
+    };
+
+    if mass_flowrate_value.is_nan() {
+        return Err(CIETSolverError::NotANumber);
+    }
+
+    return Ok(MassRate::new::<kilogram_per_second>(mass_flowrate_value));
+}
+
+
+/// a general branch made of therminol pipes and components
+/// (TherminolPipe or TherminolCustomComponent) in series
+///
+/// unlike the ctah, heater and dhx branches, the components are not
+/// fixed, the user adds them in the order the fluid flows through them
+///
+/// this is meant for building hydraulic networks (eg. DRACS loops
+/// or test sections) without writing a new branch struct each time
+pub struct TherminolSeriesBranch {
+
+    name: String,
+
+    // the branch owns all its components so that it can be
+    // stored, returned from functions and sent to a solver thread
+    fluid_component_vector:
+        Vec<Box<dyn TherminolFluidComponent>>,
+
+    solver_settings: SeriesBranchSolverSettings,
+}
+
+impl TherminolSeriesBranch {
+
+    /// constructor, returns an empty branch
+    pub fn new(name: &str) -> Self {
+        return Self {
+            name: name.to_string(),
+            fluid_component_vector: vec![],
+            solver_settings: SeriesBranchSolverSettings::new(),
+        };
+    }
+
+    /// constructor, returns a branch made of the components given,
+    /// in the order the fluid flows through them
+    pub fn new_from_components(
+        name: &str,
+        fluid_component_vector: Vec<Box<dyn TherminolFluidComponent>>)
+        -> Self {
+        return Self {
+            name: name.to_string(),
+            fluid_component_vector,
+            solver_settings: SeriesBranchSolverSettings::new(),
+        };
+    }
+
+    /// adds a component to the end of the branch
+    /// and returns its index within the branch
+    pub fn add_component(
+        &mut self,
+        fluid_component: Box<dyn TherminolFluidComponent>) -> usize {
+
+        self.fluid_component_vector.push(fluid_component);
+
+        return self.fluid_component_vector.len() - 1;
+    }
+
+    /// sets the internal pressure source of a component
+    /// in the branch (eg. a pump)
+    pub fn set_component_internal_pressure_source(
+        &mut self,
+        component_index: usize,
+        internal_pressure: Pressure){
+
+        self.fluid_component_vector[component_index]
+            .set_internal_pressure_source(internal_pressure);
+    }
+
+    /// returns references to the components owned by this branch
+    /// in the order the fluid flows through them
+    pub fn get_fluid_component_vector(&self)
+        -> Vec<&dyn FluidComponent> {
+
+        return self.fluid_component_vector.iter()
+            .map(|component| component.as_fluid_component())
+            .collect();
+    }
+
+    /// sets the fluid temperature of every component in the branch,
+    /// the therminol properties are then evaluated at this temperature
+    pub fn set_fluid_temperature(&mut self, fluid_temp: ThermodynamicTemperature){
+        for fluid_component in self.fluid_component_vector.iter_mut() {
+            fluid_component.set_fluid_temperature(fluid_temp);
+        }
+    }
+
+    /// returns the fluid temperature of the first component in
+    /// the branch, or None if the branch has no components
+    pub fn get_fluid_temperature(&self) -> Option<ThermodynamicTemperature> {
+        return self.fluid_component_vector.first()
+            .map(|fluid_component| fluid_component.get_fluid_temperature());
+    }
+
+
+    pub fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// sets the brent settings used to find the mass flowrate
+    /// through the branch at a given pressure change
+    pub fn set_solver_settings(&mut self, solver_settings: SeriesBranchSolverSettings){
+        self.solver_settings = solver_settings;
+    }
+
+    pub fn get_solver_settings(&self) -> SeriesBranchSolverSettings {
+        return self.solver_settings;
+    }
+
+    /// calculates mass flowrate from pressure change,
+    /// taking into account any check valves in the branch,
+    /// and returns an error if brent cannot find it
+    pub fn try_get_mass_flowrate_from_pressure_change(
+        &self,
+        pressure_change: Pressure) -> Result<MassRate, CIETSolverError> {
+
+        return find_series_branch_mass_flowrate(
+            &|mass_flowrate| self.get_pressure_change(mass_flowrate),
+            self.get_zero_flow_pressure_loss_range(),
+            pressure_change,
+            &self.solver_settings);
+    }
+}
+
+impl TherminolSeriesComponents for TherminolSeriesBranch {
+
+    fn get_therminol_components(&self) -> Vec<&dyn TherminolFluidComponent> {
+        return self.fluid_component_vector.iter()
+            .map(|component| component.as_ref())
+            .collect();
+    }
+
+    fn set_component_fluid_temperature(
+        &mut self,
+        component_index: usize,
+        fluid_temp: ThermodynamicTemperature){
+
+        self.fluid_component_vector[component_index]
+            .set_fluid_temperature(fluid_temp);
+    }
+
+    fn set_component_valve_opening_percent(
+        &mut self,
+        component_index: usize,
+        opening_percent: f64){
+
+        self.fluid_component_vector[component_index]
+            .set_valve_opening_percent(opening_percent);
+    }
+}
+
+impl FluidComponentCollectionMethods for TherminolSeriesBranch {
+
+    /// calculates pressure change when given a mass flowrate
+    fn get_pressure_change(
+        &self,
+        fluid_mass_flowrate: MassRate) -> Pressure{
+        let fluid_component_collection_vector =
+            self.get_fluid_component_vector();
+
+        let pressure_change =
+            <Self as FluidComponentCollectionSeriesAssociatedFunctions>
+            ::calculate_pressure_change_from_mass_flowrate(
+                fluid_mass_flowrate,
+                &fluid_component_collection_vector);
+
+        return pressure_change;
+    }
 
+    /// calculates mass flowrate from pressure change,
+    /// taking into account any check valves in the branch
+    ///
+    /// this can only return a mass flowrate, so if brent fails,
+    /// NaN is returned, the parallel branch solver picks up the
+    /// NaN and reports it as a solver error
+    /// (try_get_mass_flowrate_from_pressure_change gives the error)
+    fn get_mass_flowrate_from_pressure_change(
+        &self,
+        pressure_change: Pressure) -> MassRate{
 
+        return self.try_get_mass_flowrate_from_pressure_change(pressure_change)
+            .unwrap_or(MassRate::new::<kilogram_per_second>(f64::NAN));
+    }
+}
 
+impl FluidComponentCollectionSeriesAssociatedFunctions for TherminolSeriesBranch {}
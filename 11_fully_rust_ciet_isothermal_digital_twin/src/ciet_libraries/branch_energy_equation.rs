@@ -0,0 +1,269 @@
+extern crate fluid_mechanics_rust;
+use fluid_mechanics_rust::prelude::*;
+
+use crate::TherminolSeriesComponents;
+
+/// the axial nodes of one component in a branch
+///
+/// node 0 is where positive flow enters the component,
+/// every node has the same fluid volume
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComponentThermalNodes {
+    pub name: String,
+    pub node_temperatures: Vec<ThermodynamicTemperature>,
+    pub node_volume: Volume,
+}
+
+impl ComponentThermalNodes {
+
+    /// returns the volume averaged fluid temperature of the component,
+    /// this is what its density and viscosity are evaluated at
+    pub fn get_average_temperature(&self) -> ThermodynamicTemperature {
+
+        let temperature_sum_kelvin: f64 = self.node_temperatures.iter()
+            .map(|node_temperature| node_temperature.get::<kelvin>())
+            .sum();
+
+        return ThermodynamicTemperature::new::<kelvin>(
+            temperature_sum_kelvin/self.node_temperatures.len() as f64);
+    }
+}
+
+/// heat going into the fluid of one component
+///
+/// the heat source (eg. a heater) is split evenly between the
+/// component's nodes, and so is the thermal conductance (UA) to
+/// whatever is on the other side of the wall (eg. the ctah air,
+/// or the other side of a heat exchanger), which is at the
+/// ambient temperature
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ComponentHeatTransfer {
+    pub heat_source: Power,
+    pub thermal_conductance: ThermalConductance,
+    pub ambient_temperature: ThermodynamicTemperature,
+}
+
+impl ComponentHeatTransfer {
+
+    /// a component which puts this much power into the fluid
+    pub fn heat_source(heat_source: Power) -> Self {
+        return Self {
+            heat_source,
+            thermal_conductance: ThermalConductance::new::<watt_per_kelvin>(0.0),
+            ambient_temperature: ThermodynamicTemperature::new::<kelvin>(0.0),
+        };
+    }
+
+    /// a component which exchanges heat with something at the
+    /// ambient temperature through a thermal conductance (UA)
+    pub fn thermal_conductance(
+        thermal_conductance: ThermalConductance,
+        ambient_temperature: ThermodynamicTemperature) -> Self {
+        return Self {
+            heat_source: Power::new::<watt>(0.0),
+            thermal_conductance,
+            ambient_temperature,
+        };
+    }
+}
+
+/// splits every component in a branch into nodes,
+/// all at the component's current fluid temperature
+pub fn build_branch_thermal_nodes(
+    branch: &dyn TherminolSeriesComponents,
+    nodes_per_component: usize) -> Vec<ComponentThermalNodes> {
+
+    let nodes_per_component = nodes_per_component.max(1);
+
+    return branch.get_therminol_components().iter()
+        .map(|component| {
+
+            let component_volume =
+                component.get_cross_sectional_area_immutable()*
+                component.get_component_length_immutable();
+
+            return ComponentThermalNodes {
+                name: component.get_name().to_string(),
+                node_temperatures: vec![component.get_fluid_temperature();
+                    nodes_per_component],
+                node_volume: component_volume/nodes_per_component as f64,
+            };
+        })
+        .collect();
+}
+
+/// brings every component in a branch to its average node
+/// temperature, so that the next hydraulic solve sees the
+/// new densities and viscosities
+pub fn update_branch_fluid_temperatures(
+    branch: &mut dyn TherminolSeriesComponents,
+    branch_thermal_nodes: &[ComponentThermalNodes]){
+
+    for (component_index, component_thermal_nodes) in
+        branch_thermal_nodes.iter().enumerate() {
+            branch.set_component_fluid_temperature(
+                component_index,
+                component_thermal_nodes.get_average_temperature());
+    }
+}
+
+/// solves the energy equation over the nodes of one branch for one
+/// timestep, going through the nodes in the direction of flow
+///
+/// for each node, with mass m, specific heat capacity cp
+/// and upstream temperature T_up:
+///
+/// m cp (T - T_old)/dt = |mdot| cp (T_up - T) + Q - UA (T - T_ambient)
+///
+/// where Q and UA are the node's share of the component heat transfer
+/// (zero for components not in component_heat_transfer),
+/// this is implicit and upwinded, so it is stable for any timestep,
+/// and is solved for T one node at a time
+///
+/// returns the branch outlet temperature and the heat lost through
+/// the thermal conductances in this branch (negative if heat was
+/// gained through them)
+pub fn solve_branch_energy_equation(
+    branch_thermal_nodes: &mut [ComponentThermalNodes],
+    branch_mass_flowrate: MassRate,
+    inlet_temperature: ThermodynamicTemperature,
+    timestep: Time,
+    component_heat_transfer: &[(&str, ComponentHeatTransfer)],
+    therminol_properties: &TherminolVP1Properties)
+    -> (ThermodynamicTemperature, Power) {
+
+    let positive_flow = branch_mass_flowrate.value >= 0.0;
+    let mass_flowrate_magnitude = branch_mass_flowrate.abs();
+
+    // i'm collecting the nodes in the order the fluid meets them,
+    // along with the heat source (W), conductance (W/K)
+    // and ambient temperature (K) of each node
+    let mut nodes_in_flow_order:
+        Vec<(&mut ThermodynamicTemperature, Volume, f64, f64, f64)> = vec![];
+
+    for component_thermal_nodes in branch_thermal_nodes.iter_mut() {
+
+        let node_volume = component_thermal_nodes.node_volume;
+        let node_count = component_thermal_nodes.node_temperatures.len() as f64;
+
+        let heat_transfer = component_heat_transfer.iter()
+            .find(|(name, _)| *name == component_thermal_nodes.name)
+            .map(|(_, heat_transfer)| *heat_transfer);
+
+        let (node_heat_source_watts,
+             node_thermal_conductance_watts_per_kelvin,
+             ambient_temperature_kelvin) = match heat_transfer {
+            Some(heat_transfer) => (
+                heat_transfer.heat_source.get::<watt>()/node_count,
+                heat_transfer.thermal_conductance.get::<watt_per_kelvin>()/node_count,
+                heat_transfer.ambient_temperature.get::<kelvin>()),
+            None => (0.0, 0.0, 0.0),
+        };
+
+        for node_temperature in component_thermal_nodes.node_temperatures.iter_mut() {
+            nodes_in_flow_order.push((node_temperature,
+                                      node_volume,
+                                      node_heat_source_watts,
+                                      node_thermal_conductance_watts_per_kelvin,
+                                      ambient_temperature_kelvin));
+        }
+    }
+
+    if !positive_flow {
+        nodes_in_flow_order.reverse();
+    }
+
+    let mut upstream_temperature_kelvin = inlet_temperature.get::<kelvin>();
+    let mut heat_loss_rate_watts = 0.0;
+
+    for (node_temperature, node_volume,
+         node_heat_source_watts,
+         node_thermal_conductance_watts_per_kelvin,
+         ambient_temperature_kelvin) in nodes_in_flow_order {
+
+        let old_temperature_kelvin = node_temperature.get::<kelvin>();
+
+        // properties are taken at the old temperature
+        let density = therminol_properties.density(*node_temperature);
+        let specific_heat_capacity =
+            therminol_properties.specific_heat_capacity(*node_temperature);
+
+        // the cp for advection is taken halfway between the upstream
+        // and node temperatures, so that |mdot| cp (T_up - T) is the
+        // enthalpy change between them, otherwise the heat put in and
+        // taken out of a loop do not balance at steady state
+        let advection_specific_heat_capacity =
+            therminol_properties.specific_heat_capacity(
+                ThermodynamicTemperature::new::<kelvin>(
+                    0.5*(upstream_temperature_kelvin + old_temperature_kelvin)));
+
+        // m cp / dt and |mdot| cp, both in W/K
+        let node_heat_capacity_rate: f64 =
+            (density*node_volume*specific_heat_capacity/timestep)
+            .get::<watt_per_kelvin>();
+        let advection_heat_capacity_rate: f64 =
+            (mass_flowrate_magnitude*advection_specific_heat_capacity)
+            .get::<watt_per_kelvin>();
+
+        let new_temperature_kelvin =
+            (node_heat_capacity_rate*old_temperature_kelvin
+             + advection_heat_capacity_rate*upstream_temperature_kelvin
+             + node_heat_source_watts
+             + node_thermal_conductance_watts_per_kelvin*ambient_temperature_kelvin)
+            /(node_heat_capacity_rate
+              + advection_heat_capacity_rate
+              + node_thermal_conductance_watts_per_kelvin);
+
+        heat_loss_rate_watts += node_thermal_conductance_watts_per_kelvin*
+            (new_temperature_kelvin - ambient_temperature_kelvin);
+
+        *node_temperature = ThermodynamicTemperature::new::<kelvin>(
+            new_temperature_kelvin);
+        upstream_temperature_kelvin = new_temperature_kelvin;
+    }
+
+    return (ThermodynamicTemperature::new::<kelvin>(upstream_temperature_kelvin),
+            Power::new::<watt>(heat_loss_rate_watts));
+}
+
+/// returns the mass flowrate weighted temperature of the streams
+/// flowing into a junction, or the previous temperature if nothing
+/// flows in
+///
+/// branch_outlets are the mass flowrate and outlet temperature of
+/// every branch connected to the junction, only those flowing into
+/// it are counted, ie. positive flow for the bottom of a loop
+/// (into_junction_is_positive = true) and negative flow for the top
+pub fn get_junction_mixed_temperature(
+    branch_outlets: &[(MassRate, ThermodynamicTemperature)],
+    into_junction_is_positive: bool,
+    previous_temperature: ThermodynamicTemperature)
+    -> ThermodynamicTemperature {
+
+    let inflows: Vec<(f64, f64)> = branch_outlets.iter()
+        .map(|(mass_flowrate, outlet_temperature)| {
+            let mass_flowrate_kg_per_s = match into_junction_is_positive {
+                true => mass_flowrate.get::<kilogram_per_second>(),
+                false => -mass_flowrate.get::<kilogram_per_second>(),
+            };
+            (mass_flowrate_kg_per_s, outlet_temperature.get::<kelvin>())
+        })
+        .filter(|(mass_flowrate_kg_per_s, _)| *mass_flowrate_kg_per_s > 0.0)
+        .collect();
+
+    let total_mass_flowrate_kg_per_s: f64 = inflows.iter()
+        .map(|(mass_flowrate_kg_per_s, _)| mass_flowrate_kg_per_s)
+        .sum();
+
+    if total_mass_flowrate_kg_per_s <= 0.0 {
+        return previous_temperature;
+    }
+
+    let weighted_temperature_sum: f64 = inflows.iter()
+        .map(|(mass_flowrate_kg_per_s, temperature_kelvin)|
+             mass_flowrate_kg_per_s*temperature_kelvin)
+        .sum();
+
+    return ThermodynamicTemperature::new::<kelvin>(
+        weighted_temperature_sum/total_mass_flowrate_kg_per_s);
+}
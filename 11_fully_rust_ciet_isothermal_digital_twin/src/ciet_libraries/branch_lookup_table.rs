@@ -0,0 +1,258 @@
+extern crate fluid_mechanics_rust;
+use fluid_mechanics_rust::prelude::*;
+
+/// This is a precomputed table of mass flowrate against
+/// pressure change for a branch
+///
+/// every brent iteration in the parallel branch solver asks every
+/// branch for its mass flowrate, and every branch does its own brent
+/// iterations walking through all of its components and their
+/// correlations, this is slow if we want to update ciet many times
+/// a second
+///
+/// so instead, the branch is solved exactly at a fixed number of
+/// pressure changes at startup and the table linearly interpolates
+/// between them
+///
+/// the table stores the mass flowrate which the branch gives for
+/// each pressure change (which is what the parallel branch solver
+/// asks for), this way the behaviour of any check valves
+/// in the branch is captured in the table as well
+///
+/// the table has the number of points given, plus one more
+/// at zero flow if needed
+///
+/// outside the range of the table, NaN is returned so that the
+/// solver reports an error rather than extrapolating
+pub struct BranchLookupTable {
+
+    // the table is kept in pascals and kg/s so that nothing
+    // needs to be allocated or converted during a lookup
+    //
+    // pressure changes are in ascending order, and the mass flowrates
+    // at each of them are in descending order
+    pressure_changes_pascals: Vec<f64>,
+    mass_flowrates_kg_per_s: Vec<f64>,
+
+    // the same table reversed, so the mass flowrates are in
+    // ascending order for looking up pressure change
+    reversed_pressure_changes_pascals: Vec<f64>,
+    reversed_mass_flowrates_kg_per_s: Vec<f64>,
+
+    // the fluid temperature the table was built at,
+    // the table must be rebuilt if this changes
+    temperature: ThermodynamicTemperature,
+
+    // added to the branch pressure change after the table is built,
+    // eg. if the ctah pump pressure changes by 100 Pa, the whole
+    // curve moves up by 100 Pa, so no need to rebuild the table
+    pressure_offset: Pressure,
+}
+
+impl BranchLookupTable {
+
+    /// builds the table for a branch between a lower and upper
+    /// mass flowrate
+    ///
+    /// number_of_points must be at least 2
+    pub fn new(
+        branch: &dyn FluidComponentCollectionMethods,
+        lower_mass_flowrate: MassRate,
+        upper_mass_flowrate: MassRate,
+        number_of_points: usize,
+        temperature: ThermodynamicTemperature) -> Self {
+
+        if number_of_points < 2 {
+            panic!("a lookup table needs at least 2 points, got {}",
+                   number_of_points);
+        }
+
+        // the table is sampled evenly in mass flowrate, near zero flow
+        // the mass flowrate goes roughly with the square root of
+        // pressure change, so even pressure change steps would
+        // leave too few points there
+        let mass_flowrate_step = (upper_mass_flowrate - lower_mass_flowrate)
+            / (number_of_points - 1) as f64;
+
+        let mut sampled_mass_flowrates: Vec<MassRate> = (0..number_of_points)
+            .map(|point| lower_mass_flowrate + mass_flowrate_step * point as f64)
+            .collect();
+
+        // zero flow is added as a point as well if it is not already
+        // there, check valves close at zero flow, so the curve
+        // has a kink which linear interpolation would otherwise smear out
+        let zero_flowrate = MassRate::new::<kilogram_per_second>(0.0);
+
+        let zero_flow_index = sampled_mass_flowrates.partition_point(
+            |mass_flowrate| *mass_flowrate < zero_flowrate);
+
+        if zero_flow_index > 0 
+            && zero_flow_index < sampled_mass_flowrates.len() 
+            && sampled_mass_flowrates[zero_flow_index] != zero_flowrate {
+            sampled_mass_flowrates.insert(zero_flow_index, zero_flowrate);
+        }
+
+        // pressure change decreases as mass flowrate increases
+        // (more friction), so reversing gives ascending pressure change
+        let pressure_changes_pascals: Vec<f64> = sampled_mass_flowrates.iter()
+            .rev()
+            .map(|mass_flowrate| branch.get_pressure_change(*mass_flowrate).value)
+            .collect();
+
+        // the mass flowrates are then calculated back from the pressure
+        // change, usually this gives the sampled mass flowrate back,
+        // but for a branch with a check valve (eg. the dhx branch),
+        // reverse flow gives zero (check valve closed)
+        let mass_flowrates_kg_per_s: Vec<f64> = pressure_changes_pascals.iter()
+            .map(|pressure_change_pascals|
+                 branch.get_mass_flowrate_from_pressure_change(
+                     Pressure::new::<pascal>(*pressure_change_pascals)).value)
+            .collect();
+
+        let reversed_pressure_changes_pascals: Vec<f64> = 
+            pressure_changes_pascals.iter().rev().copied().collect();
+        let reversed_mass_flowrates_kg_per_s: Vec<f64> = 
+            mass_flowrates_kg_per_s.iter().rev().copied().collect();
+
+        return Self {
+            pressure_changes_pascals,
+            mass_flowrates_kg_per_s,
+            reversed_pressure_changes_pascals,
+            reversed_mass_flowrates_kg_per_s,
+            temperature,
+            pressure_offset: Pressure::new::<pascal>(0.0),
+        };
+    }
+
+    /// the fluid temperature the table was built at
+    pub fn get_temperature(&self) -> ThermodynamicTemperature {
+        return self.temperature;
+    }
+
+    /// sets the pressure change added to the tabulated branch
+    /// pressure change, eg. the change in pump pressure since
+    /// the table was built
+    pub fn set_pressure_offset(&mut self, pressure_offset: Pressure){
+        self.pressure_offset = pressure_offset;
+    }
+
+    pub fn get_pressure_offset(&self) -> Pressure {
+        return self.pressure_offset;
+    }
+
+    /// returns the lowest and highest pressure change in the table,
+    /// including the pressure offset
+    pub fn get_pressure_change_range(&self) -> (Pressure, Pressure) {
+        let lowest_pressure_change = self.pressure_changes_pascals[0];
+        let highest_pressure_change =
+            self.pressure_changes_pascals[self.pressure_changes_pascals.len() - 1];
+
+        return (Pressure::new::<pascal>(lowest_pressure_change) 
+                + self.pressure_offset,
+                Pressure::new::<pascal>(highest_pressure_change) 
+                + self.pressure_offset);
+    }
+
+    /// compares the table against the exact calculation for the
+    /// branch, halfway between each pair of table points
+    /// (where the interpolation error is usually largest)
+    ///
+    /// returns the largest absolute mass flowrate error
+    ///
+    /// the branch given must be the one the table was built from,
+    /// in the same state (eg. same pump pressure) as when the
+    /// pressure offset was last set
+    pub fn get_max_mass_flowrate_error(
+        &self,
+        branch: &dyn FluidComponentCollectionMethods) -> MassRate {
+
+        let mut max_mass_flowrate_error = MassRate::new::<kilogram_per_second>(0.0);
+
+        for point in 0..self.pressure_changes_pascals.len() - 1 {
+
+            let test_pressure_change = self.pressure_offset +
+                Pressure::new::<pascal>(
+                    (self.pressure_changes_pascals[point] 
+                     + self.pressure_changes_pascals[point+1]) * 0.5);
+
+            let exact_mass_flowrate =
+                branch.get_mass_flowrate_from_pressure_change(
+                    test_pressure_change);
+            let interpolated_mass_flowrate =
+                self.get_mass_flowrate_from_pressure_change(
+                    test_pressure_change);
+
+            let mass_flowrate_error =
+                (exact_mass_flowrate - interpolated_mass_flowrate).abs();
+
+            if mass_flowrate_error > max_mass_flowrate_error {
+                max_mass_flowrate_error = mass_flowrate_error;
+            }
+        }
+
+        return max_mass_flowrate_error;
+    }
+
+    /// linear interpolation of y against x, where x is in ascending
+    /// order, returns NaN outside the range of x
+    fn interpolate(x_values: &[f64], y_values: &[f64], x: f64) -> f64 {
+
+        let last_index = x_values.len() - 1;
+
+        if !(x >= x_values[0] && x <= x_values[last_index]) {
+            return f64::NAN;
+        }
+
+        // find the first point above x,
+        // points are evenly spaced in pressure change but not in
+        // mass flowrate, so a binary search is used for both
+        let upper_index = x_values.partition_point(|x_value| *x_value < x)
+            .clamp(1, last_index);
+        let lower_index = upper_index - 1;
+
+        let x_lower = x_values[lower_index];
+        let x_upper = x_values[upper_index];
+
+        if x_upper == x_lower {
+            return y_values[lower_index];
+        }
+
+        let interpolation_fraction = (x - x_lower) / (x_upper - x_lower);
+
+        return y_values[lower_index] + interpolation_fraction *
+            (y_values[upper_index] - y_values[lower_index]);
+    }
+}
+
+impl FluidComponentCollectionMethods for BranchLookupTable {
+
+    /// interpolates the pressure change from the table,
+    ///
+    /// the mass flowrate decreases as pressure change increases,
+    /// so the reversed table is used
+    fn get_pressure_change(
+        &self,
+        fluid_mass_flowrate: MassRate) -> Pressure{
+
+        let pressure_change_value = Self::interpolate(
+            &self.reversed_mass_flowrates_kg_per_s, 
+            &self.reversed_pressure_changes_pascals, 
+            fluid_mass_flowrate.value);
+
+        return Pressure::new::<pascal>(pressure_change_value)
+            + self.pressure_offset;
+    }
+
+    /// interpolates the mass flowrate from the table
+    fn get_mass_flowrate_from_pressure_change(
+        &self,
+        pressure_change: Pressure) -> MassRate{
+
+        let mass_flowrate_value = Self::interpolate(
+            &self.pressure_changes_pascals, 
+            &self.mass_flowrates_kg_per_s,
+            (pressure_change - self.pressure_offset).value);
+
+        return MassRate::new::<kilogram_per_second>(mass_flowrate_value);
+    }
+}
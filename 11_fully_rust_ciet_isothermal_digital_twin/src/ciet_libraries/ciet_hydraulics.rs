@@ -0,0 +1,343 @@
+extern crate fluid_mechanics_rust;
+use crate::{ctah_branch::*, HeaterBranch, DHXBranch, ParallelBranchSolver,
+    CIETSolverError, BranchRole, TherminolSeriesComponents,
+    SeriesBranchSolverSettings};
+
+use fluid_mechanics_rust::prelude::*;
+
+/// the heater, dhx and ctah branch valve positions
+/// (open or closed), in that order
+pub type BranchValvesOpen = (bool, bool, bool);
+
+/// This is the hydraulic state shared by the isothermal and
+/// non-isothermal ciet facilities
+///
+/// it owns the heater, dhx and ctah branches (in parallel) and the
+/// solver for them, along with the ctah pump settings, the branch
+/// valves and the latest branch flowrates, so that both facilities
+/// handle the pump, the valves and the open branches the same way
+///
+/// the facilities decide when to solve and what to do with the
+/// flowrates (eg. caching, lookup tables or heat transfer)
+pub struct CIETHydraulics {
+
+    pub ctah_pump_pressure: Pressure,
+
+    /// ctah pump speed in rpm, this only does anything
+    /// if the pump has a curve
+    pub ctah_pump_speed_rpm: f64,
+
+    pub ctah_branch_mass_flowrate: MassRate,
+    pub dhx_branch_mass_flowrate: MassRate,
+    pub heater_branch_mass_flowrate: MassRate,
+
+    // each branch has a valve which can be opened or closed,
+    // a closed branch carries no flow
+    pub heater_branch_valve_open: bool,
+    pub dhx_branch_valve_open: bool,
+    pub ctah_branch_valve_open: bool,
+
+    // how far open (0-100%) the valve components in each branch are,
+    // this only does anything if the branch has valve components
+    // (eg. ciet built from its topology), and 0% is the same as
+    // closing the branch
+    pub heater_branch_valve_opening_percent: f64,
+    pub dhx_branch_valve_opening_percent: f64,
+    pub ctah_branch_valve_opening_percent: f64,
+
+    pub ctah_branch: CTAHBranch,
+    pub heater_branch: HeaterBranch,
+    pub dhx_branch: DHXBranch,
+
+    pub parallel_branch_solver: ParallelBranchSolver,
+}
+
+impl CIETHydraulics {
+
+    /// takes ownership of the branches, the ctah pump starts at
+    /// 0 Pa and 0 rpm, and every valve starts fully open
+    pub fn new(ctah_branch: CTAHBranch,
+               heater_branch: HeaterBranch,
+               dhx_branch: DHXBranch) -> Self {
+
+        return Self {
+            ctah_pump_pressure: Pressure::new::<pascal>(0.0),
+            ctah_pump_speed_rpm: 0.0,
+            ctah_branch_mass_flowrate: MassRate::new::<kilogram_per_second>(0.0),
+            dhx_branch_mass_flowrate: MassRate::new::<kilogram_per_second>(0.0),
+            heater_branch_mass_flowrate: MassRate::new::<kilogram_per_second>(0.0),
+            heater_branch_valve_open: true,
+            dhx_branch_valve_open: true,
+            ctah_branch_valve_open: true,
+            heater_branch_valve_opening_percent: 100.0,
+            dhx_branch_valve_opening_percent: 100.0,
+            ctah_branch_valve_opening_percent: 100.0,
+            ctah_branch,
+            heater_branch,
+            dhx_branch,
+            parallel_branch_solver: ParallelBranchSolver::new(),
+        };
+    }
+
+    /// sets the ctah pump pressure, if it is not a number (or
+    /// infinite), the pump is left as it is and an error is returned
+    ///
+    /// this keeps every setpoint finite, so that the setpoints can
+    /// be compared to those of the last converged calculation
+    pub fn set_ctah_pump_pressure(
+        &mut self,
+        pump_pressure: Pressure) -> Result<(), CIETSolverError> {
+
+        if !pump_pressure.value.is_finite() {
+            return Err(CIETSolverError::InvalidCTAHPumpPressure { 
+                pump_pressure });
+        }
+
+        self.ctah_pump_pressure = pump_pressure;
+
+        return Ok(());
+    }
+
+    /// sets the ctah pump speed in rpm, if it is not a number (or
+    /// infinite), the pump is left as it is and an error is returned
+    pub fn set_ctah_pump_speed(
+        &mut self,
+        pump_speed_rpm: f64) -> Result<(), CIETSolverError> {
+
+        if !pump_speed_rpm.is_finite() {
+            return Err(CIETSolverError::InvalidCTAHPumpSpeed { 
+                pump_speed_rpm });
+        }
+
+        self.ctah_pump_speed_rpm = pump_speed_rpm;
+
+        return Ok(());
+    }
+
+    /// sets how far open (0-100%) the valve of the branch with this
+    /// role is, openings outside 0-100% are clamped to it
+    ///
+    /// if the opening is not a number (or infinite), the valve is
+    /// left as it is and an error is returned
+    pub fn set_valve_opening_percent(
+        &mut self,
+        branch_role: BranchRole,
+        opening_percent: f64) -> Result<(), CIETSolverError> {
+
+        if !opening_percent.is_finite() {
+            return Err(CIETSolverError::InvalidValveOpening { 
+                branch_role, opening_percent });
+        }
+
+        let opening_percent = opening_percent.clamp(0.0, 100.0);
+
+        match branch_role {
+            BranchRole::Heater => 
+                self.heater_branch_valve_opening_percent = opening_percent,
+            BranchRole::DHX => 
+                self.dhx_branch_valve_opening_percent = opening_percent,
+            BranchRole::CTAH => 
+                self.ctah_branch_valve_opening_percent = opening_percent,
+        }
+
+        return Ok(());
+    }
+
+    /// returns the heater, dhx and ctah valve openings (0-100%)
+    pub fn get_valve_openings(&self) -> (f64, f64, f64) {
+        return (self.heater_branch_valve_opening_percent,
+                self.dhx_branch_valve_opening_percent,
+                self.ctah_branch_valve_opening_percent);
+    }
+
+    /// returns whether the heater, dhx and ctah branches carry flow,
+    /// a branch carries no flow if its valve is closed or 0% open
+    pub fn get_valves_open(&self) -> BranchValvesOpen {
+        return (self.heater_branch_valve_open
+                && self.heater_branch_valve_opening_percent > 0.0,
+                self.dhx_branch_valve_open
+                && self.dhx_branch_valve_opening_percent > 0.0,
+                self.ctah_branch_valve_open
+                && self.ctah_branch_valve_opening_percent > 0.0);
+    }
+
+    /// passes the pump pressure and speed on to the ctah pump,
+    /// and the valve openings on to the valve components in
+    /// each branch
+    ///
+    /// this is done before every solve, so that the branches
+    /// always match the settings above
+    pub fn apply_pump_and_valve_settings(&mut self){
+        self.ctah_branch.set_ctah_pump_pressure(self.ctah_pump_pressure);
+        self.ctah_branch.set_ctah_pump_speed(self.ctah_pump_speed_rpm);
+
+        self.heater_branch.set_valve_opening_percent(
+            self.heater_branch_valve_opening_percent);
+        self.dhx_branch.set_valve_opening_percent(
+            self.dhx_branch_valve_opening_percent);
+        self.ctah_branch.set_valve_opening_percent(
+            self.ctah_branch_valve_opening_percent);
+    }
+
+    /// sets the brent settings each branch uses to find its mass
+    /// flowrate at a given pressure change
+    pub fn set_branch_solver_settings(
+        &mut self,
+        solver_settings: SeriesBranchSolverSettings){
+        self.heater_branch.set_solver_settings(solver_settings);
+        self.dhx_branch.set_solver_settings(solver_settings);
+        self.ctah_branch.set_solver_settings(solver_settings);
+    }
+
+    /// returns true if there can be no flow at all because of
+    /// the dhx check valve
+    ///
+    /// if the heater valve is closed and the pump pushes forward
+    /// (or not at all), the only loop left is ctah and dhx,
+    /// the pump would push fluid backwards through the dhx
+    /// check valve, so there is no flow
+    ///
+    /// the pump pushes with its pump pressure and its pump curve
+    /// at the current speed, so it is the total of both at zero
+    /// flow which decides this, a pump pressure of -0 Pa is the
+    /// same as 0 Pa here
+    ///
+    /// the brent solver can fail to converge in this case,
+    /// so the facilities check this before solving
+    /// (fewer than two open branches is handled by the
+    /// parallel branch solver)
+    pub fn get_dhx_check_valve_blocks_flow(&self) -> bool {
+
+        let (heater_branch_valve_open, _, _) = self.get_valves_open();
+
+        let pump_pushes_forward: bool =
+            self.ctah_branch.get_ctah_pump_shutoff_pressure().value >= 0.0;
+
+        return !heater_branch_valve_open && pump_pushes_forward;
+    }
+
+    /// returns the branch with this role
+    pub fn get_branch(&self, branch_role: BranchRole)
+        -> &dyn TherminolSeriesComponents {
+        match branch_role {
+            BranchRole::Heater => return &self.heater_branch,
+            BranchRole::DHX => return &self.dhx_branch,
+            BranchRole::CTAH => return &self.ctah_branch,
+        }
+    }
+
+    /// returns the latest mass flowrate of the branch with this role
+    pub fn get_branch_mass_flowrate(&self, branch_role: BranchRole) -> MassRate {
+        match branch_role {
+            BranchRole::Heater => return self.heater_branch_mass_flowrate,
+            BranchRole::DHX => return self.dhx_branch_mass_flowrate,
+            BranchRole::CTAH => return self.ctah_branch_mass_flowrate,
+        }
+    }
+
+    /// returns the branches whose valves are open,
+    /// in the order heater, dhx, ctah
+    ///
+    /// the branches can be anything which stands in for them
+    /// (eg. lookup tables or branches with fluid inertia)
+    pub fn get_open_branches<'a>(
+        heater_branch: &'a dyn FluidComponentCollectionMethods,
+        dhx_branch: &'a dyn FluidComponentCollectionMethods,
+        ctah_branch: &'a dyn FluidComponentCollectionMethods,
+        valves_open: BranchValvesOpen)
+        -> Vec<&'a dyn FluidComponentCollectionMethods> {
+
+        let (heater_branch_valve_open,
+             dhx_branch_valve_open,
+             ctah_branch_valve_open) = valves_open;
+
+        let mut open_branches:
+            Vec<&dyn FluidComponentCollectionMethods> = vec![];
+
+        if heater_branch_valve_open {
+            open_branches.push(heater_branch);
+        }
+
+        if dhx_branch_valve_open {
+            open_branches.push(dhx_branch);
+        }
+
+        if ctah_branch_valve_open {
+            open_branches.push(ctah_branch);
+        }
+
+        return open_branches;
+    }
+
+    /// matches the flowrates of the open branches (in the same order
+    /// as get_open_branches) back to their branches, closed branches
+    /// have zero flow
+    ///
+    /// returns the heater, dhx and ctah branch flowrates
+    /// in that order
+    pub fn get_branch_mass_flowrates(
+        valves_open: BranchValvesOpen,
+        open_branch_mass_flowrates: Vec<MassRate>)
+        -> (MassRate, MassRate, MassRate) {
+
+        let zero_flowrate = MassRate::new::<kilogram_per_second>(0.0);
+
+        let (heater_branch_valve_open,
+             dhx_branch_valve_open,
+             ctah_branch_valve_open) = valves_open;
+
+        let mut open_branch_mass_flowrates =
+            open_branch_mass_flowrates.into_iter();
+
+        // the solver gives one flowrate per open branch
+        let mut get_branch_flowrate = |valve_open: bool| -> MassRate {
+            match valve_open {
+                true => open_branch_mass_flowrates.next().unwrap(),
+                false => zero_flowrate,
+            }
+        };
+
+        let heater_branch_flowrate =
+            get_branch_flowrate(heater_branch_valve_open);
+        let dhx_branch_flowrate =
+            get_branch_flowrate(dhx_branch_valve_open);
+        let ctah_branch_flowrate =
+            get_branch_flowrate(ctah_branch_valve_open);
+
+        return (heater_branch_flowrate,
+                dhx_branch_flowrate,
+                ctah_branch_flowrate);
+    }
+
+    /// solves the branch flowrates at the current pump and valve
+    /// settings, going through every component in every branch
+    ///
+    /// returns the heater, dhx and ctah branch flowrates in
+    /// that order, they are not stored, so the facility can
+    /// decide whether to keep them
+    pub fn solve_mass_flowrates(&mut self) ->
+        Result<(MassRate, MassRate, MassRate), CIETSolverError> {
+
+        let zero_flowrate = MassRate::new::<kilogram_per_second>(0.0);
+
+        self.apply_pump_and_valve_settings();
+
+        if self.get_dhx_check_valve_blocks_flow() {
+            return Ok((zero_flowrate, zero_flowrate, zero_flowrate));
+        }
+
+        let valves_open = self.get_valves_open();
+
+        let open_branches = Self::get_open_branches(
+            &self.heater_branch,
+            &self.dhx_branch,
+            &self.ctah_branch,
+            valves_open);
+
+        let (_pressure_change, open_branch_mass_flowrates) =
+            self.parallel_branch_solver.solve(&open_branches)?;
+
+        return Ok(Self::get_branch_mass_flowrates(
+                valves_open, open_branch_mass_flowrates));
+    }
+}
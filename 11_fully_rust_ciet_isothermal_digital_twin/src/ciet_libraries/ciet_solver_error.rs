@@ -0,0 +1,152 @@
+extern crate fluid_mechanics_rust;
+use std::fmt;
+
+use fluid_mechanics_rust::prelude::*;
+
+use crate::BranchRole;
+
+extern crate roots;
+use roots::SearchError;
+
+/// These are the ways in which the ciet solvers can fail
+///
+/// previously, find_root_brent(...).unwrap() would panic on
+/// any of these and take the whole opcua server down with it,
+/// now the solvers return them so that the user can decide
+/// what to do (eg. keep the last converged state)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CIETSolverError {
+
+    /// the pressure change bracket does not contain a root,
+    /// ie. the total mass flowrate has the same sign at
+    /// both ends of the bracket
+    BracketFailure {
+        lower_bound: Pressure,
+        upper_bound: Pressure,
+    },
+
+    /// the mass flowrate bracket of a series branch does not
+    /// contain a root, ie. the branch cannot give this pressure
+    /// change anywhere in the bracket, even after expanding it
+    MassFlowrateBracketFailure {
+        lower_bound: MassRate,
+        upper_bound: MassRate,
+    },
+
+    /// the solver did not converge within the maximum
+    /// number of iterations
+    NonConvergence {
+        max_iter: usize,
+    },
+
+    /// a correlation (or a branch flowrate calculation)
+    /// returned NaN somewhere during the solve
+    NotANumber,
+
+    /// a branch lookup table differs from the exact branch
+    /// calculation by more than the tolerance given
+    LookupTableOutOfTolerance {
+        max_mass_flowrate_error: MassRate,
+    },
+
+    /// the fluid temperature is outside the range of the
+    /// therminol property correlations
+    FluidTemperatureOutOfRange {
+        fluid_temperature: ThermodynamicTemperature,
+    },
+
+    /// a branch valve opening is not a number (or infinite),
+    /// so it cannot be set
+    InvalidValveOpening {
+        branch_role: BranchRole,
+        opening_percent: f64,
+    },
+
+    /// the ctah pump pressure is not a number (or infinite),
+    /// so it cannot be set
+    InvalidCTAHPumpPressure {
+        pump_pressure: Pressure,
+    },
+
+    /// the ctah pump speed is not a number (or infinite),
+    /// so it cannot be set
+    InvalidCTAHPumpSpeed {
+        pump_speed_rpm: f64,
+    },
+
+    /// the calculation converged where a component with the error
+    /// out of range policy is outside the reynolds numbers its
+    /// correlations were fitted over
+    CorrelationOutOfRange {
+        reynolds_number: f64,
+        lowest_reynolds_number: f64,
+        highest_reynolds_number: f64,
+    },
+}
+
+impl fmt::Display for CIETSolverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CIETSolverError::BracketFailure { lower_bound, upper_bound } =>
+                write!(f, "no root between {} Pa and {} Pa",
+                       lower_bound.value, upper_bound.value),
+            CIETSolverError::MassFlowrateBracketFailure { lower_bound, upper_bound } =>
+                write!(f, "no root between {} kg/s and {} kg/s",
+                       lower_bound.value, upper_bound.value),
+            CIETSolverError::NonConvergence { max_iter } =>
+                write!(f, "did not converge within {} iterations",
+                       max_iter),
+            CIETSolverError::NotANumber =>
+                write!(f, "a correlation returned NaN"),
+            CIETSolverError::LookupTableOutOfTolerance { max_mass_flowrate_error } =>
+                write!(f, "lookup table is off by up to {} kg/s",
+                       max_mass_flowrate_error.value),
+            CIETSolverError::FluidTemperatureOutOfRange { fluid_temperature } =>
+                write!(f, "fluid temperature {} degC is outside the \
+                       therminol property range",
+                       fluid_temperature.get::<degree_celsius>()),
+            CIETSolverError::InvalidValveOpening { branch_role, opening_percent } =>
+                write!(f, "{} branch valve opening of {} % is not a number \
+                       between 0 and 100", branch_role, opening_percent),
+            CIETSolverError::InvalidCTAHPumpPressure { pump_pressure } =>
+                write!(f, "ctah pump pressure of {} Pa is not a number",
+                       pump_pressure.get::<pascal>()),
+            CIETSolverError::InvalidCTAHPumpSpeed { pump_speed_rpm } =>
+                write!(f, "ctah pump speed of {} rpm is not a number",
+                       pump_speed_rpm),
+            CIETSolverError::CorrelationOutOfRange { 
+                reynolds_number, lowest_reynolds_number, highest_reynolds_number } =>
+                write!(f, "converged at Re = {:.1}, outside the fitted \
+                       range of {} to {}", reynolds_number,
+                       lowest_reynolds_number, highest_reynolds_number),
+        }
+    }
+}
+
+impl std::error::Error for CIETSolverError {}
+
+impl CIETSolverError {
+
+    /// converts the error from the roots crate brent solver
+    /// into a ciet solver error
+    ///
+    /// the brent solver does not use derivatives, so a zero
+    /// derivative error should not happen, if it does,
+    /// it is treated as non convergence
+    pub fn from_brent_search_error(
+        search_error: SearchError,
+        lower_bound: Pressure,
+        upper_bound: Pressure,
+        max_iter: usize) -> Self {
+
+        match search_error {
+            SearchError::NoBracketing =>
+                return CIETSolverError::BracketFailure {
+                    lower_bound, upper_bound },
+            SearchError::NoConvergency =>
+                return CIETSolverError::NonConvergence { max_iter },
+            SearchError::ZeroDerivative =>
+                return CIETSolverError::NonConvergence { max_iter },
+        }
+    }
+}
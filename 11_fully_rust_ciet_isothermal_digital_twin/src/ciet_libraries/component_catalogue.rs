@@ -0,0 +1,649 @@
+extern crate fluid_mechanics_rust;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use fluid_mechanics_rust::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::therminol_pipe::TherminolPipe;
+use crate::therminol_component::TherminolCustomComponent;
+use crate::therminol_valve::{TherminolValve, ValveCharacteristic};
+use crate::therminol_check_valve::TherminolCheckValve;
+use crate::{TherminolSeriesBranch, TherminolFluidComponent, 
+    CustomKCorrelation, CustomDarcyCorrelation, OutOfRangePolicy,
+    therminol_temperature_in_range};
+
+/// the ciet component catalogue which ships with this crate,
+/// it has the same components as component_libraries
+pub const CIET_COMPONENT_CATALOGUE_TOML: &str =
+    include_str!("ciet_component_catalogue.toml");
+
+/// geometry of a therminol pipe in the catalogue
+///
+/// pipes use the churchill friction factor correlation
+/// and a form loss K
+///
+/// reverse_form_loss_k is the form loss K for reverse flow, if it
+/// differs from form_loss_k
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PipeSpecification {
+    pub name: String,
+    pub hydraulic_diameter_meters: f64,
+    pub component_length_meters: f64,
+    pub absolute_roughness_millimeters: f64,
+    pub incline_angle_degrees: f64,
+    pub form_loss_k: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverse_form_loss_k: Option<f64>,
+}
+
+/// geometry of a therminol custom component in the catalogue
+///
+/// custom_k and custom_darcy are correlations, either the name
+/// of a ciet fit (eg. "static_mixer_41") or one of the general
+/// forms, see CustomKCorrelation and CustomDarcyCorrelation
+///
+/// fitted_reynolds_number_range is the lowest and highest reynolds
+/// number the correlations were fitted over, eg. 
+/// fitted_reynolds_number_range = [100.0, 2000.0], if it is left
+/// out, only table correlations know their range, the lowest 
+/// reynolds number must be above zero
+///
+/// out_of_range_policy is what the component does outside this
+/// range, "clamp", "extrapolate" (the default) or "error", 
+/// see OutOfRangePolicy
+///
+/// reverse_custom_k is the custom K correlation for reverse flow,
+/// if it differs from custom_k, eg. 
+/// reverse_custom_k = { constant = { k = 1.0e10 } }
+/// for a component which (nearly) stops reverse flow
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CustomComponentSpecification {
+    pub name: String,
+    pub hydraulic_diameter_meters: f64,
+    pub component_length_meters: f64,
+    pub cross_sectional_area_square_meters: f64,
+    pub absolute_roughness_millimeters: f64,
+    pub incline_angle_degrees: f64,
+    pub custom_k: CustomKCorrelation,
+    pub custom_darcy: CustomDarcyCorrelation,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverse_custom_k: Option<CustomKCorrelation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fitted_reynolds_number_range: Option<(f64, f64)>,
+    #[serde(default)]
+    pub out_of_range_policy: OutOfRangePolicy,
+}
+
+/// geometry of a therminol valve in the catalogue
+///
+/// valves only have form losses, no wall friction,
+/// fully_open_k is the form loss of the valve at 100% opening,
+/// the characteristic gives the form loss at other openings,
+/// see ValveCharacteristic
+///
+/// if throttling_loss_only is true, the fully open loss is taken
+/// to be in the rest of the branch already, so the valve only adds
+/// the extra loss from throttling (this is how the ciet branch valves
+/// are set up, their loss is in the fitted component correlations)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ValveSpecification {
+    pub name: String,
+    pub hydraulic_diameter_meters: f64,
+    pub component_length_meters: f64,
+    pub incline_angle_degrees: f64,
+    pub fully_open_k: f64,
+    pub characteristic: ValveCharacteristic,
+
+    #[serde(default)]
+    pub throttling_loss_only: bool,
+}
+
+/// geometry of a therminol check valve in the catalogue
+///
+/// the valve lets positive flow through once the pressure across
+/// it reaches the cracking pressure, and holds back reverse flow,
+/// reverse_leakage_k is the form loss for reverse flow if the
+/// valve leaks, leave it out for a valve which does not leak
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CheckValveSpecification {
+    pub name: String,
+    pub hydraulic_diameter_meters: f64,
+    pub component_length_meters: f64,
+    pub incline_angle_degrees: f64,
+    pub fully_open_k: f64,
+
+    #[serde(default)]
+    pub cracking_pressure_pascals: f64,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverse_leakage_k: Option<f64>,
+}
+
+/// These are the ways loading components from a catalogue can fail
+#[derive(Clone, Debug, PartialEq)]
+pub enum ComponentCatalogueError {
+
+    /// the catalogue file could not be read
+    FileRead(String),
+
+    /// the catalogue is not valid toml or json, or is missing fields
+    Parse(String),
+
+    /// the file extension is not .toml or .json
+    UnknownFileFormat(String),
+
+    /// there is no component with this name in the catalogue
+    ComponentNotFound(String),
+
+    /// a correlation has invalid parameters
+    /// (eg. a table with more K values than reynolds numbers)
+    InvalidCorrelation(String),
+
+    /// a valve characteristic has invalid parameters
+    /// (eg. an equal percentage rangeability of 1 or less)
+    InvalidValveCharacteristic(String),
+
+    /// a check valve has a negative cracking pressure or form loss,
+    /// or a reverse leakage form loss which is not positive
+    InvalidCheckValve(String),
+
+    /// the catalogue fluid temperature (in degrees celsius) is 
+    /// outside the range of the therminol property correlations
+    FluidTemperatureOutOfRange(f64),
+
+    /// a component has a diameter, length or flow area which is
+    /// not positive and finite, or a negative roughness
+    InvalidGeometry(String),
+}
+
+impl fmt::Display for ComponentCatalogueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ComponentCatalogueError::FileRead(message) =>
+                write!(f, "could not read catalogue: {}", message),
+            ComponentCatalogueError::Parse(message) =>
+                write!(f, "could not parse catalogue: {}", message),
+            ComponentCatalogueError::UnknownFileFormat(path) =>
+                write!(f, "catalogue must be .toml or .json: {}", path),
+            ComponentCatalogueError::ComponentNotFound(name) =>
+                write!(f, "no component named {} in catalogue", name),
+            ComponentCatalogueError::InvalidCorrelation(message) =>
+                write!(f, "invalid correlation: {}", message),
+            ComponentCatalogueError::InvalidValveCharacteristic(message) =>
+                write!(f, "invalid valve characteristic: {}", message),
+            ComponentCatalogueError::InvalidCheckValve(message) =>
+                write!(f, "invalid check valve: {}", message),
+            ComponentCatalogueError::FluidTemperatureOutOfRange(
+                fluid_temperature_degrees_celsius) =>
+                write!(f, "catalogue fluid temperature {} degC is outside \
+                       the therminol property range", 
+                       fluid_temperature_degrees_celsius),
+            ComponentCatalogueError::InvalidGeometry(message) =>
+                write!(f, "invalid geometry: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ComponentCatalogueError {}
+
+/// This is a catalogue of therminol pipes, custom components, valves
+/// and check valves,
+/// read from a toml or json file
+///
+/// it replaces hard coding geometry into a struct for each
+/// component, so components can be added or re-parameterised
+/// without recompiling, and the same file can be shared with
+/// other programs (eg. the python bindings)
+///
+/// in toml, the catalogue looks like:
+///
+/// ```toml
+/// fluid_temperature_degrees_celsius = 21.0
+///
+/// [[pipe]]
+/// name = "pipe_6a"
+/// hydraulic_diameter_meters = 0.0279
+/// component_length_meters = 0.1526
+/// absolute_roughness_millimeters = 0.015
+/// incline_angle_degrees = 51.526384
+/// form_loss_k = 5.05
+///
+/// [[custom_component]]
+/// name = "static_mixer_41_label_6"
+/// hydraulic_diameter_meters = 0.0279
+/// component_length_meters = 0.33
+/// cross_sectional_area_square_meters = 0.000611
+/// absolute_roughness_millimeters = 0.015
+/// incline_angle_degrees = 51.526384
+/// custom_k = "static_mixer_41"
+/// custom_darcy = "static_mixer_41"
+///
+/// [[valve]]
+/// name = "ctah_branch_valve"
+/// hydraulic_diameter_meters = 0.0279
+/// component_length_meters = 0.05
+/// incline_angle_degrees = 0.0
+/// fully_open_k = 0.5
+/// characteristic = { equal_percentage = { rangeability = 50.0 } }
+/// throttling_loss_only = true
+///
+/// [[check_valve]]
+/// name = "dhx_branch_check_valve"
+/// hydraulic_diameter_meters = 0.0279
+/// component_length_meters = 0.05
+/// incline_angle_degrees = 0.0
+/// fully_open_k = 0.0
+/// cracking_pressure_pascals = 0.0
+/// ```
+///
+/// json uses the same field names, with "pipe",
+/// "custom_component", "valve" and "check_valve" as arrays
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ComponentCatalogue {
+
+    pub fluid_temperature_degrees_celsius: f64,
+
+    #[serde(default, rename = "pipe")]
+    pub pipes: Vec<PipeSpecification>,
+
+    #[serde(default, rename = "custom_component")]
+    pub custom_components: Vec<CustomComponentSpecification>,
+
+    #[serde(default, rename = "valve")]
+    pub valves: Vec<ValveSpecification>,
+
+    #[serde(default, rename = "check_valve")]
+    pub check_valves: Vec<CheckValveSpecification>,
+}
+
+impl ComponentCatalogue {
+
+    /// returns the catalogue of ciet components which ships with
+    /// this crate
+    pub fn ciet_default() -> Self {
+        return Self::from_toml_str(CIET_COMPONENT_CATALOGUE_TOML)
+            .expect("the ciet component catalogue shipped with this crate is invalid");
+    }
+
+    pub fn from_toml_str(catalogue_toml: &str)
+        -> Result<Self, ComponentCatalogueError> {
+
+        return toml::from_str(catalogue_toml)
+            .map_err(|error| ComponentCatalogueError::Parse(error.to_string()));
+    }
+
+    pub fn from_json_str(catalogue_json: &str)
+        -> Result<Self, ComponentCatalogueError> {
+
+        return serde_json::from_str(catalogue_json)
+            .map_err(|error| ComponentCatalogueError::Parse(error.to_string()));
+    }
+
+    /// reads a catalogue from a .toml or .json file
+    pub fn from_file<P: AsRef<Path>>(path: P)
+        -> Result<Self, ComponentCatalogueError> {
+
+        let path = path.as_ref();
+
+        let catalogue_string = fs::read_to_string(path)
+            .map_err(|error| ComponentCatalogueError::FileRead(
+                    format!("{}: {}", path.display(), error)))?;
+
+        let extension = path.extension()
+            .and_then(|extension| extension.to_str());
+
+        match extension {
+            Some("toml") => return Self::from_toml_str(&catalogue_string),
+            Some("json") => return Self::from_json_str(&catalogue_string),
+            _ => return Err(ComponentCatalogueError::UnknownFileFormat(
+                    path.display().to_string())),
+        }
+    }
+
+    pub fn to_toml_string(&self) -> Result<String, ComponentCatalogueError> {
+        return toml::to_string(self)
+            .map_err(|error| ComponentCatalogueError::Parse(error.to_string()));
+    }
+
+    pub fn to_json_string(&self) -> Result<String, ComponentCatalogueError> {
+        return serde_json::to_string_pretty(self)
+            .map_err(|error| ComponentCatalogueError::Parse(error.to_string()));
+    }
+
+    /// returns the names of all pipes, custom components, valves
+    /// and check valves in the catalogue
+    pub fn get_component_names(&self) -> Vec<&str> {
+
+        let pipe_names = self.pipes.iter()
+            .map(|pipe| pipe.name.as_str());
+        let custom_component_names = self.custom_components.iter()
+            .map(|custom_component| custom_component.name.as_str());
+        let valve_names = self.valves.iter()
+            .map(|valve| valve.name.as_str());
+        let check_valve_names = self.check_valves.iter()
+            .map(|check_valve| check_valve.name.as_str());
+
+        return pipe_names.chain(custom_component_names)
+            .chain(valve_names).chain(check_valve_names).collect();
+    }
+
+    /// returns the catalogue fluid temperature, the therminol
+    /// property correlations only hold between 20 and 180 C,
+    /// so anything else is an error (NaN included)
+    fn get_fluid_temperature(&self) 
+        -> Result<ThermodynamicTemperature, ComponentCatalogueError> {
+
+        let fluid_temperature = ThermodynamicTemperature::new::<degree_celsius>(
+            self.fluid_temperature_degrees_celsius);
+
+        if !therminol_temperature_in_range(fluid_temperature) {
+            return Err(ComponentCatalogueError::FluidTemperatureOutOfRange(
+                    self.fluid_temperature_degrees_celsius));
+        }
+
+        return Ok(fluid_temperature);
+    }
+
+    // checks that the diameter, length and (for custom components)
+    // flow area are positive and finite, and the roughness (for 
+    // pipes and custom components) is zero or more, otherwise the
+    // correlations give NaN or infinite pressure changes
+    fn validate_geometry(
+        name: &str,
+        hydraulic_diameter_meters: f64,
+        component_length_meters: f64,
+        cross_sectional_area_square_meters: Option<f64>,
+        absolute_roughness_millimeters: Option<f64>) 
+        -> Result<(), ComponentCatalogueError> {
+
+        let positive_and_finite = |value: f64| -> bool {
+            value > 0.0 && value.is_finite()
+        };
+
+        let area_valid = match cross_sectional_area_square_meters {
+            Some(area) => positive_and_finite(area),
+            None => true,
+        };
+
+        let roughness_valid = match absolute_roughness_millimeters {
+            Some(roughness) => roughness >= 0.0 && roughness.is_finite(),
+            None => true,
+        };
+
+        if !positive_and_finite(hydraulic_diameter_meters)
+            || !positive_and_finite(component_length_meters)
+            || !area_valid || !roughness_valid {
+            return Err(ComponentCatalogueError::InvalidGeometry(
+                    format!("{}: diameter {} m, length {} m, area {:?} m2, \
+                            roughness {:?} mm", 
+                            name, hydraulic_diameter_meters,
+                            component_length_meters, 
+                            cross_sectional_area_square_meters,
+                            absolute_roughness_millimeters)));
+        }
+
+        return Ok(());
+    }
+
+    /// builds a therminol pipe from the catalogue
+    pub fn get_therminol_pipe(&self, name: &str)
+        -> Result<TherminolPipe, ComponentCatalogueError> {
+
+        let pipe = self.pipes.iter()
+            .find(|pipe| pipe.name == name)
+            .ok_or_else(|| ComponentCatalogueError::ComponentNotFound(
+                    name.to_string()))?;
+
+        Self::validate_geometry(
+            &pipe.name,
+            pipe.hydraulic_diameter_meters,
+            pipe.component_length_meters,
+            None,
+            Some(pipe.absolute_roughness_millimeters))?;
+
+        let mut therminol_pipe = TherminolPipe::new(
+            &pipe.name,
+            self.get_fluid_temperature()?,
+            Angle::new::<degree>(pipe.incline_angle_degrees),
+            Length::new::<meter>(pipe.component_length_meters),
+            Length::new::<meter>(pipe.hydraulic_diameter_meters),
+            pipe.form_loss_k,
+            Length::new::<millimeter>(pipe.absolute_roughness_millimeters));
+
+        therminol_pipe.set_reverse_form_loss_k(pipe.reverse_form_loss_k);
+
+        return Ok(therminol_pipe);
+    }
+
+    /// builds a therminol custom component from the catalogue
+    pub fn get_therminol_custom_component(&self, name: &str)
+        -> Result<TherminolCustomComponent<'static>, ComponentCatalogueError> {
+
+        let custom_component = self.custom_components.iter()
+            .find(|custom_component| custom_component.name == name)
+            .ok_or_else(|| ComponentCatalogueError::ComponentNotFound(
+                    name.to_string()))?;
+
+        // unknown correlation names are already caught when the 
+        // catalogue is parsed, but the parameters are only checked here
+        let reverse_custom_k_validation = match &custom_component.reverse_custom_k {
+            Some(reverse_custom_k) => reverse_custom_k.validate(),
+            None => Ok(()),
+        };
+
+        let roughness_ratio = 
+            custom_component.absolute_roughness_millimeters * 1e-3
+            / custom_component.hydraulic_diameter_meters;
+
+        custom_component.custom_k.validate()
+            .and(custom_component.custom_darcy.validate())
+            .and(custom_component.custom_darcy
+                 .validate_roughness_ratio(roughness_ratio))
+            .and(reverse_custom_k_validation)
+            .map_err(|message| ComponentCatalogueError::InvalidCorrelation(
+                    format!("{}: {}", custom_component.name, message)))?;
+
+        if let Some((lowest_reynolds_number, highest_reynolds_number)) = 
+            custom_component.fitted_reynolds_number_range {
+
+            // clamping to Re = 0 would give an infinite K for the 
+            // a + b/Re fits, so the range has to start above zero
+            let valid_range = lowest_reynolds_number > 0.0
+                && highest_reynolds_number.is_finite()
+                && lowest_reynolds_number < highest_reynolds_number;
+
+            if !valid_range {
+                return Err(ComponentCatalogueError::InvalidCorrelation(
+                        format!("{}: fitted reynolds number range {} to {} \
+                                must go from above zero up to a \
+                                higher finite value", 
+                                custom_component.name,
+                                lowest_reynolds_number, 
+                                highest_reynolds_number)));
+            }
+        }
+
+        Self::validate_geometry(
+            &custom_component.name,
+            custom_component.hydraulic_diameter_meters,
+            custom_component.component_length_meters,
+            Some(custom_component.cross_sectional_area_square_meters),
+            Some(custom_component.absolute_roughness_millimeters))?;
+
+        let mut therminol_custom_component = TherminolCustomComponent::new_from_correlations(
+            &custom_component.name,
+            self.get_fluid_temperature()?,
+            Angle::new::<degree>(custom_component.incline_angle_degrees),
+            Length::new::<meter>(custom_component.component_length_meters),
+            Area::new::<square_meter>(
+                custom_component.cross_sectional_area_square_meters),
+            Length::new::<meter>(custom_component.hydraulic_diameter_meters),
+            Length::new::<millimeter>(
+                custom_component.absolute_roughness_millimeters),
+            custom_component.custom_k.clone(),
+            custom_component.custom_darcy.clone());
+
+        therminol_custom_component.set_fitted_reynolds_number_range(
+            custom_component.fitted_reynolds_number_range);
+        therminol_custom_component.set_out_of_range_policy(
+            custom_component.out_of_range_policy);
+        therminol_custom_component.set_reverse_custom_k_correlation(
+            custom_component.reverse_custom_k.clone());
+
+        return Ok(therminol_custom_component);
+    }
+
+    /// builds a therminol valve from the catalogue, fully open
+    pub fn get_therminol_valve(&self, name: &str)
+        -> Result<TherminolValve, ComponentCatalogueError> {
+
+        let valve = self.valves.iter()
+            .find(|valve| valve.name == name)
+            .ok_or_else(|| ComponentCatalogueError::ComponentNotFound(
+                    name.to_string()))?;
+
+        valve.characteristic.validate()
+            .map_err(|message| ComponentCatalogueError::InvalidValveCharacteristic(
+                    format!("{}: {}", valve.name, message)))?;
+
+        Self::validate_geometry(
+            &valve.name,
+            valve.hydraulic_diameter_meters,
+            valve.component_length_meters,
+            None,
+            None)?;
+
+        let mut therminol_valve = TherminolValve::new(
+            &valve.name,
+            self.get_fluid_temperature()?,
+            Angle::new::<degree>(valve.incline_angle_degrees),
+            Length::new::<meter>(valve.component_length_meters),
+            Length::new::<meter>(valve.hydraulic_diameter_meters),
+            valve.fully_open_k,
+            valve.characteristic);
+
+        therminol_valve.set_throttling_loss_only(valve.throttling_loss_only);
+
+        return Ok(therminol_valve);
+    }
+
+    /// builds a therminol check valve from the catalogue
+    pub fn get_therminol_check_valve(&self, name: &str)
+        -> Result<TherminolCheckValve, ComponentCatalogueError> {
+
+        let check_valve = self.check_valves.iter()
+            .find(|check_valve| check_valve.name == name)
+            .ok_or_else(|| ComponentCatalogueError::ComponentNotFound(
+                    name.to_string()))?;
+
+        let reverse_leakage_k_valid = match check_valve.reverse_leakage_k {
+            Some(reverse_leakage_k) => reverse_leakage_k > 0.0,
+            None => true,
+        };
+
+        if check_valve.fully_open_k.is_nan() || check_valve.fully_open_k < 0.0
+            || check_valve.cracking_pressure_pascals.is_nan()
+            || check_valve.cracking_pressure_pascals < 0.0
+            || !reverse_leakage_k_valid {
+            return Err(ComponentCatalogueError::InvalidCheckValve(
+                    check_valve.name.clone()));
+        }
+
+        Self::validate_geometry(
+            &check_valve.name,
+            check_valve.hydraulic_diameter_meters,
+            check_valve.component_length_meters,
+            None,
+            None)?;
+
+        let mut therminol_check_valve = TherminolCheckValve::new(
+            &check_valve.name,
+            self.get_fluid_temperature()?,
+            Angle::new::<degree>(check_valve.incline_angle_degrees),
+            Length::new::<meter>(check_valve.component_length_meters),
+            Length::new::<meter>(check_valve.hydraulic_diameter_meters),
+            check_valve.fully_open_k,
+            Pressure::new::<pascal>(check_valve.cracking_pressure_pascals));
+
+        therminol_check_valve.set_reverse_leakage_k(check_valve.reverse_leakage_k);
+
+        return Ok(therminol_check_valve);
+    }
+
+    /// builds a pipe, a custom component, a valve or a check valve
+    /// from the catalogue, whichever has this name
+    pub fn get_fluid_component(&self, name: &str)
+        -> Result<Box<dyn TherminolFluidComponent>, ComponentCatalogueError> {
+
+        if self.pipes.iter().any(|pipe| pipe.name == name) {
+            return Ok(Box::new(self.get_therminol_pipe(name)?));
+        }
+
+        if self.valves.iter().any(|valve| valve.name == name) {
+            return Ok(Box::new(self.get_therminol_valve(name)?));
+        }
+
+        if self.check_valves.iter().any(|check_valve| check_valve.name == name) {
+            return Ok(Box::new(self.get_therminol_check_valve(name)?));
+        }
+
+        return Ok(Box::new(self.get_therminol_custom_component(name)?));
+    }
+
+    /// returns the elevation change across a component,
+    /// ie. its length times the sine of its incline angle
+    pub fn get_elevation_change(&self, name: &str)
+        -> Result<Length, ComponentCatalogueError> {
+
+        let pipe_geometry = self.pipes.iter()
+            .find(|pipe| pipe.name == name)
+            .map(|pipe| (pipe.component_length_meters,
+                         pipe.incline_angle_degrees));
+
+        let custom_component_geometry = self.custom_components.iter()
+            .find(|custom_component| custom_component.name == name)
+            .map(|custom_component|
+                 (custom_component.component_length_meters,
+                  custom_component.incline_angle_degrees));
+
+        let valve_geometry = self.valves.iter()
+            .find(|valve| valve.name == name)
+            .map(|valve| (valve.component_length_meters,
+                          valve.incline_angle_degrees));
+
+        let check_valve_geometry = self.check_valves.iter()
+            .find(|check_valve| check_valve.name == name)
+            .map(|check_valve| (check_valve.component_length_meters,
+                                check_valve.incline_angle_degrees));
+
+        let (component_length_meters, incline_angle_degrees) =
+            pipe_geometry.or(custom_component_geometry).or(valve_geometry)
+            .or(check_valve_geometry)
+            .ok_or_else(|| ComponentCatalogueError::ComponentNotFound(
+                    name.to_string()))?;
+
+        return Ok(Length::new::<meter>(
+                component_length_meters *
+                incline_angle_degrees.to_radians().sin()));
+    }
+
+    /// builds a series branch from catalogue components,
+    /// in the order the fluid flows through them
+    pub fn build_series_branch(
+        &self,
+        branch_name: &str,
+        component_names: &[&str])
+        -> Result<TherminolSeriesBranch, ComponentCatalogueError> {
+
+        let mut series_branch = TherminolSeriesBranch::new(branch_name);
+
+        for component_name in component_names {
+            series_branch.add_component(
+                self.get_fluid_component(component_name)?);
+        }
+
+        return Ok(series_branch);
+    }
+}
@@ -3,84 +3,115 @@ use fluid_mechanics_rust::prelude::*;
 
 use crate::{Pipe6a, StaticMixer41, CTAHVertical, CTAHHorizontal, 
     Pipe8a, StaticMixer40, Pipe9, Pipe10, Pipe11, Pipe12, CTAHPump, Pipe13, Pipe14, 
-    therminol_pipe::TherminolPipe, therminol_component::TherminolCustomComponent, Pipe16, Pipe15, Branch17, Flowmeter40};
-
-extern crate roots;
-use roots::find_root_brent;
-use roots::SimpleConvergency;
-
-pub struct CTAHBranch<'ctah_branch_lifetime> {
-
-    pipe6a: Pipe6a, 
-    // component 6
-    static_mixer_41: StaticMixer41, 
-    // 7a
-    ctah_vertical: CTAHVertical, 
-    // 7b
-    ctah_horizontal: CTAHHorizontal, 
-    // 8a
-    pipe_8a: Pipe8a,
-    // 8
-    static_mixer_40: StaticMixer40, // 8
-    // 9
-    pipe_9: Pipe9,
-    // 10
-    pipe_10: Pipe10,
-    // 11
-    pipe_11: Pipe11,
-    //12
-    pipe_12: Pipe12,
-    // between 12 and 13 
-    ctah_pump: CTAHPump,
-    //13
-    pipe_13: Pipe13,
-    //14
-    pipe_14: Pipe14,
-    //15 
-    flowmeter_40_14a: Flowmeter40,
-    //16
-    pipe_15: Pipe15,
-    //17
-    pipe_16: Pipe16,
-    //18
-    branch_17: Branch17,
-
-    fluid_component_vector_immutable: 
-        Vec<&'ctah_branch_lifetime dyn FluidComponent>
+    Pipe16, Pipe15, Branch17, Flowmeter40,
+    TherminolFluidComponent, TherminolSeriesComponents, PumpCurve,
+    find_series_branch_mass_flowrate, ComponentPressureBreakdown,
+    FacilityTopologyError, TherminolSeriesBranch, SeriesBranchSolverSettings,
+    CIETSolverError};
+
+
+pub struct CTAHBranch {
+
+    // the ctah branch is a series branch with a pump among its
+    // components, it owns all its components (including the ctah
+    // pump) so that it can be stored, returned from functions, 
+    // sent to a solver thread and have its pump pressure 
+    // changed without being rebuilt
+    series_branch: TherminolSeriesBranch,
+
+    // where the ctah pump is in the component vector
+    ctah_pump_index: usize,
+
+    // if the pump has a curve, it adds pressure depending on
+    // the flowrate and speed, on top of the pump pressure
+    ctah_pump_curve: Option<PumpCurve>,
+    ctah_pump_speed_rpm: f64,
 }
 
-impl<'ctah_branch_lifetime> CTAHBranch<'ctah_branch_lifetime> {
+/// the ctah pump is the 10th element of the ctah branch
+/// component vector starting from 0
+/// (between pipe 12 and 13)
+const CTAH_PUMP_INDEX: usize = 10;
+
+impl CTAHBranch {
 
     /// constructor, returns an instance of the ctah branch
+    /// with all its components already in place
+    ///
+    /// the ctah pump pressure starts at 0 Pa
     pub fn new() -> Self {
 
-        
-
-        // constructor will return the CTAH branch with all its items
-        // but the vector will be empty
-        let ctah_branch_vector_empty: Vec<&'ctah_branch_lifetime dyn FluidComponent>
-            = vec![];
-
-        return Self { 
-            pipe6a: Pipe6a::new(),
-            static_mixer_41: StaticMixer41::new(),
-            ctah_vertical: CTAHVertical::new(),
-            ctah_horizontal: CTAHHorizontal::new(),
-            pipe_8a: Pipe8a::new(),
-            static_mixer_40: StaticMixer40::new(),
-            pipe_9: Pipe9::new(),
-            pipe_10: Pipe10::new(),
-            pipe_11: Pipe11::new(),
-            pipe_12: Pipe12::new(),
-            ctah_pump: CTAHPump::new(), 
-            pipe_13: Pipe13::new(),
-            pipe_14: Pipe14::new(),
-            flowmeter_40_14a: Flowmeter40::new(),
-            pipe_15: Pipe15::new(),
-            pipe_16: Pipe16::new(),
-            branch_17: Branch17::new(),
-            fluid_component_vector_immutable: ctah_branch_vector_empty,
+        // each component is built once by its factory
+        // and then owned by the branch
+        let ctah_branch_vector: Vec<Box<dyn TherminolFluidComponent>> = vec![
+            // element number: 0 
+            Box::new(Pipe6a::new().get()),
+            // 1
+            Box::new(StaticMixer41::new().get()),
+            // 2
+            Box::new(CTAHVertical::new().get()),
+            // 3
+            Box::new(CTAHHorizontal::new().get()),
+            // 4
+            Box::new(Pipe8a::new().get()),
+            // 5
+            Box::new(StaticMixer40::new().get()),
+            // 6
+            Box::new(Pipe9::new().get()),
+            // 7
+            Box::new(Pipe10::new().get()),
+            // 8
+            Box::new(Pipe11::new().get()),
+            // 9
+            Box::new(Pipe12::new().get()),
+            // 10
+            Box::new(CTAHPump::new().get()),
+            // 11
+            Box::new(Pipe13::new().get()),
+            // 12
+            Box::new(Pipe14::new().get()),
+            // 13
+            Box::new(Flowmeter40::new().get()),
+            // 14
+            Box::new(Pipe15::new().get()),
+            // 15
+            Box::new(Pipe16::new().get()),
+            // 16
+            Box::new(Branch17::new().get()),
+        ];
+
+        return Self {
+            series_branch: TherminolSeriesBranch::new_from_components(
+                "ctah_branch", ctah_branch_vector),
+            ctah_pump_index: CTAH_PUMP_INDEX,
+            ctah_pump_curve: None,
+            ctah_pump_speed_rpm: 0.0,
+        };
+    }
+
+    /// constructor, returns a ctah branch made of the components
+    /// given (eg. from a component catalogue) instead of the
+    /// hard coded ones, in the order the fluid flows through them
+    ///
+    /// ctah_pump_index is where the pump is in the component vector,
+    /// an error is returned if this is outside the branch
+    pub fn new_from_components(
+        fluid_component_vector: Vec<Box<dyn TherminolFluidComponent>>,
+        ctah_pump_index: usize) -> Result<Self, FacilityTopologyError> {
+
+        if ctah_pump_index >= fluid_component_vector.len() {
+            return Err(FacilityTopologyError::PumpIndexOutOfRange {
+                pump_index: ctah_pump_index,
+                component_count: fluid_component_vector.len() });
         }
+
+        return Ok(Self {
+            series_branch: TherminolSeriesBranch::new_from_components(
+                "ctah_branch", fluid_component_vector),
+            ctah_pump_index,
+            ctah_pump_curve: None,
+            ctah_pump_speed_rpm: 0.0,
+        });
     }
 
 
@@ -88,208 +119,182 @@ impl<'ctah_branch_lifetime> CTAHBranch<'ctah_branch_lifetime> {
 
     /// sets the ctah pump pressure to whatever value the user specifies
     ///
-    /// it basically deletes the existing ctah pump and instantiates a
-    /// new one at the correct position
-    ///
+    /// the ctah pump is owned by the branch, so the user does not
+    /// need to supply a new pump object (or rebuild the branch)
+    /// every time the pump pressure changes
     pub fn set_ctah_pump_pressure(&mut self,
-                                  user_specified_pressure: Pressure,
-                                  ctah_pump: &'ctah_branch_lifetime mut 
-                                  TherminolCustomComponent){
+                                  user_specified_pressure: Pressure){
 
         // should we do max/min pressure??? IDK
-        // i'll just have an actual ctah pump object
-        
-        // the ctah pump will be usually at 10th element of the vector starting
-        // from 0
+        self.series_branch.set_component_internal_pressure_source(
+            self.ctah_pump_index, user_specified_pressure);
 
+    }
 
-        ctah_pump.set_internal_pressure_source(user_specified_pressure);
-
-        self.fluid_component_vector_immutable[10] = ctah_pump;
-        // inside the CTAH branch i should have all my components
-        // so for ease of use and readability, i may want to nest the 
-        // actual component objects within the ctah branch
-
+    /// returns the pump pressure currently set in the branch
+    pub fn get_ctah_pump_pressure(&self) -> Pressure {
+        return self.get_therminol_components()[self.ctah_pump_index]
+            .get_internal_pressure_source_immutable();
     }
 
-    /// these help to return the components to the environment
+    /// gives the ctah pump a head against flowrate curve (or takes
+    /// it away with None), the pump then adds the curve pressure at
+    /// the pump speed on top of the pump pressure
     ///
-    /// what you are supposed to do is first
-    /// to 
-
-    pub fn get_pipe6a(&self) -> TherminolPipe {
-        return self.pipe6a.get();
+    /// without a curve, the pump is just a pressure source
+    pub fn set_ctah_pump_curve(&mut self, ctah_pump_curve: Option<PumpCurve>){
+        self.ctah_pump_curve = ctah_pump_curve;
     }
 
-    pub fn get_static_mixer_41(&self) -> TherminolCustomComponent {
-        return self.static_mixer_41.get();
+    pub fn get_ctah_pump_curve(&self) -> Option<PumpCurve> {
+        return self.ctah_pump_curve;
     }
 
-    pub fn get_ctah_vertical(&self) -> TherminolCustomComponent {
-        return self.ctah_vertical.get();
+    /// sets the ctah pump speed in rpm, this does nothing
+    /// unless the pump has a curve
+    pub fn set_ctah_pump_speed(&mut self, ctah_pump_speed_rpm: f64){
+        self.ctah_pump_speed_rpm = ctah_pump_speed_rpm;
     }
 
-    pub fn get_ctah_horizontal(&self) -> TherminolCustomComponent{
-        return self.ctah_horizontal.get();
+    pub fn get_ctah_pump_speed(&self) -> f64 {
+        return self.ctah_pump_speed_rpm;
     }
 
-    pub fn get_pipe_8a(&self) -> TherminolPipe {
-        return self.pipe_8a.get();
-    }
+    /// returns the pressure the pump curve adds at this mass
+    /// flowrate and the current pump speed, with the fluid density
+    /// in the pump, or zero if the pump has no curve
+    pub fn get_ctah_pump_curve_pressure(&self, fluid_mass_flowrate: MassRate)
+        -> Pressure {
 
-    pub fn get_static_mixer_40(&self) -> TherminolCustomComponent {
-        return self.static_mixer_40.get();
-    }
-    pub fn get_pipe_9(&self) -> TherminolPipe {
-        return self.pipe_9.get();
-    }
+        let ctah_pump_curve = match self.ctah_pump_curve {
+            Some(ctah_pump_curve) => ctah_pump_curve,
+            None => return Pressure::new::<pascal>(0.0),
+        };
 
-    pub fn get_pipe_10(&self) -> TherminolPipe {
-        return self.pipe_10.get();
-    }
+        let fluid_density = self.get_therminol_components()[self.ctah_pump_index]
+            .as_fluid_component().get_fluid_density_immutable();
 
-    pub fn get_pipe_11(&self) -> TherminolPipe {
-        return self.pipe_11.get();
+        return ctah_pump_curve.get_pressure_change(
+            fluid_mass_flowrate, self.ctah_pump_speed_rpm, fluid_density);
     }
 
-    pub fn get_pipe_12(&self) -> TherminolPipe {
-        return self.pipe_12.get();
-    }
+    /// returns the total pressure the pump gives at zero flow,
+    /// this is the pump pressure plus the pump curve pressure 
+    /// at zero flow and the current pump speed
+    ///
+    /// if this is positive (or zero), the pump pushes fluid 
+    /// forward through the ctah branch, or not at all
+    pub fn get_ctah_pump_shutoff_pressure(&self) -> Pressure {
 
-    pub fn get_ctah_pump(&self) -> TherminolCustomComponent {
-        return self.ctah_pump.get();
-    }
+        let zero_flowrate = MassRate::new::<kilogram_per_second>(0.0);
 
-    pub fn get_pipe_13(&self) -> TherminolPipe {
-        return self.pipe_13.get();
+        return self.get_ctah_pump_pressure() 
+            + self.get_ctah_pump_curve_pressure(zero_flowrate);
     }
-    pub fn get_pipe_14(&self) -> TherminolPipe {
-        return self.pipe_14.get();
+
+    /// returns references to the components owned by this branch
+    /// in the order the fluid flows through them
+    /// (from pipe 6a down to branch 17)
+    pub fn get_fluid_component_vector(&self) 
+        -> Vec<&dyn FluidComponent> {
+        return self.series_branch.get_fluid_component_vector();
     }
-    pub fn get_flowmeter_40_14a(&self) -> TherminolCustomComponent {
-        return self.flowmeter_40_14a.get();
+
+    /// sets the fluid temperature of every component in the branch
+    pub fn set_fluid_temperature(&mut self, fluid_temp: ThermodynamicTemperature){
+        self.series_branch.set_fluid_temperature(fluid_temp);
     }
 
-    pub fn get_pipe_15(&self) -> TherminolPipe {
-        return self.pipe_15.get();
+    /// returns the fluid temperature of the first component in
+    /// the branch, or None if the branch has no components
+    pub fn get_fluid_temperature(&self) -> Option<ThermodynamicTemperature> {
+        return self.series_branch.get_fluid_temperature();
     }
-    pub fn get_pipe_16(&self) -> TherminolPipe {
-        return self.pipe_16.get();
+
+    /// sets the brent settings used to find the mass flowrate
+    /// through the branch at a given pressure change
+    pub fn set_solver_settings(&mut self, solver_settings: SeriesBranchSolverSettings){
+        self.series_branch.set_solver_settings(solver_settings);
     }
-    pub fn get_branch_17(&self) -> TherminolPipe {
-        return self.branch_17.get();
+
+    pub fn get_solver_settings(&self) -> SeriesBranchSolverSettings {
+        return self.series_branch.get_solver_settings();
     }
 
+    /// calculates mass flowrate from pressure change,
+    /// taking into account any check valves in the branch
+    /// (the pump curve pressure is in get_pressure_change),
+    /// and returns an error if brent cannot find it
+    pub fn try_get_mass_flowrate_from_pressure_change(
+        &self,
+        pressure_change: Pressure) -> Result<MassRate, CIETSolverError> {
+
+        return find_series_branch_mass_flowrate(
+            &|mass_flowrate| self.get_pressure_change(mass_flowrate),
+            self.get_zero_flow_pressure_loss_range(),
+            pressure_change,
+            &self.get_solver_settings());
+    }
 }
 
-impl<'ctah_branch_lifetime> FluidComponentCollectionMethods for CTAHBranch<'ctah_branch_lifetime> {
+impl FluidComponentCollectionMethods for CTAHBranch {
 
     /// calculates pressure change when given a mass flowrate
     fn get_pressure_change(
         &self, 
         fluid_mass_flowrate: MassRate) -> Pressure{
-        let fluid_component_collection_vector = 
-            self.get_immutable_fluid_component_vector();
-
-        let pressure_change = 
-            <Self as FluidComponentCollectionSeriesAssociatedFunctions>
-            ::calculate_pressure_change_from_mass_flowrate(
-                fluid_mass_flowrate, 
-                fluid_component_collection_vector);
-
-        return pressure_change;
+        return self.series_branch.get_pressure_change(fluid_mass_flowrate)
+            + self.get_ctah_pump_curve_pressure(fluid_mass_flowrate);
     }
 
-    /// calculates mass flowrate from pressure change
-
+    /// calculates mass flowrate from pressure change,
+    /// NaN is returned if brent fails, same as the
+    /// therminol series branch
     fn get_mass_flowrate_from_pressure_change(
         &self,
         pressure_change: Pressure) -> MassRate{
 
-
-        let fluid_component_collection_vector = 
-            self.get_immutable_fluid_component_vector();
-
-
-        // i'm keeping bounds artificially low for ciet
-        // -1 or +1 kg/s
-        let upper_bound = MassRate::new::<kilogram_per_second>(1.0);
-
-
-        let lower_bound = MassRate::new::<kilogram_per_second>(-1.0);
-
-
-        // now we have a function comparing the pressure change
-        // to the pressure change of the calculated value
-
-        let mass_flow_from_pressure_chg_root = 
-            |mass_flow_kg_per_s: f64| -> f64 {
-
-            let mass_flow_kg_per_s_double = mass_flow_kg_per_s; 
-
-            let mass_rate = 
-                MassRate::new::<kilogram_per_second>(
-                    mass_flow_kg_per_s_double);
-
-
-            let pressure_change_tested = 
-                Self::calculate_pressure_change_from_mass_flowrate(
-                mass_rate, 
-                fluid_component_collection_vector);
-
-            // now i've obtained the pressure change, i convert it to f64
-
-            let pressure_change_user_stipulated_pascals_f64 = 
-                pressure_change.value;
-
-            // since we are finding root, then we must also
-            // subtract it from our pressure change value
-
-
-            let pressure_change_error: f64 =
-                pressure_change_user_stipulated_pascals_f64 - 
-                pressure_change_tested.value;
-
-            return pressure_change_error;
-
-        };
-
-        let mut convergency = SimpleConvergency { eps:1e-9_f64, max_iter:30 };
-
-        let mass_flowrate_result 
-            = find_root_brent(
-                upper_bound.value,
-                lower_bound.value,
-                &mass_flow_from_pressure_chg_root,
-                &mut convergency);
-
-        return MassRate::new::<kilogram_per_second>(mass_flowrate_result.unwrap());
+        return self.try_get_mass_flowrate_from_pressure_change(pressure_change)
+            .unwrap_or(MassRate::new::<kilogram_per_second>(f64::NAN));
     }
-
-
 }
 
-impl<'ctah_branch_lifetime> FluidComponentCollection<'ctah_branch_lifetime> 
-for CTAHBranch<'ctah_branch_lifetime> {
-
-            fn get_immutable_fluid_component_vector(&self)
-                -> &Vec<&'ctah_branch_lifetime dyn FluidComponent> {
+impl TherminolSeriesComponents for CTAHBranch {
 
-                    return &self.fluid_component_vector_immutable;
-                }
+    fn get_therminol_components(&self) -> Vec<&dyn TherminolFluidComponent> {
+        return self.series_branch.get_therminol_components();
+    }
 
-            fn set_fluid_component_vector(
-                &mut self, 
-                fluid_component_vector: 
-                Vec<&'ctah_branch_lifetime dyn FluidComponent>){
+    fn set_component_fluid_temperature(
+        &mut self,
+        component_index: usize,
+        fluid_temp: ThermodynamicTemperature){
+        self.series_branch.set_component_fluid_temperature(
+            component_index, fluid_temp);
+    }
 
-                self.fluid_component_vector_immutable = 
-                    fluid_component_vector;
+    fn set_component_valve_opening_percent(
+        &mut self,
+        component_index: usize,
+        opening_percent: f64){
+        self.series_branch.set_component_valve_opening_percent(
+            component_index, opening_percent);
+    }
 
-            }
+    /// the pump curve pressure counts as part of
+    /// the pump pressure of the ctah pump
+    fn get_component_pressure_breakdown(&self, mass_flowrate: MassRate)
+        -> Vec<ComponentPressureBreakdown> {
 
-}
+        let mut component_pressure_breakdown: Vec<ComponentPressureBreakdown> = 
+            self.get_therminol_components().iter()
+            .map(|component| ComponentPressureBreakdown::new(
+                    *component, mass_flowrate))
+            .collect();
 
-impl<'ctah_branch_lifetime> 
-FluidComponentCollectionSeriesAssociatedFunctions for CTAHBranch<'ctah_branch_lifetime> {}
+        component_pressure_breakdown[self.ctah_pump_index].pump_pressure += 
+            self.get_ctah_pump_curve_pressure(mass_flowrate);
 
+        return component_pressure_breakdown;
+    }
+}
@@ -2,245 +2,156 @@ extern crate fluid_mechanics_rust;
 use fluid_mechanics_rust::prelude::*;
 
 use crate::{
-    therminol_pipe::TherminolPipe, therminol_component::TherminolCustomComponent, 
-    Pipe26, StaticMixer21, Pipe25a, DHXShellSideHeatExchanger, 
-    StaticMixer20, Pipe23a, Pipe22, Flowmeter20WithHighKCheckValve, 
-    Pipe21, Pipe20, Pipe19, Flowmeter20};
-
-extern crate roots;
-use roots::find_root_brent;
-use roots::SimpleConvergency;
-
-pub struct DHXBranch<'dhx_branch_lifetime> {
-
-    pipe26: Pipe26,
-    // item 25
-    static_mixer_21: StaticMixer21,
-    pipe25a: Pipe25a,
-    // item 24
-    dhx_shell_side_heat_exchanger: DHXShellSideHeatExchanger,
-    // item 23
-    static_mixer_20: StaticMixer20,
-    pipe23a: Pipe23a,
-    pipe22: Pipe22,
-    // item 21a
-    flowmeter20: Flowmeter20,
-    flowmeter20_check_valve: Flowmeter20WithHighKCheckValve,
-    pipe21: Pipe21,
-    pipe20: Pipe20,
-    pipe19: Pipe19,
-
-
-    fluid_component_vector_immutable: 
-        Vec<&'dhx_branch_lifetime dyn FluidComponent>
+    Pipe26, StaticMixer21, Pipe25a, DHXShellSideHeatExchanger,
+    StaticMixer20, Pipe23a, Pipe22,
+    Pipe21, Pipe20, Pipe19, Flowmeter20, TherminolFluidComponent,
+    TherminolSeriesComponents, DHXBranchCheckValve,
+    TherminolSeriesBranch, SeriesBranchSolverSettings, CIETSolverError};
+
+
+pub struct DHXBranch {
+
+    // the dhx branch is a series branch with a check valve among
+    // its components, it owns all its components so that it can be
+    // stored, returned from functions and sent to a solver thread
+    series_branch: TherminolSeriesBranch,
 }
 
-impl<'dhx_branch_lifetime> DHXBranch<'dhx_branch_lifetime> {
+impl DHXBranch {
 
     /// constructor, returns an instance of the dhx branch
+    /// with all its components already in place
     pub fn new() -> Self {
 
-        let empty_vec: Vec<&'dhx_branch_lifetime dyn FluidComponent> = vec![];
-        
-
-        // constructor will return the dhx branch with all its factories
-        // but the vector will be empty
-        //
-
-        Self {
-            pipe26: Pipe26::new(),
+        // each component is built once by its factory
+        // and then owned by the branch
+        let dhx_branch_vector: Vec<Box<dyn TherminolFluidComponent>> = vec![
+            Box::new(Pipe26::new().get()),
             // item 25
-            static_mixer_21: StaticMixer21::new(),
-            pipe25a: Pipe25a::new(),
+            Box::new(StaticMixer21::new().get()),
+            Box::new(Pipe25a::new().get()),
             // item 24
-            dhx_shell_side_heat_exchanger: DHXShellSideHeatExchanger::new(),
+            Box::new(DHXShellSideHeatExchanger::new().get()),
             // item 23
-            static_mixer_20: StaticMixer20::new(),
-            pipe23a: Pipe23a::new(),
-            pipe22: Pipe22::new(),
+            Box::new(StaticMixer20::new().get()),
+            Box::new(Pipe23a::new().get()),
+            Box::new(Pipe22::new().get()),
             // item 21a
-            flowmeter20: Flowmeter20::new(),
-            flowmeter20_check_valve: Flowmeter20WithHighKCheckValve::new(),
-            pipe21: Pipe21::new(),
-            pipe20: Pipe20::new(),
-            pipe19: Pipe19::new(),
-            fluid_component_vector_immutable: empty_vec
-        }
-    }
-
-    pub fn get_pipe26(&self) -> TherminolPipe {
-        return self.pipe26.get();
+            Box::new(Flowmeter20::new().get()),
+            Box::new(DHXBranchCheckValve::new().get()),
+            Box::new(Pipe21::new().get()),
+            Box::new(Pipe20::new().get()),
+            Box::new(Pipe19::new().get()),
+        ];
+
+        return Self::new_from_components(dhx_branch_vector);
     }
 
-    pub fn get_static_mixer_21(&self) -> TherminolCustomComponent {
-        return self.static_mixer_21.get();
-    }
-
-    pub fn get_pipe25a(&self) -> TherminolPipe {
-        return self.pipe25a.get();
-    }
-
-    pub fn get_dhx_shell_side_heat_exchanger(&self) -> TherminolCustomComponent {
-        return self.dhx_shell_side_heat_exchanger.get();
-    }
-
-    pub fn get_static_mixer_20(&self) -> TherminolCustomComponent {
-        return self.static_mixer_20.get();
+    /// constructor, returns a dhx branch made of the components
+    /// given (eg. from a component catalogue) instead of the
+    /// hard coded ones, in the order the fluid flows through them
+    ///
+    /// the check valve is a component like any other, so it must
+    /// be among the components given (eg. dhx_branch_check_valve
+    /// in the ciet catalogue) for the branch to keep its check
+    /// valve behaviour
+    pub fn new_from_components(
+        fluid_component_vector: Vec<Box<dyn TherminolFluidComponent>>)
+        -> Self {
+
+        return Self {
+            series_branch: TherminolSeriesBranch::new_from_components(
+                "dhx_branch", fluid_component_vector),
+        };
     }
 
-    pub fn get_pipe23a(&self) -> TherminolPipe {
-        return self.pipe23a.get();
+    /// returns references to the components owned by this branch
+    /// in the order the fluid flows through them
+    pub fn get_fluid_component_vector(&self)
+        -> Vec<&dyn FluidComponent> {
+        return self.series_branch.get_fluid_component_vector();
     }
 
-    pub fn get_pipe22(&self) -> TherminolPipe {
-        return self.pipe22.get();
+    /// sets the fluid temperature of every component in the branch
+    pub fn set_fluid_temperature(&mut self, fluid_temp: ThermodynamicTemperature){
+        self.series_branch.set_fluid_temperature(fluid_temp);
     }
 
-    pub fn get_flowmeter20(&self) -> TherminolCustomComponent {
-        return self.flowmeter20.get();
+    /// returns the fluid temperature of the first component in
+    /// the branch, or None if the branch has no components
+    pub fn get_fluid_temperature(&self) -> Option<ThermodynamicTemperature> {
+        return self.series_branch.get_fluid_temperature();
     }
 
-    pub fn get_flowmeter20_check_valve(&self) -> TherminolCustomComponent {
-        return self.flowmeter20_check_valve.get();
+    /// sets the brent settings used to find the mass flowrate
+    /// through the branch at a given pressure change
+    pub fn set_solver_settings(&mut self, solver_settings: SeriesBranchSolverSettings){
+        self.series_branch.set_solver_settings(solver_settings);
     }
 
-    pub fn get_pipe21(&self) -> TherminolPipe {
-        return self.pipe21.get();
+    pub fn get_solver_settings(&self) -> SeriesBranchSolverSettings {
+        return self.series_branch.get_solver_settings();
     }
 
-    pub fn get_pipe20(&self) -> TherminolPipe {
-        return self.pipe20.get();
+    /// calculates mass flowrate from pressure change,
+    /// and returns an error if brent cannot find it
+    pub fn try_get_mass_flowrate_from_pressure_change(
+        &self,
+        pressure_change: Pressure) -> Result<MassRate, CIETSolverError> {
+        return self.series_branch
+            .try_get_mass_flowrate_from_pressure_change(pressure_change);
     }
 
-    pub fn get_pipe19(&self) -> TherminolPipe {
-        return self.pipe19.get();
+    /// returns the index of the dhx branch check valve within the
+    /// branch, or None if the branch was built without one
+    pub fn get_dhx_branch_check_valve_index(&self) -> Option<usize> {
+        return self.get_first_check_valve_index();
     }
 }
 
 
-impl<'dhx_branch_lifetime> FluidComponentCollectionMethods for DHXBranch<'dhx_branch_lifetime> {
+impl FluidComponentCollectionMethods for DHXBranch {
 
     /// calculates pressure change when given a mass flowrate
     fn get_pressure_change(
-        &self, 
+        &self,
         fluid_mass_flowrate: MassRate) -> Pressure{
-        let fluid_component_collection_vector = 
-            self.get_immutable_fluid_component_vector();
-
-        let pressure_change = 
-            <Self as FluidComponentCollectionSeriesAssociatedFunctions>
-            ::calculate_pressure_change_from_mass_flowrate(
-                fluid_mass_flowrate, 
-                fluid_component_collection_vector);
-
-        return pressure_change;
+        return self.series_branch.get_pressure_change(fluid_mass_flowrate);
     }
 
     /// calculates mass flowrate from pressure change
     /// for dhx branch
     ///
-    /// no check valve behaviour here
-    /// it will be put in the ciet part
+    /// the dhx branch check valve is one of the components,
+    /// so it only lets flow through from the top of the branch
+    /// to the bottom (positive flow)
     fn get_mass_flowrate_from_pressure_change(
         &self,
         pressure_change: Pressure) -> MassRate{
-
-
-
-        let fluid_component_collection_vector = 
-            self.get_immutable_fluid_component_vector();
-
-        // here is where i implement the check valve behaviour
-        let zero_flowrate = 
-            MassRate::new::<kilogram_per_second>(0.0);
-        let dhx_branch_hydrostatic_pressure = 
-            self.get_pressure_change(MassRate::new::<kilogram_per_second>(0.0));
-
-        if pressure_change.value > dhx_branch_hydrostatic_pressure.value {
-
-            return zero_flowrate;
-
-        } 
-
-        // i'm keeping bounds artificially low for ciet
-        // -1 or +1 kg/s
-        let upper_bound = MassRate::new::<kilogram_per_second>(1.0);
-
-
-        let lower_bound = MassRate::new::<kilogram_per_second>(-1.0);
-
-
-        // now we have a function comparing the pressure change
-        // to the pressure change of the calculated value
-
-        let mass_flow_from_pressure_chg_root = 
-            |mass_flow_kg_per_s: f64| -> f64 {
-
-            let mass_flow_kg_per_s_double = mass_flow_kg_per_s; 
-
-            let mass_rate = 
-                MassRate::new::<kilogram_per_second>(
-                    mass_flow_kg_per_s_double);
-
-
-            let pressure_change_tested = 
-                Self::calculate_pressure_change_from_mass_flowrate(
-                mass_rate, 
-                fluid_component_collection_vector);
-
-            // now i've obtained the pressure change, i convert it to f64
-
-            let pressure_change_user_stipulated_pascals_f64 = 
-                pressure_change.value;
-
-            // since we are finding root, then we must also
-            // subtract it from our pressure change value
-
-
-            let pressure_change_error: f64 =
-                pressure_change_user_stipulated_pascals_f64 - 
-                pressure_change_tested.value;
-
-            return pressure_change_error;
-
-        };
-
-        let mut convergency = SimpleConvergency { eps:1e-9_f64, max_iter:30 };
-
-        let mass_flowrate_result 
-            = find_root_brent(
-                upper_bound.value,
-                lower_bound.value,
-                &mass_flow_from_pressure_chg_root,
-                &mut convergency);
-
-        return MassRate::new::<kilogram_per_second>(mass_flowrate_result.unwrap());
+        return self.series_branch
+            .get_mass_flowrate_from_pressure_change(pressure_change);
     }
-
-
 }
 
-impl<'dhx_branch_lifetime> FluidComponentCollection<'dhx_branch_lifetime> 
-for DHXBranch<'dhx_branch_lifetime> {
-
-            fn get_immutable_fluid_component_vector(&self)
-                -> &Vec<&'dhx_branch_lifetime dyn FluidComponent> {
+impl TherminolSeriesComponents for DHXBranch {
 
-                    return &self.fluid_component_vector_immutable;
-                }
-
-            fn set_fluid_component_vector(
-                &mut self, 
-                fluid_component_vector: 
-                Vec<&'dhx_branch_lifetime dyn FluidComponent>){
-
-                self.fluid_component_vector_immutable = 
-                    fluid_component_vector;
+    fn get_therminol_components(&self) -> Vec<&dyn TherminolFluidComponent> {
+        return self.series_branch.get_therminol_components();
+    }
 
-            }
+    fn set_component_fluid_temperature(
+        &mut self,
+        component_index: usize,
+        fluid_temp: ThermodynamicTemperature){
+        self.series_branch.set_component_fluid_temperature(
+            component_index, fluid_temp);
+    }
 
+    fn set_component_valve_opening_percent(
+        &mut self,
+        component_index: usize,
+        opening_percent: f64){
+        self.series_branch.set_component_valve_opening_percent(
+            component_index, opening_percent);
+    }
 }
-
-impl<'dhx_branch_lifetime> 
-FluidComponentCollectionSeriesAssociatedFunctions for DHXBranch<'dhx_branch_lifetime> {}
-
@@ -1,200 +1,131 @@
 extern crate fluid_mechanics_rust;
 use fluid_mechanics_rust::prelude::*;
 
-use crate::{Branch5, 
-    therminol_pipe::TherminolPipe, therminol_component::TherminolCustomComponent, 
-    Pipe4, Pipe3, StaticMixer10, Pipe2a, HeaterTopHead1a, 
-    CietHeaterVersion1, HeaterBottomHead1b, Pipe18};
-extern crate roots;
-use roots::find_root_brent;
-use roots::SimpleConvergency;
-
-pub struct HeaterBranch<'heater_branch_lifetime> {
-
-    branch5: Branch5,
-    pipe4: Pipe4,
-    pipe3: Pipe3,
-    mixer10: StaticMixer10,
-    pipe2a: Pipe2a,
-    heater_top_head_1a: HeaterTopHead1a,
-    ciet_heater: CietHeaterVersion1,
-    heater_bottom_head_1b: HeaterBottomHead1b,
-    pipe18: Pipe18,
-
-
-    fluid_component_vector_immutable: 
-        Vec<&'heater_branch_lifetime dyn FluidComponent>
+use crate::{Branch5,
+    Pipe4, Pipe3, StaticMixer10, Pipe2a, HeaterTopHead1a,
+    CietHeaterVersion1, HeaterBottomHead1b, Pipe18, TherminolFluidComponent, TherminolSeriesComponents,
+    TherminolSeriesBranch, SeriesBranchSolverSettings, CIETSolverError};
+
+pub struct HeaterBranch {
+
+    // the heater branch is a plain series branch, it owns all
+    // its components so that it can be stored, returned from
+    // functions and sent to a solver thread
+    series_branch: TherminolSeriesBranch,
 }
 
-impl<'heater_branch_lifetime> HeaterBranch<'heater_branch_lifetime> {
+impl HeaterBranch {
 
     /// constructor, returns an instance of the heater branch
+    /// with all its components already in place
     pub fn new() -> Self {
 
-        let empty_vec: Vec<&'heater_branch_lifetime dyn FluidComponent> = vec![];
-        
-
-        // constructor will return the heater branch with all its factories
-        // but the vector will be empty
-        //
-
-        Self {
-            branch5: Branch5::new(),
-            pipe4: Pipe4::new(),
-            pipe3: Pipe3::new(),
-            mixer10: StaticMixer10::new(),
-            pipe2a: Pipe2a::new(),
-            heater_top_head_1a: HeaterTopHead1a::new(),
-            ciet_heater: CietHeaterVersion1::new(),
-            heater_bottom_head_1b: HeaterBottomHead1b::new(),
-            pipe18: Pipe18::new(),
-            fluid_component_vector_immutable: empty_vec
-        }
+        // each component is built once by its factory
+        // and then owned by the branch
+        let heater_branch_vector: Vec<Box<dyn TherminolFluidComponent>> = vec![
+            Box::new(Branch5::new().get()),
+            Box::new(Pipe4::new().get()),
+            Box::new(Pipe3::new().get()),
+            Box::new(StaticMixer10::new().get()),
+            Box::new(Pipe2a::new().get()),
+            Box::new(HeaterTopHead1a::new().get()),
+            Box::new(CietHeaterVersion1::new().get()),
+            Box::new(HeaterBottomHead1b::new().get()),
+            Box::new(Pipe18::new().get()),
+        ];
+
+        return Self::new_from_components(heater_branch_vector);
     }
 
-    pub fn get_branch5(&self) -> TherminolPipe {
-        return self.branch5.get();
-    }
+    /// constructor, returns a heater branch made of the components
+    /// given (eg. from a component catalogue) instead of the
+    /// hard coded ones, in the order the fluid flows through them
+    pub fn new_from_components(
+        fluid_component_vector: Vec<Box<dyn TherminolFluidComponent>>)
+        -> Self {
 
-    pub fn get_pipe4(&self) -> TherminolPipe {
-        return self.pipe4.get();
-    }
-    pub fn get_pipe3(&self) -> TherminolPipe {
-        return self.pipe3.get();
-    }
-    pub fn get_mixer10(&self) -> TherminolCustomComponent {
-        return self.mixer10.get();
-    }
-    pub fn get_pipe2a(&self) -> TherminolPipe {
-        return self.pipe2a.get();
-    }
-    pub fn get_heater_top_head_1a(&self) -> TherminolCustomComponent {
-        return self.heater_top_head_1a.get();
+        return Self {
+            series_branch: TherminolSeriesBranch::new_from_components(
+                "heater_branch", fluid_component_vector),
+        };
     }
-    pub fn get_ciet_heater(&self) -> TherminolCustomComponent {
-        return self.ciet_heater.get();
+
+    /// returns references to the components owned by this branch
+    /// in the order the fluid flows through them
+    pub fn get_fluid_component_vector(&self)
+        -> Vec<&dyn FluidComponent> {
+        return self.series_branch.get_fluid_component_vector();
     }
-    pub fn get_heater_bottom_head_1b(&self) -> TherminolCustomComponent {
-        return self.heater_bottom_head_1b.get();
+
+    /// sets the fluid temperature of every component in the branch
+    pub fn set_fluid_temperature(&mut self, fluid_temp: ThermodynamicTemperature){
+        self.series_branch.set_fluid_temperature(fluid_temp);
     }
-    pub fn get_pipe18(&self) -> TherminolPipe {
-        return self.pipe18.get();
+
+    /// returns the fluid temperature of the first component in
+    /// the branch, or None if the branch has no components
+    pub fn get_fluid_temperature(&self) -> Option<ThermodynamicTemperature> {
+        return self.series_branch.get_fluid_temperature();
     }
 
+    /// sets the brent settings used to find the mass flowrate
+    /// through the branch at a given pressure change
+    pub fn set_solver_settings(&mut self, solver_settings: SeriesBranchSolverSettings){
+        self.series_branch.set_solver_settings(solver_settings);
+    }
 
+    pub fn get_solver_settings(&self) -> SeriesBranchSolverSettings {
+        return self.series_branch.get_solver_settings();
+    }
 
+    /// calculates mass flowrate from pressure change,
+    /// and returns an error if brent cannot find it
+    pub fn try_get_mass_flowrate_from_pressure_change(
+        &self,
+        pressure_change: Pressure) -> Result<MassRate, CIETSolverError> {
+        return self.series_branch
+            .try_get_mass_flowrate_from_pressure_change(pressure_change);
+    }
 }
 
-impl<'heater_branch_lifetime> FluidComponentCollectionMethods for HeaterBranch<'heater_branch_lifetime> {
+impl FluidComponentCollectionMethods for HeaterBranch {
 
     /// calculates pressure change when given a mass flowrate
     fn get_pressure_change(
-        &self, 
+        &self,
         fluid_mass_flowrate: MassRate) -> Pressure{
-        let fluid_component_collection_vector = 
-            self.get_immutable_fluid_component_vector();
-
-        let pressure_change = 
-            <Self as FluidComponentCollectionSeriesAssociatedFunctions>
-            ::calculate_pressure_change_from_mass_flowrate(
-                fluid_mass_flowrate, 
-                fluid_component_collection_vector);
-
-        return pressure_change;
+        return self.series_branch.get_pressure_change(fluid_mass_flowrate);
     }
 
-    /// calculates mass flowrate from pressure change
-
+    /// calculates mass flowrate from pressure change,
+    /// taking into account any check valves in the branch
     fn get_mass_flowrate_from_pressure_change(
         &self,
         pressure_change: Pressure) -> MassRate{
-
-
-
-
-        let fluid_component_collection_vector = 
-            self.get_immutable_fluid_component_vector();
-
-
-        // i'm keeping bounds artificially low for ciet
-        // -1 or +1 kg/s
-        let upper_bound = MassRate::new::<kilogram_per_second>(1.0);
-
-
-        let lower_bound = MassRate::new::<kilogram_per_second>(-1.0);
-
-
-        // now we have a function comparing the pressure change
-        // to the pressure change of the calculated value
-
-        let mass_flow_from_pressure_chg_root = 
-            |mass_flow_kg_per_s: f64| -> f64 {
-
-            let mass_flow_kg_per_s_double = mass_flow_kg_per_s; 
-
-            let mass_rate = 
-                MassRate::new::<kilogram_per_second>(
-                    mass_flow_kg_per_s_double);
-
-
-            let pressure_change_tested = 
-                Self::calculate_pressure_change_from_mass_flowrate(
-                mass_rate, 
-                fluid_component_collection_vector);
-
-            // now i've obtained the pressure change, i convert it to f64
-
-            let pressure_change_user_stipulated_pascals_f64 = 
-                pressure_change.value;
-
-            // since we are finding root, then we must also
-            // subtract it from our pressure change value
-
-
-            let pressure_change_error: f64 =
-                pressure_change_user_stipulated_pascals_f64 - 
-                pressure_change_tested.value;
-
-            return pressure_change_error;
-
-        };
-
-        let mut convergency = SimpleConvergency { eps:1e-9_f64, max_iter:30 };
-
-        let mass_flowrate_result 
-            = find_root_brent(
-                upper_bound.value,
-                lower_bound.value,
-                &mass_flow_from_pressure_chg_root,
-                &mut convergency);
-
-        return MassRate::new::<kilogram_per_second>(mass_flowrate_result.unwrap());
+        return self.series_branch
+            .get_mass_flowrate_from_pressure_change(pressure_change);
     }
-
-
 }
 
-impl<'heater_branch_lifetime> FluidComponentCollection<'heater_branch_lifetime> 
-for HeaterBranch<'heater_branch_lifetime> {
-
-            fn get_immutable_fluid_component_vector(&self)
-                -> &Vec<&'heater_branch_lifetime dyn FluidComponent> {
-
-                    return &self.fluid_component_vector_immutable;
-                }
+impl TherminolSeriesComponents for HeaterBranch {
 
-            fn set_fluid_component_vector(
-                &mut self, 
-                fluid_component_vector: 
-                Vec<&'heater_branch_lifetime dyn FluidComponent>){
-
-                self.fluid_component_vector_immutable = 
-                    fluid_component_vector;
+    fn get_therminol_components(&self) -> Vec<&dyn TherminolFluidComponent> {
+        return self.series_branch.get_therminol_components();
+    }
 
-            }
+    fn set_component_fluid_temperature(
+        &mut self,
+        component_index: usize,
+        fluid_temp: ThermodynamicTemperature){
+        self.series_branch.set_component_fluid_temperature(
+            component_index, fluid_temp);
+    }
 
+    fn set_component_valve_opening_percent(
+        &mut self,
+        component_index: usize,
+        opening_percent: f64){
+        self.series_branch.set_component_valve_opening_percent(
+            component_index, opening_percent);
+    }
 }
-
-impl<'heater_branch_lifetime> 
-FluidComponentCollectionSeriesAssociatedFunctions for HeaterBranch<'heater_branch_lifetime> {}
-
@@ -3,6 +3,7 @@ use fluid_mechanics_rust::prelude::*;
 
 use super::therminol_pipe::*;
 use super::therminol_component::*;
+use super::therminol_check_valve::*;
 
 
 /// contains a class for the ctah branch
@@ -119,7 +120,7 @@ impl StaticMixer41 {
 
     /// returns an instance of MX-41
     /// or component no.6
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "static_mixer_41_label_6";
 
@@ -214,7 +215,7 @@ impl CTAHVertical {
 
     }
 
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "ctah_vertical_label_7a";
 
@@ -304,7 +305,7 @@ impl CTAHHorizontal {
     /// returns an instance of the
     /// horizontal portion of CTAH
 
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "ctah_horizontal_label_7b";
 
@@ -429,7 +430,7 @@ impl StaticMixer40 {
     }
 
     /// returns an instance of MX-40
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "static_mixer_40_label_8";
 
@@ -676,7 +677,10 @@ impl CTAHPump {
 
     /// returns an instance of the pump with an internal
     /// pressure term set by the user in the get method
-    pub fn get(&self) -> TherminolCustomComponent {
+    ///
+    /// the custom k and darcy functions are static, so the 
+    /// pump object does not borrow from the CTAHPump factory
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "ctah_pump";
 
@@ -856,7 +860,7 @@ impl Flowmeter40 {
     }
 
     /// returns an instance of FM-40 (14a)
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "flowmeter_40_14a";
 
@@ -1282,7 +1286,7 @@ impl StaticMixer10 {
     }
 
     /// returns an instance of MX-10
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "static_mixer_10_label_2";
 
@@ -1430,7 +1434,7 @@ impl HeaterTopHead1a {
     }
 
     /// returns an instance of heater top head 1a
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "heater_top_head_label_1a";
 
@@ -1525,7 +1529,7 @@ impl CietHeaterVersion1 {
     }
 
     /// returns an instance of CIET heater version 1
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "heater_version_1_label_1";
 
@@ -1618,7 +1622,7 @@ impl HeaterBottomHead1b {
     }
 
     /// returns an instance of heater bottom head 1b
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "heater_bottom_head_label_1b";
 
@@ -1814,7 +1818,7 @@ impl StaticMixer21 {
     /// returns an instance of MX-21
     ///
     /// It is labelled 25 on diagram
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "static_mixer_21_label_25";
 
@@ -1960,7 +1964,7 @@ impl DHXShellSideHeatExchanger {
 
     /// returns an instance of dhx shell side
     /// heat exchanger 24
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "dhx_shell_side_label_24";
 
@@ -2053,7 +2057,7 @@ impl StaticMixer20 {
 
     /// returns an instance of MX-20
     /// label 23
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "static_mixer_20_label_23";
 
@@ -2239,7 +2243,7 @@ impl Flowmeter20 {
 
     /// returns an isntance of 
     /// FM-20 (label 21a)
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "flowmeter_20_label_21a";
 
@@ -2355,7 +2359,7 @@ impl Flowmeter20WithHighKCheckValve {
 
     /// returns an instance of FM-20
     /// with artificial check valve behaviour
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "flowmeter_20_label_21a_with_check_valve";
 
@@ -2393,6 +2397,50 @@ impl Flowmeter20WithHighKCheckValve {
     }
 }
 
+/// check valve within CIET DHX branch, next to FM-20
+///
+/// it only allows flow from top to bottom of the dhx branch
+/// (the forward direction of flow for FM20), and does not leak
+///
+/// its pressure loss when open is in the fitted FM-20 
+/// correlation already, so it has no form loss or
+/// cracking pressure of its own
+pub struct DHXBranchCheckValve {
+}
+
+impl DHXBranchCheckValve {
+
+    /// returns an instance of the dhx branch check valve
+    pub fn get(&self) -> TherminolCheckValve {
+
+        let name = "dhx_branch_check_valve";
+
+        let fluid_temp = ThermodynamicTemperature::new::<degree_celsius>(21.0);
+        let hydraulic_diameter = Length::new::<meter>(2.79e-2);
+        let component_length = Length::new::<meter>(0.05);
+        let incline_angle = Angle::new::<degree>(0.0);
+        let fully_open_k = 0.0;
+        let cracking_pressure = Pressure::new::<pascal>(0.0);
+
+        let dhx_branch_check_valve = TherminolCheckValve::new(
+            name,
+            fluid_temp,
+            incline_angle,
+            component_length,
+            hydraulic_diameter,
+            fully_open_k,
+            cracking_pressure);
+
+        return dhx_branch_check_valve;
+    }
+
+    pub fn new() -> Self {
+
+        return Self {  }
+
+    }
+}
+
 /// pipe 21 within CIET DHX loop
 pub struct Pipe21 {
     // pipe 21
@@ -2581,7 +2629,7 @@ impl Flowmeter30 {
     }
 
     /// returns an instance of FM-30
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "flowmeter_30";
 
@@ -2678,7 +2726,7 @@ impl Flowmeter60 {
     }
 
     /// returns an instance of FM-60 within DRACS loop
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "flowmeter_60_label_37a";
 
@@ -2770,7 +2818,7 @@ impl StaticMixer60 {
 
     /// returns an instance of MX-60
     /// static mixer 
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "static_mixer_60_label_36";
 
@@ -2860,7 +2908,7 @@ impl StaticMixer61 {
     }
 
     /// returns an instance of static mixer 61
-    pub fn get(&self) -> TherminolCustomComponent {
+    pub fn get(&self) -> TherminolCustomComponent<'static> {
 
         let name = "static_mixer_61_label_31";
 
@@ -0,0 +1,621 @@
+extern crate fluid_mechanics_rust;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::component_libraries::*;
+
+/// This is a custom form loss correlation K(Re) for a
+/// therminol custom component
+///
+/// the closures in component_libraries (eg. StaticMixer41::custom_k)
+/// cannot be serialised, compared or printed, this enum can,
+/// so custom components can live in config files and their
+/// correlations can be shown on the opcua server
+///
+/// all correlations follow the ciet reverse flow convention,
+/// K is calculated with the magnitude of Re and takes the sign
+/// of Re, so the pressure loss always opposes the flow
+///
+/// in toml, the existing ciet fits are just their names,
+/// and the other forms are tables, eg.
+///
+/// ```toml
+/// custom_k = "static_mixer_41"
+/// custom_k = { constant = { k = 3.9 } }
+/// custom_k = { power_law = { a = 21.0, b = 4000.0, c = -1.0 } }
+/// custom_k = { table = { reynolds_numbers = [100.0, 1000.0], k_values = [61.0, 25.0] } }
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum CustomKCorrelation {
+
+    /// K = k
+    #[serde(rename = "constant")]
+    Constant { k: f64 },
+
+    /// K = a + b Re^c
+    #[serde(rename = "power_law")]
+    PowerLaw { a: f64, b: f64, c: f64 },
+
+    /// K is linearly interpolated between points in Re,
+    /// and held constant beyond the first and last point
+    ///
+    /// reynolds_numbers must be in ascending order
+    #[serde(rename = "table")]
+    Table { reynolds_numbers: Vec<f64>, k_values: Vec<f64> },
+
+    // the existing ciet fits in component_libraries
+
+    #[serde(rename = "static_mixer_41")]
+    StaticMixer41,
+    #[serde(rename = "ctah_vertical")]
+    CTAHVertical,
+    #[serde(rename = "ctah_horizontal")]
+    CTAHHorizontal,
+    #[serde(rename = "static_mixer_40")]
+    StaticMixer40,
+    #[serde(rename = "ctah_pump")]
+    CTAHPump,
+    #[serde(rename = "flowmeter_40")]
+    Flowmeter40,
+    #[serde(rename = "static_mixer_10")]
+    StaticMixer10,
+    #[serde(rename = "heater_top_head_1a")]
+    HeaterTopHead1a,
+    #[serde(rename = "ciet_heater_version_1")]
+    CietHeaterVersion1,
+    #[serde(rename = "heater_bottom_head_1b")]
+    HeaterBottomHead1b,
+    #[serde(rename = "static_mixer_21")]
+    StaticMixer21,
+    #[serde(rename = "dhx_shell_side_heat_exchanger")]
+    DHXShellSideHeatExchanger,
+    #[serde(rename = "static_mixer_20")]
+    StaticMixer20,
+    #[serde(rename = "flowmeter_20")]
+    Flowmeter20,
+    #[serde(rename = "flowmeter_20_with_high_k_check_valve")]
+    Flowmeter20WithHighKCheckValve,
+    #[serde(rename = "flowmeter_30")]
+    Flowmeter30,
+    #[serde(rename = "flowmeter_60")]
+    Flowmeter60,
+    #[serde(rename = "static_mixer_60")]
+    StaticMixer60,
+    #[serde(rename = "static_mixer_61")]
+    StaticMixer61,
+}
+
+/// This is a custom darcy friction factor correlation f(Re, e/D)
+/// for a therminol custom component
+///
+/// as with CustomKCorrelation, f is calculated with the magnitude
+/// of Re and takes the sign of Re
+///
+/// ```toml
+/// custom_darcy = "ctah_vertical"
+/// custom_darcy = { constant = { darcy = 0.0 } }
+/// custom_darcy = { churchill = { multiplier = 1.2 } }
+/// custom_darcy = { power_law = { a = 0.0, b = 64.0, c = -1.0 } }
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum CustomDarcyCorrelation {
+
+    /// f = darcy
+    #[serde(rename = "constant")]
+    Constant { darcy: f64 },
+
+    /// f = multiplier * churchill friction factor
+    #[serde(rename = "churchill")]
+    Churchill { multiplier: f64 },
+
+    /// f = a + b Re^c
+    #[serde(rename = "power_law")]
+    PowerLaw { a: f64, b: f64, c: f64 },
+
+    /// f is linearly interpolated between points in Re,
+    /// and held constant beyond the first and last point
+    ///
+    /// reynolds_numbers must be in ascending order
+    #[serde(rename = "table")]
+    Table { reynolds_numbers: Vec<f64>, darcy_values: Vec<f64> },
+
+    // the existing ciet fits in component_libraries
+
+    #[serde(rename = "static_mixer_41")]
+    StaticMixer41,
+    #[serde(rename = "ctah_vertical")]
+    CTAHVertical,
+    #[serde(rename = "ctah_horizontal")]
+    CTAHHorizontal,
+    #[serde(rename = "static_mixer_40")]
+    StaticMixer40,
+    #[serde(rename = "ctah_pump")]
+    CTAHPump,
+    #[serde(rename = "flowmeter_40")]
+    Flowmeter40,
+    #[serde(rename = "static_mixer_10")]
+    StaticMixer10,
+    #[serde(rename = "heater_top_head_1a")]
+    HeaterTopHead1a,
+    #[serde(rename = "ciet_heater_version_1")]
+    CietHeaterVersion1,
+    #[serde(rename = "heater_bottom_head_1b")]
+    HeaterBottomHead1b,
+    #[serde(rename = "static_mixer_21")]
+    StaticMixer21,
+    #[serde(rename = "dhx_shell_side_heat_exchanger")]
+    DHXShellSideHeatExchanger,
+    #[serde(rename = "static_mixer_20")]
+    StaticMixer20,
+    #[serde(rename = "flowmeter_20")]
+    Flowmeter20,
+    #[serde(rename = "flowmeter_20_with_high_k_check_valve")]
+    Flowmeter20WithHighKCheckValve,
+    #[serde(rename = "flowmeter_30")]
+    Flowmeter30,
+    #[serde(rename = "flowmeter_60")]
+    Flowmeter60,
+    #[serde(rename = "static_mixer_60")]
+    StaticMixer60,
+    #[serde(rename = "static_mixer_61")]
+    StaticMixer61,
+}
+
+/// This is what a custom component does when its correlations
+/// are used outside the reynolds numbers they were fitted over
+///
+/// clamp: the correlations are evaluated at the nearest end of
+/// the fitted range (with the sign of Re), the velocity in the
+/// pressure loss is still the actual one
+///
+/// extrapolate: the correlations are used as they are, and the
+/// component shows up in the flow diagnostic warnings
+///
+/// error: the calculation fails if it converges outside the
+/// fitted range, and the facility keeps its last converged state
+///
+/// the solvers try flowrates well outside the fitted range on
+/// their way to the answer, so only clamping changes the
+/// correlations themselves, the error policy is applied to the
+/// converged flowrates
+///
+/// in toml, eg. out_of_range_policy = "clamp"
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum OutOfRangePolicy {
+
+    #[serde(rename = "clamp")]
+    Clamp,
+
+    #[default]
+    #[serde(rename = "extrapolate")]
+    Extrapolate,
+
+    #[serde(rename = "error")]
+    Error,
+}
+
+impl OutOfRangePolicy {
+
+    /// returns the reynolds number the correlations should be 
+    /// evaluated at, this is only different from the actual
+    /// reynolds number if clamping outside a known range
+    pub fn get_correlation_reynolds_number(
+        &self,
+        reynolds_number: f64,
+        fitted_reynolds_number_range: Option<(f64, f64)>) -> f64 {
+
+        // zero flow has no pressure loss whatever the correlation
+        if reynolds_number == 0.0 {
+            return reynolds_number;
+        }
+
+        match (self, fitted_reynolds_number_range) {
+            (OutOfRangePolicy::Clamp, 
+             Some((lowest_reynolds_number, highest_reynolds_number))) => {
+
+                let clamped_reynolds_number_magnitude = reynolds_number.abs()
+                    .clamp(lowest_reynolds_number, highest_reynolds_number);
+
+                return with_sign_of_reynolds_number(
+                    clamped_reynolds_number_magnitude, reynolds_number);
+            },
+            _ => return reynolds_number,
+        }
+    }
+}
+
+impl fmt::Display for OutOfRangePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutOfRangePolicy::Clamp => write!(f, "clamp"),
+            OutOfRangePolicy::Extrapolate => write!(f, "extrapolate"),
+            OutOfRangePolicy::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// gives the value the sign of the reynolds number,
+/// this is the ciet reverse flow convention
+///
+/// the custom component pressure loss goes as K m^2 (not K m|m|),
+/// so K and the darcy friction factor must be negative in reverse
+/// flow for the loss to oppose the flow
+pub fn with_sign_of_reynolds_number(value: f64, reynolds_number: f64) -> f64 {
+    if reynolds_number < 0.0 {
+        return -value;
+    }
+    return value;
+}
+
+/// linearly interpolates y against x, holding y constant
+/// beyond the first and last point
+///
+/// returns NaN if the table is invalid, so that the solvers
+/// report an error rather than using a made up value
+fn interpolate_table(x_values: &[f64], y_values: &[f64], x: f64) -> f64 {
+
+    if validate_table(x_values, y_values).is_err() {
+        return f64::NAN;
+    }
+
+    let last_index = x_values.len() - 1;
+
+    if x <= x_values[0] {
+        return y_values[0];
+    }
+    if x >= x_values[last_index] {
+        return y_values[last_index];
+    }
+
+    let upper_index = x_values.partition_point(|x_value| *x_value < x);
+    let lower_index = upper_index - 1;
+
+    let interpolation_fraction = (x - x_values[lower_index])
+        / (x_values[upper_index] - x_values[lower_index]);
+
+    return y_values[lower_index] + interpolation_fraction *
+        (y_values[upper_index] - y_values[lower_index]);
+}
+
+fn validate_table(x_values: &[f64], y_values: &[f64]) -> Result<(), String> {
+
+    if x_values.is_empty() {
+        return Err("table has no points".to_string());
+    }
+
+    if x_values.len() != y_values.len() {
+        return Err(format!("table has {} reynolds numbers but {} values",
+                           x_values.len(), y_values.len()));
+    }
+
+    if x_values.iter().chain(y_values.iter()).any(|value| !value.is_finite()) {
+        return Err("table has values which are not finite".to_string());
+    }
+
+    if x_values.windows(2).any(|pair| pair[1] <= pair[0]) {
+        return Err("table reynolds numbers are not in ascending order"
+                   .to_string());
+    }
+
+    return Ok(());
+}
+
+/// returns the first and last reynolds number of a table,
+/// or None if the table is invalid
+fn get_table_reynolds_number_range(
+    x_values: &[f64], y_values: &[f64]) -> Option<(f64, f64)> {
+
+    if validate_table(x_values, y_values).is_err() {
+        return None;
+    }
+
+    return Some((x_values[0], x_values[x_values.len() - 1]));
+}
+
+fn validate_coefficients(coefficients: &[f64]) -> Result<(), String> {
+    if coefficients.iter().any(|coefficient| !coefficient.is_finite()) {
+        return Err("correlation has coefficients which are not finite"
+                   .to_string());
+    }
+    return Ok(());
+}
+
+impl CustomKCorrelation {
+
+    /// returns the name used in config files, eg. "static_mixer_41"
+    /// or "power_law"
+    pub fn get_name(&self) -> String {
+        return get_serde_name(self);
+    }
+
+    /// returns K at this reynolds number
+    pub fn custom_k(&self, reynolds_number: f64) -> f64 {
+
+        let reynolds_number_magnitude = reynolds_number.abs();
+
+        let custom_k_value = match self {
+            CustomKCorrelation::Constant { k } => *k,
+            CustomKCorrelation::PowerLaw { a, b, c } =>
+                a + b * reynolds_number_magnitude.powf(*c),
+            CustomKCorrelation::Table { reynolds_numbers, k_values } =>
+                interpolate_table(reynolds_numbers, k_values,
+                                  reynolds_number_magnitude),
+
+            // the ciet fits already handle reverse flow themselves
+            CustomKCorrelation::StaticMixer41 =>
+                return StaticMixer41::custom_k(reynolds_number),
+            CustomKCorrelation::CTAHVertical =>
+                return CTAHVertical::custom_k(reynolds_number),
+            CustomKCorrelation::CTAHHorizontal =>
+                return CTAHHorizontal::custom_k(reynolds_number),
+            CustomKCorrelation::StaticMixer40 =>
+                return StaticMixer40::custom_k(reynolds_number),
+            CustomKCorrelation::CTAHPump =>
+                return CTAHPump::custom_k(reynolds_number),
+            CustomKCorrelation::Flowmeter40 =>
+                return Flowmeter40::custom_k(reynolds_number),
+            CustomKCorrelation::StaticMixer10 =>
+                return StaticMixer10::custom_k(reynolds_number),
+            CustomKCorrelation::HeaterTopHead1a =>
+                return HeaterTopHead1a::custom_k(reynolds_number),
+            CustomKCorrelation::CietHeaterVersion1 =>
+                return CietHeaterVersion1::custom_k(reynolds_number),
+            CustomKCorrelation::HeaterBottomHead1b =>
+                return HeaterBottomHead1b::custom_k(reynolds_number),
+            CustomKCorrelation::StaticMixer21 =>
+                return StaticMixer21::custom_k(reynolds_number),
+            CustomKCorrelation::DHXShellSideHeatExchanger =>
+                return DHXShellSideHeatExchanger::custom_k(reynolds_number),
+            CustomKCorrelation::StaticMixer20 =>
+                return StaticMixer20::custom_k(reynolds_number),
+            CustomKCorrelation::Flowmeter20 =>
+                return Flowmeter20::custom_k(reynolds_number),
+            CustomKCorrelation::Flowmeter20WithHighKCheckValve =>
+                return Flowmeter20WithHighKCheckValve::custom_k(reynolds_number),
+            CustomKCorrelation::Flowmeter30 =>
+                return Flowmeter30::custom_k(reynolds_number),
+            CustomKCorrelation::Flowmeter60 =>
+                return Flowmeter60::custom_k(reynolds_number),
+            CustomKCorrelation::StaticMixer60 =>
+                return StaticMixer60::custom_k(reynolds_number),
+            CustomKCorrelation::StaticMixer61 =>
+                return StaticMixer61::custom_k(reynolds_number),
+        };
+
+        return with_sign_of_reynolds_number(custom_k_value, reynolds_number);
+    }
+
+    /// returns the lowest and highest reynolds number (magnitude)
+    /// the correlation was fitted to, if this is known
+    ///
+    /// only tables know this (their first and last point), for
+    /// the other correlations it is set on the custom component
+    pub fn get_fitted_reynolds_number_range(&self) -> Option<(f64, f64)> {
+        match self {
+            CustomKCorrelation::Table { reynolds_numbers, k_values } =>
+                return get_table_reynolds_number_range(
+                    reynolds_numbers, k_values),
+            _ => return None,
+        }
+    }
+
+    /// checks the correlation parameters, eg. that a table has
+    /// as many K values as reynolds numbers
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            CustomKCorrelation::Constant { k } =>
+                return validate_coefficients(&[*k]),
+            CustomKCorrelation::PowerLaw { a, b, c } =>
+                return validate_coefficients(&[*a, *b, *c]),
+            CustomKCorrelation::Table { reynolds_numbers, k_values } =>
+                return validate_table(reynolds_numbers, k_values),
+            _ => return Ok(()),
+        }
+    }
+}
+
+impl CustomDarcyCorrelation {
+
+    /// returns the name used in config files, eg. "static_mixer_41"
+    /// or "churchill"
+    pub fn get_name(&self) -> String {
+        return get_serde_name(self);
+    }
+
+    /// returns the darcy friction factor at this reynolds number
+    /// and roughness ratio
+    pub fn custom_darcy(&self, reynolds_number: f64, roughness_ratio: f64) -> f64 {
+
+        use fluid_mechanics_rust::churchill_friction_factor;
+
+        let reynolds_number_magnitude = reynolds_number.abs();
+
+        let darcy = match self {
+            CustomDarcyCorrelation::Constant { darcy } => *darcy,
+            CustomDarcyCorrelation::Churchill { multiplier } => {
+
+                // a negative roughness is caught when the component
+                // is built from the catalogue, but in case it gets
+                // here, NaN lets the solver report it rather than 
+                // panicking in the solver thread
+                if roughness_ratio.is_nan() || roughness_ratio < 0.0 {
+                    return f64::NAN;
+                }
+
+                multiplier * churchill_friction_factor::darcy(
+                    reynolds_number_magnitude, roughness_ratio)
+            },
+            CustomDarcyCorrelation::PowerLaw { a, b, c } =>
+                a + b * reynolds_number_magnitude.powf(*c),
+            CustomDarcyCorrelation::Table { reynolds_numbers, darcy_values } =>
+                interpolate_table(reynolds_numbers, darcy_values,
+                                  reynolds_number_magnitude),
+
+            // the ciet fits already handle reverse flow themselves
+            CustomDarcyCorrelation::StaticMixer41 =>
+                return StaticMixer41::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::CTAHVertical =>
+                return CTAHVertical::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::CTAHHorizontal =>
+                return CTAHHorizontal::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::StaticMixer40 =>
+                return StaticMixer40::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::CTAHPump =>
+                return CTAHPump::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::Flowmeter40 =>
+                return Flowmeter40::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::StaticMixer10 =>
+                return StaticMixer10::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::HeaterTopHead1a =>
+                return HeaterTopHead1a::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::CietHeaterVersion1 =>
+                return CietHeaterVersion1::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::HeaterBottomHead1b =>
+                return HeaterBottomHead1b::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::StaticMixer21 =>
+                return StaticMixer21::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::DHXShellSideHeatExchanger =>
+                return DHXShellSideHeatExchanger::custom_darcy(
+                    reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::StaticMixer20 =>
+                return StaticMixer20::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::Flowmeter20 =>
+                return Flowmeter20::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::Flowmeter20WithHighKCheckValve =>
+                return Flowmeter20WithHighKCheckValve::custom_darcy(
+                    reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::Flowmeter30 =>
+                return Flowmeter30::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::Flowmeter60 =>
+                return Flowmeter60::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::StaticMixer60 =>
+                return StaticMixer60::custom_darcy(reynolds_number, roughness_ratio),
+            CustomDarcyCorrelation::StaticMixer61 =>
+                return StaticMixer61::custom_darcy(reynolds_number, roughness_ratio),
+        };
+
+        return with_sign_of_reynolds_number(darcy, reynolds_number);
+    }
+
+    /// returns the lowest and highest reynolds number (magnitude)
+    /// the correlation was fitted to, if this is known
+    ///
+    /// as with CustomKCorrelation, only tables know this
+    pub fn get_fitted_reynolds_number_range(&self) -> Option<(f64, f64)> {
+        match self {
+            CustomDarcyCorrelation::Table { reynolds_numbers, darcy_values } =>
+                return get_table_reynolds_number_range(
+                    reynolds_numbers, darcy_values),
+            _ => return None,
+        }
+    }
+
+    /// checks the correlation parameters, eg. that a table has
+    /// as many darcy values as reynolds numbers
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            CustomDarcyCorrelation::Constant { darcy } =>
+                return validate_coefficients(&[*darcy]),
+            CustomDarcyCorrelation::Churchill { multiplier } =>
+                return validate_coefficients(&[*multiplier]),
+            CustomDarcyCorrelation::PowerLaw { a, b, c } =>
+                return validate_coefficients(&[*a, *b, *c]),
+            CustomDarcyCorrelation::Table { reynolds_numbers, darcy_values } =>
+                return validate_table(reynolds_numbers, darcy_values),
+            _ => return Ok(()),
+        }
+    }
+
+    /// checks the roughness ratio (roughness over hydraulic diameter)
+    /// of the component using this correlation, the churchill
+    /// correlation needs it to be zero or more
+    pub fn validate_roughness_ratio(&self, roughness_ratio: f64) 
+        -> Result<(), String> {
+        match self {
+            CustomDarcyCorrelation::Churchill { .. } 
+            if !(roughness_ratio >= 0.0 && roughness_ratio.is_finite()) =>
+                return Err(format!(
+                        "churchill needs a roughness ratio of zero or more, \
+                        not {}", roughness_ratio)),
+            _ => return Ok(()),
+        }
+    }
+}
+
+/// prints the correlation as a formula (eg. for the opcua server),
+/// ciet fits are printed with their name as well
+impl fmt::Display for CustomKCorrelation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CustomKCorrelation::Constant { k } =>
+                write!(f, "K = {}", k),
+            CustomKCorrelation::PowerLaw { a, b, c } =>
+                write!(f, "K = {} + {} Re^{}", a, b, c),
+            CustomKCorrelation::Table { reynolds_numbers, .. } =>
+                write!(f, "K from a table of {} points", reynolds_numbers.len()),
+            CustomKCorrelation::StaticMixer41
+                | CustomKCorrelation::StaticMixer40
+                | CustomKCorrelation::StaticMixer10
+                | CustomKCorrelation::StaticMixer21
+                | CustomKCorrelation::StaticMixer20
+                | CustomKCorrelation::StaticMixer60
+                | CustomKCorrelation::StaticMixer61 =>
+                write!(f, "{}: K = 21 + 4000/Re", self.get_name()),
+            CustomKCorrelation::CTAHVertical =>
+                write!(f, "{}: K = 3.9", self.get_name()),
+            CustomKCorrelation::CTAHHorizontal =>
+                write!(f, "{}: K = 400 + 52000/Re", self.get_name()),
+            CustomKCorrelation::CTAHPump
+                | CustomKCorrelation::CietHeaterVersion1 =>
+                write!(f, "{}: K = 0", self.get_name()),
+            CustomKCorrelation::Flowmeter40
+                | CustomKCorrelation::Flowmeter20
+                | CustomKCorrelation::Flowmeter30
+                | CustomKCorrelation::Flowmeter60 =>
+                write!(f, "{}: K = 18 + 93000/Re^1.35", self.get_name()),
+            CustomKCorrelation::HeaterTopHead1a =>
+                write!(f, "{}: K = 3.75", self.get_name()),
+            CustomKCorrelation::HeaterBottomHead1b =>
+                write!(f, "{}: K = 3.95", self.get_name()),
+            CustomKCorrelation::DHXShellSideHeatExchanger =>
+                write!(f, "{}: K = 23.9", self.get_name()),
+            CustomKCorrelation::Flowmeter20WithHighKCheckValve =>
+                write!(f, "{}: K = 18 + 93000/Re^1.35, \
+                       1e10 + 1e10/Re in reverse flow", self.get_name()),
+        }
+    }
+}
+
+impl fmt::Display for CustomDarcyCorrelation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CustomDarcyCorrelation::Constant { darcy } =>
+                write!(f, "f = {}", darcy),
+            CustomDarcyCorrelation::Churchill { multiplier } =>
+                write!(f, "f = {} churchill", multiplier),
+            CustomDarcyCorrelation::PowerLaw { a, b, c } =>
+                write!(f, "f = {} + {} Re^{}", a, b, c),
+            CustomDarcyCorrelation::Table { reynolds_numbers, .. } =>
+                write!(f, "f from a table of {} points", reynolds_numbers.len()),
+            CustomDarcyCorrelation::CTAHVertical
+                | CustomDarcyCorrelation::HeaterTopHead1a
+                | CustomDarcyCorrelation::CietHeaterVersion1
+                | CustomDarcyCorrelation::HeaterBottomHead1b
+                | CustomDarcyCorrelation::DHXShellSideHeatExchanger =>
+                write!(f, "{}: f = churchill", self.get_name()),
+            _ => write!(f, "{}: f = 0", self.get_name()),
+        }
+    }
+}
+
+/// the serde name is the string for unit variants, or the only
+/// key of the map for the other variants
+fn get_serde_name<T: Serialize>(correlation: &T) -> String {
+    match serde_json::to_value(correlation) {
+        Ok(serde_json::Value::String(name)) => return name,
+        Ok(serde_json::Value::Object(map)) =>
+            return map.keys().next().cloned().unwrap_or_default(),
+        _ => return String::new(),
+    }
+}
@@ -0,0 +1,373 @@
+extern crate fluid_mechanics_rust;
+use crate::{TherminolSeriesBranch, ParallelBranchSolver, CIETSolverError,
+    ComponentCatalogue, ComponentCatalogueError, therminol_temperature_in_range,
+    ComponentThermalNodes, ComponentHeatTransfer, build_branch_thermal_nodes,
+    update_branch_fluid_temperatures, solve_branch_energy_equation,
+    get_junction_mixed_temperature};
+
+use fluid_mechanics_rust::prelude::*;
+
+/// components of the DRACS dhx branch, from the top of DRACS
+/// (the tchx inlet) down to the bottom, through the dhx tube side
+///
+/// when the dhx heats this branch, the fluid rises through it,
+/// so its flow is negative
+pub const DRACS_DHX_BRANCH_COMPONENT_NAMES: [&str; 8] = [
+    "pipe_34",
+    "pipe_33",
+    "pipe_32",
+    "pipe_31a",
+    "static_mixer_61_label_31",
+    "pipe_30b",
+    "dhx_tube_side_label_30",
+    "pipe_30a",
+];
+
+/// components of the DRACS tchx branch, from the top of DRACS
+/// down through the tchx to the bottom
+///
+/// when the tchx cools this branch, the fluid sinks through it,
+/// so its flow is positive
+pub const DRACS_TCHX_BRANCH_COMPONENT_NAMES: [&str; 8] = [
+    "pipe_35a",
+    "tchx_label_35b",
+    "static_mixer_60_label_36",
+    "pipe_36a",
+    "pipe_37",
+    "flowmeter_60_label_37a",
+    "pipe_38",
+    "pipe_39",
+];
+
+/// name of the dhx tube side, it picks up heat from the
+/// dhx shell side in the primary loop
+pub const DRACS_DHX_TUBE_SIDE_COMPONENT_NAME: &str = "dhx_tube_side_label_30";
+
+/// name of the tchx (tube cooling heat exchanger), which loses
+/// heat to the air
+pub const DRACS_TCHX_COMPONENT_NAME: &str = "tchx_label_35b";
+
+/// This is a struct representing the DRACS loop of ciet
+/// (direct reactor auxiliary cooling system)
+///
+/// DRACS is a closed loop with no pump, the dhx tube side heats
+/// the fluid in one leg and the tchx cools it in the other,
+/// and the density difference between the legs drives natural
+/// circulation
+///
+/// it is split at its top and bottom into two branches in parallel,
+/// the dhx branch and the tchx branch, which are solved the same way
+/// as the primary loop in CIETNonIsothermalFacility, ie. quasi steady
+/// hydraulics with the parallel branch solver, then the 1D energy
+/// equation over the axial nodes of each component
+///
+/// the heat coming in through the dhx is given at each timestep,
+/// usually by CIETNonIsothermalFacility, which couples the dhx
+/// tube side to the dhx shell side in the primary loop
+///
+/// the geometry in the catalogue is approximate, both branches
+/// drop by the same height so that DRACS is a closed loop
+pub struct DRACSLoop {
+
+    pub dhx_branch_mass_flowrate: MassRate,
+    pub tchx_branch_mass_flowrate: MassRate,
+
+    /// overall heat transfer coefficient times area (UA)
+    /// of the tchx, split evenly between its nodes
+    pub tchx_thermal_conductance: ThermalConductance,
+
+    /// the air temperature on the other side of the tchx
+    pub tchx_air_temperature: ThermodynamicTemperature,
+
+    dhx_branch: TherminolSeriesBranch,
+    tchx_branch: TherminolSeriesBranch,
+
+    parallel_branch_solver: ParallelBranchSolver,
+
+    dhx_branch_thermal_nodes: Vec<ComponentThermalNodes>,
+    tchx_branch_thermal_nodes: Vec<ComponentThermalNodes>,
+
+    top_node_temperature: ThermodynamicTemperature,
+    bottom_node_temperature: ThermodynamicTemperature,
+
+    // heat removed by the tchx and picked up in the dhx tube
+    // side in the latest timestep
+    tchx_heat_removal_rate: Power,
+    dhx_heat_gain_rate: Power,
+
+    therminol_properties: TherminolVP1Properties,
+}
+
+/// for this object,
+///
+/// i want to be able to
+/// (1) build DRACS from the component catalogue
+/// (2) set the tchx cooling
+/// (3) advance DRACS by one timestep given the heat transfer
+/// into the dhx tube side
+/// (4) obtain the flowrates, node temperatures and heat transfer rates
+impl DRACSLoop {
+
+    pub fn get_dhx_branch_mass_flowrate(&self) -> MassRate {
+        return self.dhx_branch_mass_flowrate;
+    }
+
+    pub fn get_tchx_branch_mass_flowrate(&self) -> MassRate {
+        return self.tchx_branch_mass_flowrate;
+    }
+
+    /// sets the UA of the tchx and the air temperature
+    /// it loses heat to
+    pub fn set_tchx_cooling(
+        &mut self,
+        tchx_thermal_conductance: ThermalConductance,
+        tchx_air_temperature: ThermodynamicTemperature){
+        self.tchx_thermal_conductance = tchx_thermal_conductance;
+        self.tchx_air_temperature = tchx_air_temperature;
+    }
+
+    /// returns the heat removed by the tchx in the latest timestep
+    pub fn get_tchx_heat_removal_rate(&self) -> Power {
+        return self.tchx_heat_removal_rate;
+    }
+
+    /// returns the heat picked up by the dhx tube side
+    /// in the latest timestep
+    pub fn get_dhx_heat_gain_rate(&self) -> Power {
+        return self.dhx_heat_gain_rate;
+    }
+
+    pub fn get_top_node_temperature(&self) -> ThermodynamicTemperature {
+        return self.top_node_temperature;
+    }
+
+    pub fn get_bottom_node_temperature(&self) -> ThermodynamicTemperature {
+        return self.bottom_node_temperature;
+    }
+
+    pub fn get_dhx_branch_thermal_nodes(&self) -> &[ComponentThermalNodes] {
+        return &self.dhx_branch_thermal_nodes;
+    }
+
+    pub fn get_tchx_branch_thermal_nodes(&self) -> &[ComponentThermalNodes] {
+        return &self.tchx_branch_thermal_nodes;
+    }
+
+    /// returns the axial nodes of a component in either
+    /// branch, or None if there is no component with this name
+    pub fn get_component_thermal_nodes(&self, name: &str)
+        -> Option<&ComponentThermalNodes> {
+
+        return self.dhx_branch_thermal_nodes.iter()
+            .chain(self.tchx_branch_thermal_nodes.iter())
+            .find(|component_thermal_nodes| component_thermal_nodes.name == name);
+    }
+
+    /// returns the average fluid temperature of the dhx tube side,
+    /// or the bottom node temperature if the dhx branch has no
+    /// dhx tube side
+    pub fn get_dhx_tube_side_temperature(&self) -> ThermodynamicTemperature {
+
+        return match self.get_component_thermal_nodes(
+            DRACS_DHX_TUBE_SIDE_COMPONENT_NAME) {
+            Some(dhx_tube_side_nodes) => dhx_tube_side_nodes.get_average_temperature(),
+            None => self.bottom_node_temperature,
+        };
+    }
+
+    /// sets every node in DRACS (and its top and bottom)
+    /// to the same fluid temperature
+    pub fn set_uniform_fluid_temperature(
+        &mut self,
+        fluid_temperature: ThermodynamicTemperature)
+        -> Result<(), CIETSolverError> {
+
+        if !therminol_temperature_in_range(fluid_temperature) {
+            return Err(CIETSolverError::FluidTemperatureOutOfRange {
+                fluid_temperature });
+        }
+
+        for component_thermal_nodes in self.dhx_branch_thermal_nodes.iter_mut()
+            .chain(self.tchx_branch_thermal_nodes.iter_mut()) {
+            for node_temperature in component_thermal_nodes.node_temperatures.iter_mut() {
+                *node_temperature = fluid_temperature;
+            }
+        }
+
+        self.top_node_temperature = fluid_temperature;
+        self.bottom_node_temperature = fluid_temperature;
+
+        self.update_component_fluid_temperatures();
+
+        return Ok(());
+    }
+
+    /// returns the first node (or top or bottom) temperature
+    /// outside the therminol property range, if any
+    pub fn get_out_of_range_temperature(&self) -> Option<ThermodynamicTemperature> {
+
+        let mut temperatures = self.dhx_branch_thermal_nodes.iter()
+            .chain(self.tchx_branch_thermal_nodes.iter())
+            .flat_map(|nodes| nodes.node_temperatures.iter().copied())
+            .chain([self.top_node_temperature, self.bottom_node_temperature]);
+
+        return temperatures.find(|temperature|
+                                 !therminol_temperature_in_range(*temperature));
+    }
+
+    /// advances DRACS by one timestep
+    ///
+    /// dhx_tube_side_heat_transfer is the heat going into the dhx
+    /// tube side over this timestep, eg. a thermal conductance to the
+    /// dhx shell side temperature
+    ///
+    /// the flowrates are solved first with the current component
+    /// temperatures, then the energy equation is solved over every
+    /// node, same as CIETNonIsothermalFacility::advance_timestep
+    ///
+    /// returns the dhx and tchx branch flowrates in that order,
+    /// if the solver fails, the error is returned and DRACS
+    /// keeps its last state
+    pub fn advance_timestep(
+        &mut self,
+        timestep: Time,
+        dhx_tube_side_heat_transfer: ComponentHeatTransfer) ->
+        Result<(MassRate, MassRate), CIETSolverError> {
+
+        if let Some(fluid_temperature) = self.get_out_of_range_temperature() {
+            return Err(CIETSolverError::FluidTemperatureOutOfRange {
+                fluid_temperature });
+        }
+
+        // there is no pump in DRACS, so the flow is only driven
+        // by the difference in hydrostatic pressure between the branches
+        let branches: Vec<&dyn FluidComponentCollectionMethods> =
+            vec![&self.dhx_branch, &self.tchx_branch];
+
+        let (_pressure_change, branch_mass_flowrates) =
+            self.parallel_branch_solver.solve(&branches)?;
+
+        let dhx_branch_flowrate = branch_mass_flowrates[0];
+        let tchx_branch_flowrate = branch_mass_flowrates[1];
+
+        self.dhx_branch_mass_flowrate = dhx_branch_flowrate;
+        self.tchx_branch_mass_flowrate = tchx_branch_flowrate;
+
+        let component_heat_transfer = [
+            (DRACS_DHX_TUBE_SIDE_COMPONENT_NAME, dhx_tube_side_heat_transfer),
+            (DRACS_TCHX_COMPONENT_NAME, ComponentHeatTransfer::thermal_conductance(
+                    self.tchx_thermal_conductance, self.tchx_air_temperature)),
+        ];
+
+        let mut branch_outlets: Vec<(MassRate, ThermodynamicTemperature)> = vec![];
+        let mut branch_heat_loss_rates: Vec<Power> = vec![];
+
+        for (branch_thermal_nodes, branch_mass_flowrate) in [
+            (&mut self.dhx_branch_thermal_nodes, dhx_branch_flowrate),
+            (&mut self.tchx_branch_thermal_nodes, tchx_branch_flowrate)] {
+
+            // positive flow comes in from the top of DRACS,
+            // negative flow from the bottom
+            let inlet_temperature = match branch_mass_flowrate.value >= 0.0 {
+                true => self.top_node_temperature,
+                false => self.bottom_node_temperature,
+            };
+
+            let (outlet_temperature, branch_heat_loss_rate) =
+                solve_branch_energy_equation(
+                    branch_thermal_nodes,
+                    branch_mass_flowrate,
+                    inlet_temperature,
+                    timestep,
+                    &component_heat_transfer,
+                    &self.therminol_properties);
+
+            branch_outlets.push((branch_mass_flowrate, outlet_temperature));
+            branch_heat_loss_rates.push(branch_heat_loss_rate);
+        }
+
+        // heat lost from the dhx branch is heat not picked up
+        // by the dhx tube side
+        self.dhx_heat_gain_rate = -branch_heat_loss_rates[0];
+        self.tchx_heat_removal_rate = branch_heat_loss_rates[1];
+
+        self.top_node_temperature = get_junction_mixed_temperature(
+            &branch_outlets, false, self.top_node_temperature);
+        self.bottom_node_temperature = get_junction_mixed_temperature(
+            &branch_outlets, true, self.bottom_node_temperature);
+
+        self.update_component_fluid_temperatures();
+
+        return Ok((dhx_branch_flowrate, tchx_branch_flowrate));
+    }
+
+    fn update_component_fluid_temperatures(&mut self){
+
+        update_branch_fluid_temperatures(
+            &mut self.dhx_branch, &self.dhx_branch_thermal_nodes);
+        update_branch_fluid_temperatures(
+            &mut self.tchx_branch, &self.tchx_branch_thermal_nodes);
+    }
+
+    // constructors
+
+    /// takes ownership of the dhx and tchx branches (components
+    /// listed from the top of DRACS down) and splits every component
+    /// into nodes_per_component axial nodes (at least one)
+    ///
+    /// the tchx has no cooling until it is set
+    pub fn new(dhx_branch: TherminolSeriesBranch,
+               tchx_branch: TherminolSeriesBranch,
+               nodes_per_component: usize) -> Self {
+
+        let dhx_branch_thermal_nodes =
+            build_branch_thermal_nodes(&dhx_branch, nodes_per_component);
+        let tchx_branch_thermal_nodes =
+            build_branch_thermal_nodes(&tchx_branch, nodes_per_component);
+
+        let initial_temperature = tchx_branch.get_fluid_temperature()
+            .or(dhx_branch.get_fluid_temperature())
+            .unwrap_or(ThermodynamicTemperature::new::<degree_celsius>(21.0));
+
+        let mut dracs_loop = Self {
+            dhx_branch_mass_flowrate: MassRate::new::<kilogram_per_second>(0.0),
+            tchx_branch_mass_flowrate: MassRate::new::<kilogram_per_second>(0.0),
+            tchx_thermal_conductance: ThermalConductance::new::<watt_per_kelvin>(0.0),
+            tchx_air_temperature: initial_temperature,
+            dhx_branch,
+            tchx_branch,
+            parallel_branch_solver: ParallelBranchSolver::new(),
+            dhx_branch_thermal_nodes,
+            tchx_branch_thermal_nodes,
+            top_node_temperature: initial_temperature,
+            bottom_node_temperature: initial_temperature,
+            tchx_heat_removal_rate: Power::new::<watt>(0.0),
+            dhx_heat_gain_rate: Power::new::<watt>(0.0),
+            therminol_properties: TherminolVP1Properties::new(),
+        };
+
+        dracs_loop.update_component_fluid_temperatures();
+
+        return dracs_loop;
+    }
+
+    /// builds DRACS out of the DRACS components in the catalogue
+    pub fn from_catalogue(
+        catalogue: &ComponentCatalogue,
+        nodes_per_component: usize) -> Result<Self, ComponentCatalogueError> {
+
+        let dhx_branch = catalogue.build_series_branch(
+            "dracs_dhx_branch", &DRACS_DHX_BRANCH_COMPONENT_NAMES)?;
+        let tchx_branch = catalogue.build_series_branch(
+            "dracs_tchx_branch", &DRACS_TCHX_BRANCH_COMPONENT_NAMES)?;
+
+        return Ok(Self::new(dhx_branch, tchx_branch, nodes_per_component));
+    }
+
+    /// builds DRACS out of the catalogue shipped with this crate
+    pub fn ciet_default(nodes_per_component: usize) -> Self {
+        return Self::from_catalogue(
+            &ComponentCatalogue::ciet_default(), nodes_per_component)
+            .expect("the ciet component catalogue shipped with this crate has no DRACS");
+    }
+}
@@ -0,0 +1,707 @@
+extern crate fluid_mechanics_rust;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use fluid_mechanics_rust::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{ComponentCatalogue, ComponentCatalogueError,
+    CTAHBranch, HeaterBranch, DHXBranch, CIETIsothermalFacility,
+    CIETNonIsothermalFacility, HeatTransferComponentNames,
+    TherminolFluidComponent, BranchPressureTap, Manometer};
+
+/// the ciet primary loop topology which ships with this crate,
+/// it uses the components in the ciet component catalogue
+pub const CIET_FACILITY_TOPOLOGY_TOML: &str =
+    include_str!("ciet_facility_topology.toml");
+
+fn default_elevation_tolerance_meters() -> f64 {
+    return 1e-3;
+}
+
+/// what a branch does in the isothermal ciet facility,
+/// the facility needs exactly one branch of each
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BranchRole {
+    #[serde(rename = "heater")]
+    Heater,
+    #[serde(rename = "dhx")]
+    DHX,
+    #[serde(rename = "ctah")]
+    CTAH,
+}
+
+impl fmt::Display for BranchRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BranchRole::Heater => write!(f, "heater"),
+            BranchRole::DHX => write!(f, "dhx"),
+            BranchRole::CTAH => write!(f, "ctah"),
+        }
+    }
+}
+
+/// a branch in the topology file
+///
+/// components are catalogue names in the order the fluid
+/// flows through them (positive flow leaves the top of the branch),
+/// the ctah branch must also name its pump
+///
+/// heat_transfer_components are the components of this branch
+/// which exchange heat in the non-isothermal facility, that is
+/// the heater in the heater branch, the ctah in the ctah branch
+/// and the dhx shell side in the dhx branch
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BranchTopology {
+    pub name: String,
+    pub role: BranchRole,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pump: Option<String>,
+
+    pub components: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub heat_transfer_components: Vec<String>,
+}
+
+/// a pressure tap in the topology file
+///
+/// the tap is on one of the parallel branches, just after
+/// the component named (in the direction of positive flow),
+/// with no component named, it is at the top of the branch
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PressureTapTopology {
+    pub name: String,
+    pub branch: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_component: Option<String>,
+}
+
+/// These are the ways a facility topology can be invalid
+#[derive(Clone, Debug, PartialEq)]
+pub enum FacilityTopologyError {
+
+    /// the topology file could not be read
+    FileRead(String),
+
+    /// the topology is not valid toml or json, or is missing fields
+    Parse(String),
+
+    /// the file extension is not .toml or .json
+    UnknownFileFormat(String),
+
+    /// a component could not be built from the catalogue
+    Catalogue(ComponentCatalogueError),
+
+    /// a branch uses a component which is not in the catalogue
+    MissingComponent {
+        branch: String,
+        component: String,
+    },
+
+    /// two branches have the same name
+    DuplicateBranchName(String),
+
+    /// two catalogue entries have the same name,
+    /// so it is not clear which one a branch means
+    DuplicateComponentName(String),
+
+    /// the same component appears twice in the topology,
+    /// a physical component can only be in one place
+    ComponentUsedTwice(String),
+
+    /// parallel_branches names a branch which is not defined
+    UnknownBranch(String),
+
+    /// there is no parallel branch with this role
+    MissingBranchRole(BranchRole),
+
+    /// more than one parallel branch has this role
+    DuplicateBranchRole(BranchRole),
+
+    /// the ctah branch has no pump, the pump is not one of its
+    /// components, or a branch other than ctah has a pump
+    InvalidPump {
+        branch: String,
+        pump: Option<String>,
+    },
+
+    /// a ctah branch was given a pump index past the end
+    /// of its components
+    PumpIndexOutOfRange {
+        pump_index: usize,
+        component_count: usize,
+    },
+
+    /// a heat transfer component is not one of the
+    /// components of its branch
+    InvalidHeatTransferComponent {
+        branch: String,
+        component: String,
+    },
+
+    /// the branch with this role has no heat transfer components,
+    /// so the non-isothermal facility cannot be built
+    MissingHeatTransferComponents(BranchRole),
+
+    /// parallel branches start and end at the same two nodes,
+    /// so their elevation changes must match
+    InconsistentElevation {
+        branch: String,
+        elevation_change: Length,
+        reference_branch: String,
+        reference_elevation_change: Length,
+    },
+
+    /// a pressure tap is not on a parallel branch,
+    /// or the component it comes after is not in its branch
+    InvalidPressureTap {
+        pressure_tap: String,
+        branch: String,
+        after_component: Option<String>,
+    },
+
+    /// two pressure taps have the same name
+    DuplicatePressureTapName(String),
+
+    /// two manometers have the same name
+    DuplicateManometerName(String),
+
+    /// a manometer is connected to a pressure tap which
+    /// is not defined
+    UnknownPressureTap {
+        manometer: String,
+        pressure_tap: String,
+    },
+}
+
+impl fmt::Display for FacilityTopologyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FacilityTopologyError::FileRead(message) =>
+                write!(f, "could not read topology: {}", message),
+            FacilityTopologyError::Parse(message) =>
+                write!(f, "could not parse topology: {}", message),
+            FacilityTopologyError::UnknownFileFormat(path) =>
+                write!(f, "topology must be .toml or .json: {}", path),
+            FacilityTopologyError::Catalogue(catalogue_error) =>
+                write!(f, "{}", catalogue_error),
+            FacilityTopologyError::MissingComponent { branch, component } =>
+                write!(f, "branch {} uses {}, which is not in the catalogue",
+                       branch, component),
+            FacilityTopologyError::DuplicateBranchName(name) =>
+                write!(f, "more than one branch is named {}", name),
+            FacilityTopologyError::DuplicateComponentName(name) =>
+                write!(f, "more than one catalogue component is named {}", name),
+            FacilityTopologyError::ComponentUsedTwice(name) =>
+                write!(f, "component {} is used more than once", name),
+            FacilityTopologyError::UnknownBranch(name) =>
+                write!(f, "no branch named {}", name),
+            FacilityTopologyError::MissingBranchRole(role) =>
+                write!(f, "no parallel branch has the {} role", role),
+            FacilityTopologyError::DuplicateBranchRole(role) =>
+                write!(f, "more than one parallel branch has the {} role", role),
+            FacilityTopologyError::InvalidPump { branch, pump } =>
+                write!(f, "branch {} has an invalid pump: {:?}", branch, pump),
+            FacilityTopologyError::PumpIndexOutOfRange { pump_index, component_count } =>
+                write!(f, "ctah pump index {} is outside the ctah branch, \
+                       which has {} components", pump_index, component_count),
+            FacilityTopologyError::InvalidHeatTransferComponent { branch, component } =>
+                write!(f, "branch {} exchanges heat through {}, \
+                       which is not one of its components", branch, component),
+            FacilityTopologyError::MissingHeatTransferComponents(role) =>
+                write!(f, "the {} branch has no heat transfer components", role),
+            FacilityTopologyError::InconsistentElevation {
+                branch, elevation_change,
+                reference_branch, reference_elevation_change } =>
+                write!(f, "branch {} rises {} m but branch {} rises {} m",
+                       branch, elevation_change.value,
+                       reference_branch, reference_elevation_change.value),
+            FacilityTopologyError::InvalidPressureTap {
+                pressure_tap, branch, after_component } =>
+                write!(f, "pressure tap {} cannot go on branch {} after {:?}",
+                       pressure_tap, branch, after_component),
+            FacilityTopologyError::DuplicatePressureTapName(name) =>
+                write!(f, "more than one pressure tap is named {}", name),
+            FacilityTopologyError::DuplicateManometerName(name) =>
+                write!(f, "more than one manometer is named {}", name),
+            FacilityTopologyError::UnknownPressureTap { manometer, pressure_tap } =>
+                write!(f, "manometer {} uses pressure tap {}, which is not defined",
+                       manometer, pressure_tap),
+        }
+    }
+}
+
+impl std::error::Error for FacilityTopologyError {}
+
+impl From<ComponentCatalogueError> for FacilityTopologyError {
+    fn from(catalogue_error: ComponentCatalogueError) -> Self {
+        return FacilityTopologyError::Catalogue(catalogue_error);
+    }
+}
+
+/// This describes how catalogue components are assembled into
+/// branches, and which branches are in parallel
+///
+/// it is read from a toml or json file, so the facility can
+/// be re-plumbed without recompiling, in toml it looks like:
+///
+/// ```toml
+/// parallel_branches = ["heater_branch", "dhx_branch", "ctah_branch"]
+/// elevation_tolerance_meters = 0.001
+///
+/// [[branch]]
+/// name = "ctah_branch"
+/// role = "ctah"
+/// pump = "ctah_pump"
+/// components = ["pipe_6a", "static_mixer_41_label_6", ...]
+/// heat_transfer_components = ["ctah_vertical_label_7a", ...]
+///
+/// [[pressure_tap]]
+/// name = "ctah_inlet_tap"
+/// branch = "ctah_branch"
+/// after_component = "static_mixer_41_label_6"
+///
+/// [[manometer]]
+/// name = "ctah_manometer"
+/// high_pressure_tap = "ctah_inlet_tap"
+/// low_pressure_tap = "ctah_outlet_tap"
+/// ```
+///
+/// the top level keys must come before the first [[branch]]
+/// in toml, json uses the same field names with "branch",
+/// "pressure_tap" and "manometer" as arrays
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FacilityTopology {
+
+    /// names of the branches connected in parallel
+    /// between the top and bottom of ciet
+    pub parallel_branches: Vec<String>,
+
+    /// how far apart the elevation changes of parallel
+    /// branches may be, defaults to 1 mm
+    #[serde(default = "default_elevation_tolerance_meters")]
+    pub elevation_tolerance_meters: f64,
+
+    #[serde(default, rename = "branch")]
+    pub branches: Vec<BranchTopology>,
+
+    /// pressure taps at component boundaries,
+    /// the manometers read between them
+    #[serde(default, rename = "pressure_tap")]
+    pub pressure_taps: Vec<PressureTapTopology>,
+
+    #[serde(default, rename = "manometer")]
+    pub manometers: Vec<Manometer>,
+}
+
+impl FacilityTopology {
+
+    /// returns the ciet primary loop topology which ships
+    /// with this crate
+    pub fn ciet_default() -> Self {
+        return Self::from_toml_str(CIET_FACILITY_TOPOLOGY_TOML)
+            .expect("the ciet facility topology shipped with this crate is invalid");
+    }
+
+    pub fn from_toml_str(topology_toml: &str)
+        -> Result<Self, FacilityTopologyError> {
+
+        return toml::from_str(topology_toml)
+            .map_err(|error| FacilityTopologyError::Parse(error.to_string()));
+    }
+
+    pub fn from_json_str(topology_json: &str)
+        -> Result<Self, FacilityTopologyError> {
+
+        return serde_json::from_str(topology_json)
+            .map_err(|error| FacilityTopologyError::Parse(error.to_string()));
+    }
+
+    /// reads a topology from a .toml or .json file
+    pub fn from_file<P: AsRef<Path>>(path: P)
+        -> Result<Self, FacilityTopologyError> {
+
+        let path = path.as_ref();
+
+        let topology_string = fs::read_to_string(path)
+            .map_err(|error| FacilityTopologyError::FileRead(
+                    format!("{}: {}", path.display(), error)))?;
+
+        let extension = path.extension()
+            .and_then(|extension| extension.to_str());
+
+        match extension {
+            Some("toml") => return Self::from_toml_str(&topology_string),
+            Some("json") => return Self::from_json_str(&topology_string),
+            _ => return Err(FacilityTopologyError::UnknownFileFormat(
+                    path.display().to_string())),
+        }
+    }
+
+    pub fn to_toml_string(&self) -> Result<String, FacilityTopologyError> {
+        return toml::to_string(self)
+            .map_err(|error| FacilityTopologyError::Parse(error.to_string()));
+    }
+
+    pub fn to_json_string(&self) -> Result<String, FacilityTopologyError> {
+        return serde_json::to_string_pretty(self)
+            .map_err(|error| FacilityTopologyError::Parse(error.to_string()));
+    }
+
+    /// returns the branch with this name, if any
+    pub fn get_branch(&self, name: &str) -> Option<&BranchTopology> {
+        return self.branches.iter()
+            .find(|branch| branch.name == name);
+    }
+
+    /// returns the parallel branch with this role
+    fn get_parallel_branch_with_role(&self, role: BranchRole)
+        -> Result<&BranchTopology, FacilityTopologyError> {
+
+        let mut branches_with_role = self.parallel_branches.iter()
+            .filter_map(|name| self.get_branch(name))
+            .filter(|branch| branch.role == role);
+
+        let branch = branches_with_role.next()
+            .ok_or(FacilityTopologyError::MissingBranchRole(role))?;
+
+        if branches_with_role.next().is_some() {
+            return Err(FacilityTopologyError::DuplicateBranchRole(role));
+        }
+
+        return Ok(branch);
+    }
+
+    /// works out where a pressure tap is on its branch
+    ///
+    /// the branch must be one of the parallel branches, and the
+    /// component the tap comes after must be in that branch
+    pub fn get_branch_pressure_tap(&self, pressure_tap: &PressureTapTopology)
+        -> Result<BranchPressureTap, FacilityTopologyError> {
+
+        let invalid_pressure_tap = || FacilityTopologyError::InvalidPressureTap {
+            pressure_tap: pressure_tap.name.clone(),
+            branch: pressure_tap.branch.clone(),
+            after_component: pressure_tap.after_component.clone() };
+
+        if !self.parallel_branches.contains(&pressure_tap.branch) {
+            return Err(invalid_pressure_tap());
+        }
+
+        let branch = self.get_branch(&pressure_tap.branch)
+            .ok_or_else(invalid_pressure_tap)?;
+
+        let components_upstream = match &pressure_tap.after_component {
+            None => 0,
+            Some(after_component) => branch.components.iter()
+                .position(|component_name| component_name == after_component)
+                .ok_or_else(invalid_pressure_tap)? + 1,
+        };
+
+        return Ok(BranchPressureTap {
+            name: pressure_tap.name.clone(),
+            branch_role: branch.role,
+            components_upstream });
+    }
+
+    /// returns the elevation change from the bottom to the
+    /// top of a branch, summed over its components
+    pub fn get_branch_elevation_change(
+        branch: &BranchTopology,
+        catalogue: &ComponentCatalogue) -> Result<Length, FacilityTopologyError> {
+
+        let mut elevation_change = Length::new::<meter>(0.0);
+
+        for component_name in branch.components.iter() {
+            elevation_change += catalogue.get_elevation_change(component_name)
+                .map_err(|_| FacilityTopologyError::MissingComponent {
+                    branch: branch.name.clone(),
+                    component: component_name.clone() })?;
+        }
+
+        return Ok(elevation_change);
+    }
+
+    /// checks the topology against the catalogue, returning
+    /// the first problem found
+    ///
+    /// this is also done by build_isothermal_ciet_facility and
+    /// build_non_isothermal_ciet_facility, so there is no need 
+    /// to call it separately before building
+    pub fn validate(&self, catalogue: &ComponentCatalogue)
+        -> Result<(), FacilityTopologyError> {
+
+        // names must be unique, both for branches in the topology
+        // and components in the catalogue
+        let mut branch_names: HashSet<&str> = HashSet::new();
+        for branch in self.branches.iter() {
+            if !branch_names.insert(&branch.name) {
+                return Err(FacilityTopologyError::DuplicateBranchName(
+                        branch.name.clone()));
+            }
+        }
+
+        let mut catalogue_names: HashSet<&str> = HashSet::new();
+        for component_name in catalogue.get_component_names() {
+            if !catalogue_names.insert(component_name) {
+                return Err(FacilityTopologyError::DuplicateComponentName(
+                        component_name.to_string()));
+            }
+        }
+
+        // every component must exist, and can only be used once
+        let mut used_component_names: HashSet<&str> = HashSet::new();
+        for branch in self.branches.iter() {
+            for component_name in branch.components.iter() {
+
+                if !catalogue_names.contains(component_name.as_str()) {
+                    return Err(FacilityTopologyError::MissingComponent {
+                        branch: branch.name.clone(),
+                        component: component_name.clone() });
+                }
+
+                if !used_component_names.insert(component_name) {
+                    return Err(FacilityTopologyError::ComponentUsedTwice(
+                            component_name.clone()));
+                }
+            }
+        }
+
+        // only the ctah branch has a pump, and it must be
+        // one of the components in the branch
+        for branch in self.branches.iter() {
+
+            let pump_is_valid = match (branch.role, &branch.pump) {
+                (BranchRole::CTAH, Some(pump)) =>
+                    branch.components.contains(pump),
+                (BranchRole::CTAH, None) => false,
+                (_, Some(_)) => false,
+                (_, None) => true,
+            };
+
+            if !pump_is_valid {
+                return Err(FacilityTopologyError::InvalidPump {
+                    branch: branch.name.clone(),
+                    pump: branch.pump.clone() });
+            }
+        }
+
+        // heat can only be exchanged through the branch's own components
+        for branch in self.branches.iter() {
+            for component_name in branch.heat_transfer_components.iter() {
+                if !branch.components.contains(component_name) {
+                    return Err(FacilityTopologyError::InvalidHeatTransferComponent {
+                        branch: branch.name.clone(),
+                        component: component_name.clone() });
+                }
+            }
+        }
+
+        // the parallel branches must all exist, and the facility
+        // needs exactly one heater, dhx and ctah branch among them
+        for name in self.parallel_branches.iter() {
+            if self.get_branch(name).is_none() {
+                return Err(FacilityTopologyError::UnknownBranch(name.clone()));
+            }
+        }
+
+        for role in [BranchRole::Heater, BranchRole::DHX, BranchRole::CTAH] {
+            self.get_parallel_branch_with_role(role)?;
+        }
+
+        // parallel branches go from the bottom to the top of ciet,
+        // so they should all rise by the same height, otherwise a
+        // component is probably missing or has the wrong angle
+        let elevation_tolerance =
+            Length::new::<meter>(self.elevation_tolerance_meters);
+
+        let mut reference: Option<(&str, Length)> = None;
+
+        for name in self.parallel_branches.iter() {
+
+            // checked above that this branch exists
+            let branch = self.get_branch(name).unwrap();
+            let elevation_change =
+                Self::get_branch_elevation_change(branch, catalogue)?;
+
+            match reference {
+                None => reference = Some((name, elevation_change)),
+                Some((reference_branch, reference_elevation_change)) => {
+                    if (elevation_change - reference_elevation_change).abs()
+                        > elevation_tolerance {
+                        return Err(FacilityTopologyError::InconsistentElevation {
+                            branch: name.clone(),
+                            elevation_change,
+                            reference_branch: reference_branch.to_string(),
+                            reference_elevation_change });
+                    }
+                },
+            }
+        }
+
+        // pressure taps go on the parallel branches, and
+        // manometers read between two of them
+        let mut pressure_tap_names: HashSet<&str> = HashSet::new();
+        for pressure_tap in self.pressure_taps.iter() {
+
+            if !pressure_tap_names.insert(&pressure_tap.name) {
+                return Err(FacilityTopologyError::DuplicatePressureTapName(
+                        pressure_tap.name.clone()));
+            }
+
+            self.get_branch_pressure_tap(pressure_tap)?;
+        }
+
+        let mut manometer_names: HashSet<&str> = HashSet::new();
+        for manometer in self.manometers.iter() {
+
+            if !manometer_names.insert(&manometer.name) {
+                return Err(FacilityTopologyError::DuplicateManometerName(
+                        manometer.name.clone()));
+            }
+
+            for pressure_tap in [&manometer.high_pressure_tap,
+                                 &manometer.low_pressure_tap] {
+                if !pressure_tap_names.contains(pressure_tap.as_str()) {
+                    return Err(FacilityTopologyError::UnknownPressureTap {
+                        manometer: manometer.name.clone(),
+                        pressure_tap: pressure_tap.clone() });
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// builds the components of a branch from the catalogue
+    fn build_component_vector(
+        branch: &BranchTopology,
+        catalogue: &ComponentCatalogue)
+        -> Result<Vec<Box<dyn TherminolFluidComponent>>, FacilityTopologyError> {
+
+        let mut fluid_component_vector:
+            Vec<Box<dyn TherminolFluidComponent>> = vec![];
+
+        for component_name in branch.components.iter() {
+            fluid_component_vector.push(
+                catalogue.get_fluid_component(component_name)?);
+        }
+
+        return Ok(fluid_component_vector);
+    }
+
+    /// returns the heat transfer components of the heater, dhx
+    /// and ctah branches, for the non-isothermal facility
+    ///
+    /// each of these branches must have at least one
+    pub fn get_heat_transfer_component_names(&self)
+        -> Result<HeatTransferComponentNames, FacilityTopologyError> {
+
+        let get_component_names = |role: BranchRole|
+            -> Result<Vec<String>, FacilityTopologyError> {
+
+            let heat_transfer_components = self.get_parallel_branch_with_role(role)?
+                .heat_transfer_components.clone();
+
+            if heat_transfer_components.is_empty() {
+                return Err(FacilityTopologyError::MissingHeatTransferComponents(role));
+            }
+
+            return Ok(heat_transfer_components);
+        };
+
+        return Ok(HeatTransferComponentNames {
+            heater_branch: get_component_names(BranchRole::Heater)?,
+            dhx_branch: get_component_names(BranchRole::DHX)?,
+            ctah_branch: get_component_names(BranchRole::CTAH)?,
+        });
+    }
+
+    /// validates the topology, then assembles the ctah, heater
+    /// and dhx branches (in that order) from the catalogue
+    fn build_branches(
+        &self,
+        catalogue: &ComponentCatalogue)
+        -> Result<(CTAHBranch, HeaterBranch, DHXBranch), FacilityTopologyError> {
+
+        self.validate(catalogue)?;
+
+        let heater_topology =
+            self.get_parallel_branch_with_role(BranchRole::Heater)?;
+        let dhx_topology =
+            self.get_parallel_branch_with_role(BranchRole::DHX)?;
+        let ctah_topology =
+            self.get_parallel_branch_with_role(BranchRole::CTAH)?;
+
+        // validate has already checked the pump is in the branch,
+        // this is just in case
+        let ctah_pump_index = ctah_topology.components.iter()
+            .position(|component_name|
+                      Some(component_name) == ctah_topology.pump.as_ref())
+            .ok_or_else(|| FacilityTopologyError::InvalidPump {
+                branch: ctah_topology.name.clone(),
+                pump: ctah_topology.pump.clone() })?;
+
+        let heater_branch = HeaterBranch::new_from_components(
+            Self::build_component_vector(heater_topology, catalogue)?);
+        let dhx_branch = DHXBranch::new_from_components(
+            Self::build_component_vector(dhx_topology, catalogue)?);
+        let ctah_branch = CTAHBranch::new_from_components(
+            Self::build_component_vector(ctah_topology, catalogue)?,
+            ctah_pump_index)?;
+
+        return Ok((ctah_branch, heater_branch, dhx_branch));
+    }
+
+    /// validates the topology, then assembles the branches from the
+    /// catalogue and returns a ciet facility ready to solve,
+    /// with its pressure taps and manometers in place
+    pub fn build_isothermal_ciet_facility(
+        &self,
+        catalogue: &ComponentCatalogue)
+        -> Result<CIETIsothermalFacility, FacilityTopologyError> {
+
+        let (ctah_branch, heater_branch, dhx_branch) =
+            self.build_branches(catalogue)?;
+
+        let mut ciet_isothermal_facility = CIETIsothermalFacility::new(
+            ctah_branch, heater_branch, dhx_branch);
+
+        for pressure_tap in self.pressure_taps.iter() {
+            ciet_isothermal_facility.add_pressure_tap(
+                self.get_branch_pressure_tap(pressure_tap)?)?;
+        }
+
+        for manometer in self.manometers.iter() {
+            ciet_isothermal_facility.add_manometer(manometer.clone())?;
+        }
+
+        return Ok(ciet_isothermal_facility);
+    }
+
+    /// validates the topology, then assembles the branches from the
+    /// catalogue and returns a ciet facility with heat transfer,
+    /// each component split into nodes_per_component axial nodes
+    ///
+    /// the heater power, ctah cooling and DRACS go through the
+    /// heat transfer components of each branch
+    pub fn build_non_isothermal_ciet_facility(
+        &self,
+        catalogue: &ComponentCatalogue,
+        nodes_per_component: usize)
+        -> Result<CIETNonIsothermalFacility, FacilityTopologyError> {
+
+        let (ctah_branch, heater_branch, dhx_branch) =
+            self.build_branches(catalogue)?;
+
+        return CIETNonIsothermalFacility::new(
+            ctah_branch, heater_branch, dhx_branch,
+            self.get_heat_transfer_component_names()?,
+            nodes_per_component);
+    }
+}
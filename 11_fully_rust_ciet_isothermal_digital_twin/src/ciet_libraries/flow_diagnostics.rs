@@ -0,0 +1,163 @@
+extern crate fluid_mechanics_rust;
+use std::fmt;
+
+use fluid_mechanics_rust::prelude::*;
+
+use crate::{TherminolFluidComponent, BranchRole, OutOfRangePolicy};
+
+/// below this reynolds number (magnitude), flow in a pipe
+/// is taken to be laminar
+pub const LAMINAR_REYNOLDS_NUMBER_LIMIT: f64 = 2300.0;
+
+/// above this reynolds number (magnitude), flow in a pipe
+/// is taken to be turbulent
+pub const TURBULENT_REYNOLDS_NUMBER_LIMIT: f64 = 4000.0;
+
+/// the flow regime in a component, going by its reynolds number
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FlowRegime {
+    Laminar,
+    Transition,
+    Turbulent,
+}
+
+impl FlowRegime {
+
+    /// the regime depends only on the magnitude of Re,
+    /// so reverse flow is treated the same as forward flow
+    pub fn from_reynolds_number(reynolds_number: f64) -> Self {
+
+        let reynolds_number_magnitude = reynolds_number.abs();
+
+        if reynolds_number_magnitude < LAMINAR_REYNOLDS_NUMBER_LIMIT {
+            return FlowRegime::Laminar;
+        }
+
+        if reynolds_number_magnitude <= TURBULENT_REYNOLDS_NUMBER_LIMIT {
+            return FlowRegime::Transition;
+        }
+
+        return FlowRegime::Turbulent;
+    }
+}
+
+impl fmt::Display for FlowRegime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FlowRegime::Laminar => write!(f, "laminar"),
+            FlowRegime::Transition => write!(f, "transition"),
+            FlowRegime::Turbulent => write!(f, "turbulent"),
+        }
+    }
+}
+
+/// the reynolds number, mean velocity and flow regime in one
+/// component at a mass flowrate
+///
+/// the reynolds number and velocity take the sign of the mass
+/// flowrate, so they are negative in reverse flow
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComponentFlowDiagnostics {
+    pub name: String,
+    pub mass_flowrate: MassRate,
+    pub reynolds_number: f64,
+    pub mean_velocity: Velocity,
+    pub flow_regime: FlowRegime,
+    pub fitted_reynolds_number_range: Option<(f64, f64)>,
+    pub out_of_range_policy: OutOfRangePolicy,
+}
+
+impl ComponentFlowDiagnostics {
+
+    /// works out the flow diagnostics for a component at this
+    /// mass flowrate, Re = m D_h/(A mu) and u = m/(rho A)
+    pub fn new(
+        component: &dyn TherminolFluidComponent,
+        mass_flowrate: MassRate) -> Self {
+
+        let cross_sectional_area = component.get_cross_sectional_area_immutable();
+        let hydraulic_diameter = component.get_hydraulic_diameter_immutable();
+        let fluid_viscosity = component.get_fluid_viscosity_immutable();
+        let fluid_density = component.get_fluid_density_immutable();
+
+        let reynolds_number: f64 = (mass_flowrate/cross_sectional_area*
+            hydraulic_diameter/fluid_viscosity).value;
+        let mean_velocity: Velocity =
+            mass_flowrate/(fluid_density*cross_sectional_area);
+
+        return Self {
+            name: component.get_name().to_string(),
+            mass_flowrate,
+            reynolds_number,
+            mean_velocity,
+            flow_regime: FlowRegime::from_reynolds_number(reynolds_number),
+            fitted_reynolds_number_range:
+                component.get_fitted_reynolds_number_range(),
+            out_of_range_policy: component.get_out_of_range_policy(),
+        };
+    }
+
+    /// returns true if the loss correlations of the component are
+    /// being used outside the reynolds numbers they were fitted over
+    ///
+    /// with no flow, no correlation is used, so this is
+    /// false at zero flow, as it is if the range is not known
+    pub fn is_outside_fitted_range(&self) -> bool {
+
+        if self.mass_flowrate.value == 0.0 {
+            return false;
+        }
+
+        match self.fitted_reynolds_number_range {
+            Some((lowest_reynolds_number, highest_reynolds_number)) => {
+                let reynolds_number_magnitude = self.reynolds_number.abs();
+
+                return reynolds_number_magnitude < lowest_reynolds_number
+                    || reynolds_number_magnitude > highest_reynolds_number;
+            },
+            None => return false,
+        }
+    }
+
+    /// returns a warning if the loss correlations are being used
+    /// outside the reynolds numbers they were fitted over,
+    /// along with the out of range policy of the component
+    pub fn get_warning(&self) -> Option<String> {
+
+        if !self.is_outside_fitted_range() {
+            return None;
+        }
+
+        // is_outside_fitted_range is only true if the range is known
+        let (lowest_reynolds_number, highest_reynolds_number) =
+            self.fitted_reynolds_number_range.unwrap();
+
+        return Some(format!(
+                "{}: Re = {:.1} is outside the fitted range of {} to {} ({})",
+                self.name, self.reynolds_number,
+                lowest_reynolds_number, highest_reynolds_number,
+                self.out_of_range_policy));
+    }
+}
+
+/// the flow diagnostics of every component in one of the
+/// parallel branches of ciet, in the order the fluid flows
+/// through them
+#[derive(Clone, Debug, PartialEq)]
+pub struct BranchFlowDiagnostics {
+    pub branch_role: BranchRole,
+    pub mass_flowrate: MassRate,
+    pub components: Vec<ComponentFlowDiagnostics>,
+}
+
+/// returns a warning for each component whose loss correlations
+/// are being used outside the reynolds numbers they were
+/// fitted over
+pub fn get_flow_diagnostic_warnings(
+    branch_flow_diagnostics: &[BranchFlowDiagnostics]) -> Vec<String> {
+
+    return branch_flow_diagnostics.iter()
+        .flat_map(|branch| branch.components.iter())
+        .filter_map(|component| component.get_warning())
+        .collect();
+}
@@ -0,0 +1,423 @@
+extern crate fluid_mechanics_rust;
+use fluid_mechanics_rust::prelude::*;
+
+/// an edge in the hydraulic network, ie. a branch of pipes and
+/// components (in series) which connects two nodes (junctions)
+///
+/// positive mass flowrate goes from the from_node to the to_node,
+/// and the pressure change of the branch is the pressure at the
+/// to_node minus the pressure at the from_node
+pub struct HydraulicNetworkEdge {
+    pub name: String,
+    pub from_node: usize,
+    pub to_node: usize,
+
+    // a pump pressure on the edge is added in series with the branch,
+    // this way pumps can be changed without rebuilding the branch
+    pub pump_pressure: Pressure,
+
+    branch: Box<dyn FluidComponentCollectionMethods + Send + Sync>,
+}
+
+impl HydraulicNetworkEdge {
+
+    /// returns the mass flowrate through this edge given the pressure
+    /// at its from node and to node
+    pub fn get_mass_flowrate_from_node_pressures(
+        &self,
+        from_node_pressure: Pressure,
+        to_node_pressure: Pressure) -> MassRate {
+
+        // the pump supplies part of the pressure change,
+        // the branch supplies the rest
+        let branch_pressure_change =
+            to_node_pressure - from_node_pressure - self.pump_pressure;
+
+        return self.branch.get_mass_flowrate_from_pressure_change(
+            branch_pressure_change);
+    }
+
+    pub fn get_branch(&self) -> &dyn FluidComponentCollectionMethods {
+        return self.branch.as_ref();
+    }
+}
+
+/// results of a hydraulic network solve
+///
+/// node pressures are relative to the first node (node 0),
+/// whose pressure is taken to be 0 Pa
+#[derive(Clone, Debug)]
+pub struct HydraulicNetworkSolution {
+    pub node_pressures: Vec<Pressure>,
+    pub edge_mass_flowrates: Vec<MassRate>,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// This is a graph based hydraulic network
+///
+/// nodes are junctions, and edges are branches of pipes and
+/// components in series, optionally with a pump
+///
+/// the network is solved for all node pressures at once using
+/// Newton's method on mass conservation at each node, and the
+/// edge (branch) mass flowrates follow from the node pressures
+///
+/// this lets us model loops with more than two junctions, eg. the
+/// ciet primary loop coupled to the DRACS loop, or bypass lines
+pub struct HydraulicNetwork {
+
+    node_names: Vec<String>,
+    edges: Vec<HydraulicNetworkEdge>,
+
+    /// the solve stops once the mass imbalance at every node is
+    /// below this value
+    pub mass_flowrate_tolerance: MassRate,
+
+    /// the maximum number of newton iterations
+    pub max_iterations: usize,
+
+    /// the largest change in any node pressure in one newton
+    /// iteration, this keeps the branch flowrates within the
+    /// bounds the branches are able to solve for
+    pub max_pressure_step: Pressure,
+}
+
+impl HydraulicNetwork {
+
+    /// constructor, returns an empty network
+    pub fn new() -> Self {
+        return Self {
+            node_names: vec![],
+            edges: vec![],
+            mass_flowrate_tolerance:
+                MassRate::new::<kilogram_per_second>(1e-7),
+            max_iterations: 100,
+            max_pressure_step: Pressure::new::<pascal>(10000_f64),
+        };
+    }
+
+    /// adds a node (junction) and returns its index
+    ///
+    /// the first node added is the pressure reference (0 Pa)
+    pub fn add_node(&mut self, name: &str) -> usize {
+        self.node_names.push(name.to_string());
+        return self.node_names.len() - 1;
+    }
+
+    /// adds an edge (branch) between two nodes and returns its index
+    pub fn add_edge(
+        &mut self,
+        name: &str,
+        from_node: usize,
+        to_node: usize,
+        branch: Box<dyn FluidComponentCollectionMethods + Send + Sync>)
+        -> usize {
+
+        if from_node >= self.node_names.len() ||
+            to_node >= self.node_names.len() {
+            panic!("edge {} connects to a node which does not exist", name);
+        }
+
+        self.edges.push(HydraulicNetworkEdge {
+            name: name.to_string(),
+            from_node,
+            to_node,
+            pump_pressure: Pressure::new::<pascal>(0.0),
+            branch,
+        });
+
+        return self.edges.len() - 1;
+    }
+
+    /// sets the pump pressure on an edge
+    pub fn set_edge_pump_pressure(
+        &mut self,
+        edge_index: usize,
+        pump_pressure: Pressure){
+
+        self.edges[edge_index].pump_pressure = pump_pressure;
+    }
+
+    pub fn get_node_index(&self, name: &str) -> Option<usize> {
+        return self.node_names.iter().position(|node_name| node_name == name);
+    }
+
+    pub fn get_edge_index(&self, name: &str) -> Option<usize> {
+        return self.edges.iter().position(|edge| edge.name == name);
+    }
+
+    pub fn get_node_names(&self) -> &Vec<String> {
+        return &self.node_names;
+    }
+
+    pub fn get_edges(&self) -> &Vec<HydraulicNetworkEdge> {
+        return &self.edges;
+    }
+
+    /// returns the mass flowrate in every edge for a given
+    /// set of node pressures
+    pub fn get_edge_mass_flowrates(
+        &self,
+        node_pressures: &[Pressure]) -> Vec<MassRate> {
+
+        return self.edges.iter()
+            .map(|edge| edge.get_mass_flowrate_from_node_pressures(
+                    node_pressures[edge.from_node],
+                    node_pressures[edge.to_node]))
+            .collect();
+    }
+
+    /// returns the net mass flowrate into every node except the
+    /// reference node (node 0), these must all be zero for
+    /// mass to be conserved
+    fn get_node_mass_imbalances(
+        &self,
+        edge_mass_flowrates: &[MassRate]) -> Vec<f64> {
+
+        let mut node_mass_imbalances: Vec<f64> =
+            vec![0.0; self.node_names.len()];
+
+        for (edge, mass_flowrate) in
+            self.edges.iter().zip(edge_mass_flowrates.iter()) {
+
+            node_mass_imbalances[edge.to_node] += mass_flowrate.value;
+            node_mass_imbalances[edge.from_node] -= mass_flowrate.value;
+        }
+
+        // the reference node balance is implied by the others
+        node_mass_imbalances.remove(0);
+
+        return node_mass_imbalances;
+    }
+
+    /// solves for all node pressures and edge mass flowrates
+    ///
+    /// the initial guess is 0 Pa at every node
+    pub fn solve(&self) -> HydraulicNetworkSolution {
+
+        let initial_node_pressures: Vec<Pressure> =
+            vec![Pressure::new::<pascal>(0.0); self.node_names.len()];
+
+        return self.solve_from(initial_node_pressures);
+    }
+
+    /// solves for all node pressures and edge mass flowrates
+    /// starting from a user supplied guess of node pressures
+    /// (eg. the previous solution)
+    ///
+    /// i'm using Newton's method on the node mass balances, the
+    /// jacobian is estimated with finite differences of each
+    /// edge's mass flowrate with respect to its pressure change
+    pub fn solve_from(
+        &self,
+        initial_node_pressures: Vec<Pressure>) -> HydraulicNetworkSolution {
+
+        let number_of_nodes = self.node_names.len();
+
+        let mut node_pressures = initial_node_pressures;
+        // the reference node is always at 0 Pa
+        node_pressures[0] = Pressure::new::<pascal>(0.0);
+
+        let mut edge_mass_flowrates =
+            self.get_edge_mass_flowrates(&node_pressures);
+        let mut node_mass_imbalances =
+            self.get_node_mass_imbalances(&edge_mass_flowrates);
+
+        let mut iterations: usize = 0;
+
+        while iterations < self.max_iterations {
+
+            if Self::max_abs(&node_mass_imbalances) <
+                self.mass_flowrate_tolerance.value {
+                return HydraulicNetworkSolution {
+                    node_pressures,
+                    edge_mass_flowrates,
+                    iterations,
+                    converged: true,
+                };
+            }
+
+            iterations += 1;
+
+            // step 1, build the jacobian of the node mass imbalances
+            // with respect to the (non reference) node pressures
+            //
+            // an edge into node n adds m_edge to its balance, an edge
+            // out of node n subtracts it
+            let mut jacobian: Vec<Vec<f64>> =
+                vec![vec![0.0; number_of_nodes - 1]; number_of_nodes - 1];
+
+            for edge in self.edges.iter() {
+
+                let conductance = self.get_edge_conductance(
+                    edge,
+                    node_pressures[edge.from_node],
+                    node_pressures[edge.to_node]);
+
+                // d(m_edge)/d(p_to) = conductance
+                // d(m_edge)/d(p_from) = -conductance
+                let mut add_derivative =
+                    |node: usize, with_respect_to_node: usize, value: f64| {
+                        // the reference node has no equation and
+                        // is not an unknown
+                        if node == 0 || with_respect_to_node == 0 {
+                            return;
+                        }
+                        jacobian[node - 1][with_respect_to_node - 1] += value;
+                    };
+
+                add_derivative(edge.to_node, edge.to_node, conductance);
+                add_derivative(edge.to_node, edge.from_node, -conductance);
+                add_derivative(edge.from_node, edge.to_node, -conductance);
+                add_derivative(edge.from_node, edge.from_node, conductance);
+            }
+
+            // step 2, solve for the newton step
+            let negative_imbalances: Vec<f64> =
+                node_mass_imbalances.iter().map(|imbalance| -imbalance).collect();
+
+            let mut pressure_step =
+                Self::solve_linear_system(jacobian, negative_imbalances);
+
+            // step 3, limit the step so that the branches are not asked
+            // for flowrates outside their bounds
+            let largest_step = Self::max_abs(&pressure_step);
+
+            if largest_step > self.max_pressure_step.value {
+                let scale = self.max_pressure_step.value / largest_step;
+                pressure_step.iter_mut().for_each(|step| *step *= scale);
+            }
+
+            // step 4, halve the step until the mass imbalance goes down
+            // (or we give up halving and take the step anyway)
+            let current_residual = Self::max_abs(&node_mass_imbalances);
+            let mut step_fraction = 1.0;
+
+            loop {
+                let mut trial_node_pressures = node_pressures.clone();
+
+                for (node, step) in pressure_step.iter().enumerate() {
+                    trial_node_pressures[node + 1] +=
+                        Pressure::new::<pascal>(step * step_fraction);
+                }
+
+                let trial_edge_mass_flowrates =
+                    self.get_edge_mass_flowrates(&trial_node_pressures);
+                let trial_node_mass_imbalances =
+                    self.get_node_mass_imbalances(&trial_edge_mass_flowrates);
+
+                let residual_reduced =
+                    Self::max_abs(&trial_node_mass_imbalances) < current_residual;
+
+                if residual_reduced || step_fraction < 1e-3 {
+                    node_pressures = trial_node_pressures;
+                    edge_mass_flowrates = trial_edge_mass_flowrates;
+                    node_mass_imbalances = trial_node_mass_imbalances;
+                    break;
+                }
+
+                step_fraction *= 0.5;
+            }
+        }
+
+        let converged = Self::max_abs(&node_mass_imbalances) <
+            self.mass_flowrate_tolerance.value;
+
+        return HydraulicNetworkSolution {
+            node_pressures,
+            edge_mass_flowrates,
+            iterations,
+            converged,
+        };
+    }
+
+    /// estimates d(mass flowrate)/d(pressure change) for an edge
+    /// using central differences (kg/s per Pa)
+    ///
+    /// a closed check valve has zero conductance, which would make
+    /// the jacobian singular, so a small minimum is used
+    fn get_edge_conductance(
+        &self,
+        edge: &HydraulicNetworkEdge,
+        from_node_pressure: Pressure,
+        to_node_pressure: Pressure) -> f64 {
+
+        let pressure_increment = Pressure::new::<pascal>(1.0);
+
+        let mass_flowrate_upper = edge.get_mass_flowrate_from_node_pressures(
+            from_node_pressure, to_node_pressure + pressure_increment);
+        let mass_flowrate_lower = edge.get_mass_flowrate_from_node_pressures(
+            from_node_pressure, to_node_pressure - pressure_increment);
+
+        let conductance = (mass_flowrate_upper - mass_flowrate_lower).value /
+            (2.0 * pressure_increment.value);
+
+        // mass flowrate goes down as pressure change goes up
+        // (more pressure loss means more flow), so conductance
+        // is normally negative
+        let minimum_conductance = 1e-9;
+
+        if conductance.abs() < minimum_conductance {
+            return -minimum_conductance;
+        }
+
+        return conductance;
+    }
+
+    fn max_abs(values: &[f64]) -> f64 {
+        return values.iter().fold(0.0, |max, value| f64::max(max, value.abs()));
+    }
+
+    /// solves A x = b with gaussian elimination and partial pivoting,
+    /// the networks are small so there is no need for anything fancier
+    fn solve_linear_system(
+        mut matrix: Vec<Vec<f64>>,
+        mut right_hand_side: Vec<f64>) -> Vec<f64> {
+
+        let size = right_hand_side.len();
+
+        for column in 0..size {
+
+            // pick the largest pivot in this column
+            let mut pivot_row = column;
+            for row in column..size {
+                if matrix[row][column].abs() > matrix[pivot_row][column].abs() {
+                    pivot_row = row;
+                }
+            }
+
+            matrix.swap(column, pivot_row);
+            right_hand_side.swap(column, pivot_row);
+
+            let pivot = matrix[column][column];
+
+            if pivot == 0.0 {
+                panic!("hydraulic network jacobian is singular, \
+                       check that every node is connected");
+            }
+
+            for row in (column + 1)..size {
+                let factor = matrix[row][column] / pivot;
+
+                for inner_column in column..size {
+                    matrix[row][inner_column] -= factor * matrix[column][inner_column];
+                }
+                right_hand_side[row] -= factor * right_hand_side[column];
+            }
+        }
+
+        // back substitution
+        let mut solution = vec![0.0; size];
+
+        for row in (0..size).rev() {
+            let mut sum = right_hand_side[row];
+            for column in (row + 1)..size {
+                sum -= matrix[row][column] * solution[column];
+            }
+            solution[row] = sum / matrix[row][row];
+        }
+
+        return solution;
+    }
+}
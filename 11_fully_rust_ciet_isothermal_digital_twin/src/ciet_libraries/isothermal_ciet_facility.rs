@@ -1,49 +1,122 @@
 extern crate fluid_mechanics_rust;
 use std::time::{Instant, Duration};
-use crate::{ctah_branch::*, therminol_component::TherminolCustomComponent, HeaterBranch, 
-    DHXBranch};
+use crate::{ctah_branch::*, HeaterBranch, DHXBranch, CIETHydraulics,
+    CIETSolverError, BranchLookupTable, therminol_temperature_in_range,
+    InertialBranch, get_branch_inertance, PumpCurve, TherminolSeriesComponents,
+    BranchRole, BranchPressureTap, Manometer, ManometerReading,
+    FacilityTopologyError, BranchPressureBreakdown, 
+    pressure_breakdown_to_csv_string, BranchFlowDiagnostics,
+    get_flow_diagnostic_warnings, OutOfRangePolicy, SeriesBranchSolverSettings};
+use std::fs;
+use std::path::Path;
 
 use fluid_mechanics_rust::prelude::*;
-extern crate roots;
-use roots::find_root_brent;
-use roots::SimpleConvergency;
+
+/// the pump pressure, pump speed, valve positions and valve
+/// openings (in that order), which the converged state is cached against
+type CalculationInputs = (Pressure, f64, bool, bool, bool, f64, f64, f64);
+
+/// the pump speed and valve openings (in that order), which the
+/// lookup tables are built at
+type LookupTableInputs = (f64, (f64, f64, f64));
+
+/// how many calculations in a row the pump speed and valve openings
+/// must stay at the same new values before the lookup tables are
+/// rebuilt there
+///
+/// this way the tables are not rebuilt on every calculation while
+/// the user is still moving the pump speed or a valve
+pub const BRANCH_LOOKUP_TABLE_SETTLE_COUNT: usize = 10;
 
 /// This is a struct representing the 
 /// CIET facility in isothermal operation
 ///
-/// temperature is assumed to be 21C all round
+/// the fluid temperature is the same all round, 21C unless
+/// set otherwise, and the therminol properties in every component 
+/// are evaluated at this temperature
 ///
 /// no heat transfer equations are solved
-pub struct CIETIsothermalFacility<'ciet_collection_lifetime> {
+///
+/// the facility owns its branches (and the branches own their
+/// components), so it can be returned from functions and stored
+/// in other structs or sent to a solver thread
+pub struct CIETIsothermalFacility {
 
-    pub ctah_pump_pressure: Pressure,
-    pub ctah_branch_mass_flowrate: MassRate,
-    pub dhx_branch_mass_flowrate: MassRate,
-    pub heater_branch_mass_flowrate: MassRate,
+    // the ctah pump, the branch valves, the branches and the
+    // latest branch flowrates, these are kept the same way
+    // as in the non-isothermal facility
+    hydraulics: CIETHydraulics,
 
-    super_collection_vector_immutable: 
-        Vec<&'ciet_collection_lifetime dyn FluidComponentCollectionMethods>,
+    // the error from the latest calculation, if any,
+    // None means the latest calculation converged
+    solver_error: Option<CIETSolverError>,
 
-    ctah_branch: CTAHBranch<'ciet_collection_lifetime>,
-    heater_branch: HeaterBranch<'ciet_collection_lifetime>,
-    dhx_branch: DHXBranch<'ciet_collection_lifetime>
+    // brent iterations taken in the latest calculation
+    solver_iteration_count: usize,
 
+    // the inputs of the last converged calculation,
+    // if they have not changed, there is no need to solve again
+    converged_inputs: Option<CalculationInputs>,
 
+    // the pressure change across the branches
+    // from the last converged calculation
+    converged_pressure_change: Option<Pressure>,
 
+    // whether the latest calculation was skipped because
+    // the inputs had not changed, and a running count of both
+    last_calculation_was_cache_hit: bool,
+    cache_hit_count: u64,
+    cache_miss_count: u64,
 
-}
+    // a running count of components found outside the reynolds
+    // numbers their correlations were fitted over, once for each
+    // component in each calculation which was solved
+    correlation_out_of_range_count: u64,
 
+    // optional lookup tables for each branch, if these are
+    // built, the solver interpolates from them instead of
+    // calculating every component in every branch
+    heater_branch_lookup_table: Option<BranchLookupTable>,
+    dhx_branch_lookup_table: Option<BranchLookupTable>,
+    ctah_branch_lookup_table: Option<BranchLookupTable>,
 
-/// i also "inherit" traits from my supercollection from
-/// fluid_mechanics_rust
-///
-/// basically i need CIETIsothermalFacility to act a as a super
-/// collection of components
-/// the reason why this is here is so that i can get the 
-/// pressure change of the branch given a mass flowrate
-pub trait ParallelSuperCollection<'trait_lifetime> : 
-FluidComponentSuperCollection<'trait_lifetime> +
-FluidComponentSuperCollectionParallelAssociatedFunctions {}
+    // the ctah pump pressure and speed when the ctah table was built
+    ctah_lookup_table_pump_pressure: Pressure,
+    ctah_lookup_table_pump_speed_rpm: f64,
+
+    // the heater, dhx and ctah valve openings when the tables were built
+    lookup_table_valve_openings_percent: (f64, f64, f64),
+
+    // false if the latest calculation fell back to the exact branches
+    last_calculation_used_branch_lookup_tables: bool,
+
+    // a pump speed and valve openings which the tables were not built
+    // at, and how many calculations in a row they have stayed there
+    unsettled_lookup_table_inputs: Option<(LookupTableInputs, usize)>,
+
+    // the lower and upper mass flowrate, number of points and 
+    // mass flowrate tolerance the lookup tables were last built with,
+    // so that they can be rebuilt when the fluid temperature changes
+    branch_lookup_table_settings: Option<(MassRate, MassRate, usize, MassRate)>,
+
+    // uniform fluid temperature throughout ciet
+    fluid_temperature: ThermodynamicTemperature,
+
+    // sum of L/A over each branch, the fluid inertia
+    // used in transient calculations
+    heater_branch_inertance: ReciprocalLength,
+    dhx_branch_inertance: ReciprocalLength,
+    ctah_branch_inertance: ReciprocalLength,
+
+    // pressure taps at component boundaries in the branches,
+    // and the manometers connected across them
+    pressure_taps: Vec<BranchPressureTap>,
+    manometers: Vec<Manometer>,
+
+
+
+
+}
 
 
 /// for this object,
@@ -53,135 +126,744 @@ FluidComponentSuperCollectionParallelAssociatedFunctions {}
 /// (2) obtain flowrate readings for all flowmeters
 /// (3) obtain calculation time for the calculations as a function
 /// (4) the user should be able to execute value calculation via a function is called
+/// (5) open or close the valves on each branch
+/// (6) keep the last converged flowrates if the solver fails,
+/// and report what went wrong
+/// (7) set the solver tolerance and iteration limit, and report
+/// how many iterations the solver took
+/// (8) skip the calculation if nothing has changed since the last
+/// converged one, and start from the last converged pressure change
+/// otherwise
+/// (9) optionally use precomputed lookup tables for the branches
+/// for faster calculations
+/// (10) set the fluid temperature of the whole facility
+/// (11) step the flowrates forward in time with fluid inertia,
+/// so that they coast up or down when the pump pressure changes
+/// (12) give the ctah pump a head against flowrate curve,
+/// and set the pump speed instead of the pump pressure
+/// (13) throttle the branch valves anywhere between 0 and 100% open
+/// (14) attain manometer readings, that is the static pressure
+/// difference between pressure taps on the branches
+/// (15) break the pressure change across each component down into
+/// friction, form loss, hydrostatic and pump pressure, and write
+/// this out as csv
+/// (16) attain the reynolds number, velocity and flow regime in
+/// each component, and warn when a loss correlation is used outside
+/// the reynolds numbers it was fitted over
+/// (17) clamp, extrapolate or stop the calculation when a loss 
+/// correlation is used outside its fitted range, and count how
+/// often this happens
+/// (18) give pipes and custom components a different form loss
+/// in reverse flow, the losses always oppose the flow
 ///
 ///
-/// i would have liked to, but an not doing:
-/// (1) attain manometer readings if possible
-/// This is not quite critical for dissertation purposes
-/// and would take quite some effort
-///
-///
-impl<'ciet_collection_lifetime> CIETIsothermalFacility<'ciet_collection_lifetime> {
+impl CIETIsothermalFacility {
 
 
     pub fn get_ctah_pump_pressure(&self) -> Pressure {
-        return self.ctah_pump_pressure;
+        return self.hydraulics.ctah_pump_pressure;
     }
 
+    /// sets the ctah pump pressure,
+    ///
+    /// the facility does not need to be rebuilt after this,
+    /// just call calculate again
+    ///
+    /// if the pressure is not a number, nothing is changed
+    /// and an error is returned
     #[inline]
     pub fn set_ctah_pump_pressure(
         &mut self, 
-        user_specified_pressure: Pressure,
-        mutable_ctah_pump: &'ciet_collection_lifetime mut TherminolCustomComponent){
+        user_specified_pressure: Pressure) -> Result<(), CIETSolverError> {
 
-        self.ctah_pump_pressure = user_specified_pressure;
-        self.ctah_branch.set_ctah_pump_pressure(
-            user_specified_pressure, mutable_ctah_pump);
+        self.hydraulics.set_ctah_pump_pressure(user_specified_pressure)?;
+        self.hydraulics.ctah_branch.set_ctah_pump_pressure(
+            user_specified_pressure);
 
+        return Ok(());
+    }
+
+    pub fn get_ctah_pump_speed(&self) -> f64 {
+        return self.hydraulics.ctah_pump_speed_rpm;
+    }
+
+    /// sets the ctah pump speed in rpm,
+    ///
+    /// the pump curve pressure at this speed is solved together with
+    /// the branch resistances, so the flowrate is wherever the pump
+    /// curve meets the system curve, this does nothing unless
+    /// the pump has a curve
+    ///
+    /// the lookup tables are built at one pump speed, so at any
+    /// other speed the facility goes through every component in
+    /// every branch until the tables are rebuilt, which happens
+    /// once the speed settles (see BRANCH_LOOKUP_TABLE_SETTLE_COUNT)
+    ///
+    /// if the speed is not a number, nothing is changed
+    /// and an error is returned
+    pub fn set_ctah_pump_speed(
+        &mut self, 
+        ctah_pump_speed_rpm: f64) -> Result<(), CIETSolverError> {
+
+        self.hydraulics.set_ctah_pump_speed(ctah_pump_speed_rpm)?;
+        self.hydraulics.ctah_branch.set_ctah_pump_speed(ctah_pump_speed_rpm);
+
+        return Ok(());
+    }
+
+    pub fn get_ctah_pump_curve(&self) -> Option<PumpCurve> {
+        return self.hydraulics.ctah_branch.get_ctah_pump_curve();
+    }
+
+    /// gives the ctah pump a head against flowrate curve, or takes
+    /// it away with None, in which case the pump is only a pressure 
+    /// source again
+    ///
+    /// the pump pressure still acts on top of the pump curve,
+    /// so it is usually set to zero when using a curve
+    ///
+    /// any lookup tables are rebuilt with the same settings as
+    /// before, if they could not be rebuilt, the error from
+    /// build_branch_lookup_tables is returned
+    pub fn set_ctah_pump_curve(
+        &mut self, 
+        ctah_pump_curve: Option<PumpCurve>) -> Result<(), CIETSolverError> {
+
+        self.hydraulics.ctah_branch.set_ctah_pump_curve(ctah_pump_curve);
+        self.converged_inputs = None;
+
+        if let Some((lower_mass_flowrate, upper_mass_flowrate, 
+                     number_of_points, mass_flowrate_tolerance)) 
+            = self.branch_lookup_table_settings {
+
+            return self.build_branch_lookup_tables(
+                lower_mass_flowrate, 
+                upper_mass_flowrate, 
+                number_of_points, 
+                mass_flowrate_tolerance);
+        }
+
+        return Ok(());
     }
 
     pub fn get_ctah_branch_mass_flowrate(&self) -> MassRate {
-        return self.ctah_branch_mass_flowrate;
+        return self.hydraulics.ctah_branch_mass_flowrate;
     }
 
     pub fn get_dhx_branch_mass_flowrate(&self) -> MassRate {
-        return self.dhx_branch_mass_flowrate;
+        return self.hydraulics.dhx_branch_mass_flowrate;
     }
 
     pub fn get_heater_branch_mass_flowrate(&self) -> MassRate {
-        return self.heater_branch_mass_flowrate;
+        return self.hydraulics.heater_branch_mass_flowrate;
     }
 
+    /// opens (true) or closes (false) the heater branch valve
+    pub fn set_heater_branch_valve_open(&mut self, valve_open: bool){
+        self.hydraulics.heater_branch_valve_open = valve_open;
+    }
 
-    pub fn calculate(&'ciet_collection_lifetime mut self) -> 
-        (Duration,MassRate,MassRate,MassRate)
-        {
+    /// opens (true) or closes (false) the dhx branch valve
+    pub fn set_dhx_branch_valve_open(&mut self, valve_open: bool){
+        self.hydraulics.dhx_branch_valve_open = valve_open;
+    }
 
-            // start the timer
-            let start = Instant::now();
+    /// opens (true) or closes (false) the ctah branch valve
+    pub fn set_ctah_branch_valve_open(&mut self, valve_open: bool){
+        self.hydraulics.ctah_branch_valve_open = valve_open;
+    }
+
+    pub fn get_heater_branch_valve_open(&self) -> bool {
+        return self.hydraulics.heater_branch_valve_open;
+    }
+
+    pub fn get_dhx_branch_valve_open(&self) -> bool {
+        return self.hydraulics.dhx_branch_valve_open;
+    }
+
+    pub fn get_ctah_branch_valve_open(&self) -> bool {
+        return self.hydraulics.ctah_branch_valve_open;
+    }
+
+    /// sets how far open (0-100%) the heater branch valve is,
+    /// 0% is the same as closing the branch
+    ///
+    /// the lookup tables are built at one set of valve openings,
+    /// so at any other openings the facility goes through every 
+    /// component in every branch until the tables are rebuilt,
+    /// which happens once the openings settle
+    /// (see BRANCH_LOOKUP_TABLE_SETTLE_COUNT)
+    ///
+    /// openings outside 0-100% are clamped to it, and if the opening
+    /// is not a number, nothing is changed and an error is returned
+    pub fn set_heater_branch_valve_opening(
+        &mut self, 
+        opening_percent: f64) -> Result<(), CIETSolverError> {
+        return self.hydraulics.set_valve_opening_percent(
+            BranchRole::Heater, opening_percent);
+    }
 
-            // i'm using the same algorithm from ciet digital twin v1
+    /// sets how far open (0-100%) the dhx branch valve is,
+    /// 0% is the same as closing the branch
+    pub fn set_dhx_branch_valve_opening(
+        &mut self, 
+        opening_percent: f64) -> Result<(), CIETSolverError> {
+        return self.hydraulics.set_valve_opening_percent(
+            BranchRole::DHX, opening_percent);
+    }
 
-            let pressure_change_root = 
-                |pressure_change_pascals: f64| -> f64 {
+    /// sets how far open (0-100%) the ctah branch valve is,
+    /// 0% is the same as closing the branch
+    pub fn set_ctah_branch_valve_opening(
+        &mut self, 
+        opening_percent: f64) -> Result<(), CIETSolverError> {
+        return self.hydraulics.set_valve_opening_percent(
+            BranchRole::CTAH, opening_percent);
+    }
 
-                    // let's get heater branch mass flowrate
-                    // given the iterated pressure change
-                    //
-                    let test_pressure_change = 
-                        Pressure::new::<pascal>(pressure_change_pascals);
+    pub fn get_heater_branch_valve_opening(&self) -> f64 {
+        return self.hydraulics.heater_branch_valve_opening_percent;
+    }
 
-                    let heater_branch_mass_flowrate = 
-                        self.heater_branch.
-                        get_mass_flowrate_from_pressure_change(
-                            test_pressure_change);
+    pub fn get_dhx_branch_valve_opening(&self) -> f64 {
+        return self.hydraulics.dhx_branch_valve_opening_percent;
+    }
 
+    pub fn get_ctah_branch_valve_opening(&self) -> f64 {
+        return self.hydraulics.ctah_branch_valve_opening_percent;
+    }
 
+    pub fn get_fluid_temperature(&self) -> ThermodynamicTemperature {
+        return self.fluid_temperature;
+    }
 
-                    let dhx_branch_mass_flowrate = 
-                        self.dhx_branch.
-                        get_mass_flowrate_from_pressure_change(
-                            test_pressure_change);
+    /// sets the fluid temperature in every component of every branch,
+    ///
+    /// the facility does not need to be rebuilt after this, 
+    /// the next calculation is solved again at the new temperature,
+    /// and any lookup tables are rebuilt with the same settings
+    /// as before
+    ///
+    /// if the temperature is outside the range of the therminol
+    /// property correlations, nothing is changed and an error is
+    /// returned, if the lookup tables could not be rebuilt, the error
+    /// from build_branch_lookup_tables is returned and the facility
+    /// goes through every component in every branch instead
+    pub fn set_fluid_temperature(
+        &mut self, 
+        fluid_temperature: ThermodynamicTemperature) 
+        -> Result<(), CIETSolverError> {
 
+        if !therminol_temperature_in_range(fluid_temperature) {
+            return Err(CIETSolverError::FluidTemperatureOutOfRange { 
+                fluid_temperature });
+        }
 
+        if fluid_temperature == self.fluid_temperature {
+            return Ok(());
+        }
 
+        self.fluid_temperature = fluid_temperature;
+        self.hydraulics.heater_branch.set_fluid_temperature(fluid_temperature);
+        self.hydraulics.dhx_branch.set_fluid_temperature(fluid_temperature);
+        self.hydraulics.ctah_branch.set_fluid_temperature(fluid_temperature);
 
-                    
-                    let ctah_branch_mass_flowrate = 
-                        self.ctah_branch.
-                        get_mass_flowrate_from_pressure_change(
-                            test_pressure_change);
+        // the cached solution was at the old temperature
+        self.converged_inputs = None;
 
-                    let total_mass_flowrate = 
-                        heater_branch_mass_flowrate 
-                        + dhx_branch_mass_flowrate
-                        + ctah_branch_mass_flowrate;
+        if let Some((lower_mass_flowrate, upper_mass_flowrate, 
+                     number_of_points, mass_flowrate_tolerance)) 
+            = self.branch_lookup_table_settings {
 
-                    return total_mass_flowrate.value;
+            return self.build_branch_lookup_tables(
+                lower_mass_flowrate, 
+                upper_mass_flowrate, 
+                number_of_points, 
+                mass_flowrate_tolerance);
+        }
 
+        return Ok(());
+    }
 
-                };
+    /// returns the error from the latest calculation,
+    /// or None if it converged
+    pub fn get_solver_error(&self) -> Option<CIETSolverError> {
+        return self.solver_error;
+    }
 
-            let zero_flowrate = MassRate::new::<kilogram_per_second>(0.0);
+    /// returns the number of brent iterations taken
+    /// in the latest calculation
+    pub fn get_solver_iteration_count(&self) -> usize {
+        return self.solver_iteration_count;
+    }
 
-            let upper_bound = self.heater_branch.
-                get_pressure_change(zero_flowrate) +
-                Pressure::new::<pascal>(50000_f64);
+    /// sets the brent tolerance on the pressure change (in pascals)
+    ///
+    /// the next calculation will be solved again even if
+    /// the inputs have not changed
+    pub fn set_solver_tolerance(&mut self, tolerance: f64){
+        self.hydraulics.parallel_branch_solver.tolerance = tolerance;
+        self.converged_inputs = None;
+    }
 
-            let lower_bound = self.heater_branch.
-                get_pressure_change(zero_flowrate) +
-                Pressure::new::<pascal>(-50000_f64);
+    /// sets the maximum number of brent iterations
+    ///
+    /// the next calculation will be solved again even if
+    /// the inputs have not changed
+    pub fn set_solver_max_iterations(&mut self, max_iter: usize){
+        self.hydraulics.parallel_branch_solver.max_iter = max_iter;
+        self.converged_inputs = None;
+    }
 
+    /// sets the brent settings (bracket, tolerance and maximum
+    /// iterations) each branch uses to find its mass flowrate
+    /// at the pressure change across it
+    ///
+    /// the next calculation will be solved again even if
+    /// the inputs have not changed
+    pub fn set_branch_solver_settings(
+        &mut self,
+        solver_settings: SeriesBranchSolverSettings){
+        self.hydraulics.set_branch_solver_settings(solver_settings);
+        self.converged_inputs = None;
+    }
 
-            let mut convergency = SimpleConvergency { eps:1e-9_f64, max_iter:30 };
+    /// returns the pressure change across the branches from the
+    /// last converged calculation, if there has been one with flow
+    pub fn get_converged_pressure_change(&self) -> Option<Pressure> {
+        return self.converged_pressure_change;
+    }
 
-            let pressure_change_value 
-                = find_root_brent(
-                    upper_bound.value,
-                    lower_bound.value,
-                    &pressure_change_root,
-                    &mut convergency).unwrap();
+    /// returns true if the latest calculation was skipped
+    /// because the inputs had not changed
+    pub fn get_last_calculation_was_cache_hit(&self) -> bool {
+        return self.last_calculation_was_cache_hit;
+    }
 
-            //let pressure_change_value = 0.0;
+    /// returns the number of calculations skipped because
+    /// the inputs had not changed
+    pub fn get_cache_hit_count(&self) -> u64 {
+        return self.cache_hit_count;
+    }
 
-            let pressure_change = 
-                Pressure::new::<pascal>(pressure_change_value);
+    /// returns the number of calculations which had to be solved
+    pub fn get_cache_miss_count(&self) -> u64 {
+        return self.cache_miss_count;
+    }
 
-            let ctah_branch_flowrate = self.ctah_branch.
-                get_mass_flowrate_from_pressure_change(pressure_change);
+    /// returns the number of times a component was found outside
+    /// the reynolds numbers its correlations were fitted over,
+    /// each component is counted once in each solved calculation
+    /// (including those which failed because of it)
+    pub fn get_correlation_out_of_range_count(&self) -> u64 {
+        return self.correlation_out_of_range_count;
+    }
 
-            let heater_branch_flowrate = self.heater_branch.
-                get_mass_flowrate_from_pressure_change(pressure_change);
+    /// builds a mass flowrate against pressure change lookup table
+    /// for each branch between the lower and upper mass flowrate,
+    /// and checks each table against the exact branch calculation
+    ///
+    /// if any table is off by more than the mass flowrate tolerance,
+    /// no tables are used and an error is returned,
+    /// otherwise the facility uses the tables from now on
+    ///
+    /// if a calculation using the tables fails (eg. the answer is
+    /// outside the table), the facility falls back to the exact 
+    /// branch calculation for that calculation
+    pub fn build_branch_lookup_tables(
+        &mut self,
+        lower_mass_flowrate: MassRate,
+        upper_mass_flowrate: MassRate,
+        number_of_points: usize,
+        mass_flowrate_tolerance: MassRate) -> Result<(), CIETSolverError> {
+
+        self.clear_branch_lookup_tables();
+
+        // the settings are kept even if the tables fail the check
+        // below, so another attempt is made at the next temperature
+        self.branch_lookup_table_settings = Some((
+                lower_mass_flowrate, upper_mass_flowrate, 
+                number_of_points, mass_flowrate_tolerance));
+
+        // the tables are built at the current valve openings
+        self.hydraulics.apply_pump_and_valve_settings();
+
+        let fluid_temperature = self.fluid_temperature;
+
+        let heater_branch_lookup_table = BranchLookupTable::new(
+            &self.hydraulics.heater_branch, lower_mass_flowrate, upper_mass_flowrate,
+            number_of_points, fluid_temperature);
+        let dhx_branch_lookup_table = BranchLookupTable::new(
+            &self.hydraulics.dhx_branch, lower_mass_flowrate, upper_mass_flowrate,
+            number_of_points, fluid_temperature);
+        let ctah_branch_lookup_table = BranchLookupTable::new(
+            &self.hydraulics.ctah_branch, lower_mass_flowrate, upper_mass_flowrate,
+            number_of_points, fluid_temperature);
+
+        let max_mass_flowrate_errors = [
+            heater_branch_lookup_table.get_max_mass_flowrate_error(
+                &self.hydraulics.heater_branch),
+            dhx_branch_lookup_table.get_max_mass_flowrate_error(
+                &self.hydraulics.dhx_branch),
+            ctah_branch_lookup_table.get_max_mass_flowrate_error(
+                &self.hydraulics.ctah_branch),
+        ];
+
+        for max_mass_flowrate_error in max_mass_flowrate_errors {
+            // NaN errors fail this check too
+            if !(max_mass_flowrate_error <= mass_flowrate_tolerance) {
+                return Err(CIETSolverError::LookupTableOutOfTolerance { 
+                    max_mass_flowrate_error });
+            }
+        }
+
+        self.heater_branch_lookup_table = Some(heater_branch_lookup_table);
+        self.dhx_branch_lookup_table = Some(dhx_branch_lookup_table);
+        self.ctah_branch_lookup_table = Some(ctah_branch_lookup_table);
+        self.ctah_lookup_table_pump_pressure = self.hydraulics.ctah_pump_pressure;
+        self.ctah_lookup_table_pump_speed_rpm = self.hydraulics.ctah_pump_speed_rpm;
+        self.lookup_table_valve_openings_percent = self.hydraulics.get_valve_openings();
+
+        return Ok(());
+    }
+
+    /// stops using the lookup tables, all calculations after
+    /// this go through every component in every branch
+    pub fn clear_branch_lookup_tables(&mut self){
+        self.branch_lookup_table_settings = None;
+        self.heater_branch_lookup_table = None;
+        self.dhx_branch_lookup_table = None;
+        self.ctah_branch_lookup_table = None;
+        self.converged_inputs = None;
+    }
+
+    /// returns true if the facility is using lookup tables
+    pub fn get_using_branch_lookup_tables(&self) -> bool {
+        return self.ctah_branch_lookup_table.is_some();
+    }
+
+    /// returns true if the latest calculation was solved with the
+    /// lookup tables, false if there are no tables or the calculation
+    /// fell back to the exact branches
+    pub fn get_last_calculation_used_branch_lookup_tables(&self) -> bool {
+        return self.last_calculation_used_branch_lookup_tables;
+    }
+
+    /// returns true if the facility has lookup tables but the latest
+    /// calculation went through every component in every branch
+    /// instead (eg. the pump speed or valve openings are not the
+    /// ones the tables were built at, and have not settled yet)
+    pub fn get_branch_lookup_tables_bypassed(&self) -> bool {
+        return self.get_using_branch_lookup_tables() 
+            && !self.last_calculation_used_branch_lookup_tables;
+    }
 
-            // again, check valve behaviour algorithm
+    /// rebuilds the lookup tables once the pump speed and valve
+    /// openings have stayed at new values for
+    /// BRANCH_LOOKUP_TABLE_SETTLE_COUNT calculations in a row
+    ///
+    /// the tables can only be used at the pump speed and valve
+    /// openings they were built at, so without this, the facility
+    /// would go through every component in every branch from the
+    /// first change onwards
+    ///
+    /// if the tables could not be rebuilt, the facility goes through
+    /// every component in every branch instead, same as
+    /// set_fluid_temperature
+    fn rebuild_branch_lookup_tables_once_settled(&mut self){
+
+        let (lower_mass_flowrate, upper_mass_flowrate, 
+             number_of_points, mass_flowrate_tolerance) = 
+            match (self.get_using_branch_lookup_tables(), 
+                   self.branch_lookup_table_settings) {
+                (true, Some(branch_lookup_table_settings)) => 
+                    branch_lookup_table_settings,
+                _ => {
+                    self.unsettled_lookup_table_inputs = None;
+                    return;
+                },
+            };
+
+        let lookup_table_inputs: LookupTableInputs = (
+            self.hydraulics.ctah_pump_speed_rpm,
+            self.hydraulics.get_valve_openings());
+
+        if lookup_table_inputs == (self.ctah_lookup_table_pump_speed_rpm,
+                                   self.lookup_table_valve_openings_percent) {
+            self.unsettled_lookup_table_inputs = None;
+            return;
+        }
 
-            let dhx_branch_flowrate = self.dhx_branch.
-                get_mass_flowrate_from_pressure_change(pressure_change);
+        let settled_count: usize = match self.unsettled_lookup_table_inputs {
+            Some((unsettled_inputs, settled_count)) 
+                if unsettled_inputs == lookup_table_inputs => settled_count + 1,
+            _ => 1,
+        };
 
+        if settled_count < BRANCH_LOOKUP_TABLE_SETTLE_COUNT {
+            self.unsettled_lookup_table_inputs = 
+                Some((lookup_table_inputs, settled_count));
+            return;
+        }
 
-            self.ctah_branch_mass_flowrate = ctah_branch_flowrate;
-            self.heater_branch_mass_flowrate = heater_branch_flowrate;
-            self.dhx_branch_mass_flowrate = dhx_branch_flowrate;
+        self.unsettled_lookup_table_inputs = None;
+
+        // this also clears the cached solution, so the next
+        // calculation is solved again with the new tables
+        let _ = self.build_branch_lookup_tables(
+            lower_mass_flowrate, 
+            upper_mass_flowrate, 
+            number_of_points, 
+            mass_flowrate_tolerance);
+    }
+
+    /// returns the pump pressure, pump speed, valve positions 
+    /// and valve openings, this is what the converged state is 
+    /// cached against
+    fn get_inputs(&self) -> CalculationInputs {
+        return (self.hydraulics.ctah_pump_pressure,
+                self.hydraulics.ctah_pump_speed_rpm,
+                self.hydraulics.heater_branch_valve_open,
+                self.hydraulics.dhx_branch_valve_open,
+                self.hydraulics.ctah_branch_valve_open,
+                self.hydraulics.heater_branch_valve_opening_percent,
+                self.hydraulics.dhx_branch_valve_opening_percent,
+                self.hydraulics.ctah_branch_valve_opening_percent);
+    }
+
+    /// solves for the branch flowrates given the current
+    /// ctah pump pressure and valve positions
+    ///
+    /// this never panics on a solver failure, if the solver
+    /// fails, the last converged flowrates are returned and the
+    /// error can be obtained from get_solver_error
+    ///
+    /// this only borrows the facility for the duration of the
+    /// calculation, so a facility object can be constructed once
+    /// and then calculated over and over again
+    /// (eg. once every time the opcua server polls)
+    pub fn calculate(&mut self) -> 
+        (Duration,MassRate,MassRate,MassRate)
+        {
+
+            let start = Instant::now();
+
+            if let Ok(calculation_results) = self.try_calculate() {
+                return calculation_results;
+            }
+
+            // fall back to the last converged state
+            let elapsed_time: Duration= start.elapsed();
+
+            return (elapsed_time,
+                    self.hydraulics.ctah_branch_mass_flowrate,
+                    self.hydraulics.heater_branch_mass_flowrate,
+                    self.hydraulics.dhx_branch_mass_flowrate);
+        }
+
+    /// solves for the branch flowrates given the current
+    /// ctah pump pressure and valve positions
+    ///
+    /// if the solver fails, the error is returned and the
+    /// facility keeps its last converged flowrates
+    pub fn try_calculate(&mut self) -> 
+        Result<(Duration,MassRate,MassRate,MassRate), CIETSolverError>
+        {
+
+            // start the timer
+            let start = Instant::now();
+
+            let zero_flowrate = MassRate::new::<kilogram_per_second>(0.0);
+
+            // the lookup tables follow the pump speed and valve
+            // openings once they settle
+            self.rebuild_branch_lookup_tables_once_settled();
+
+            // if nothing has changed since the last converged 
+            // calculation, the answer is the same, so don't solve again
+            //
+            // the inputs are compared directly rather than 
+            // tracked through the setters
+            let inputs = self.get_inputs();
+
+            if self.converged_inputs == Some(inputs) {
+
+                self.last_calculation_was_cache_hit = true;
+                self.cache_hit_count += 1;
+                self.solver_iteration_count = 0;
+
+                let elapsed_time: Duration= start.elapsed();
+
+                return Ok((elapsed_time,
+                        self.hydraulics.ctah_branch_mass_flowrate,
+                        self.hydraulics.heater_branch_mass_flowrate,
+                        self.hydraulics.dhx_branch_mass_flowrate));
+            }
+
+            self.last_calculation_was_cache_hit = false;
+            self.cache_miss_count += 1;
+
+            self.hydraulics.apply_pump_and_valve_settings();
+
+            // only the open branches go into the parallel branch solver
+            //
+            // the heater branch goes in first so that the first pressure 
+            // bracket is centred on its zero flow pressure change 
+            // (or on the dhx branch if the heater is closed),
+            // this is the same bracket as ciet digital twin v1
+            let valves_open = self.hydraulics.get_valves_open();
+            let (heater_branch_valve_open,
+                 dhx_branch_valve_open,
+                 ctah_branch_valve_open) = valves_open;
+
+            // first, check if flow is possible at all
+            // (see get_dhx_check_valve_blocks_flow)
+            if self.hydraulics.get_dhx_check_valve_blocks_flow() {
+
+                self.hydraulics.ctah_branch_mass_flowrate = zero_flowrate;
+                self.hydraulics.heater_branch_mass_flowrate = zero_flowrate;
+                self.hydraulics.dhx_branch_mass_flowrate = zero_flowrate;
+                self.solver_error = None;
+                self.solver_iteration_count = 0;
+                self.converged_inputs = Some(inputs);
+
+                let elapsed_time: Duration= start.elapsed();
+
+                return Ok((elapsed_time,
+                        zero_flowrate,
+                        zero_flowrate,
+                        zero_flowrate));
+            }
+
+            // if there are lookup tables, try them first
+            //
+            // the ctah pump only shifts the ctah branch pressure change,
+            // so the table is shifted by however much the pump pressure
+            // has changed since it was built
+            if let Some(ctah_branch_lookup_table) = 
+                self.ctah_branch_lookup_table.as_mut() {
+                    ctah_branch_lookup_table.set_pressure_offset(
+                        self.hydraulics.ctah_pump_pressure 
+                        - self.ctah_lookup_table_pump_pressure);
+            }
+
+            let mut solver_result: 
+                Option<Result<(Pressure, Vec<MassRate>), CIETSolverError>> 
+                = None;
+
+            // a change in pump speed changes the shape of the pump 
+            // curve, not just its height, so the ctah table can only
+            // be used at the speed it was built at, and likewise the
+            // tables can only be used at the valve openings they
+            // were built at
+            let lookup_tables_match_inputs: bool = 
+                self.hydraulics.ctah_pump_speed_rpm == self.ctah_lookup_table_pump_speed_rpm
+                && self.hydraulics.get_valve_openings() == self.lookup_table_valve_openings_percent;
+
+            if let (true,
+                    Some(heater_branch_lookup_table),
+                    Some(dhx_branch_lookup_table),
+                    Some(ctah_branch_lookup_table)) = 
+                (lookup_tables_match_inputs,
+                 &self.heater_branch_lookup_table,
+                 &self.dhx_branch_lookup_table,
+                 &self.ctah_branch_lookup_table) {
+
+                    let open_branches = CIETHydraulics::get_open_branches(
+                        heater_branch_lookup_table,
+                        dhx_branch_lookup_table,
+                        ctah_branch_lookup_table,
+                        valves_open);
+
+                    // the tables only give a mass flowrate within their
+                    // range, so the solver has to stay where all the
+                    // open tables overlap
+                    let mut lower_limit = Pressure::new::<pascal>(f64::NEG_INFINITY);
+                    let mut upper_limit = Pressure::new::<pascal>(f64::INFINITY);
+
+                    for (valve_open, branch_lookup_table) in [
+                        (heater_branch_valve_open, heater_branch_lookup_table),
+                        (dhx_branch_valve_open, dhx_branch_lookup_table),
+                        (ctah_branch_valve_open, ctah_branch_lookup_table)] {
+
+                        if valve_open {
+                            let (lowest_pressure_change, highest_pressure_change) =
+                                branch_lookup_table.get_pressure_change_range();
+                            lower_limit = lower_limit.max(lowest_pressure_change);
+                            upper_limit = upper_limit.min(highest_pressure_change);
+                        }
+                    }
+
+                    solver_result = Some(
+                        self.hydraulics.parallel_branch_solver
+                        .solve_within_pressure_change_limits(
+                            &open_branches, Some((lower_limit, upper_limit))));
+            }
+
+            // otherwise (or if the answer is outside the tables),
+            // calculate every component in every branch
+            //
+            // again, check valve behaviour is handled by
+            // the branches (see find_series_branch_mass_flowrate)
+            self.last_calculation_used_branch_lookup_tables = 
+                matches!(solver_result, Some(Ok(_)));
+
+            let solver_result = match solver_result {
+                Some(Ok(solution)) => Ok(solution),
+                _ => {
+                    let open_branches = CIETHydraulics::get_open_branches(
+                        &self.hydraulics.heater_branch,
+                        &self.hydraulics.dhx_branch,
+                        &self.hydraulics.ctah_branch,
+                        valves_open);
+
+                    // the solver starts its bracket from the last converged
+                    // pressure change, which is usually very close to the answer
+                    self.hydraulics.parallel_branch_solver.solve(&open_branches)
+                },
+            };
+
+            self.solver_iteration_count = 
+                self.hydraulics.parallel_branch_solver.get_last_iteration_count();
+
+            let (pressure_change, open_branch_mass_flowrates) = 
+                match solver_result {
+                    Ok(solution) => solution,
+                    Err(solver_error) => {
+                        self.solver_error = Some(solver_error);
+                        self.converged_inputs = None;
+                        return Err(solver_error);
+                    },
+                };
+
+            // now match the flowrates back to their branches,
+            // closed branches have zero flow
+            let (heater_branch_flowrate,
+                 dhx_branch_flowrate,
+                 ctah_branch_flowrate) = 
+                CIETHydraulics::get_branch_mass_flowrates(
+                    valves_open, open_branch_mass_flowrates);
+
+            // the answer may be outside the fitted range of some
+            // correlations, if any of these should stop the 
+            // calculation, keep the last converged state
+            if let Err(solver_error) = self.check_correlation_ranges(
+                heater_branch_flowrate, 
+                dhx_branch_flowrate, 
+                ctah_branch_flowrate) {
+
+                self.solver_error = Some(solver_error);
+                self.converged_inputs = None;
+                return Err(solver_error);
+            }
+
+
+            self.hydraulics.ctah_branch_mass_flowrate = ctah_branch_flowrate;
+            self.hydraulics.heater_branch_mass_flowrate = heater_branch_flowrate;
+            self.hydraulics.dhx_branch_mass_flowrate = dhx_branch_flowrate;
+            self.solver_error = None;
+            self.converged_inputs = Some(inputs);
+            self.converged_pressure_change = Some(pressure_change);
 
 
 
@@ -189,72 +871,538 @@ impl<'ciet_collection_lifetime> CIETIsothermalFacility<'ciet_collection_lifetime
             // elapsed time to the environment
 
 
+            let elapsed_time: Duration= start.elapsed();
+
+            return Ok((elapsed_time,
+                    ctah_branch_flowrate,
+                    heater_branch_flowrate,
+                    dhx_branch_flowrate));
+
+        }
+
+
+    /// steps the branch flowrates forward by one timestep,
+    /// starting from the current flowrates, taking into account 
+    /// the fluid inertia (L/A) of each branch
+    ///
+    /// unlike calculate, a change in pump pressure does not change
+    /// the flowrates at once, they coast up or down towards the
+    /// new steady state, closing a valve still stops the flow
+    /// in that branch at once
+    ///
+    /// the momentum equation is implicit, so any timestep is stable,
+    /// and a very large timestep gives the steady state
+    ///
+    /// this never panics on a solver failure, if the solver fails,
+    /// the flowrates from the start of the timestep are returned and
+    /// the error can be obtained from get_solver_error
+    pub fn advance_timestep(&mut self, timestep: Time) -> 
+        (Duration,MassRate,MassRate,MassRate)
+        {
+
+            let start = Instant::now();
+
+            if let Ok(calculation_results) = self.try_advance_timestep(timestep) {
+                return calculation_results;
+            }
+
             let elapsed_time: Duration= start.elapsed();
 
             return (elapsed_time,
+                    self.hydraulics.ctah_branch_mass_flowrate,
+                    self.hydraulics.heater_branch_mass_flowrate,
+                    self.hydraulics.dhx_branch_mass_flowrate);
+        }
+
+    /// steps the branch flowrates forward by one timestep
+    /// (see advance_timestep)
+    ///
+    /// the cache and lookup tables are for the steady state,
+    /// so they are not used here, every component in every branch
+    /// is calculated, and every timestep counts as a cache miss
+    pub fn try_advance_timestep(&mut self, timestep: Time) -> 
+        Result<(Duration,MassRate,MassRate,MassRate), CIETSolverError>
+        {
+
+            let start = Instant::now();
+
+            let zero_flowrate = MassRate::new::<kilogram_per_second>(0.0);
+
+            // the flowrates are no longer a converged steady state,
+            // so the next call to calculate must solve again
+            //
+            // the timestep is never skipped, so it is a cache miss,
+            // otherwise the hit and miss counts would not add up to
+            // the number of calculations
+            self.converged_inputs = None;
+            self.last_calculation_was_cache_hit = false;
+            self.cache_miss_count += 1;
+            self.last_calculation_used_branch_lookup_tables = false;
+
+            self.hydraulics.apply_pump_and_valve_settings();
+
+            let valves_open = self.hydraulics.get_valves_open();
+
+            // same as the steady state, with the heater closed the
+            // pump would push fluid backwards through the dhx check
+            // valve, so there is no flow
+            if self.hydraulics.get_dhx_check_valve_blocks_flow() {
+
+                self.hydraulics.ctah_branch_mass_flowrate = zero_flowrate;
+                self.hydraulics.heater_branch_mass_flowrate = zero_flowrate;
+                self.hydraulics.dhx_branch_mass_flowrate = zero_flowrate;
+                self.solver_error = None;
+                self.solver_iteration_count = 0;
+
+                let elapsed_time: Duration= start.elapsed();
+
+                return Ok((elapsed_time,
+                        zero_flowrate,
+                        zero_flowrate,
+                        zero_flowrate));
+            }
+
+            // each branch starts the timestep at its current flowrate
+            let heater_inertial_branch = InertialBranch::new(
+                &self.hydraulics.heater_branch, 
+                self.hydraulics.heater_branch.get_zero_flow_pressure_loss_range(),
+                self.hydraulics.heater_branch.get_solver_settings(),
+                self.heater_branch_inertance,
+                self.hydraulics.heater_branch_mass_flowrate, timestep);
+            let dhx_inertial_branch = InertialBranch::new(
+                &self.hydraulics.dhx_branch, 
+                self.hydraulics.dhx_branch.get_zero_flow_pressure_loss_range(),
+                self.hydraulics.dhx_branch.get_solver_settings(),
+                self.dhx_branch_inertance,
+                self.hydraulics.dhx_branch_mass_flowrate, timestep);
+            let ctah_inertial_branch = InertialBranch::new(
+                &self.hydraulics.ctah_branch, 
+                self.hydraulics.ctah_branch.get_zero_flow_pressure_loss_range(),
+                self.hydraulics.ctah_branch.get_solver_settings(),
+                self.ctah_branch_inertance,
+                self.hydraulics.ctah_branch_mass_flowrate, timestep);
+
+            let open_branches = CIETHydraulics::get_open_branches(
+                &heater_inertial_branch,
+                &dhx_inertial_branch,
+                &ctah_inertial_branch,
+                valves_open);
+
+            let solver_result = self.hydraulics.parallel_branch_solver.solve(&open_branches);
+
+            self.solver_iteration_count = 
+                self.hydraulics.parallel_branch_solver.get_last_iteration_count();
+
+            let (_pressure_change, open_branch_mass_flowrates) = 
+                match solver_result {
+                    Ok(solution) => solution,
+                    Err(solver_error) => {
+                        self.solver_error = Some(solver_error);
+                        return Err(solver_error);
+                    },
+                };
+
+            // now match the flowrates back to their branches,
+            // closed branches have zero flow
+            let (heater_branch_flowrate,
+                 dhx_branch_flowrate,
+                 ctah_branch_flowrate) = 
+                CIETHydraulics::get_branch_mass_flowrates(
+                    valves_open, open_branch_mass_flowrates);
+
+            if let Err(solver_error) = self.check_correlation_ranges(
+                heater_branch_flowrate, 
+                dhx_branch_flowrate, 
+                ctah_branch_flowrate) {
+
+                self.solver_error = Some(solver_error);
+                return Err(solver_error);
+            }
+
+            self.hydraulics.ctah_branch_mass_flowrate = ctah_branch_flowrate;
+            self.hydraulics.heater_branch_mass_flowrate = heater_branch_flowrate;
+            self.hydraulics.dhx_branch_mass_flowrate = dhx_branch_flowrate;
+            self.solver_error = None;
+
+            let elapsed_time: Duration= start.elapsed();
+
+            return Ok((elapsed_time,
                     ctah_branch_flowrate,
                     heater_branch_flowrate,
-                    dhx_branch_flowrate);
+                    dhx_branch_flowrate));
+        }
 
+    // counts the components outside the reynolds numbers their
+    // correlations were fitted over at these branch flowrates,
+    // and returns an error for the first one which has the error
+    // out of range policy
+    fn check_correlation_ranges(
+        &mut self,
+        heater_branch_flowrate: MassRate,
+        dhx_branch_flowrate: MassRate,
+        ctah_branch_flowrate: MassRate) -> Result<(), CIETSolverError> {
+
+        let mut out_of_range_error: Option<CIETSolverError> = None;
+
+        for (branch_role, mass_flowrate) in [
+            (BranchRole::Heater, heater_branch_flowrate),
+            (BranchRole::DHX, dhx_branch_flowrate),
+            (BranchRole::CTAH, ctah_branch_flowrate)] {
+
+            let out_of_range_components: Vec<_> = self.get_branch(branch_role)
+                .get_component_flow_diagnostics(mass_flowrate)
+                .into_iter()
+                .filter(|component| component.is_outside_fitted_range())
+                .collect();
+
+            self.correlation_out_of_range_count += 
+                out_of_range_components.len() as u64;
+
+            let stopping_component = out_of_range_components.iter()
+                .find(|component| 
+                      component.out_of_range_policy == OutOfRangePolicy::Error);
+
+            if let (None, Some(component)) = (out_of_range_error, stopping_component) {
+
+                // the fitted range is known for anything out of range
+                let (lowest_reynolds_number, highest_reynolds_number) = 
+                    component.fitted_reynolds_number_range.unwrap();
+
+                out_of_range_error = Some(
+                    CIETSolverError::CorrelationOutOfRange {
+                        reynolds_number: component.reynolds_number,
+                        lowest_reynolds_number,
+                        highest_reynolds_number,
+                    });
+            }
         }
 
+        match out_of_range_error {
+            Some(solver_error) => return Err(solver_error),
+            None => return Ok(()),
+        }
+    }
 
-    // constructor
+    /// adds a pressure tap to one of the branches
+    ///
+    /// the tap cannot have the same name as another tap, and the
+    /// branch must have at least as many components as are
+    /// upstream of the tap
+    pub fn add_pressure_tap(&mut self, pressure_tap: BranchPressureTap)
+        -> Result<(), FacilityTopologyError> {
+
+        let invalid_pressure_tap = FacilityTopologyError::InvalidPressureTap {
+            pressure_tap: pressure_tap.name.clone(),
+            branch: pressure_tap.branch_role.to_string(),
+            after_component: None };
+
+        if self.get_pressure_tap(&pressure_tap.name).is_some() {
+            return Err(FacilityTopologyError::DuplicatePressureTapName(
+                    pressure_tap.name));
+        }
 
-    pub fn new(ctah_branch: CTAHBranch<'ciet_collection_lifetime>,
-               heater_branch: HeaterBranch<'ciet_collection_lifetime>,
-               dhx_branch: DHXBranch<'ciet_collection_lifetime>) -> Self {
+        let number_of_components = self.get_branch(pressure_tap.branch_role)
+            .get_therminol_components().len();
 
-        // again here we have an empty vector and we move ownership of
-        // the dhx branch to this vector
-        //
+        if pressure_tap.components_upstream > number_of_components {
+            return Err(invalid_pressure_tap);
+        }
 
-        
-        return Self { 
-            ctah_pump_pressure: Pressure::new::<pascal>(0.0), 
-            ctah_branch_mass_flowrate: MassRate::new::<kilogram_per_second>(0.0), 
-            dhx_branch_mass_flowrate: MassRate::new::<kilogram_per_second>(0.0), 
-            heater_branch_mass_flowrate: MassRate::new::<kilogram_per_second>(0.0), 
-            super_collection_vector_immutable: vec![], 
-            ctah_branch: ctah_branch, 
-            heater_branch: heater_branch, 
-            dhx_branch: dhx_branch 
+        self.pressure_taps.push(pressure_tap);
+
+        return Ok(());
+    }
+
+    /// connects a manometer across two pressure taps,
+    /// both taps must already be added
+    pub fn add_manometer(&mut self, manometer: Manometer)
+        -> Result<(), FacilityTopologyError> {
+
+        if self.manometers.iter()
+            .any(|existing_manometer| existing_manometer.name == manometer.name) {
+            return Err(FacilityTopologyError::DuplicateManometerName(
+                    manometer.name));
+        }
+
+        for pressure_tap in [&manometer.high_pressure_tap, 
+                             &manometer.low_pressure_tap] {
+            if self.get_pressure_tap(pressure_tap).is_none() {
+                return Err(FacilityTopologyError::UnknownPressureTap {
+                    manometer: manometer.name.clone(),
+                    pressure_tap: pressure_tap.clone() });
+            }
         }
 
+        self.manometers.push(manometer);
+
+        return Ok(());
     }
 
+    pub fn get_pressure_taps(&self) -> &[BranchPressureTap] {
+        return &self.pressure_taps;
+    }
 
-}
+    pub fn get_manometers(&self) -> &[Manometer] {
+        return &self.manometers;
+    }
 
-/// the CIET isothermal facility must implement the parallel super
-/// collection supertrait i just defined
-impl<'ciet_object_lifetime> 
-ParallelSuperCollection<'ciet_object_lifetime> for CIETIsothermalFacility<'ciet_object_lifetime> {}
+    fn get_pressure_tap(&self, name: &str) -> Option<&BranchPressureTap> {
+        return self.pressure_taps.iter()
+            .find(|pressure_tap| pressure_tap.name == name);
+    }
 
-impl<'ciet_object_lifetime> FluidComponentSuperCollectionParallelAssociatedFunctions for 
-CIETIsothermalFacility<'ciet_object_lifetime> {}
+    /// returns the branch with this role
+    fn get_branch(&self, branch_role: BranchRole) 
+        -> &dyn TherminolSeriesComponents {
+        return self.hydraulics.get_branch(branch_role);
+    }
 
-impl<'ciet_object_lifetime> FluidComponentSuperCollection<'ciet_object_lifetime> 
-for CIETIsothermalFacility<'ciet_object_lifetime> {
+    fn get_branch_mass_flowrate(&self, branch_role: BranchRole) -> MassRate {
+        return self.hydraulics.get_branch_mass_flowrate(branch_role);
+    }
 
-    fn get_immutable_vector(&self) 
-        -> &Vec<&'ciet_object_lifetime dyn FluidComponentCollectionMethods>{
+    /// returns the static pressure at the bottom of ciet
+    /// relative to the top of ciet, going down whichever branch
+    /// carries flow at the current flowrates
+    ///
+    /// if no branch carries flow, this is the hydrostatic
+    /// pressure change down the heater branch
+    fn get_bottom_static_pressure(&self) -> Pressure {
+
+        let flowing_branch_role = [BranchRole::CTAH, BranchRole::Heater, 
+            BranchRole::DHX].into_iter()
+            .find(|branch_role| 
+                  self.get_branch_mass_flowrate(*branch_role).value != 0.0)
+            .unwrap_or(BranchRole::Heater);
+
+        return self.get_branch(flowing_branch_role)
+            .get_component_pressure_changes(
+                self.get_branch_mass_flowrate(flowing_branch_role))
+            .into_iter()
+            .fold(Pressure::new::<pascal>(0.0), 
+                  |pressure_change_sum, pressure_change| 
+                  pressure_change_sum + pressure_change);
+    }
 
-            return &self.super_collection_vector_immutable;
+    /// returns the static pressure at a pressure tap relative to
+    /// the top of ciet at the current flowrates, or None if there
+    /// is no tap with this name
+    ///
+    /// this is the pressure change across every component
+    /// from the top of the branch down to the tap, in transient
+    /// calculations, the fluid inertia is not included
+    ///
+    /// a shut check valve (or a closed branch valve) can hold any
+    /// pressure difference, so below a shut check valve, the
+    /// pressure is worked out up from the bottom of ciet instead,
+    /// a closed branch is taken to be closed at its bottom
+    pub fn get_pressure_tap_static_pressure(&self, pressure_tap_name: &str) 
+        -> Option<Pressure> {
+
+        let pressure_tap = self.get_pressure_tap(pressure_tap_name)?;
+
+        let branch = self.get_branch(pressure_tap.branch_role);
+        let mass_flowrate = self.get_branch_mass_flowrate(
+            pressure_tap.branch_role);
+
+        let component_pressure_changes = 
+            branch.get_component_pressure_changes(mass_flowrate);
+
+        let components_upstream = pressure_tap.components_upstream
+            .min(component_pressure_changes.len());
+
+        let sum_pressure_changes = |pressure_changes: &[Pressure]| -> Pressure {
+            pressure_changes.iter()
+                .fold(Pressure::new::<pascal>(0.0), 
+                      |pressure_change_sum, pressure_change| 
+                      pressure_change_sum + *pressure_change)
+        };
+
+        let below_shut_check_valve: bool = mass_flowrate.value == 0.0
+            && matches!(branch.get_first_check_valve_index(), 
+                        Some(check_valve_index) 
+                        if check_valve_index < components_upstream);
+
+        if below_shut_check_valve {
+            return Some(self.get_bottom_static_pressure()
+                - sum_pressure_changes(
+                    &component_pressure_changes[components_upstream..]));
         }
 
-    fn set_vector(
-        &mut self,
-        fluid_component_super_vector: 
-        Vec<&'ciet_object_lifetime dyn FluidComponentCollectionMethods>){
-        self.super_collection_vector_immutable = fluid_component_super_vector;
+        return Some(sum_pressure_changes(
+                &component_pressure_changes[..components_upstream]));
+    }
+
+    /// returns the static pressure at the high pressure tap less
+    /// the static pressure at the low pressure tap, or None if
+    /// either tap does not exist
+    pub fn get_static_pressure_difference(
+        &self,
+        high_pressure_tap_name: &str,
+        low_pressure_tap_name: &str) -> Option<Pressure> {
+
+        let high_tap_static_pressure = 
+            self.get_pressure_tap_static_pressure(high_pressure_tap_name)?;
+        let low_tap_static_pressure = 
+            self.get_pressure_tap_static_pressure(low_pressure_tap_name)?;
+
+        return Some(high_tap_static_pressure - low_tap_static_pressure);
+    }
 
+    /// returns the reading of every manometer at the current
+    /// flowrates, in the order the manometers were added
+    pub fn get_manometer_readings(&self) -> Vec<ManometerReading> {
+
+        return self.manometers.iter()
+            .map(|manometer| ManometerReading {
+                name: manometer.name.clone(),
+                // both taps were checked when the manometer was added
+                pressure_difference: self.get_static_pressure_difference(
+                    &manometer.high_pressure_tap,
+                    &manometer.low_pressure_tap).unwrap(),
+            })
+            .collect();
     }
 
+    /// returns the friction, form, hydrostatic and pump pressure
+    /// changes of every component in the heater, dhx and ctah 
+    /// branches (in that order) at the current flowrates
+    ///
+    /// this is for finding out which components are responsible
+    /// when the flowrates do not match experiment
+    pub fn get_pressure_breakdown(&self) -> Vec<BranchPressureBreakdown> {
+
+        return [BranchRole::Heater, BranchRole::DHX, BranchRole::CTAH]
+            .into_iter()
+            .map(|branch_role| {
+                let mass_flowrate = self.get_branch_mass_flowrate(branch_role);
+
+                BranchPressureBreakdown {
+                    branch_role,
+                    mass_flowrate,
+                    components: self.get_branch(branch_role)
+                        .get_component_pressure_breakdown(mass_flowrate),
+                }
+            })
+            .collect();
+    }
+
+    /// writes the pressure breakdown at the current flowrates
+    /// to a csv file, one line per component
+    pub fn write_pressure_breakdown_csv<P: AsRef<Path>>(&self, path: P) 
+        -> std::io::Result<()> {
+
+        return fs::write(path, 
+                         pressure_breakdown_to_csv_string(
+                             &self.get_pressure_breakdown()));
+    }
+
+    /// returns the reynolds number, mean velocity and flow regime
+    /// of every component in the heater, dhx and ctah branches
+    /// (in that order) at the current flowrates
+    pub fn get_flow_diagnostics(&self) -> Vec<BranchFlowDiagnostics> {
+
+        return [BranchRole::Heater, BranchRole::DHX, BranchRole::CTAH]
+            .into_iter()
+            .map(|branch_role| {
+                let mass_flowrate = self.get_branch_mass_flowrate(branch_role);
+
+                BranchFlowDiagnostics {
+                    branch_role,
+                    mass_flowrate,
+                    components: self.get_branch(branch_role)
+                        .get_component_flow_diagnostics(mass_flowrate),
+                }
+            })
+            .collect();
+    }
+
+    /// returns a warning for each component whose loss correlations
+    /// are used outside the reynolds numbers they were fitted over
+    /// at the current flowrates, so it is empty if the twin is 
+    /// within its fitted range
+    pub fn get_flow_diagnostic_warnings(&self) -> Vec<String> {
+        return get_flow_diagnostic_warnings(&self.get_flow_diagnostics());
+    }
+
+    /// returns the inertance (sum of L/A) of the heater, dhx 
+    /// and ctah branches in that order
+    pub fn get_branch_inertances(&self) 
+        -> (ReciprocalLength, ReciprocalLength, ReciprocalLength) {
+        return (self.heater_branch_inertance,
+                self.dhx_branch_inertance,
+                self.ctah_branch_inertance);
+    }
+
+    /// returns the three branches of ciet as parallel
+    /// fluid component collections
+    fn get_branch_vector(&self) 
+        -> Vec<&dyn FluidComponentCollectionMethods> {
+
+        return vec![&self.hydraulics.ctah_branch, 
+                    &self.hydraulics.heater_branch, 
+                    &self.hydraulics.dhx_branch];
+    }
+
+    // constructor
+
+    pub fn new(ctah_branch: CTAHBranch,
+               mut heater_branch: HeaterBranch,
+               mut dhx_branch: DHXBranch) -> Self {
+
+        // we move ownership of the branches into the facility
+        //
+        // the fluid temperature is whatever the branches were built
+        // at (eg. from the component catalogue), the ctah branch 
+        // always has components since it has a pump
+        let fluid_temperature = ctah_branch.get_fluid_temperature()
+            .unwrap_or(ThermodynamicTemperature::new::<degree_celsius>(21.0));
+
+        // and the other branches are brought to the same temperature
+        heater_branch.set_fluid_temperature(fluid_temperature);
+        dhx_branch.set_fluid_temperature(fluid_temperature);
+
+        let heater_branch_inertance = get_branch_inertance(&heater_branch);
+        let dhx_branch_inertance = get_branch_inertance(&dhx_branch);
+        let ctah_branch_inertance = get_branch_inertance(&ctah_branch);
+        
+        return Self { 
+            hydraulics: CIETHydraulics::new(
+                ctah_branch, heater_branch, dhx_branch),
+            solver_error: None,
+            solver_iteration_count: 0,
+            converged_inputs: None,
+            converged_pressure_change: None,
+            last_calculation_was_cache_hit: false,
+            cache_hit_count: 0,
+            cache_miss_count: 0,
+            correlation_out_of_range_count: 0,
+            heater_branch_lookup_table: None,
+            dhx_branch_lookup_table: None,
+            ctah_branch_lookup_table: None,
+            ctah_lookup_table_pump_pressure: Pressure::new::<pascal>(0.0),
+            ctah_lookup_table_pump_speed_rpm: 0.0,
+            lookup_table_valve_openings_percent: (100.0, 100.0, 100.0),
+            last_calculation_used_branch_lookup_tables: false,
+            unsettled_lookup_table_inputs: None,
+            branch_lookup_table_settings: None,
+            fluid_temperature,
+            heater_branch_inertance,
+            dhx_branch_inertance,
+            ctah_branch_inertance,
+            pressure_taps: vec![],
+            manometers: vec![],
+        }
+
+    }
+
+
 }
 
-impl<'ciet_object_lifetime> 
-FluidComponentCollectionMethods for CIETIsothermalFacility<'ciet_object_lifetime> {
+impl FluidComponentSuperCollectionParallelAssociatedFunctions for 
+CIETIsothermalFacility {}
+
+impl FluidComponentCollectionMethods for CIETIsothermalFacility {
 
 
     /// calculates pressure change when given a mass flowrate
@@ -262,13 +1410,13 @@ FluidComponentCollectionMethods for CIETIsothermalFacility<'ciet_object_lifetime
         &self, 
         fluid_mass_flowrate: MassRate) -> Pressure{
         let fluid_component_collection_vector = 
-            self.get_immutable_vector();
+            self.get_branch_vector();
 
         let pressure_change = 
             <Self as FluidComponentSuperCollectionParallelAssociatedFunctions>
             ::calculate_pressure_change_from_mass_flowrate(
                 fluid_mass_flowrate, 
-                fluid_component_collection_vector);
+                &fluid_component_collection_vector);
 
         return pressure_change;
     }
@@ -280,13 +1428,13 @@ FluidComponentCollectionMethods for CIETIsothermalFacility<'ciet_object_lifetime
         pressure_change: Pressure) -> MassRate{
 
         let fluid_component_collection_vector = 
-            self.get_immutable_vector();
+            self.get_branch_vector();
         
         let mass_flowrate = 
             <Self as FluidComponentSuperCollectionParallelAssociatedFunctions>
             ::calculate_mass_flowrate_from_pressure_change(
                 pressure_change, 
-                fluid_component_collection_vector);
+                &fluid_component_collection_vector);
 
         return mass_flowrate;
     }
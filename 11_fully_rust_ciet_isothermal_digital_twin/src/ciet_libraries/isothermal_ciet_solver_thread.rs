@@ -0,0 +1,233 @@
+extern crate fluid_mechanics_rust;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use fluid_mechanics_rust::prelude::*;
+
+use crate::{CIETIsothermalFacility, CIETSolverError, ManometerReading,
+    BranchPressureBreakdown, BranchFlowDiagnostics};
+
+/// how the solver thread moves the flowrates on each time
+/// it gets a setpoint
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HydraulicTimestepping {
+
+    /// the flowrates jump straight to the steady state
+    SteadyState,
+
+    /// the flowrates are stepped forward by this timestep,
+    /// with the fluid inertia of each branch
+    FixedTimestep(Time),
+
+    /// the flowrates are stepped forward by however much wall
+    /// clock time has passed since the last setpoint was solved,
+    /// so they move in real time whatever the server polling interval
+    RealTime,
+}
+
+/// these are the setpoints which the user (usually the opcua
+/// server) sends to the ciet solver thread
+#[derive(Clone, Copy, Debug)]
+pub struct IsothermalCIETSetpoints {
+    pub ctah_pump_pressure: Pressure,
+
+    /// only used if the ctah pump has a curve
+    pub ctah_pump_speed_rpm: f64,
+
+    pub heater_branch_valve_open: bool,
+    pub dhx_branch_valve_open: bool,
+    pub ctah_branch_valve_open: bool,
+
+    /// how far open (0-100%) each branch valve is
+    pub heater_branch_valve_opening_percent: f64,
+    pub dhx_branch_valve_opening_percent: f64,
+    pub ctah_branch_valve_opening_percent: f64,
+
+    pub fluid_temperature: ThermodynamicTemperature,
+    pub hydraulic_timestepping: HydraulicTimestepping,
+}
+
+/// these are the results which the ciet solver thread sends
+/// back to the user after each calculation
+///
+/// if the solver failed, the flowrates are from the last
+/// converged calculation and solver_error says what went wrong,
+/// solver_error is also set if the fluid temperature setpoint
+/// was out of range or a pump or valve setpoint was not a number
+#[derive(Clone, Debug)]
+pub struct IsothermalCIETResults {
+    pub calc_time: Duration,
+    pub solver_error: Option<CIETSolverError>,
+    pub solver_iteration_count: usize,
+    pub cache_hit: bool,
+    pub cache_hit_count: u64,
+    pub cache_miss_count: u64,
+
+    /// true if the facility has branch lookup tables but did not
+    /// use them in this calculation (see
+    /// CIETIsothermalFacility::get_branch_lookup_tables_bypassed)
+    pub branch_lookup_tables_bypassed: bool,
+
+    /// how many times a component has been found outside the
+    /// reynolds numbers its correlations were fitted over
+    pub correlation_out_of_range_count: u64,
+    pub ctah_branch_mass_flowrate: MassRate,
+    pub heater_branch_mass_flowrate: MassRate,
+    pub dhx_branch_mass_flowrate: MassRate,
+    pub fluid_temperature: ThermodynamicTemperature,
+
+    /// the timestep the flowrates were stepped forward by,
+    /// or None if they are the steady state
+    pub timestep: Option<Time>,
+
+    /// what each manometer in the facility reads at these flowrates
+    pub manometer_readings: Vec<ManometerReading>,
+
+    /// the pressure change across every component at these
+    /// flowrates, split into friction, form, hydrostatic and pump
+    pub pressure_breakdown: Vec<BranchPressureBreakdown>,
+
+    /// the reynolds number, velocity and flow regime in every
+    /// component at these flowrates
+    pub flow_diagnostics: Vec<BranchFlowDiagnostics>,
+}
+
+/// how many setpoints can queue up before the sender has to
+/// either wait or drop its setpoints
+///
+/// the solver only uses the latest setpoint anyway, so this
+/// does not need to be large
+pub const SETPOINT_CHANNEL_CAPACITY: usize = 16;
+
+/// moves the ciet facility into its own solver thread
+///
+/// the user gets back a sender for setpoints and a receiver for
+/// results, so the user never has to wait for a calculation
+/// to finish
+///
+/// (1) the solver thread waits for setpoints
+/// (2) if several setpoints have queued up while it was busy, only
+/// the latest one is used
+/// (3) it calculates ciet (either the steady state or one timestep,
+/// depending on the setpoints) and sends the results back
+///
+/// the thread stops once the setpoint sender or the result
+/// receiver is dropped
+pub fn spawn_isothermal_ciet_solver_thread(
+    mut ciet_isothermal_facility: CIETIsothermalFacility) ->
+    (SyncSender<IsothermalCIETSetpoints>,
+     Receiver<IsothermalCIETResults>,
+     JoinHandle<()>) {
+
+    let (setpoint_sender, setpoint_receiver) =
+        mpsc::sync_channel::<IsothermalCIETSetpoints>(
+            SETPOINT_CHANNEL_CAPACITY);
+
+    let (result_sender, result_receiver) =
+        mpsc::channel::<IsothermalCIETResults>();
+
+    let solver_thread = thread::spawn(move || {
+
+        // for real time stepping, the first timestep is
+        // measured from when the thread starts
+        let mut last_solve_time = Instant::now();
+
+        // this loop ends when the setpoint sender is dropped
+        for setpoints in setpoint_receiver.iter() {
+
+            // skip to the latest setpoint if the solver fell behind
+            let setpoints = match setpoint_receiver.try_iter().last() {
+                Some(latest_setpoints) => latest_setpoints,
+                None => setpoints,
+            };
+
+            ciet_isothermal_facility.set_heater_branch_valve_open(
+                setpoints.heater_branch_valve_open);
+            ciet_isothermal_facility.set_dhx_branch_valve_open(
+                setpoints.dhx_branch_valve_open);
+            ciet_isothermal_facility.set_ctah_branch_valve_open(
+                setpoints.ctah_branch_valve_open);
+
+            // if a pump or valve setpoint is not a number or the
+            // temperature is out of range, ciet stays where it was and
+            // the (first) error is reported with the results, so the
+            // setpoints compared against the last converged
+            // calculation are always numbers
+            let setpoint_results = [
+                ciet_isothermal_facility.set_ctah_pump_pressure(
+                    setpoints.ctah_pump_pressure),
+                ciet_isothermal_facility.set_ctah_pump_speed(
+                    setpoints.ctah_pump_speed_rpm),
+                ciet_isothermal_facility.set_heater_branch_valve_opening(
+                    setpoints.heater_branch_valve_opening_percent),
+                ciet_isothermal_facility.set_dhx_branch_valve_opening(
+                    setpoints.dhx_branch_valve_opening_percent),
+                ciet_isothermal_facility.set_ctah_branch_valve_opening(
+                    setpoints.ctah_branch_valve_opening_percent),
+                ciet_isothermal_facility.set_fluid_temperature(
+                    setpoints.fluid_temperature),
+            ];
+
+            let setpoint_error: Option<CIETSolverError> = setpoint_results
+                .iter()
+                .find_map(|setpoint_result| setpoint_result.err());
+
+            let wall_clock_timestep = Time::new::<second>(
+                last_solve_time.elapsed().as_secs_f64());
+            last_solve_time = Instant::now();
+
+            let timestep: Option<Time> = match setpoints.hydraulic_timestepping {
+                HydraulicTimestepping::SteadyState => None,
+                HydraulicTimestepping::FixedTimestep(timestep) => Some(timestep),
+                HydraulicTimestepping::RealTime => Some(wall_clock_timestep),
+            };
+
+            let (calc_time,
+                 ctah_branch_mass_flowrate,
+                 heater_branch_mass_flowrate,
+                 dhx_branch_mass_flowrate)
+                 = match timestep {
+                     None => ciet_isothermal_facility.calculate(),
+                     Some(timestep) => 
+                         ciet_isothermal_facility.advance_timestep(timestep),
+                 };
+
+            let results = IsothermalCIETResults {
+                calc_time,
+                solver_error: setpoint_error
+                    .or(ciet_isothermal_facility.get_solver_error()),
+                solver_iteration_count: 
+                    ciet_isothermal_facility.get_solver_iteration_count(),
+                cache_hit: 
+                    ciet_isothermal_facility.get_last_calculation_was_cache_hit(),
+                cache_hit_count: ciet_isothermal_facility.get_cache_hit_count(),
+                cache_miss_count: ciet_isothermal_facility.get_cache_miss_count(),
+                branch_lookup_tables_bypassed: 
+                    ciet_isothermal_facility.get_branch_lookup_tables_bypassed(),
+                correlation_out_of_range_count: 
+                    ciet_isothermal_facility.get_correlation_out_of_range_count(),
+                ctah_branch_mass_flowrate,
+                heater_branch_mass_flowrate,
+                dhx_branch_mass_flowrate,
+                fluid_temperature: 
+                    ciet_isothermal_facility.get_fluid_temperature(),
+                timestep,
+                manometer_readings: 
+                    ciet_isothermal_facility.get_manometer_readings(),
+                pressure_breakdown: 
+                    ciet_isothermal_facility.get_pressure_breakdown(),
+                flow_diagnostics: 
+                    ciet_isothermal_facility.get_flow_diagnostics(),
+            };
+
+            // if nobody is listening anymore, there is no point
+            // in calculating
+            if result_sender.send(results).is_err() {
+                break;
+            }
+        }
+    });
+
+    return (setpoint_sender, result_receiver, solver_thread);
+}
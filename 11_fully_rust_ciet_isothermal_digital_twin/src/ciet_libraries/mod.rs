@@ -5,6 +5,32 @@ pub mod therminol_pipe;
 /// contains a class or struct for isothermal therminol components
 pub mod therminol_component;
 
+/// contains a class or struct for therminol valves whose opening
+/// can be changed, with linear, equal percentage and quick opening
+/// characteristics
+pub mod therminol_valve;
+pub use therminol_valve::*;
+
+/// contains a class or struct for therminol check valves with a
+/// cracking pressure and (optionally) some reverse leakage
+pub mod therminol_check_valve;
+pub use therminol_check_valve::*;
+
+/// contains serialisable form loss and friction factor correlations
+/// for therminol custom components
+pub mod custom_correlations;
+pub use custom_correlations::*;
+
+/// contains a head against flowrate curve for the ctah pump,
+/// scaled to other pump speeds with the affinity laws
+pub mod pump_curve;
+pub use pump_curve::*;
+
+/// contains a trait for therminol pipes and components whose
+/// fluid temperature can be changed after they are built
+pub mod therminol_fluid_component;
+pub use therminol_fluid_component::*;
+
 /// contains classes for ciet components within 
 /// ctah branch
 /// heater branch
@@ -13,10 +39,63 @@ pub mod therminol_component;
 pub mod component_libraries;
 pub use component_libraries::*;
 
+/// contains a catalogue of pipes and custom components
+/// which can be loaded from toml or json files
+/// instead of being hard coded
+pub mod component_catalogue;
+pub use component_catalogue::*;
+
+
+/// contains the friction, form, hydrostatic and pump pressure
+/// changes of each component at a mass flowrate, and a csv writer
+pub mod pressure_breakdown;
+pub use pressure_breakdown::*;
+
+/// contains the reynolds number, velocity and flow regime of each
+/// component at a mass flowrate, with warnings for correlations
+/// used outside their fitted range
+pub mod flow_diagnostics;
+pub use flow_diagnostics::*;
 
 /// contains class or struct for isothermal branches in ciet
 /// a branch is a series of pipes in ciet
 pub mod branch;
+pub use branch::*;
+
+/// contains the errors which the ciet solvers return
+/// instead of panicking
+pub mod ciet_solver_error;
+pub use ciet_solver_error::*;
+
+/// contains a precomputed mass flowrate against pressure change
+/// table for branches, to speed up the parallel branch solver
+pub mod branch_lookup_table;
+pub use branch_lookup_table::*;
+
+/// contains a solver for any number of branches connected
+/// in parallel
+pub mod parallel_branch_solver;
+pub use parallel_branch_solver::*;
+
+/// contains branches with fluid inertia, so that the parallel
+/// branch solver can step the flowrates forward in time
+pub mod transient_hydraulics;
+pub use transient_hydraulics::*;
+
+/// contains the axial nodes of branch components and the 1D
+/// energy equation solved over them
+pub mod branch_energy_equation;
+pub use branch_energy_equation::*;
+
+/// contains a graph based hydraulic network solver
+/// for loops with several junctions (eg. ciet coupled to DRACS)
+pub mod hydraulic_network;
+pub use hydraulic_network::*;
+
+/// contains the ctah pump, branch valves, branches and flowrates
+/// shared by the isothermal and non-isothermal ciet facilities
+pub mod ciet_hydraulics;
+pub use ciet_hydraulics::*;
 
 /// contains the class representing ciet facility in isothermal operation
 /// primary loop only
@@ -24,5 +103,30 @@ pub mod branch;
 pub mod isothermal_ciet_facility;
 pub use isothermal_ciet_facility::*;
 
+/// contains the class representing the ciet primary loop with
+/// heat transfer, the heater and ctah are the heat source and sink
+pub mod non_isothermal_ciet_facility;
+pub use non_isothermal_ciet_facility::*;
+
+/// contains the DRACS loop, which is cooled by the tchx and
+/// coupled to the primary loop through the dhx
+pub mod dracs_loop;
+pub use dracs_loop::*;
+
+/// contains pressure taps at component boundaries and the
+/// manometers which read the static pressure difference between them
+pub mod pressure_taps;
+pub use pressure_taps::*;
+
+/// contains a topology file format which assembles catalogue
+/// components into branches and the ciet facility
+pub mod facility_topology;
+pub use facility_topology::*;
+
+/// contains the solver thread which owns the ciet facility
+/// and exchanges setpoints and results with the user over channels
+pub mod isothermal_ciet_solver_thread;
+pub use isothermal_ciet_solver_thread::*;
+
 
 
@@ -0,0 +1,683 @@
+extern crate fluid_mechanics_rust;
+use crate::{ctah_branch::*, HeaterBranch, DHXBranch, CIETHydraulics,
+    CIETSolverError, therminol_temperature_in_range, DRACSLoop, PumpCurve,
+    ComponentThermalNodes, ComponentHeatTransfer, build_branch_thermal_nodes,
+    update_branch_fluid_temperatures, solve_branch_energy_equation,
+    get_junction_mixed_temperature, BranchRole, FacilityTopologyError};
+
+use fluid_mechanics_rust::prelude::*;
+
+/// the components of each branch which exchange heat,
+/// these usually come from the facility topology
+/// (see FacilityTopology::get_heat_transfer_component_names)
+///
+/// the heater power goes into the heater branch components,
+/// the ctah branch components lose heat to the ctah air, and
+/// the dhx branch components exchange heat with DRACS
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeatTransferComponentNames {
+    pub heater_branch: Vec<String>,
+    pub dhx_branch: Vec<String>,
+    pub ctah_branch: Vec<String>,
+}
+
+impl HeatTransferComponentNames {
+
+    /// returns the heat transfer components of the branch
+    /// with this role
+    pub fn get_component_names(&self, branch_role: BranchRole) -> &[String] {
+        match branch_role {
+            BranchRole::Heater => return &self.heater_branch,
+            BranchRole::DHX => return &self.dhx_branch,
+            BranchRole::CTAH => return &self.ctah_branch,
+        }
+    }
+}
+
+/// This is a struct representing the CIET primary loop
+/// with heat transfer
+///
+/// the hydraulics are the same as the isothermal facility
+/// (the heater, dhx and ctah branches in parallel), but each
+/// component is split into axial nodes which have their own
+/// fluid temperature, and a 1D energy equation is solved over them
+///
+/// the heater adds its power to the fluid in the heat transfer
+/// components of the heater branch (the CietHeaterVersion1 in ciet),
+/// and the ctah (CTAHVertical and CTAHHorizontal in ciet) removes 
+/// heat to the ctah air through a thermal conductance (UA)
+///
+/// each component's density and viscosity are evaluated at its
+/// average node temperature, so the hydrostatic pressure change
+/// of hot and cold legs differs and buoyancy drives flow,
+/// this gives natural circulation when the pump is off
+///
+/// DRACS can be coupled to the dhx (see set_dracs_loop), the dhx
+/// shell side (the heat transfer components of the dhx branch)
+/// and the DRACS dhx tube side then exchange heat through
+/// a thermal conductance (UA), without DRACS the dhx branch is
+/// adiabatic
+///
+/// what is not modelled (yet):
+/// (1) heat capacity of the pipe walls and heater
+/// (2) heat losses from pipes to the room
+/// (3) axial conduction in the fluid, only advection is solved
+/// (4) the temperature profile along the dhx, each side of the
+/// dhx sees the average temperature of the other side
+pub struct CIETNonIsothermalFacility {
+
+    // the ctah pump, the branch valves, the branches and the
+    // latest branch flowrates, these are kept the same way
+    // as in the isothermal facility
+    hydraulics: CIETHydraulics,
+
+    // the components of each branch which exchange heat
+    heat_transfer_component_names: HeatTransferComponentNames,
+
+    /// power going into the fluid in the heater
+    pub heater_power: Power,
+
+    /// overall heat transfer coefficient times area (UA)
+    /// of the ctah, split evenly between its components
+    pub ctah_thermal_conductance: ThermalConductance,
+
+    /// the air temperature on the other side of the ctah
+    pub ctah_air_temperature: ThermodynamicTemperature,
+
+    /// overall heat transfer coefficient times area (UA) between
+    /// the dhx shell side and the DRACS dhx tube side, split
+    /// evenly between the dhx shell side components
+    pub dhx_thermal_conductance: ThermalConductance,
+
+    // axial nodes of every component in each branch,
+    // in the same order as the components in the branch
+    heater_branch_thermal_nodes: Vec<ComponentThermalNodes>,
+    dhx_branch_thermal_nodes: Vec<ComponentThermalNodes>,
+    ctah_branch_thermal_nodes: Vec<ComponentThermalNodes>,
+
+    // DRACS, if it is coupled to the dhx
+    dracs_loop: Option<DRACSLoop>,
+
+    // the top and bottom of ciet, where the branches meet,
+    // the fluid coming in from each branch is perfectly mixed
+    top_node_temperature: ThermodynamicTemperature,
+    bottom_node_temperature: ThermodynamicTemperature,
+
+    // heat removed by the ctah in the latest timestep
+    ctah_heat_removal_rate: Power,
+
+    // heat going from the dhx shell side to DRACS
+    // in the latest timestep
+    dhx_heat_transfer_rate: Power,
+
+    simulation_time: Time,
+
+    // the error from the latest timestep, if any
+    solver_error: Option<CIETSolverError>,
+
+    therminol_properties: TherminolVP1Properties,
+}
+
+/// for this object,
+///
+/// i want to be able to
+/// (1) set the ctah pump pressure (or speed), valves, heater power
+/// and ctah cooling
+/// (2) advance the facility by one timestep, solving the hydraulics
+/// first and then the energy equation with those flowrates
+/// (3) obtain the node temperatures of every component,
+/// and the top and bottom temperatures of ciet
+/// (4) keep the last state if the solver fails, and report
+/// what went wrong
+/// (5) couple DRACS to the dhx, and advance it along
+/// with the primary loop
+impl CIETNonIsothermalFacility {
+
+    pub fn get_ctah_pump_pressure(&self) -> Pressure {
+        return self.hydraulics.ctah_pump_pressure;
+    }
+
+    /// sets the ctah pump pressure, a pressure which is not a
+    /// number is rejected
+    pub fn set_ctah_pump_pressure(
+        &mut self, 
+        user_specified_pressure: Pressure) -> Result<(), CIETSolverError> {
+        return self.hydraulics.set_ctah_pump_pressure(user_specified_pressure);
+    }
+
+    pub fn get_ctah_pump_speed(&self) -> f64 {
+        return self.hydraulics.ctah_pump_speed_rpm;
+    }
+
+    /// sets the ctah pump speed in rpm, this does nothing
+    /// unless the pump has a curve, a speed which is not a
+    /// number is rejected
+    pub fn set_ctah_pump_speed(
+        &mut self, 
+        ctah_pump_speed_rpm: f64) -> Result<(), CIETSolverError> {
+        return self.hydraulics.set_ctah_pump_speed(ctah_pump_speed_rpm);
+    }
+
+    /// gives the ctah pump a head against flowrate curve, or takes
+    /// it away with None, the pump pressure still acts on top of it
+    pub fn set_ctah_pump_curve(&mut self, ctah_pump_curve: Option<PumpCurve>){
+        self.hydraulics.ctah_branch.set_ctah_pump_curve(ctah_pump_curve);
+    }
+
+    pub fn get_ctah_pump_curve(&self) -> Option<PumpCurve> {
+        return self.hydraulics.ctah_branch.get_ctah_pump_curve();
+    }
+
+    pub fn get_ctah_branch_mass_flowrate(&self) -> MassRate {
+        return self.hydraulics.ctah_branch_mass_flowrate;
+    }
+
+    pub fn get_dhx_branch_mass_flowrate(&self) -> MassRate {
+        return self.hydraulics.dhx_branch_mass_flowrate;
+    }
+
+    pub fn get_heater_branch_mass_flowrate(&self) -> MassRate {
+        return self.hydraulics.heater_branch_mass_flowrate;
+    }
+
+    pub fn set_heater_branch_valve_open(&mut self, valve_open: bool){
+        self.hydraulics.heater_branch_valve_open = valve_open;
+    }
+
+    pub fn set_dhx_branch_valve_open(&mut self, valve_open: bool){
+        self.hydraulics.dhx_branch_valve_open = valve_open;
+    }
+
+    pub fn set_ctah_branch_valve_open(&mut self, valve_open: bool){
+        self.hydraulics.ctah_branch_valve_open = valve_open;
+    }
+
+    /// sets how far open (0-100%) the heater branch valve is,
+    /// same as the isothermal facility, openings are clamped
+    /// to 0-100% and non numbers are rejected
+    pub fn set_heater_branch_valve_opening(
+        &mut self, 
+        opening_percent: f64) -> Result<(), CIETSolverError> {
+        return self.hydraulics.set_valve_opening_percent(
+            BranchRole::Heater, opening_percent);
+    }
+
+    /// sets how far open (0-100%) the dhx branch valve is
+    pub fn set_dhx_branch_valve_opening(
+        &mut self, 
+        opening_percent: f64) -> Result<(), CIETSolverError> {
+        return self.hydraulics.set_valve_opening_percent(
+            BranchRole::DHX, opening_percent);
+    }
+
+    /// sets how far open (0-100%) the ctah branch valve is
+    pub fn set_ctah_branch_valve_opening(
+        &mut self, 
+        opening_percent: f64) -> Result<(), CIETSolverError> {
+        return self.hydraulics.set_valve_opening_percent(
+            BranchRole::CTAH, opening_percent);
+    }
+
+    pub fn set_heater_power(&mut self, heater_power: Power){
+        self.heater_power = heater_power;
+    }
+
+    pub fn get_heater_power(&self) -> Power {
+        return self.heater_power;
+    }
+
+    /// sets the UA of the ctah and the air temperature
+    /// it loses heat to
+    pub fn set_ctah_cooling(
+        &mut self,
+        ctah_thermal_conductance: ThermalConductance,
+        ctah_air_temperature: ThermodynamicTemperature){
+        self.ctah_thermal_conductance = ctah_thermal_conductance;
+        self.ctah_air_temperature = ctah_air_temperature;
+    }
+
+    /// returns the heat removed by the ctah in the latest timestep
+    pub fn get_ctah_heat_removal_rate(&self) -> Power {
+        return self.ctah_heat_removal_rate;
+    }
+
+    /// couples DRACS to the dhx through the given thermal
+    /// conductance (UA), DRACS is then advanced with the
+    /// primary loop at every timestep
+    pub fn set_dracs_loop(
+        &mut self,
+        dracs_loop: DRACSLoop,
+        dhx_thermal_conductance: ThermalConductance){
+        self.dracs_loop = Some(dracs_loop);
+        self.dhx_thermal_conductance = dhx_thermal_conductance;
+    }
+
+    pub fn get_dracs_loop(&self) -> Option<&DRACSLoop> {
+        return self.dracs_loop.as_ref();
+    }
+
+    /// for setting the tchx cooling of the coupled DRACS
+    pub fn get_dracs_loop_mut(&mut self) -> Option<&mut DRACSLoop> {
+        return self.dracs_loop.as_mut();
+    }
+
+    /// returns the heat going from the dhx shell side to DRACS
+    /// in the latest timestep, zero without DRACS
+    pub fn get_dhx_heat_transfer_rate(&self) -> Power {
+        return self.dhx_heat_transfer_rate;
+    }
+
+    pub fn get_top_node_temperature(&self) -> ThermodynamicTemperature {
+        return self.top_node_temperature;
+    }
+
+    pub fn get_bottom_node_temperature(&self) -> ThermodynamicTemperature {
+        return self.bottom_node_temperature;
+    }
+
+    pub fn get_simulation_time(&self) -> Time {
+        return self.simulation_time;
+    }
+
+    /// returns the error from the latest timestep,
+    /// or None if it converged
+    pub fn get_solver_error(&self) -> Option<CIETSolverError> {
+        return self.solver_error;
+    }
+
+    pub fn get_heater_branch_thermal_nodes(&self) -> &[ComponentThermalNodes] {
+        return &self.heater_branch_thermal_nodes;
+    }
+
+    pub fn get_dhx_branch_thermal_nodes(&self) -> &[ComponentThermalNodes] {
+        return &self.dhx_branch_thermal_nodes;
+    }
+
+    pub fn get_ctah_branch_thermal_nodes(&self) -> &[ComponentThermalNodes] {
+        return &self.ctah_branch_thermal_nodes;
+    }
+
+    /// returns the components of each branch which exchange heat
+    pub fn get_heat_transfer_component_names(&self) -> &HeatTransferComponentNames {
+        return &self.heat_transfer_component_names;
+    }
+
+    /// returns the axial nodes of a component in any of the
+    /// branches, or None if there is no component with this name
+    pub fn get_component_thermal_nodes(&self, name: &str)
+        -> Option<&ComponentThermalNodes> {
+
+        return self.heater_branch_thermal_nodes.iter()
+            .chain(self.dhx_branch_thermal_nodes.iter())
+            .chain(self.ctah_branch_thermal_nodes.iter())
+            .find(|component_thermal_nodes| component_thermal_nodes.name == name);
+    }
+
+    /// sets every node in the facility (and the top and bottom
+    /// of ciet) to the same fluid temperature, including DRACS
+    /// if it is coupled
+    pub fn set_uniform_fluid_temperature(
+        &mut self,
+        fluid_temperature: ThermodynamicTemperature)
+        -> Result<(), CIETSolverError> {
+
+        if !therminol_temperature_in_range(fluid_temperature) {
+            return Err(CIETSolverError::FluidTemperatureOutOfRange {
+                fluid_temperature });
+        }
+
+        for component_thermal_nodes in self.heater_branch_thermal_nodes.iter_mut()
+            .chain(self.dhx_branch_thermal_nodes.iter_mut())
+            .chain(self.ctah_branch_thermal_nodes.iter_mut()) {
+            for node_temperature in component_thermal_nodes.node_temperatures.iter_mut() {
+                *node_temperature = fluid_temperature;
+            }
+        }
+
+        self.top_node_temperature = fluid_temperature;
+        self.bottom_node_temperature = fluid_temperature;
+
+        self.update_component_fluid_temperatures();
+
+        if let Some(dracs_loop) = self.dracs_loop.as_mut() {
+            dracs_loop.set_uniform_fluid_temperature(fluid_temperature)?;
+        }
+
+        return Ok(());
+    }
+
+    /// advances the facility by one timestep
+    ///
+    /// the flowrates are solved first with the current component
+    /// temperatures (the momentum equation is taken as quasi steady),
+    /// then the energy equation is solved over every node with those
+    /// flowrates, and finally each component is brought to its new
+    /// average node temperature for the next timestep
+    ///
+    /// the energy equation is implicit (backward euler) and upwinded,
+    /// so any timestep is stable, but the top and bottom of ciet take
+    /// their temperatures from the previous timestep
+    ///
+    /// if the solver fails, the error is returned and the
+    /// facility keeps its last state
+    pub fn advance_timestep(&mut self, timestep: Time) ->
+        Result<(MassRate, MassRate, MassRate), CIETSolverError> {
+
+        // the therminol correlations only hold between 20 and 180 C,
+        // so stop here rather than extrapolate
+        if let Some(fluid_temperature) = self.get_out_of_range_temperature() {
+            let solver_error = CIETSolverError::FluidTemperatureOutOfRange {
+                fluid_temperature };
+            self.solver_error = Some(solver_error);
+            return Err(solver_error);
+        }
+
+        let (ctah_branch_flowrate,
+             heater_branch_flowrate,
+             dhx_branch_flowrate) = match self.solve_mass_flowrates() {
+            Ok(mass_flowrates) => mass_flowrates,
+            Err(solver_error) => {
+                self.solver_error = Some(solver_error);
+                return Err(solver_error);
+            },
+        };
+
+        // the dhx shell side and DRACS tube side each exchange heat
+        // with the other side's temperature from the start of the
+        // timestep, so DRACS can be advanced on its own before
+        // the primary loop energy equation
+        //
+        // DRACS goes first, so that if it fails the primary loop
+        // keeps its last state too
+        let dhx_shell_side_temperature = self.get_dhx_shell_side_temperature();
+
+        let dhx_tube_side_temperature = match self.dracs_loop.as_mut() {
+            Some(dracs_loop) => {
+
+                let dhx_tube_side_temperature =
+                    dracs_loop.get_dhx_tube_side_temperature();
+
+                if let Some(dhx_shell_side_temperature) = dhx_shell_side_temperature {
+                    let dhx_tube_side_heat_transfer =
+                        ComponentHeatTransfer::thermal_conductance(
+                            self.dhx_thermal_conductance,
+                            dhx_shell_side_temperature);
+
+                    if let Err(solver_error) = dracs_loop.advance_timestep(
+                        timestep, dhx_tube_side_heat_transfer) {
+                        self.solver_error = Some(solver_error);
+                        return Err(solver_error);
+                    }
+                }
+
+                Some(dhx_tube_side_temperature)
+            },
+            None => None,
+        };
+
+        self.hydraulics.ctah_branch_mass_flowrate = ctah_branch_flowrate;
+        self.hydraulics.heater_branch_mass_flowrate = heater_branch_flowrate;
+        self.hydraulics.dhx_branch_mass_flowrate = dhx_branch_flowrate;
+
+        // now for the energy equation,
+        // the heater power is split between the heater components,
+        // the ctah UA between the ctah components (eg. its vertical 
+        // and horizontal sections), and the dhx UA between the 
+        // dhx shell side components, which lose heat to DRACS
+        let heater_component_names = 
+            self.heat_transfer_component_names.get_component_names(
+                BranchRole::Heater);
+        let ctah_component_names = 
+            self.heat_transfer_component_names.get_component_names(
+                BranchRole::CTAH);
+        let dhx_component_names = 
+            self.heat_transfer_component_names.get_component_names(
+                BranchRole::DHX);
+
+        let heater_component_heat_transfer = ComponentHeatTransfer::heat_source(
+            self.heater_power/heater_component_names.len() as f64);
+
+        let ctah_component_heat_transfer = ComponentHeatTransfer::thermal_conductance(
+            self.ctah_thermal_conductance/ctah_component_names.len() as f64,
+            self.ctah_air_temperature);
+
+        let mut component_heat_transfer: Vec<(&str, ComponentHeatTransfer)> = vec![];
+
+        for heater_component_name in heater_component_names.iter() {
+            component_heat_transfer.push(
+                (heater_component_name, heater_component_heat_transfer));
+        }
+
+        for ctah_component_name in ctah_component_names.iter() {
+            component_heat_transfer.push(
+                (ctah_component_name, ctah_component_heat_transfer));
+        }
+
+        if let Some(dhx_tube_side_temperature) = dhx_tube_side_temperature {
+
+            let dhx_component_heat_transfer = 
+                ComponentHeatTransfer::thermal_conductance(
+                    self.dhx_thermal_conductance/dhx_component_names.len() as f64,
+                    dhx_tube_side_temperature);
+
+            for dhx_component_name in dhx_component_names.iter() {
+                component_heat_transfer.push(
+                    (dhx_component_name, dhx_component_heat_transfer));
+            }
+        }
+
+        let mut branch_outlets: Vec<(MassRate, ThermodynamicTemperature)> = vec![];
+
+        // the only heat lost from the dhx branch goes to DRACS,
+        // and the only heat lost from the ctah branch goes to the ctah air
+        let mut dhx_heat_transfer_rate = Power::new::<watt>(0.0);
+        let mut ctah_heat_removal_rate = Power::new::<watt>(0.0);
+
+        for (branch_role, branch_thermal_nodes, branch_mass_flowrate) in [
+            (BranchRole::Heater, &mut self.heater_branch_thermal_nodes, 
+             heater_branch_flowrate),
+            (BranchRole::DHX, &mut self.dhx_branch_thermal_nodes, 
+             dhx_branch_flowrate),
+            (BranchRole::CTAH, &mut self.ctah_branch_thermal_nodes, 
+             ctah_branch_flowrate)] {
+
+            // positive flow comes in from the top of ciet,
+            // negative flow from the bottom
+            let inlet_temperature = match branch_mass_flowrate.value >= 0.0 {
+                true => self.top_node_temperature,
+                false => self.bottom_node_temperature,
+            };
+
+            let (outlet_temperature, branch_heat_loss_rate) =
+                solve_branch_energy_equation(
+                    branch_thermal_nodes,
+                    branch_mass_flowrate,
+                    inlet_temperature,
+                    timestep,
+                    &component_heat_transfer,
+                    &self.therminol_properties);
+
+            branch_outlets.push((branch_mass_flowrate, outlet_temperature));
+
+            match branch_role {
+                BranchRole::Heater => (),
+                BranchRole::DHX => dhx_heat_transfer_rate = branch_heat_loss_rate,
+                BranchRole::CTAH => ctah_heat_removal_rate = branch_heat_loss_rate,
+            }
+        }
+
+        // the top of ciet gets fluid from branches with negative flow,
+        // and the bottom from branches with positive flow
+        self.top_node_temperature = get_junction_mixed_temperature(
+            &branch_outlets, false, self.top_node_temperature);
+        self.bottom_node_temperature = get_junction_mixed_temperature(
+            &branch_outlets, true, self.bottom_node_temperature);
+
+        self.dhx_heat_transfer_rate = dhx_heat_transfer_rate;
+        self.ctah_heat_removal_rate = ctah_heat_removal_rate;
+        self.simulation_time += timestep;
+        self.solver_error = None;
+
+        self.update_component_fluid_temperatures();
+
+        return Ok((ctah_branch_flowrate,
+                heater_branch_flowrate,
+                dhx_branch_flowrate));
+    }
+
+    /// solves the branch flowrates given the current component
+    /// temperatures, pump pressure and valve positions
+    ///
+    /// returns the ctah, heater and dhx branch flowrates
+    /// in that order
+    fn solve_mass_flowrates(&mut self) ->
+        Result<(MassRate, MassRate, MassRate), CIETSolverError> {
+
+        // there is no cache or lookup table here, the component
+        // temperatures change every timestep
+        let (heater_branch_flowrate,
+             dhx_branch_flowrate,
+             ctah_branch_flowrate) = self.hydraulics.solve_mass_flowrates()?;
+
+        return Ok((ctah_branch_flowrate,
+                heater_branch_flowrate,
+                dhx_branch_flowrate));
+    }
+
+    /// returns the temperature DRACS sees on the dhx shell side,
+    /// the mean of the average node temperatures of the dhx
+    /// heat transfer components
+    fn get_dhx_shell_side_temperature(&self) -> Option<ThermodynamicTemperature> {
+
+        let dhx_component_names = 
+            self.heat_transfer_component_names.get_component_names(BranchRole::DHX);
+
+        if dhx_component_names.is_empty() {
+            return None;
+        }
+
+        let mut temperature_sum_kelvin: f64 = 0.0;
+
+        for dhx_component_name in dhx_component_names.iter() {
+            temperature_sum_kelvin += self.get_component_thermal_nodes(
+                dhx_component_name)?
+                .get_average_temperature().get::<kelvin>();
+        }
+
+        return Some(ThermodynamicTemperature::new::<kelvin>(
+                temperature_sum_kelvin/dhx_component_names.len() as f64));
+    }
+
+    /// returns the first node (or top or bottom) temperature
+    /// outside the therminol property range, if any
+    fn get_out_of_range_temperature(&self) -> Option<ThermodynamicTemperature> {
+
+        let mut temperatures = self.heater_branch_thermal_nodes.iter()
+            .chain(self.dhx_branch_thermal_nodes.iter())
+            .chain(self.ctah_branch_thermal_nodes.iter())
+            .flat_map(|nodes| nodes.node_temperatures.iter().copied())
+            .chain([self.top_node_temperature, self.bottom_node_temperature]);
+
+        if let Some(fluid_temperature) = temperatures.find(|temperature|
+                                 !therminol_temperature_in_range(*temperature)) {
+            return Some(fluid_temperature);
+        }
+
+        return self.dracs_loop.as_ref()
+            .and_then(|dracs_loop| dracs_loop.get_out_of_range_temperature());
+    }
+
+    /// brings every component in the branches to its average
+    /// node temperature, so that the next hydraulic solve
+    /// sees the new densities and viscosities
+    fn update_component_fluid_temperatures(&mut self){
+
+        update_branch_fluid_temperatures(
+            &mut self.hydraulics.heater_branch, &self.heater_branch_thermal_nodes);
+        update_branch_fluid_temperatures(
+            &mut self.hydraulics.dhx_branch, &self.dhx_branch_thermal_nodes);
+        update_branch_fluid_temperatures(
+            &mut self.hydraulics.ctah_branch, &self.ctah_branch_thermal_nodes);
+    }
+
+    // constructor
+
+    /// takes ownership of the branches and splits every component
+    /// into nodes_per_component axial nodes (at least one), starting
+    /// at whatever fluid temperature the components were built at
+    ///
+    /// every branch needs at least one heat transfer component,
+    /// and each must be one of the components of its branch,
+    /// otherwise an error is returned
+    ///
+    /// the heater is off and the ctah has no cooling until
+    /// they are set
+    pub fn new(ctah_branch: CTAHBranch,
+               heater_branch: HeaterBranch,
+               dhx_branch: DHXBranch,
+               heat_transfer_component_names: HeatTransferComponentNames,
+               nodes_per_component: usize) 
+        -> Result<Self, FacilityTopologyError> {
+
+        let heater_branch_thermal_nodes =
+            build_branch_thermal_nodes(&heater_branch, nodes_per_component);
+        let dhx_branch_thermal_nodes =
+            build_branch_thermal_nodes(&dhx_branch, nodes_per_component);
+        let ctah_branch_thermal_nodes =
+            build_branch_thermal_nodes(&ctah_branch, nodes_per_component);
+
+        for (branch_role, branch_thermal_nodes) in [
+            (BranchRole::Heater, &heater_branch_thermal_nodes),
+            (BranchRole::DHX, &dhx_branch_thermal_nodes),
+            (BranchRole::CTAH, &ctah_branch_thermal_nodes)] {
+
+            let component_names = 
+                heat_transfer_component_names.get_component_names(branch_role);
+
+            if component_names.is_empty() {
+                return Err(FacilityTopologyError::MissingHeatTransferComponents(
+                        branch_role));
+            }
+
+            for component_name in component_names.iter() {
+                if !branch_thermal_nodes.iter()
+                    .any(|component_thermal_nodes| 
+                         &component_thermal_nodes.name == component_name) {
+                    return Err(FacilityTopologyError::InvalidHeatTransferComponent {
+                        branch: branch_role.to_string(),
+                        component: component_name.clone() });
+                }
+            }
+        }
+
+        // the top and bottom start at the temperature of the
+        // ctah branch, which always has components since it has a pump
+        let initial_temperature = ctah_branch.get_fluid_temperature()
+            .unwrap_or(ThermodynamicTemperature::new::<degree_celsius>(21.0));
+
+        let mut facility = Self {
+            heater_power: Power::new::<watt>(0.0),
+            ctah_thermal_conductance: ThermalConductance::new::<watt_per_kelvin>(0.0),
+            ctah_air_temperature: initial_temperature,
+            dhx_thermal_conductance: ThermalConductance::new::<watt_per_kelvin>(0.0),
+            hydraulics: CIETHydraulics::new(
+                ctah_branch, heater_branch, dhx_branch),
+            heat_transfer_component_names,
+            heater_branch_thermal_nodes,
+            dhx_branch_thermal_nodes,
+            ctah_branch_thermal_nodes,
+            dracs_loop: None,
+            top_node_temperature: initial_temperature,
+            bottom_node_temperature: initial_temperature,
+            ctah_heat_removal_rate: Power::new::<watt>(0.0),
+            dhx_heat_transfer_rate: Power::new::<watt>(0.0),
+            simulation_time: Time::new::<second>(0.0),
+            solver_error: None,
+            therminol_properties: TherminolVP1Properties::new(),
+        };
+
+        facility.update_component_fluid_temperatures();
+
+        return Ok(facility);
+    }
+}
@@ -0,0 +1,320 @@
+extern crate fluid_mechanics_rust;
+use std::cell::Cell;
+
+use fluid_mechanics_rust::prelude::*;
+
+use crate::CIETSolverError;
+
+extern crate roots;
+use roots::find_root_brent;
+use roots::SimpleConvergency;
+use roots::Convergency;
+
+/// This solves any number of branches connected in parallel
+/// between two common nodes (eg. the top and bottom of ciet)
+///
+/// all branches see the same pressure change, and the solver
+/// finds the pressure change where the branch mass flowrates
+/// sum to zero (mass conservation)
+///
+/// the convention is the same as in ciet, positive flowrate
+/// leaves the top of the branch and negative flowrate enters it
+///
+/// branches can be anything which implements
+/// FluidComponentCollectionMethods, so the ciet branches,
+/// DRACS branches or test sections can all go in here
+///
+/// the solver remembers the last converged pressure change,
+/// and the next solve starts its bracket search from there
+pub struct ParallelBranchSolver {
+
+    /// the pressure change is first searched for in a bracket centred
+    /// on the zero flow pressure change of the first branch,
+    /// plus or minus this value
+    pub pressure_bracket_half_width: Pressure,
+
+    /// if there is a previous converged pressure change, the
+    /// bracket is centred on that instead, plus or minus this value
+    ///
+    /// this is much smaller since the pressure change does not
+    /// usually move much from one tick to the next
+    pub seeded_pressure_bracket_half_width: Pressure,
+
+    /// if the bracket does not contain a root, its width is doubled,
+    /// up to this many times before giving up
+    pub max_bracket_expansions: usize,
+
+    /// brent tolerance on the pressure change (in pascals)
+    pub tolerance: f64,
+
+    /// maximum brent iterations
+    pub max_iter: usize,
+
+    previous_pressure_change: Option<Pressure>,
+
+    last_iteration_count: usize,
+
+    last_bracket_expansion_count: usize,
+}
+
+impl ParallelBranchSolver {
+
+    /// constructor, the bracket starts at plus or minus 50 kPa
+    /// which is what ciet has always used,
+    /// and the tolerances are the same as ciet digital twin v1
+    pub fn new() -> Self {
+        return Self {
+            pressure_bracket_half_width: Pressure::new::<pascal>(50000_f64),
+            seeded_pressure_bracket_half_width: Pressure::new::<pascal>(1000_f64),
+            max_bracket_expansions: 10,
+            tolerance: 1e-9,
+            max_iter: 30,
+            previous_pressure_change: None,
+            last_iteration_count: 0,
+            last_bracket_expansion_count: 0,
+        };
+    }
+
+    /// returns the last converged pressure change, if any
+    pub fn get_previous_pressure_change(&self) -> Option<Pressure> {
+        return self.previous_pressure_change;
+    }
+
+    /// forgets the last converged pressure change, so that the
+    /// next solve starts from the zero flow pressure change
+    /// of the first branch (eg. after the branches are changed)
+    pub fn reset_previous_pressure_change(&mut self) {
+        self.previous_pressure_change = None;
+    }
+
+    /// returns the number of brent iterations in the last solve
+    pub fn get_last_iteration_count(&self) -> usize {
+        return self.last_iteration_count;
+    }
+
+    /// returns how many times the bracket was doubled in the last solve
+    pub fn get_last_bracket_expansion_count(&self) -> usize {
+        return self.last_bracket_expansion_count;
+    }
+
+    /// returns the mass flowrate through each branch (in the same order
+    /// as the branches are given) for a pressure change across all of them
+    pub fn get_branch_mass_flowrates(
+        branches: &[&dyn FluidComponentCollectionMethods],
+        pressure_change: Pressure) -> Vec<MassRate> {
+
+        return branches.iter()
+            .map(|branch| branch.get_mass_flowrate_from_pressure_change(
+                    pressure_change))
+            .collect();
+    }
+
+    /// finds the common pressure change across all the branches
+    /// and the mass flowrate through each branch
+    ///
+    /// with fewer than two branches there is no loop for fluid
+    /// to flow through, so all flowrates are zero and the pressure
+    /// change is just that of the branch at zero flow
+    ///
+    /// if the bracket does not contain a root even after expanding,
+    /// brent does not converge, or any branch returns NaN,
+    /// an error is returned instead of panicking
+    pub fn solve(
+        &mut self,
+        branches: &[&dyn FluidComponentCollectionMethods])
+        -> Result<(Pressure, Vec<MassRate>), CIETSolverError> {
+
+        return self.solve_within_pressure_change_limits(branches, None);
+    }
+
+    /// same as solve, but the bracket never goes below the lower
+    /// or above the upper pressure change limit given
+    ///
+    /// this is for branches which can only give a mass flowrate
+    /// within a range of pressure changes (eg. lookup tables)
+    pub fn solve_within_pressure_change_limits(
+        &mut self,
+        branches: &[&dyn FluidComponentCollectionMethods],
+        pressure_change_limits: Option<(Pressure, Pressure)>)
+        -> Result<(Pressure, Vec<MassRate>), CIETSolverError> {
+
+        let zero_flowrate = MassRate::new::<kilogram_per_second>(0.0);
+
+        self.last_iteration_count = 0;
+        self.last_bracket_expansion_count = 0;
+
+        if branches.len() < 2 {
+
+            let pressure_change = match branches.first() {
+                Some(branch) => branch.get_pressure_change(zero_flowrate),
+                None => Pressure::new::<pascal>(0.0),
+            };
+
+            if pressure_change.value.is_nan() {
+                return Err(CIETSolverError::NotANumber);
+            }
+
+            return Ok((pressure_change, vec![zero_flowrate; branches.len()]));
+        }
+
+        // i'm using the same algorithm from ciet digital twin v1
+        //
+        // the root function can only return f64, so if a branch
+        // gives NaN, we flag it here and check after brent is done
+        let nan_encountered = Cell::new(false);
+
+        let pressure_change_root =
+            |pressure_change_pascals: f64| -> f64 {
+
+                // all branches are subject to the same
+                // pressure change since they are in parallel
+                let test_pressure_change =
+                    Pressure::new::<pascal>(pressure_change_pascals);
+
+                let total_mass_flowrate: f64 =
+                    Self::get_branch_mass_flowrates(
+                        branches, test_pressure_change)
+                    .iter()
+                    .map(|mass_flowrate| mass_flowrate.value)
+                    .sum();
+
+                if total_mass_flowrate.is_nan() {
+                    nan_encountered.set(true);
+                }
+
+                return total_mass_flowrate;
+            };
+
+        // the bracket is centred on the last converged pressure change
+        // if we have one, otherwise on the zero flow pressure change
+        // of the first branch
+        let (bracket_centre, mut bracket_half_width) =
+            match self.previous_pressure_change {
+                Some(previous_pressure_change) =>
+                    (previous_pressure_change,
+                     self.seeded_pressure_bracket_half_width),
+                None =>
+                    (branches[0].get_pressure_change(zero_flowrate),
+                     self.pressure_bracket_half_width),
+            };
+
+        if bracket_centre.value.is_nan() {
+            return Err(CIETSolverError::NotANumber);
+        }
+
+        // the bracket is kept within the pressure change limits,
+        // if there are any
+        let clamp_to_limits = |pressure_change: Pressure| -> Pressure {
+            match pressure_change_limits {
+                Some((lower_limit, upper_limit)) => 
+                    pressure_change.max(lower_limit).min(upper_limit),
+                None => pressure_change,
+            }
+        };
+
+        // now expand the bracket until the total mass flowrate
+        // changes sign across it
+        let mut upper_bound = clamp_to_limits(bracket_centre + bracket_half_width);
+        let mut lower_bound = clamp_to_limits(bracket_centre - bracket_half_width);
+
+        loop {
+
+            let upper_total_mass_flowrate =
+                pressure_change_root(upper_bound.value);
+            let lower_total_mass_flowrate =
+                pressure_change_root(lower_bound.value);
+
+            // no point expanding further if the branches can't
+            // give a flowrate at these pressure changes
+            if nan_encountered.get() {
+                return Err(CIETSolverError::NotANumber);
+            }
+
+            if upper_total_mass_flowrate * lower_total_mass_flowrate <= 0.0 {
+                break;
+            }
+
+            if self.last_bracket_expansion_count >= self.max_bracket_expansions {
+                return Err(CIETSolverError::BracketFailure {
+                    lower_bound, upper_bound });
+            }
+
+            bracket_half_width = bracket_half_width * 2.0;
+            upper_bound = clamp_to_limits(bracket_centre + bracket_half_width);
+            lower_bound = clamp_to_limits(bracket_centre - bracket_half_width);
+
+            self.last_bracket_expansion_count += 1;
+        }
+
+        let max_iter = self.max_iter;
+        let mut convergency = IterationCountingConvergency::new(
+            self.tolerance, max_iter);
+
+        let pressure_change_result
+            = find_root_brent(
+                upper_bound.value,
+                lower_bound.value,
+                &pressure_change_root,
+                &mut convergency);
+
+        self.last_iteration_count = convergency.iteration_count;
+
+        if nan_encountered.get() {
+            return Err(CIETSolverError::NotANumber);
+        }
+
+        let pressure_change_value = match pressure_change_result {
+            Ok(pressure_change_value) => pressure_change_value,
+            Err(search_error) => return Err(
+                CIETSolverError::from_brent_search_error(
+                    search_error, lower_bound, upper_bound, max_iter)),
+        };
+
+        let pressure_change =
+            Pressure::new::<pascal>(pressure_change_value);
+
+        let branch_mass_flowrates =
+            Self::get_branch_mass_flowrates(branches, pressure_change);
+
+        if branch_mass_flowrates.iter()
+            .any(|mass_flowrate| mass_flowrate.value.is_nan()) {
+            return Err(CIETSolverError::NotANumber);
+        }
+
+        self.previous_pressure_change = Some(pressure_change);
+
+        return Ok((pressure_change, branch_mass_flowrates));
+    }
+}
+
+/// SimpleConvergency does not tell us how many iterations
+/// brent took, so this wraps it and keeps count
+struct IterationCountingConvergency {
+    simple_convergency: SimpleConvergency<f64>,
+    iteration_count: usize,
+}
+
+impl IterationCountingConvergency {
+    fn new(tolerance: f64, max_iter: usize) -> Self {
+        return Self {
+            simple_convergency: SimpleConvergency { eps: tolerance, max_iter },
+            iteration_count: 0,
+        };
+    }
+}
+
+impl Convergency<f64> for IterationCountingConvergency {
+
+    fn is_root_found(&mut self, y: f64) -> bool {
+        return self.simple_convergency.is_root_found(y);
+    }
+
+    fn is_converged(&mut self, x1: f64, x2: f64) -> bool {
+        return self.simple_convergency.is_converged(x1, x2);
+    }
+
+    fn is_iteration_limit_reached(&mut self, iter: usize) -> bool {
+        self.iteration_count = iter;
+        return self.simple_convergency.is_iteration_limit_reached(iter);
+    }
+}
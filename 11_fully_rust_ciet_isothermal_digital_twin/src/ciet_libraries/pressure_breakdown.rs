@@ -0,0 +1,105 @@
+extern crate fluid_mechanics_rust;
+use fluid_mechanics_rust::prelude::*;
+
+use crate::{TherminolFluidComponent, BranchRole};
+
+/// the pressure change across one component at a mass flowrate,
+/// split up by where it comes from
+///
+/// the friction and form losses are positive when they hold back
+/// positive flow, the hydrostatic pressure change and pump pressure
+/// are positive when they push the fluid in the direction of
+/// positive flow
+///
+/// a check valve's cracking pressure counts as form loss
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComponentPressureBreakdown {
+    pub name: String,
+    pub mass_flowrate: MassRate,
+    pub friction_loss: Pressure,
+    pub form_loss: Pressure,
+    pub hydrostatic_pressure_change: Pressure,
+    pub pump_pressure: Pressure,
+}
+
+impl ComponentPressureBreakdown {
+
+    /// works out the breakdown for a component at this mass flowrate,
+    /// the pump pressure is the internal pressure source of the
+    /// component
+    pub fn new(
+        component: &dyn TherminolFluidComponent,
+        mass_flowrate: MassRate) -> Self {
+
+        let pressure_loss = component.get_pressure_loss_immutable(mass_flowrate);
+        let form_loss = component.get_form_loss_immutable(mass_flowrate);
+
+        return Self {
+            name: component.get_name().to_string(),
+            mass_flowrate,
+            friction_loss: pressure_loss - form_loss,
+            form_loss,
+            hydrostatic_pressure_change:
+                component.get_hydrostatic_pressure_change_immutable(),
+            pump_pressure: component.get_internal_pressure_source_immutable(),
+        };
+    }
+
+    /// returns the pressure change across the component,
+    /// this is what the branch solvers use
+    pub fn get_pressure_change(&self) -> Pressure {
+        return -self.friction_loss - self.form_loss
+            + self.hydrostatic_pressure_change + self.pump_pressure;
+    }
+}
+
+/// the pressure breakdown of every component in one of the
+/// parallel branches of ciet, in the order the fluid flows
+/// through them
+#[derive(Clone, Debug, PartialEq)]
+pub struct BranchPressureBreakdown {
+    pub branch_role: BranchRole,
+    pub mass_flowrate: MassRate,
+    pub components: Vec<ComponentPressureBreakdown>,
+}
+
+impl BranchPressureBreakdown {
+
+    /// returns the pressure change across the whole branch
+    pub fn get_pressure_change(&self) -> Pressure {
+        return self.components.iter()
+            .fold(Pressure::new::<pascal>(0.0),
+                  |pressure_change_sum, component|
+                  pressure_change_sum + component.get_pressure_change());
+    }
+}
+
+/// the first line of the pressure breakdown csv
+pub const PRESSURE_BREAKDOWN_CSV_HEADER: &str =
+    "branch,component,mass_flowrate_kg_per_s,friction_loss_pa,form_loss_pa,\
+    hydrostatic_pressure_change_pa,pump_pressure_pa,pressure_change_pa";
+
+/// writes the pressure breakdowns out as csv, with a header line
+/// and then one line per component
+pub fn pressure_breakdown_to_csv_string(
+    branch_pressure_breakdowns: &[BranchPressureBreakdown]) -> String {
+
+    let mut csv_string = format!("{}\n", PRESSURE_BREAKDOWN_CSV_HEADER);
+
+    for branch_pressure_breakdown in branch_pressure_breakdowns.iter() {
+        for component in branch_pressure_breakdown.components.iter() {
+            csv_string.push_str(&format!(
+                    "{},{},{},{},{},{},{},{}\n",
+                    branch_pressure_breakdown.branch_role,
+                    component.name,
+                    component.mass_flowrate.get::<kilogram_per_second>(),
+                    component.friction_loss.get::<pascal>(),
+                    component.form_loss.get::<pascal>(),
+                    component.hydrostatic_pressure_change.get::<pascal>(),
+                    component.pump_pressure.get::<pascal>(),
+                    component.get_pressure_change().get::<pascal>()));
+        }
+    }
+
+    return csv_string;
+}
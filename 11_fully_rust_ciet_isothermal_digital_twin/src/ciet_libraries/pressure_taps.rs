@@ -0,0 +1,42 @@
+extern crate fluid_mechanics_rust;
+use fluid_mechanics_rust::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::BranchRole;
+
+/// a pressure tap on one of the parallel branches of ciet,
+/// at the boundary between two components
+///
+/// components_upstream is how many components of the branch
+/// the fluid goes through (in the direction of positive flow)
+/// before it reaches the tap, so 0 is the top of the branch
+/// (the top of ciet) and the number of components in the branch
+/// is the bottom of the branch (the bottom of ciet)
+#[derive(Clone, Debug, PartialEq)]
+pub struct BranchPressureTap {
+    pub name: String,
+    pub branch_role: BranchRole,
+    pub components_upstream: usize,
+}
+
+/// a manometer (or differential pressure transmitter)
+/// connected across two pressure taps
+///
+/// it reads the static pressure at the high pressure tap
+/// less the static pressure at the low pressure tap, so the
+/// reading is negative if the high pressure tap is actually
+/// at the lower pressure
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Manometer {
+    pub name: String,
+    pub high_pressure_tap: String,
+    pub low_pressure_tap: String,
+}
+
+/// what a manometer reads after a calculation
+#[derive(Clone, Debug, PartialEq)]
+pub struct ManometerReading {
+    pub name: String,
+    pub pressure_difference: Pressure,
+}
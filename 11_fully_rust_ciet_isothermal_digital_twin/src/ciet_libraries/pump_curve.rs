@@ -0,0 +1,223 @@
+extern crate fluid_mechanics_rust;
+use fluid_mechanics_rust::prelude::*;
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// These are the ways in which a pump curve can be unphysical
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PumpCurveError {
+
+    /// the rated speed must be above zero (and finite), since
+    /// the affinity laws divide by it
+    InvalidRatedSpeed(f64),
+
+    /// the head coefficients must all be finite
+    NonFiniteHeadCoefficient,
+}
+
+impl fmt::Display for PumpCurveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PumpCurveError::InvalidRatedSpeed(rated_speed_rpm) =>
+                write!(f, "rated speed of {} rpm must be above zero",
+                       rated_speed_rpm),
+            PumpCurveError::NonFiniteHeadCoefficient =>
+                write!(f, "pump curve head coefficients must be finite"),
+        }
+    }
+}
+
+impl std::error::Error for PumpCurveError {}
+
+/// the pump curve as it is written in a file, this goes through
+/// PumpCurve::new so that a file cannot give an unphysical curve
+#[derive(Deserialize)]
+struct PumpCurveCoefficients {
+    rated_speed_rpm: f64,
+    shutoff_head_meters: f64,
+    linear_head_coefficient: f64,
+    quadratic_head_coefficient: f64,
+}
+
+impl TryFrom<PumpCurveCoefficients> for PumpCurve {
+    type Error = PumpCurveError;
+
+    fn try_from(coefficients: PumpCurveCoefficients) -> Result<Self, Self::Error> {
+        return PumpCurve::new(
+            coefficients.rated_speed_rpm,
+            coefficients.shutoff_head_meters,
+            coefficients.linear_head_coefficient,
+            coefficients.quadratic_head_coefficient);
+    }
+}
+
+/// This is a head against flowrate curve for a centrifugal pump,
+/// scaled to other pump speeds with the affinity laws
+///
+/// at the rated speed, the head (in meters of fluid) is
+///
+/// H = h0 + h1 Q + h2 Q|Q|
+///
+/// where Q is the volumetric flowrate in m3/s, h0 is the shutoff
+/// head (no flow), and h1 and h2 are usually negative so the head
+/// drops as the flow goes up
+///
+/// at any other speed N, with s = N/N_rated, the affinity laws
+/// (Q goes as s, H goes as s^2) give
+///
+/// H = h0 s^2 + h1 s Q + h2 Q|Q|
+///
+/// so at zero speed, the pump is just a resistance (h2 Q|Q|)
+/// which opposes flow in either direction
+///
+/// speeds are in rpm, a VFD frequency can be converted with
+/// the rated speed and rated frequency of the motor
+///
+/// the curve can only be built through its constructors,
+/// which check that the rated speed is above zero and
+/// the head coefficients are finite
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(try_from = "PumpCurveCoefficients")]
+pub struct PumpCurve {
+    rated_speed_rpm: f64,
+    shutoff_head_meters: f64,
+    linear_head_coefficient: f64,
+    quadratic_head_coefficient: f64,
+}
+
+impl PumpCurve {
+
+    /// constructor, returns an error if the rated speed is not
+    /// above zero or any head coefficient is not finite
+    pub fn new(
+        rated_speed_rpm: f64,
+        shutoff_head_meters: f64,
+        linear_head_coefficient: f64,
+        quadratic_head_coefficient: f64) -> Result<Self, PumpCurveError> {
+
+        // NaN fails this check too
+        if !rated_speed_rpm.is_finite() || rated_speed_rpm <= 0.0 {
+            return Err(PumpCurveError::InvalidRatedSpeed(rated_speed_rpm));
+        }
+
+        if !shutoff_head_meters.is_finite() 
+            || !linear_head_coefficient.is_finite()
+            || !quadratic_head_coefficient.is_finite() {
+            return Err(PumpCurveError::NonFiniteHeadCoefficient);
+        }
+
+        return Ok(Self {
+            rated_speed_rpm,
+            shutoff_head_meters,
+            linear_head_coefficient,
+            quadratic_head_coefficient,
+        });
+    }
+
+    pub fn get_rated_speed_rpm(&self) -> f64 {
+        return self.rated_speed_rpm;
+    }
+
+    pub fn get_shutoff_head_meters(&self) -> f64 {
+        return self.shutoff_head_meters;
+    }
+
+    pub fn get_linear_head_coefficient(&self) -> f64 {
+        return self.linear_head_coefficient;
+    }
+
+    pub fn get_quadratic_head_coefficient(&self) -> f64 {
+        return self.quadratic_head_coefficient;
+    }
+
+    /// returns the head the pump gives at this volumetric flowrate
+    /// and speed
+    pub fn get_head(
+        &self,
+        volumetric_flowrate: VolumeRate,
+        pump_speed_rpm: f64) -> Length {
+
+        let speed_ratio = pump_speed_rpm/self.rated_speed_rpm;
+        let flowrate_cubic_meters_per_second = volumetric_flowrate.value;
+
+        let head_meters =
+            self.shutoff_head_meters*speed_ratio*speed_ratio
+            + self.linear_head_coefficient*speed_ratio
+            *flowrate_cubic_meters_per_second
+            + self.quadratic_head_coefficient
+            *flowrate_cubic_meters_per_second
+            *flowrate_cubic_meters_per_second.abs();
+
+        return Length::new::<meter>(head_meters);
+    }
+
+    /// returns the pressure the pump adds to the fluid (rho g H)
+    /// at this mass flowrate and speed
+    pub fn get_pressure_change(
+        &self,
+        fluid_mass_flowrate: MassRate,
+        pump_speed_rpm: f64,
+        fluid_density: MassDensity) -> Pressure {
+
+        let gravitational_acceleration =
+            Acceleration::new::<meter_per_second_squared>(9.81);
+
+        let pump_head = self.get_head(
+            fluid_mass_flowrate/fluid_density, pump_speed_rpm);
+
+        return fluid_density*gravitational_acceleration*pump_head;
+    }
+
+    /// returns a curve which falls from the shutoff head at no flow
+    /// to zero head at the runout flowrate, at the rated speed
+    ///
+    /// a zero runout flowrate gives an infinite quadratic
+    /// coefficient, so it is rejected like any other
+    pub fn from_shutoff_head_and_runout_flowrate(
+        rated_speed_rpm: f64,
+        shutoff_head: Length,
+        runout_flowrate: VolumeRate) -> Result<Self, PumpCurveError> {
+
+        let shutoff_head_meters = shutoff_head.get::<meter>();
+        let runout_flowrate_cubic_meters_per_second = runout_flowrate.value;
+
+        return Self::new(
+            rated_speed_rpm,
+            shutoff_head_meters,
+            0.0,
+            -shutoff_head_meters/
+                (runout_flowrate_cubic_meters_per_second
+                 *runout_flowrate_cubic_meters_per_second));
+    }
+
+    /// a PLACEHOLDER curve for the ctah pump
+    ///
+    /// this is not fitted to manufacturer data, it is only sized so
+    /// that ciet runs at around 0.1 kg/s at the rated speed
+    /// (with all branches open), swap in the measured curve when
+    /// it is available
+    ///
+    /// anything calculated with this curve should be labelled as
+    /// coming from a placeholder (the ciet server only uses it
+    /// if asked to, and says so in its solver status)
+    ///
+    /// the shutoff head is 0.5 m and the runout flowrate is
+    /// 8e-4 m3/s at 3450 rpm, these are known to be valid, so
+    /// the curve is built directly
+    pub fn ciet_ctah_pump() -> Self {
+
+        let shutoff_head_meters: f64 = 0.5;
+        let runout_flowrate_cubic_meters_per_second: f64 = 8.0e-4;
+
+        return Self {
+            rated_speed_rpm: 3450.0,
+            shutoff_head_meters,
+            linear_head_coefficient: 0.0,
+            quadratic_head_coefficient: -shutoff_head_meters/
+                (runout_flowrate_cubic_meters_per_second
+                 *runout_flowrate_cubic_meters_per_second),
+        };
+    }
+}
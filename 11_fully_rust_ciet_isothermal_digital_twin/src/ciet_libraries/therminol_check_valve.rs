@@ -0,0 +1,295 @@
+extern crate fluid_mechanics_rust;
+use fluid_mechanics_rust::prelude::*;
+
+/// this struct or class is to be instantiated into an object
+/// which can represent a therminol check valve
+///
+/// the valve lets fluid through in the direction of positive flow
+/// in its branch, once the pressure across it reaches the cracking
+/// pressure, and holds back reverse flow, either completely or
+/// with some leakage
+///
+/// the pressure loss at a mass flowrate m is
+///
+/// forward (m > 0): cracking pressure + K m^2/(2 rho A^2)
+/// reverse (m < 0): -K_leak m^2/(2 rho A^2)
+///
+/// at zero flow the valve can hold any pressure loss between 0
+/// and the cracking pressure (any back pressure at all if it
+/// does not leak), so the pressure loss is not a function of the
+/// mass flowrate there, see get_zero_flow_pressure_loss_range
+/// and find_series_branch_mass_flowrate for how the branches
+/// deal with this
+///
+/// a check valve which does not leak has no reverse flow, its
+/// pressure loss for reverse flow is then calculated as if the
+/// valve were held open (with the forward K), but any branch
+/// with this valve gives zero flow rather than reverse flow
+pub struct TherminolCheckValve {
+
+    therminol_properties: TherminolVP1Properties,
+    fluid_temp: ThermodynamicTemperature,
+    fluid_mass_flowrate: MassRate,
+
+    internal_pressure: Pressure,
+    incline_angle: Angle,
+    component_length: Length,
+    hydraulic_diameter: Length,
+
+    pressure_loss: Pressure,
+    name: String,
+
+    fully_open_k: f64,
+    cracking_pressure: Pressure,
+
+    // the form loss for reverse flow, None if the valve
+    // does not leak
+    reverse_leakage_k: Option<f64>,
+}
+
+impl FluidComponent for TherminolCheckValve {
+    fn get_pressure_loss(&mut self) -> Pressure {
+
+        let mass_flowrate = self.fluid_mass_flowrate;
+        self.pressure_loss = self.get_pressure_loss_immutable(mass_flowrate);
+
+        return self.pressure_loss;
+    }
+
+    fn get_pressure_loss_immutable(
+        &self,
+        mass_flowrate: MassRate) -> Pressure {
+
+        let zero_pressure = Pressure::new::<pascal>(0.0);
+
+        if mass_flowrate.value == 0.0 {
+            return zero_pressure;
+        }
+
+        let forward_flow = mass_flowrate.value > 0.0;
+
+        let (form_loss_k, cracking_pressure) = match forward_flow {
+            true => (self.fully_open_k, self.cracking_pressure),
+            false => (self.reverse_leakage_k.unwrap_or(self.fully_open_k),
+                      zero_pressure),
+        };
+
+        let cross_sectional_area = self.get_cross_sectional_area_immutable();
+        let fluid_density = self.get_fluid_density_immutable();
+
+        let pressure_loss = cracking_pressure
+            + 0.5*form_loss_k*mass_flowrate*mass_flowrate.abs()/
+            (fluid_density*cross_sectional_area*cross_sectional_area);
+
+        return pressure_loss;
+    }
+
+    fn set_pressure_loss(&mut self, pressure_loss: Pressure){
+        self.pressure_loss = pressure_loss;
+    }
+
+    fn set_mass_flowrate(&mut self, mass_flowrate: MassRate){
+        self.fluid_mass_flowrate = mass_flowrate;
+    }
+
+    fn get_mass_flowrate(&mut self) -> MassRate {
+
+        let pressure_loss = self.pressure_loss;
+        let mass_flowrate =
+            self.get_mass_flowrate_from_pressure_loss_immutable(pressure_loss);
+
+        self.set_mass_flowrate(mass_flowrate);
+
+        return self.fluid_mass_flowrate;
+    }
+
+    /// inverts the pressure loss, within the zero flow pressure
+    /// loss range the valve is shut and there is no flow
+    ///
+    /// a valve with no form loss cannot set the flowrate once
+    /// it is open, so NaN is returned then
+    fn get_mass_flowrate_from_pressure_loss_immutable(
+        &self,
+        pressure_loss: Pressure) -> MassRate {
+
+        let (lowest_shut_pressure_loss, highest_shut_pressure_loss) =
+            self.get_zero_flow_pressure_loss_range();
+
+        if pressure_loss >= lowest_shut_pressure_loss
+            && pressure_loss <= highest_shut_pressure_loss {
+            return MassRate::new::<kilogram_per_second>(0.0);
+        }
+
+        let forward_flow = pressure_loss > highest_shut_pressure_loss;
+
+        let (form_loss_k, pressure_loss_past_opening) = match forward_flow {
+            true => (self.fully_open_k, pressure_loss - self.cracking_pressure),
+            // a valve which does not leak is always shut in
+            // reverse flow, so this is only reached if it leaks
+            false => (self.reverse_leakage_k.unwrap_or(self.fully_open_k),
+                      pressure_loss),
+        };
+
+        if form_loss_k <= 0.0 {
+            return MassRate::new::<kilogram_per_second>(f64::NAN);
+        }
+
+        let cross_sectional_area = self.get_cross_sectional_area_immutable();
+        let fluid_density = self.get_fluid_density_immutable();
+
+        let mass_flowrate_magnitude: MassRate = cross_sectional_area*
+            (2.0*fluid_density*pressure_loss_past_opening.abs()/form_loss_k).sqrt();
+
+        match forward_flow {
+            true => return mass_flowrate_magnitude,
+            false => return -mass_flowrate_magnitude,
+        }
+    }
+
+    fn get_cross_sectional_area(&mut self) -> Area {
+        return self.get_cross_sectional_area_immutable();
+    }
+
+    fn get_cross_sectional_area_immutable(&self) -> Area {
+        return self.hydraulic_diameter*
+            self.hydraulic_diameter*
+            PI/4.0_f64;
+    }
+
+    fn get_hydraulic_diameter(&mut self) -> Length {
+        return self.hydraulic_diameter;
+    }
+
+    fn get_hydraulic_diameter_immutable(&self) -> Length {
+        return self.hydraulic_diameter;
+    }
+
+    fn get_fluid_viscosity(&mut self) -> DynamicViscosity {
+        return self.get_fluid_viscosity_immutable();
+    }
+
+    fn get_fluid_viscosity_immutable(&self) -> DynamicViscosity {
+        return self.therminol_properties.viscosity(self.fluid_temp);
+    }
+
+    fn get_fluid_density(&mut self) -> MassDensity {
+        return self.get_fluid_density_immutable();
+    }
+
+    fn get_fluid_density_immutable(&self) -> MassDensity {
+        return self.therminol_properties.density(self.fluid_temp);
+    }
+
+    fn get_component_length(&mut self) -> Length {
+        return self.component_length;
+    }
+
+    fn get_component_length_immutable(&self) -> Length {
+        return self.component_length;
+    }
+
+    fn get_incline_angle(&mut self) -> Angle {
+        return self.incline_angle;
+    }
+
+    fn get_incline_angle_immutable(&self) -> Angle {
+        return self.incline_angle;
+    }
+
+    fn get_internal_pressure_source(&mut self) -> Pressure {
+        return self.internal_pressure;
+    }
+
+    fn get_internal_pressure_source_immutable(&self) -> Pressure {
+        return self.internal_pressure;
+    }
+
+    fn set_internal_pressure_source(&mut self,
+                                    internal_pressure: Pressure){
+        self.internal_pressure = internal_pressure;
+    }
+
+}
+
+impl TherminolCheckValve {
+
+    /// constructor for therminol check valves, the valve
+    /// does not leak unless set_reverse_leakage_k is called
+    pub fn new(name: &str,
+               fluid_temp: ThermodynamicTemperature,
+               incline_angle: Angle,
+               component_length: Length,
+               hydraulic_diameter: Length,
+               fully_open_k: f64,
+               cracking_pressure: Pressure) -> Self {
+
+        return Self {
+            name: name.to_string(),
+            therminol_properties: TherminolVP1Properties::new(),
+            fluid_temp,
+            fluid_mass_flowrate: MassRate::new::<kilogram_per_second>(0.0),
+            internal_pressure: Pressure::new::<pascal>(0.0),
+            incline_angle,
+            component_length,
+            hydraulic_diameter,
+            pressure_loss: Pressure::new::<pascal>(0.0),
+            fully_open_k,
+            cracking_pressure,
+            reverse_leakage_k: None,
+        };
+    }
+
+    /// gets the name of the therminol check valve as a string slice
+    pub fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// gets the temperature at which the therminol
+    /// properties are evaluated
+    pub fn get_fluid_temp(&self) -> ThermodynamicTemperature {
+        return self.fluid_temp;
+    }
+
+    /// sets the temperature at which the therminol
+    /// properties are evaluated
+    pub fn set_fluid_temp(&mut self, fluid_temp: ThermodynamicTemperature) {
+        self.fluid_temp = fluid_temp;
+    }
+
+    pub fn get_fully_open_k(&self) -> f64 {
+        return self.fully_open_k;
+    }
+
+    pub fn get_cracking_pressure(&self) -> Pressure {
+        return self.cracking_pressure;
+    }
+
+    pub fn set_cracking_pressure(&mut self, cracking_pressure: Pressure) {
+        self.cracking_pressure = cracking_pressure;
+    }
+
+    pub fn get_reverse_leakage_k(&self) -> Option<f64> {
+        return self.reverse_leakage_k;
+    }
+
+    /// sets the form loss for reverse flow through the valve,
+    /// or None if the valve does not leak at all
+    pub fn set_reverse_leakage_k(&mut self, reverse_leakage_k: Option<f64>) {
+        self.reverse_leakage_k = reverse_leakage_k;
+    }
+
+    /// returns the lowest and highest pressure loss the valve
+    /// can hold while shut (at zero flow)
+    ///
+    /// this is 0 to the cracking pressure for a valve which leaks,
+    /// and minus infinity to the cracking pressure otherwise
+    pub fn get_zero_flow_pressure_loss_range(&self) -> (Pressure, Pressure) {
+
+        let lowest_shut_pressure_loss = match self.reverse_leakage_k {
+            Some(_) => Pressure::new::<pascal>(0.0),
+            None => Pressure::new::<pascal>(f64::NEG_INFINITY),
+        };
+
+        return (lowest_shut_pressure_loss, self.cracking_pressure);
+    }
+}
@@ -1,6 +1,11 @@
 extern crate fluid_mechanics_rust;
 use fluid_mechanics_rust::prelude::*;
 
+use std::sync::Arc;
+
+use crate::{CustomKCorrelation, CustomDarcyCorrelation, OutOfRangePolicy,
+with_sign_of_reynolds_number};
+
 
 
 
@@ -8,15 +13,122 @@ use fluid_mechanics_rust::prelude::*;
 // for clarity we will list them in a
 // supertrait
 // This makes it easy to see what traits are being implemented here
-
+//
+// FluidCustomComponentCalcPressureLoss from fluid_mechanics_rust has
+// setters which take any &dyn Fn, these cannot be stored in a
+// component which is sent to a solver thread, so the custom k and
+// darcy accessors are declared here instead, with setters which
+// only take Sync functions
+
+/// the custom k and darcy friction factor of a therminol custom 
+/// component, the functions must be Sync so that the component
+/// stays thread safe
 pub trait TherminolCustomComponentTraits<'trait_lifetime> :
  FluidComponent
-+ FluidCustomComponentCalcPressureLoss<'trait_lifetime>
-+ FluidCustomComponentCalcPressureChange<'trait_lifetime>
-{}
+{
+    fn get_custom_component_absolute_roughness(
+        &mut self) -> Length;
+
+    fn get_custom_component_absolute_roughness_immutable(
+        &self) -> Length;
+
+    fn get_custom_darcy(&mut self) 
+        -> &dyn Fn(f64, f64) -> f64;
+
+    fn get_custom_darcy_immutable(&self) 
+        -> &dyn Fn(f64, f64) -> f64;
+
+    fn get_custom_k(&mut self) 
+        -> &dyn Fn(f64) -> f64;
+
+    fn get_custom_k_immutable(&self) 
+        -> &dyn Fn(f64) -> f64;
+
+    fn set_custom_k(
+        &mut self,
+        custom_k: &'trait_lifetime (dyn Fn(f64) -> f64 + Sync));
+
+    fn set_custom_darcy(
+        &mut self,
+        custom_darcy: &'trait_lifetime (dyn Fn(f64,f64) -> f64 + Sync));
+}
+
+// the pressure loss and mass flowrate calculations for custom 
+// components in fluid_mechanics_rust are associated functions of
+// FluidCustomComponentCalcPressureLoss, this type has no state
+// and is only used to call them
+struct CustomComponentCalculations;
+
+impl<'trait_lifetime> 
+FluidCustomComponentCalcPressureLoss<'trait_lifetime> 
+for CustomComponentCalculations {
+
+    // there is no roughness or custom k and darcy stored here,
+    // they are passed into the associated functions instead
+
+    fn get_custom_component_absolute_roughness(
+        &mut self) -> Length {
+        return Length::new::<meter>(0.0);
+    }
+
+    fn get_custom_component_absolute_roughness_immutable(
+        &self) -> Length {
+        return Length::new::<meter>(0.0);
+    }
+
+    fn get_custom_darcy(&mut self) 
+        -> &dyn Fn(f64, f64) -> f64 {
+        return &no_custom_darcy;
+    }
+
+    fn get_custom_darcy_immutable(&self) 
+        -> &dyn Fn(f64, f64) -> f64 {
+        return &no_custom_darcy;
+    }
+
+    fn get_custom_k(&mut self) 
+        -> &dyn Fn(f64) -> f64 {
+        return &no_custom_k;
+    }
+
+    fn get_custom_k_immutable(&self) 
+        -> &dyn Fn(f64) -> f64 {
+        return &no_custom_k;
+    }
+
+    fn set_custom_k(
+        &mut self,
+        _custom_k: &'trait_lifetime dyn Fn(f64) -> f64){
+    }
+
+    fn set_custom_darcy(
+        &mut self,
+        _custom_darcy: &'trait_lifetime dyn Fn(f64,f64) -> f64){
+    }
+}
+
+fn no_custom_k(_reynolds_number: f64) -> f64 {
+    return 0.0;
+}
+
+fn no_custom_darcy(_reynolds_number: f64, _roughness_ratio: f64) -> f64 {
+    return 0.0;
+}
 
 // first we create an therminol pipe struct
 // and start implementing it
+//
+/// a therminol component with its own K and darcy friction factor
+/// correlations
+///
+/// reverse flow convention: the correlations give the magnitude
+/// of K and the darcy friction factor at |Re|, and the component 
+/// gives them the sign of Re, so the pressure loss always opposes
+/// the flow, whatever sign the correlation itself returns
+///
+/// the pressure loss is then odd in the mass flowrate, unless a
+/// different K is set for reverse flow 
+/// (see set_reverse_custom_k_correlation)
 pub struct TherminolCustomComponent<'pipe_lifetime> {
 
     therminol_properties: TherminolVP1Properties,
@@ -33,24 +145,49 @@ pub struct TherminolCustomComponent<'pipe_lifetime> {
     absolute_roughness: Length,
     name: String,
     
-    // these are trait object references which cannot be brought into
+    // these are trait objects which cannot be brought into
     // the scope
-    custom_k: &'pipe_lifetime dyn Fn(f64) -> f64,
-    custom_darcy: &'pipe_lifetime dyn Fn(f64,f64) ->f64,
-
-}
-
-impl<'pipe_lifetime> 
-TherminolCustomComponentTraits<'pipe_lifetime> for TherminolCustomComponent<'pipe_lifetime> {}
-
+    //
+    // they must be Send and Sync so that the component (and ciet) 
+    // can be sent to a solver thread
+    //
+    // they are boxed so that the component can own closures built
+    // from a correlation, a plain function reference is boxed too
+    //
+    // these are what the solvers use, the unguarded custom k and 
+    // darcy with the out of range policy applied
+    custom_k: Box<dyn Fn(f64) -> f64 + Send + Sync + 'pipe_lifetime>,
+    custom_darcy: Box<dyn Fn(f64,f64) -> f64 + Send + Sync + 'pipe_lifetime>,
+
+    // the custom k and darcy as they were given, these are shared
+    // with the closures above, so the closures can be rebuilt
+    // whenever the fitted range or policy changes
+    unguarded_custom_k: Arc<dyn Fn(f64) -> f64 + Send + Sync + 'pipe_lifetime>,
+    unguarded_custom_darcy: Arc<dyn Fn(f64,f64) -> f64 + Send + Sync + 'pipe_lifetime>,
+
+    // the custom k for reverse flow, if it differs from the
+    // custom k for forward flow
+    unguarded_reverse_custom_k: Option<Arc<dyn Fn(f64) -> f64 + Send + Sync + 'pipe_lifetime>>,
+    reverse_custom_k_correlation: Option<CustomKCorrelation>,
+
+    // the correlations the closures above were built from, if any,
+    // these can be serialised or shown on the opcua server
+    custom_k_correlation: Option<CustomKCorrelation>,
+    custom_darcy_correlation: Option<CustomDarcyCorrelation>,
+
+    // the reynolds numbers (magnitude) the correlations were
+    // fitted over, if set, this is used instead of the range
+    // the correlations know themselves
+    fitted_reynolds_number_range: Option<(f64, f64)>,
+
+    // what to do when the correlations are used outside
+    // the fitted range
+    out_of_range_policy: OutOfRangePolicy,
 
-impl<'pipe_lifetime> 
-FluidCustomComponentCalcPressureChange<'pipe_lifetime> 
-for TherminolCustomComponent<'pipe_lifetime> {
 }
 
 impl<'pipe_lifetime> 
-FluidCustomComponentCalcPressureLoss<'pipe_lifetime> 
+TherminolCustomComponentTraits<'pipe_lifetime> 
 for TherminolCustomComponent<'pipe_lifetime> {
 
     fn get_custom_component_absolute_roughness(
@@ -68,7 +205,7 @@ for TherminolCustomComponent<'pipe_lifetime> {
     fn get_custom_darcy(&mut self) 
         -> &dyn Fn(f64, f64) -> f64 {
 
-            return self.custom_darcy.clone();
+            return self.custom_darcy.as_ref();
 
         }
 
@@ -76,37 +213,40 @@ for TherminolCustomComponent<'pipe_lifetime> {
     fn get_custom_darcy_immutable(&self) 
         -> &dyn Fn(f64, f64) -> f64 {
 
-            return self.custom_darcy.clone();
+            return self.custom_darcy.as_ref();
 
         }
 
     fn get_custom_k(&mut self) 
         -> &dyn Fn(f64) -> f64 {
 
-            return self.custom_k.clone();
+            return self.custom_k.as_ref();
 
         }
 
     fn get_custom_k_immutable(&self) 
         -> &dyn Fn(f64) -> f64 {
 
-            return self.custom_k.clone();
+            return self.custom_k.as_ref();
 
         }
 
+    /// the custom k must be Sync so that the component can be 
+    /// sent to a solver thread
     fn set_custom_k(
         &mut self,
-        custom_k: &'pipe_lifetime dyn Fn(f64) -> f64){
-
-        self.custom_k = custom_k;
+        custom_k: &'pipe_lifetime (dyn Fn(f64) -> f64 + Sync)){
 
+        self.set_thread_safe_custom_k(custom_k);
     }
 
+    /// the custom darcy must be Sync so that the component can be 
+    /// sent to a solver thread
     fn set_custom_darcy(
         &mut self,
-        custom_darcy: &'pipe_lifetime dyn Fn(f64,f64) -> f64){
+        custom_darcy: &'pipe_lifetime (dyn Fn(f64,f64) -> f64 + Sync)){
 
-        self.custom_darcy = custom_darcy;
+        self.set_thread_safe_custom_darcy(custom_darcy);
     }
 
 
@@ -141,13 +281,13 @@ FluidComponent for TherminolCustomComponent<'pipe_lifetime>{
 
         // i need to make some immutable borrows here...
         let custom_darcy: &dyn Fn(f64, f64) -> f64 = 
-            self.custom_darcy;
+            self.custom_darcy.as_ref();
 
         let custom_k : &dyn Fn(f64) -> f64 =
-            self.custom_k;
+            self.custom_k.as_ref();
 
         let pressure_loss =
-            Self::
+            CustomComponentCalculations::
             fluid_custom_component_calc_pressure_loss(
                 fluid_mass_flowrate, 
                 cross_sectional_area, 
@@ -192,13 +332,14 @@ FluidComponent for TherminolCustomComponent<'pipe_lifetime>{
 
         // i need to make some immutable borrows here...
         let custom_darcy: &dyn Fn(f64, f64) -> f64 = 
-            self.custom_darcy;
+            self.custom_darcy.as_ref();
 
         let custom_k : &dyn Fn(f64) -> f64 =
-            self.custom_k;
+            self.custom_k.as_ref();
 
         let pressure_loss =
-            Self:: fluid_custom_component_calc_pressure_loss(
+            CustomComponentCalculations::
+            fluid_custom_component_calc_pressure_loss(
                 fluid_mass_flowrate, 
                 cross_sectional_area, 
                 hydraulic_diameter, 
@@ -255,13 +396,6 @@ FluidComponent for TherminolCustomComponent<'pipe_lifetime>{
             + hydrostatic_pressure_change
             + internal_pressure_source;
 
-        let custom_darcy : &dyn Fn(f64, f64) -> f64 = 
-            self.custom_darcy;
-
-        let custom_k : &dyn Fn(f64) -> f64 =
-            self.custom_k;
-
-
         let cross_sectional_area = 
             self.get_cross_sectional_area();
 
@@ -283,8 +417,16 @@ FluidComponent for TherminolCustomComponent<'pipe_lifetime>{
         let source_pressure = 
             self.get_internal_pressure_source();
 
+        // the closures are borrowed last, after all the &mut self 
+        // getters above are done
+        let custom_darcy : &dyn Fn(f64, f64) -> f64 = 
+            self.custom_darcy.as_ref();
+
+        let custom_k : &dyn Fn(f64) -> f64 =
+            self.custom_k.as_ref();
+
         let mass_flowrate =
-            Self::
+            CustomComponentCalculations::
             fluid_custom_component_calc_mass_flowrate_from_pressure_change(
                 pressure_change, 
                 cross_sectional_area, 
@@ -338,10 +480,10 @@ FluidComponent for TherminolCustomComponent<'pipe_lifetime>{
             + internal_pressure_source;
 
         let custom_darcy : &dyn Fn(f64, f64) -> f64 = 
-            self.custom_darcy;
+            self.custom_darcy.as_ref();
 
         let custom_k : &dyn Fn(f64) -> f64 =
-            self.custom_k;
+            self.custom_k.as_ref();
 
 
         let cross_sectional_area = 
@@ -366,7 +508,7 @@ FluidComponent for TherminolCustomComponent<'pipe_lifetime>{
             self.get_internal_pressure_source_immutable();
 
         let mass_flowrate =
-            Self::
+            CustomComponentCalculations::
             fluid_custom_component_calc_mass_flowrate_from_pressure_change(
                 pressure_change, 
                 cross_sectional_area, 
@@ -511,10 +653,10 @@ impl<'pipe_lifetime> TherminolCustomComponent<'pipe_lifetime>{
                cross_sectional_area: Area,
                hydraulic_diameter: Length,
                absolute_roughness: Length,
-               custom_k: &'pipe_lifetime dyn Fn(f64)-> f64 ,
-               custom_darcy: &'pipe_lifetime dyn Fn(f64,f64) -> f64 ) -> Self {
+               custom_k: &'pipe_lifetime (dyn Fn(f64)-> f64 + Sync),
+               custom_darcy: &'pipe_lifetime (dyn Fn(f64,f64) -> f64 + Sync)) -> Self {
 
-        return Self { 
+        let mut custom_component = Self { 
             name: name.to_string(),
             therminol_properties: TherminolVP1Properties::new(),
             fluid_temp: fluid_temp, 
@@ -526,10 +668,55 @@ impl<'pipe_lifetime> TherminolCustomComponent<'pipe_lifetime>{
             cross_sectional_area: cross_sectional_area,
             pressure_loss: Pressure::new::<pascal>(0.0),
             absolute_roughness: absolute_roughness,
-            custom_k: custom_k,
-            custom_darcy: custom_darcy,
+            custom_k: Box::new(custom_k),
+            custom_darcy: Box::new(custom_darcy),
+            unguarded_custom_k: Arc::new(custom_k),
+            unguarded_custom_darcy: Arc::new(custom_darcy),
+            unguarded_reverse_custom_k: None,
+            reverse_custom_k_correlation: None,
+            custom_k_correlation: None,
+            custom_darcy_correlation: None,
+            fitted_reynolds_number_range: None,
+            out_of_range_policy: OutOfRangePolicy::default(),
         };
 
+        custom_component.guard_custom_correlations();
+
+        return custom_component;
+
+    }
+
+    /// constructor, same as new, but the custom k and darcy come
+    /// from correlations instead of functions, so they can be
+    /// read back with get_custom_k_correlation and
+    /// get_custom_darcy_correlation
+    pub fn new_from_correlations(name: &str,
+               fluid_temp: ThermodynamicTemperature,
+               incline_angle: Angle,
+               component_length: Length,
+               cross_sectional_area: Area,
+               hydraulic_diameter: Length,
+               absolute_roughness: Length,
+               custom_k_correlation: CustomKCorrelation,
+               custom_darcy_correlation: CustomDarcyCorrelation) -> Self {
+
+        // the ctah pump fit is K = 0 and f = 0, it is just a 
+        // placeholder until the correlations are set below
+        let mut custom_component = Self::new(
+            name, 
+            fluid_temp, 
+            incline_angle, 
+            component_length, 
+            cross_sectional_area, 
+            hydraulic_diameter, 
+            absolute_roughness, 
+            &crate::CTAHPump::custom_k, 
+            &crate::CTAHPump::custom_darcy);
+
+        custom_component.set_custom_k_correlation(custom_k_correlation);
+        custom_component.set_custom_darcy_correlation(custom_darcy_correlation);
+
+        return custom_component;
     }
 
     pub fn get_name(&self) -> &str {
@@ -541,5 +728,190 @@ impl<'pipe_lifetime> TherminolCustomComponent<'pipe_lifetime>{
         self.name = name.to_string();
     }
 
+    /// gets the temperature at which the therminol 
+    /// properties are evaluated
+    pub fn get_fluid_temp(&self) -> ThermodynamicTemperature {
+        return self.fluid_temp;
+    }
+
+    /// sets the temperature at which the therminol 
+    /// properties are evaluated
+    pub fn set_fluid_temp(&mut self, fluid_temp: ThermodynamicTemperature) {
+        self.fluid_temp = fluid_temp;
+    }
+
+    /// sets the custom k,
+    /// the function must be Sync so that the component stays thread safe
+    pub fn set_thread_safe_custom_k(
+        &mut self,
+        custom_k: &'pipe_lifetime (dyn Fn(f64) -> f64 + Sync)){
+
+        self.unguarded_custom_k = Arc::new(custom_k);
+        self.custom_k_correlation = None;
+        self.guard_custom_correlations();
+    }
+
+    /// sets the custom darcy friction factor,
+    /// the function must be Sync so that the component stays thread safe
+    pub fn set_thread_safe_custom_darcy(
+        &mut self,
+        custom_darcy: &'pipe_lifetime (dyn Fn(f64,f64) -> f64 + Sync)){
+
+        self.unguarded_custom_darcy = Arc::new(custom_darcy);
+        self.custom_darcy_correlation = None;
+        self.guard_custom_correlations();
+    }
+
+    /// sets the custom k from a correlation
+    pub fn set_custom_k_correlation(
+        &mut self,
+        custom_k_correlation: CustomKCorrelation){
+
+        let correlation = custom_k_correlation.clone();
+        self.unguarded_custom_k = Arc::new(move |reynolds_number| 
+                                           correlation.custom_k(reynolds_number));
+        self.custom_k_correlation = Some(custom_k_correlation);
+        self.guard_custom_correlations();
+    }
+
+    /// sets the custom darcy friction factor from a correlation
+    pub fn set_custom_darcy_correlation(
+        &mut self,
+        custom_darcy_correlation: CustomDarcyCorrelation){
+
+        let correlation = custom_darcy_correlation.clone();
+        self.unguarded_custom_darcy = Arc::new(move |reynolds_number, roughness_ratio| 
+                                               correlation.custom_darcy(
+                                                   reynolds_number, roughness_ratio));
+        self.custom_darcy_correlation = Some(custom_darcy_correlation);
+        self.guard_custom_correlations();
+    }
+
+    /// sets the custom k for reverse flow from a correlation,
+    /// None uses the forward custom k in both directions
+    ///
+    /// only the magnitude of the reverse K is used, the loss
+    /// still opposes the flow
+    pub fn set_reverse_custom_k_correlation(
+        &mut self,
+        reverse_custom_k_correlation: Option<CustomKCorrelation>){
+
+        self.unguarded_reverse_custom_k = match reverse_custom_k_correlation.clone() {
+            Some(correlation) => Some(Arc::new(move |reynolds_number| 
+                                               correlation.custom_k(reynolds_number))),
+            None => None,
+        };
+        self.reverse_custom_k_correlation = reverse_custom_k_correlation;
+        self.guard_custom_correlations();
+    }
+
+    /// returns the custom k correlation for reverse flow, 
+    /// or None if the forward custom k is used in both directions
+    pub fn get_reverse_custom_k_correlation(&self) -> Option<&CustomKCorrelation> {
+        return self.reverse_custom_k_correlation.as_ref();
+    }
+
+    /// returns the custom k correlation, or None if the custom k
+    /// was given as a function
+    pub fn get_custom_k_correlation(&self) -> Option<&CustomKCorrelation> {
+        return self.custom_k_correlation.as_ref();
+    }
+
+    /// returns the custom darcy correlation, or None if the custom
+    /// darcy was given as a function
+    pub fn get_custom_darcy_correlation(&self) 
+        -> Option<&CustomDarcyCorrelation> {
+        return self.custom_darcy_correlation.as_ref();
+    }
+
+    /// sets the lowest and highest reynolds number (magnitude) the
+    /// custom k and darcy were fitted over, None goes back to
+    /// whatever range the correlations know themselves
+    pub fn set_fitted_reynolds_number_range(
+        &mut self,
+        fitted_reynolds_number_range: Option<(f64, f64)>){
+        self.fitted_reynolds_number_range = fitted_reynolds_number_range;
+        self.guard_custom_correlations();
+    }
+
+    /// returns the lowest and highest reynolds number (magnitude)
+    /// the custom k and darcy were fitted over
+    ///
+    /// this is the range set on the component, otherwise that
+    /// of the custom k correlation, then the custom darcy 
+    /// correlation, and None if none of these know it
+    pub fn get_fitted_reynolds_number_range(&self) -> Option<(f64, f64)> {
+
+        if self.fitted_reynolds_number_range.is_some() {
+            return self.fitted_reynolds_number_range;
+        }
+
+        let custom_k_range = self.custom_k_correlation.as_ref()
+            .and_then(|correlation| correlation.get_fitted_reynolds_number_range());
+
+        if custom_k_range.is_some() {
+            return custom_k_range;
+        }
+
+        return self.custom_darcy_correlation.as_ref()
+            .and_then(|correlation| correlation.get_fitted_reynolds_number_range());
+    }
+
+    /// sets what the component does when its correlations are used
+    /// outside the fitted range, see OutOfRangePolicy
+    pub fn set_out_of_range_policy(&mut self, out_of_range_policy: OutOfRangePolicy) {
+        self.out_of_range_policy = out_of_range_policy;
+        self.guard_custom_correlations();
+    }
+
+    pub fn get_out_of_range_policy(&self) -> OutOfRangePolicy {
+        return self.out_of_range_policy;
+    }
+
+    // rebuilds the custom k and darcy the solvers use from the
+    // unguarded ones, so they follow the out of range policy for
+    // the current fitted range, and the reverse flow convention
+    // (the magnitude from the correlation, the sign from Re)
+    //
+    // this has to be called whenever any of these change
+    fn guard_custom_correlations(&mut self) {
+
+        let fitted_reynolds_number_range = self.get_fitted_reynolds_number_range();
+        let out_of_range_policy = self.out_of_range_policy;
+
+        let unguarded_custom_k = self.unguarded_custom_k.clone();
+        let unguarded_reverse_custom_k = self.unguarded_reverse_custom_k.clone();
+        self.custom_k = Box::new(move |reynolds_number| {
+
+            let correlation_reynolds_number = 
+                out_of_range_policy.get_correlation_reynolds_number(
+                    reynolds_number, fitted_reynolds_number_range);
+
+            let custom_k_value = match (reynolds_number < 0.0, 
+                                        unguarded_reverse_custom_k.as_ref()) {
+                (true, Some(reverse_custom_k)) => 
+                    reverse_custom_k(correlation_reynolds_number),
+                _ => unguarded_custom_k(correlation_reynolds_number),
+            };
+
+            return with_sign_of_reynolds_number(
+                custom_k_value.abs(), reynolds_number);
+        });
+
+        let unguarded_custom_darcy = self.unguarded_custom_darcy.clone();
+        self.custom_darcy = Box::new(move |reynolds_number, roughness_ratio| {
+
+            let correlation_reynolds_number = 
+                out_of_range_policy.get_correlation_reynolds_number(
+                    reynolds_number, fitted_reynolds_number_range);
+
+            let custom_darcy_value = unguarded_custom_darcy(
+                correlation_reynolds_number, roughness_ratio);
+
+            return with_sign_of_reynolds_number(
+                custom_darcy_value.abs(), reynolds_number);
+        });
+    }
+
 }
 
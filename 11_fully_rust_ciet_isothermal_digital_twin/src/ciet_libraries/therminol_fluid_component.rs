@@ -0,0 +1,242 @@
+extern crate fluid_mechanics_rust;
+use fluid_mechanics_rust::prelude::*;
+
+use crate::therminol_pipe::TherminolPipe;
+use crate::therminol_component::{TherminolCustomComponent, TherminolCustomComponentTraits};
+use crate::therminol_valve::TherminolValve;
+use crate::therminol_check_valve::TherminolCheckValve;
+use crate::OutOfRangePolicy;
+
+/// lowest fluid temperature the therminol VP1 property
+/// correlations are valid for
+pub const THERMINOL_MIN_TEMPERATURE_DEGREES_C: f64 = 20.0;
+
+/// highest fluid temperature the therminol VP1 property
+/// correlations are valid for
+pub const THERMINOL_MAX_TEMPERATURE_DEGREES_C: f64 = 180.0;
+
+/// returns true if the therminol property correlations can be
+/// used at this fluid temperature
+pub fn therminol_temperature_in_range(
+    fluid_temp: ThermodynamicTemperature) -> bool {
+
+    let fluid_temp_degrees_c = fluid_temp.get::<degree_celsius>();
+
+    return fluid_temp_degrees_c >= THERMINOL_MIN_TEMPERATURE_DEGREES_C
+        && fluid_temp_degrees_c <= THERMINOL_MAX_TEMPERATURE_DEGREES_C;
+}
+
+/// This trait is for therminol pipes and custom components,
+/// whose fluid temperature can be changed after they are built
+///
+/// the branches store their components as this trait object
+/// rather than a plain FluidComponent, so that the whole facility
+/// can be brought to a new (uniform) fluid temperature without
+/// rebuilding it
+pub trait TherminolFluidComponent: FluidComponent + Send + Sync {
+
+    /// the name of the component, eg. "heater_version_1_label_1",
+    /// so that it can be found within a branch
+    fn get_name(&self) -> &str;
+
+    fn get_fluid_temperature(&self) -> ThermodynamicTemperature;
+
+    /// sets the temperature at which the therminol density and
+    /// viscosity are evaluated
+    fn set_fluid_temperature(&mut self, fluid_temp: ThermodynamicTemperature);
+
+    /// returns this component as a plain fluid component, which is
+    /// what the series and parallel calculations in
+    /// fluid_mechanics_rust take
+    fn as_fluid_component(&self) -> &dyn FluidComponent;
+
+    /// returns the opening (0-100%) if this component is a valve,
+    /// or None for pipes and other components
+    fn get_valve_opening_percent(&self) -> Option<f64> {
+        return None;
+    }
+
+    /// sets the opening (0-100%) if this component is a valve,
+    /// and does nothing otherwise
+    fn set_valve_opening_percent(&mut self, _opening_percent: f64){
+    }
+
+    /// returns the lowest and highest pressure loss the component
+    /// can have at zero flow
+    ///
+    /// for most components this is just zero, but a shut check
+    /// valve can hold anything up to its cracking pressure,
+    /// and any back pressure at all if it does not leak
+    fn get_zero_flow_pressure_loss_range(&self) -> (Pressure, Pressure) {
+        let zero_pressure = Pressure::new::<pascal>(0.0);
+        return (zero_pressure, zero_pressure);
+    }
+
+    /// returns the part of the pressure loss at this mass flowrate
+    /// which comes from form losses (K), the rest of the pressure
+    /// loss is friction
+    ///
+    /// this is zero unless the component says otherwise
+    fn get_form_loss_immutable(&self, _mass_flowrate: MassRate) -> Pressure {
+        return Pressure::new::<pascal>(0.0);
+    }
+
+    /// returns the lowest and highest reynolds number (magnitude)
+    /// the loss correlations of the component were fitted over
+    ///
+    /// this is None unless the component has fitted correlations
+    /// and knows their range
+    fn get_fitted_reynolds_number_range(&self) -> Option<(f64, f64)> {
+        return None;
+    }
+
+    /// returns what the component does when its loss correlations
+    /// are used outside the fitted range, this only matters if
+    /// the range is known
+    fn get_out_of_range_policy(&self) -> OutOfRangePolicy {
+        return OutOfRangePolicy::default();
+    }
+}
+
+impl TherminolFluidComponent for TherminolPipe {
+
+    fn get_name(&self) -> &str {
+        return TherminolPipe::get_name(self);
+    }
+
+    fn get_fluid_temperature(&self) -> ThermodynamicTemperature {
+        return self.get_fluid_temp();
+    }
+
+    fn set_fluid_temperature(&mut self, fluid_temp: ThermodynamicTemperature){
+        self.set_fluid_temp(fluid_temp);
+    }
+
+    fn as_fluid_component(&self) -> &dyn FluidComponent {
+        return self;
+    }
+
+    /// the pipe form loss is K m|m|/(2 rho A^2), with the
+    /// reverse form loss K (if set) in reverse flow
+    fn get_form_loss_immutable(&self, mass_flowrate: MassRate) -> Pressure {
+
+        let form_loss_k = self.get_directional_form_loss_k(
+            mass_flowrate.value < 0.0);
+        let cross_sectional_area = self.get_cross_sectional_area_immutable();
+        let fluid_density = self.get_fluid_density_immutable();
+
+        return 0.5*form_loss_k*mass_flowrate*mass_flowrate.abs()/
+            (fluid_density*cross_sectional_area*cross_sectional_area);
+    }
+}
+
+impl<'pipe_lifetime> TherminolFluidComponent
+for TherminolCustomComponent<'pipe_lifetime> {
+
+    fn get_name(&self) -> &str {
+        return TherminolCustomComponent::get_name(self);
+    }
+
+    fn get_fluid_temperature(&self) -> ThermodynamicTemperature {
+        return self.get_fluid_temp();
+    }
+
+    fn set_fluid_temperature(&mut self, fluid_temp: ThermodynamicTemperature){
+        self.set_fluid_temp(fluid_temp);
+    }
+
+    fn as_fluid_component(&self) -> &dyn FluidComponent {
+        return self;
+    }
+
+    /// the custom form loss is K(Re) m^2/(2 rho A^2), the component
+    /// gives K the sign of Re, so the sign comes from K
+    fn get_form_loss_immutable(&self, mass_flowrate: MassRate) -> Pressure {
+
+        if mass_flowrate.value == 0.0 {
+            return Pressure::new::<pascal>(0.0);
+        }
+
+        let cross_sectional_area = self.get_cross_sectional_area_immutable();
+        let hydraulic_diameter = self.get_hydraulic_diameter_immutable();
+        let fluid_viscosity = self.get_fluid_viscosity_immutable();
+        let fluid_density = self.get_fluid_density_immutable();
+
+        let reynolds_number: f64 = (mass_flowrate/cross_sectional_area*
+            hydraulic_diameter/fluid_viscosity).value;
+
+        let custom_k = self.get_custom_k_immutable()(reynolds_number);
+
+        return 0.5*custom_k*mass_flowrate*mass_flowrate/
+            (fluid_density*cross_sectional_area*cross_sectional_area);
+    }
+
+    fn get_fitted_reynolds_number_range(&self) -> Option<(f64, f64)> {
+        return TherminolCustomComponent::get_fitted_reynolds_number_range(self);
+    }
+
+    fn get_out_of_range_policy(&self) -> OutOfRangePolicy {
+        return TherminolCustomComponent::get_out_of_range_policy(self);
+    }
+}
+
+impl TherminolFluidComponent for TherminolValve {
+
+    fn get_name(&self) -> &str {
+        return TherminolValve::get_name(self);
+    }
+
+    fn get_fluid_temperature(&self) -> ThermodynamicTemperature {
+        return self.get_fluid_temp();
+    }
+
+    fn set_fluid_temperature(&mut self, fluid_temp: ThermodynamicTemperature){
+        self.set_fluid_temp(fluid_temp);
+    }
+
+    fn as_fluid_component(&self) -> &dyn FluidComponent {
+        return self;
+    }
+
+    fn get_valve_opening_percent(&self) -> Option<f64> {
+        return Some(self.get_opening_percent());
+    }
+
+    fn set_valve_opening_percent(&mut self, opening_percent: f64){
+        self.set_opening_percent(opening_percent);
+    }
+
+    /// the valve loss is all form loss
+    fn get_form_loss_immutable(&self, mass_flowrate: MassRate) -> Pressure {
+        return self.get_pressure_loss_immutable(mass_flowrate);
+    }
+}
+
+impl TherminolFluidComponent for TherminolCheckValve {
+
+    fn get_name(&self) -> &str {
+        return TherminolCheckValve::get_name(self);
+    }
+
+    fn get_fluid_temperature(&self) -> ThermodynamicTemperature {
+        return self.get_fluid_temp();
+    }
+
+    fn set_fluid_temperature(&mut self, fluid_temp: ThermodynamicTemperature){
+        self.set_fluid_temp(fluid_temp);
+    }
+
+    fn as_fluid_component(&self) -> &dyn FluidComponent {
+        return self;
+    }
+
+    fn get_zero_flow_pressure_loss_range(&self) -> (Pressure, Pressure) {
+        return TherminolCheckValve::get_zero_flow_pressure_loss_range(self);
+    }
+
+    /// the check valve loss (including the cracking pressure)
+    /// is all form loss
+    fn get_form_loss_immutable(&self, mass_flowrate: MassRate) -> Pressure {
+        return self.get_pressure_loss_immutable(mass_flowrate);
+    }
+}
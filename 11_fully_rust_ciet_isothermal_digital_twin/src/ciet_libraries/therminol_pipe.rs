@@ -35,6 +35,10 @@ pub struct TherminolPipe {
     absolute_roughness: Length,
     name: String,
 
+    // the form loss K for reverse flow, if it differs from 
+    // the forward form loss K
+    reverse_form_loss_k: Option<f64>,
+
 }
 
 impl<'pipe_lifetime> 
@@ -77,10 +81,11 @@ FluidComponent for TherminolPipe{
 
         // get pipe parameters and flow conditions
         // from the get methods
-        let form_loss_k = self.get_pipe_form_loss_k();
+        let mass_flowrate = self.fluid_mass_flowrate;
+        let form_loss_k = self.get_directional_form_loss_k(
+            mass_flowrate.value < 0.0);
         let absolute_roughness = self.get_pipe_absolute_roughness();
         let cross_sectional_area = self.get_cross_sectional_area();
-        let mass_flowrate = self.fluid_mass_flowrate;
         let hydraulic_diameter = self.get_hydraulic_diameter();
         let viscosity = self.get_fluid_viscosity();
         let density = self.get_fluid_density();
@@ -116,7 +121,8 @@ FluidComponent for TherminolPipe{
 
         // get pipe parameters and flow conditions
         // from the get methods
-        let form_loss_k = self.get_pipe_form_loss_k_immutable();
+        let form_loss_k = self.get_directional_form_loss_k(
+            mass_flowrate.value < 0.0);
         let absolute_roughness = self.get_pipe_absolute_roughness_immutable();
         let cross_sectional_area = self.get_cross_sectional_area_immutable();
         let hydraulic_diameter = self.get_hydraulic_diameter_immutable();
@@ -156,7 +162,11 @@ FluidComponent for TherminolPipe{
     fn get_mass_flowrate(&mut self) -> MassRate {
         // get pipe parameters and flow conditions
         // from the get methods
-        let form_loss_k = self.get_pipe_form_loss_k();
+        //
+        // the pressure loss has the same sign as the flow,
+        // so it tells us which form loss K to use
+        let form_loss_k = self.get_directional_form_loss_k(
+            self.pressure_loss.value < 0.0);
         let absolute_roughness = self.get_pipe_absolute_roughness();
         let cross_sectional_area = self.get_cross_sectional_area();
         let hydraulic_diameter = self.get_hydraulic_diameter();
@@ -200,7 +210,11 @@ FluidComponent for TherminolPipe{
         pressure_loss: Pressure) -> MassRate {
         // get pipe parameters and flow conditions
         // from the get methods
-        let form_loss_k = self.get_pipe_form_loss_k_immutable();
+        //
+        // the pressure loss has the same sign as the flow,
+        // so it tells us which form loss K to use
+        let form_loss_k = self.get_directional_form_loss_k(
+            pressure_loss.value < 0.0);
         let absolute_roughness = self.get_pipe_absolute_roughness_immutable();
         let cross_sectional_area = self.get_cross_sectional_area_immutable();
         let hydraulic_diameter = self.get_hydraulic_diameter_immutable();
@@ -390,6 +404,7 @@ impl<'pipe_lifetime> TherminolPipe{
             pressure_loss: Pressure::new::<pascal>(0.0),
             form_loss_k: form_loss_k ,
             absolute_roughness: absolute_roughness,
+            reverse_form_loss_k: None,
         };
 
 
@@ -404,5 +419,39 @@ impl<'pipe_lifetime> TherminolPipe{
 
         self.name = name.to_string();
     }
+
+    /// gets the temperature at which the therminol 
+    /// properties are evaluated
+    pub fn get_fluid_temp(&self) -> ThermodynamicTemperature {
+        return self.fluid_temp;
+    }
+
+    /// sets the temperature at which the therminol 
+    /// properties are evaluated
+    pub fn set_fluid_temp(&mut self, fluid_temp: ThermodynamicTemperature) {
+        self.fluid_temp = fluid_temp;
+    }
+
+    /// sets the form loss K for reverse flow, None uses the
+    /// forward form loss K in both directions
+    ///
+    /// either way, the pressure loss opposes the flow
+    pub fn set_reverse_form_loss_k(&mut self, reverse_form_loss_k: Option<f64>) {
+        self.reverse_form_loss_k = reverse_form_loss_k;
+    }
+
+    /// returns the form loss K for reverse flow, or None if the
+    /// forward form loss K is used in both directions
+    pub fn get_reverse_form_loss_k(&self) -> Option<f64> {
+        return self.reverse_form_loss_k;
+    }
+
+    /// returns the form loss K for flow in this direction
+    pub fn get_directional_form_loss_k(&self, reverse_flow: bool) -> f64 {
+        match (reverse_flow, self.reverse_form_loss_k) {
+            (true, Some(reverse_form_loss_k)) => return reverse_form_loss_k,
+            _ => return self.form_loss_k,
+        }
+    }
 }
 
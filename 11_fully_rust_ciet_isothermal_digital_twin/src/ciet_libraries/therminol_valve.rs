@@ -0,0 +1,349 @@
+extern crate fluid_mechanics_rust;
+use fluid_mechanics_rust::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+/// a shut valve is treated as one passing this fraction of its
+/// fully open flow coefficient, so the branch still has a finite
+/// (if very large) form loss and the solvers do not see infinities
+///
+/// to stop the flow in a branch completely, close the branch
+/// instead (eg. set_ctah_branch_valve_open(false))
+pub const SHUT_VALVE_RELATIVE_FLOW_COEFFICIENT: f64 = 1.0e-3;
+
+/// This is how the flow coefficient (Cv) of a valve changes with
+/// its opening, relative to the fully open flow coefficient
+///
+/// with x the opening as a fraction (0 shut, 1 fully open),
+/// the relative flow coefficient f(x) = Cv(x)/Cv(1) is
+///
+/// linear: f = x
+/// equal percentage: f = R^(x-1), where R is the rangeability
+/// quick opening: f = sqrt(x)
+///
+/// and since the pressure loss goes as (flow/Cv)^2, the form loss
+/// of the valve is K(x) = K_open/f(x)^2
+///
+/// in toml, eg.
+///
+/// ```toml
+/// characteristic = "linear"
+/// characteristic = { equal_percentage = { rangeability = 50.0 } }
+/// characteristic = "quick_opening"
+/// ```
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ValveCharacteristic {
+
+    #[serde(rename = "linear")]
+    Linear,
+
+    /// each equal step in opening changes the flow coefficient
+    /// by the same percentage, rangeability is Cv(1)/Cv(0)
+    #[serde(rename = "equal_percentage")]
+    EqualPercentage { rangeability: f64 },
+
+    #[serde(rename = "quick_opening")]
+    QuickOpening,
+}
+
+impl ValveCharacteristic {
+
+    /// returns the flow coefficient at this opening (0-100%)
+    /// relative to the fully open flow coefficient
+    ///
+    /// this never goes below SHUT_VALVE_RELATIVE_FLOW_COEFFICIENT
+    pub fn get_relative_flow_coefficient(&self, opening_percent: f64) -> f64 {
+
+        let opening_fraction = (opening_percent/100.0).clamp(0.0, 1.0);
+
+        let relative_flow_coefficient = match self {
+            ValveCharacteristic::Linear => opening_fraction,
+            ValveCharacteristic::EqualPercentage { rangeability } =>
+                rangeability.powf(opening_fraction - 1.0),
+            ValveCharacteristic::QuickOpening => opening_fraction.sqrt(),
+        };
+
+        return relative_flow_coefficient.max(
+            SHUT_VALVE_RELATIVE_FLOW_COEFFICIENT);
+    }
+
+    /// returns an error message if the characteristic
+    /// cannot be used (eg. a rangeability of zero)
+    pub fn validate(&self) -> Result<(), String> {
+
+        if let ValveCharacteristic::EqualPercentage { rangeability } = self {
+            if rangeability.is_nan() || *rangeability <= 1.0 {
+                return Err(format!(
+                        "equal percentage rangeability must be more than 1, got {}",
+                        rangeability));
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+/// this struct or class is to be instantiated into an object
+/// which can represent a therminol valve with a variable opening
+///
+/// the pressure loss is all form loss, with a K which depends on
+/// the opening (see ValveCharacteristic), the valve length only
+/// sets its fluid volume and inertia
+pub struct TherminolValve {
+
+    therminol_properties: TherminolVP1Properties,
+    fluid_temp: ThermodynamicTemperature,
+    fluid_mass_flowrate: MassRate,
+
+    internal_pressure: Pressure,
+    incline_angle: Angle,
+    component_length: Length,
+    hydraulic_diameter: Length,
+
+    pressure_loss: Pressure,
+    name: String,
+
+    fully_open_k: f64,
+    characteristic: ValveCharacteristic,
+    opening_percent: f64,
+
+    // if true, the fully open form loss is taken to be in the
+    // rest of the branch already (eg. in a fitted branch correlation),
+    // so only the extra loss from throttling is counted here
+    throttling_loss_only: bool,
+}
+
+impl FluidComponent for TherminolValve {
+    fn get_pressure_loss(&mut self) -> Pressure {
+
+        let mass_flowrate = self.fluid_mass_flowrate;
+        self.pressure_loss = self.get_pressure_loss_immutable(mass_flowrate);
+
+        return self.pressure_loss;
+    }
+
+    /// the pressure loss is K m|m|/(2 rho A^2), so it
+    /// always opposes the flow
+    fn get_pressure_loss_immutable(
+        &self,
+        mass_flowrate: MassRate) -> Pressure {
+
+        let form_loss_k = self.get_form_loss_k();
+        let cross_sectional_area = self.get_cross_sectional_area_immutable();
+        let fluid_density = self.get_fluid_density_immutable();
+
+        let pressure_loss = 0.5*form_loss_k*mass_flowrate*mass_flowrate.abs()/
+            (fluid_density*cross_sectional_area*cross_sectional_area);
+
+        return pressure_loss;
+    }
+
+    fn set_pressure_loss(&mut self, pressure_loss: Pressure){
+        self.pressure_loss = pressure_loss;
+    }
+
+    fn set_mass_flowrate(&mut self, mass_flowrate: MassRate){
+        self.fluid_mass_flowrate = mass_flowrate;
+    }
+
+    fn get_mass_flowrate(&mut self) -> MassRate {
+
+        let pressure_loss = self.pressure_loss;
+        let mass_flowrate =
+            self.get_mass_flowrate_from_pressure_loss_immutable(pressure_loss);
+
+        self.set_mass_flowrate(mass_flowrate);
+
+        return self.fluid_mass_flowrate;
+    }
+
+    /// inverts the pressure loss, m = A sqrt(2 rho |dP|/K)
+    /// with the sign of the pressure loss
+    ///
+    /// a valve with no form loss (fully open with throttling_loss_only)
+    /// cannot set the flowrate, so NaN is returned unless the
+    /// pressure loss is zero
+    fn get_mass_flowrate_from_pressure_loss_immutable(
+        &self,
+        pressure_loss: Pressure) -> MassRate {
+
+        let form_loss_k = self.get_form_loss_k();
+        let cross_sectional_area = self.get_cross_sectional_area_immutable();
+        let fluid_density = self.get_fluid_density_immutable();
+
+        if pressure_loss.value == 0.0 {
+            return MassRate::new::<kilogram_per_second>(0.0);
+        }
+
+        if form_loss_k <= 0.0 {
+            return MassRate::new::<kilogram_per_second>(f64::NAN);
+        }
+
+        let mass_flowrate_magnitude: MassRate = cross_sectional_area*
+            (2.0*fluid_density*pressure_loss.abs()/form_loss_k).sqrt();
+
+        match pressure_loss.value.is_sign_positive() {
+            true => return mass_flowrate_magnitude,
+            false => return -mass_flowrate_magnitude,
+        }
+    }
+
+    fn get_cross_sectional_area(&mut self) -> Area {
+        return self.get_cross_sectional_area_immutable();
+    }
+
+    fn get_cross_sectional_area_immutable(&self) -> Area {
+        return self.hydraulic_diameter*
+            self.hydraulic_diameter*
+            PI/4.0_f64;
+    }
+
+    fn get_hydraulic_diameter(&mut self) -> Length {
+        return self.hydraulic_diameter;
+    }
+
+    fn get_hydraulic_diameter_immutable(&self) -> Length {
+        return self.hydraulic_diameter;
+    }
+
+    fn get_fluid_viscosity(&mut self) -> DynamicViscosity {
+        return self.get_fluid_viscosity_immutable();
+    }
+
+    fn get_fluid_viscosity_immutable(&self) -> DynamicViscosity {
+        return self.therminol_properties.viscosity(self.fluid_temp);
+    }
+
+    fn get_fluid_density(&mut self) -> MassDensity {
+        return self.get_fluid_density_immutable();
+    }
+
+    fn get_fluid_density_immutable(&self) -> MassDensity {
+        return self.therminol_properties.density(self.fluid_temp);
+    }
+
+    fn get_component_length(&mut self) -> Length {
+        return self.component_length;
+    }
+
+    fn get_component_length_immutable(&self) -> Length {
+        return self.component_length;
+    }
+
+    fn get_incline_angle(&mut self) -> Angle {
+        return self.incline_angle;
+    }
+
+    fn get_incline_angle_immutable(&self) -> Angle {
+        return self.incline_angle;
+    }
+
+    fn get_internal_pressure_source(&mut self) -> Pressure {
+        return self.internal_pressure;
+    }
+
+    fn get_internal_pressure_source_immutable(&self) -> Pressure {
+        return self.internal_pressure;
+    }
+
+    fn set_internal_pressure_source(&mut self,
+                                    internal_pressure: Pressure){
+        self.internal_pressure = internal_pressure;
+    }
+
+}
+
+impl TherminolValve {
+
+    /// constructor for therminol valves, the valve starts
+    /// fully open
+    pub fn new(name: &str,
+               fluid_temp: ThermodynamicTemperature,
+               incline_angle: Angle,
+               component_length: Length,
+               hydraulic_diameter: Length,
+               fully_open_k: f64,
+               characteristic: ValveCharacteristic) -> Self {
+
+        return Self {
+            name: name.to_string(),
+            therminol_properties: TherminolVP1Properties::new(),
+            fluid_temp,
+            fluid_mass_flowrate: MassRate::new::<kilogram_per_second>(0.0),
+            internal_pressure: Pressure::new::<pascal>(0.0),
+            incline_angle,
+            component_length,
+            hydraulic_diameter,
+            pressure_loss: Pressure::new::<pascal>(0.0),
+            fully_open_k,
+            characteristic,
+            opening_percent: 100.0,
+            throttling_loss_only: false,
+        };
+    }
+
+    /// gets the name of the therminol valve as a string slice
+    pub fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// gets the temperature at which the therminol
+    /// properties are evaluated
+    pub fn get_fluid_temp(&self) -> ThermodynamicTemperature {
+        return self.fluid_temp;
+    }
+
+    /// sets the temperature at which the therminol
+    /// properties are evaluated
+    pub fn set_fluid_temp(&mut self, fluid_temp: ThermodynamicTemperature) {
+        self.fluid_temp = fluid_temp;
+    }
+
+    pub fn get_opening_percent(&self) -> f64 {
+        return self.opening_percent;
+    }
+
+    /// sets the valve opening, 0% is shut and 100% fully open,
+    /// anything outside this range is clamped to it
+    pub fn set_opening_percent(&mut self, opening_percent: f64) {
+        self.opening_percent = opening_percent.clamp(0.0, 100.0);
+    }
+
+    pub fn get_characteristic(&self) -> ValveCharacteristic {
+        return self.characteristic;
+    }
+
+    pub fn get_fully_open_k(&self) -> f64 {
+        return self.fully_open_k;
+    }
+
+    /// if true, only the extra form loss from throttling,
+    /// K_open (1/f^2 - 1), is counted, so a fully open valve
+    /// adds no form loss
+    ///
+    /// this is for valves whose fully open loss is already in a
+    /// fitted correlation for the rest of the branch, as in ciet
+    pub fn set_throttling_loss_only(&mut self, throttling_loss_only: bool) {
+        self.throttling_loss_only = throttling_loss_only;
+    }
+
+    pub fn get_throttling_loss_only(&self) -> bool {
+        return self.throttling_loss_only;
+    }
+
+    /// returns the form loss K of the valve at its current opening
+    pub fn get_form_loss_k(&self) -> f64 {
+
+        let relative_flow_coefficient =
+            self.characteristic.get_relative_flow_coefficient(
+                self.opening_percent);
+
+        let throttled_k = self.fully_open_k/
+            (relative_flow_coefficient*relative_flow_coefficient);
+
+        match self.throttling_loss_only {
+            true => return throttled_k - self.fully_open_k,
+            false => return throttled_k,
+        }
+    }
+}
@@ -0,0 +1,130 @@
+extern crate fluid_mechanics_rust;
+use fluid_mechanics_rust::prelude::*;
+
+use crate::{TherminolSeriesComponents, SeriesBranchSolverSettings,
+    CIETSolverError, find_series_branch_mass_flowrate};
+
+/// returns the inertance of a branch, ie. the sum of L/A over
+/// all its components
+///
+/// the pressure needed to accelerate the fluid in the branch is
+/// the inertance times the rate of change of mass flowrate
+pub fn get_branch_inertance(branch: &dyn TherminolSeriesComponents)
+    -> ReciprocalLength {
+
+    let zero_inertance: ReciprocalLength =
+        Length::new::<meter>(0.0)/Area::new::<square_meter>(1.0);
+
+    return branch.get_therminol_components().iter()
+        .map(|component| component.get_component_length_immutable()/
+             component.get_cross_sectional_area_immutable())
+        .fold(zero_inertance, |inertance_sum, component_inertance|
+              inertance_sum + component_inertance);
+}
+
+/// This is a branch with fluid inertia, over one timestep
+///
+/// the momentum equation for the branch is
+///
+/// (L/A) dm/dt = branch pressure change(m) - pressure change across branch
+///
+/// which is discretised implicitly (backward euler):
+///
+/// (L/A) (m - m_old)/dt = branch pressure change(m) - pressure change
+///
+/// so for the parallel branch solver, the branch looks like a steady
+/// branch whose pressure change is the branch pressure change
+/// minus (L/A) (m - m_old)/dt, and the parallel branch solver
+/// can find the flowrates at the end of the timestep the same way
+/// it finds the steady state flowrates
+///
+/// as the timestep gets very large, this goes back to
+/// the steady state branch
+///
+/// check valves in the branch still hold, the flow through a
+/// closed check valve stops at once rather than coasting down
+pub struct InertialBranch<'branch_lifetime> {
+    branch: &'branch_lifetime dyn FluidComponentCollectionMethods,
+    zero_flow_pressure_loss_range: (Pressure, Pressure),
+    solver_settings: SeriesBranchSolverSettings,
+    inertance: ReciprocalLength,
+    previous_mass_flowrate: MassRate,
+    timestep: Time,
+}
+
+impl<'branch_lifetime> InertialBranch<'branch_lifetime> {
+
+    /// constructor, previous_mass_flowrate is the flowrate
+    /// at the start of the timestep
+    ///
+    /// zero_flow_pressure_loss_range and solver_settings are those
+    /// of the branch (see TherminolSeriesComponents and
+    /// find_series_branch_mass_flowrate), so that its check valves
+    /// hold in the same way
+    pub fn new(
+        branch: &'branch_lifetime dyn FluidComponentCollectionMethods,
+        zero_flow_pressure_loss_range: (Pressure, Pressure),
+        solver_settings: SeriesBranchSolverSettings,
+        inertance: ReciprocalLength,
+        previous_mass_flowrate: MassRate,
+        timestep: Time) -> Self {
+
+        return Self {
+            branch,
+            zero_flow_pressure_loss_range,
+            solver_settings,
+            inertance,
+            previous_mass_flowrate,
+            timestep,
+        };
+    }
+
+    /// calculates the mass flowrate at the end of the timestep
+    /// from pressure change, and returns an error if brent
+    /// cannot find it
+    ///
+    /// this is the same search as the steady branch, but on the
+    /// pressure change with the inertial term, at zero flow the
+    /// inertial term is a constant, so the pressure changes the
+    /// shut check valves can hold are shifted by it
+    pub fn try_get_mass_flowrate_from_pressure_change(
+        &self,
+        pressure_change: Pressure) -> Result<MassRate, CIETSolverError> {
+
+        return find_series_branch_mass_flowrate(
+            &|mass_flowrate| self.get_pressure_change(mass_flowrate),
+            self.zero_flow_pressure_loss_range,
+            pressure_change,
+            &self.solver_settings);
+    }
+}
+
+impl<'branch_lifetime> FluidComponentCollectionMethods
+for InertialBranch<'branch_lifetime> {
+
+    /// calculates pressure change when given the mass flowrate
+    /// at the end of the timestep
+    fn get_pressure_change(
+        &self,
+        fluid_mass_flowrate: MassRate) -> Pressure {
+
+        let inertial_pressure_change: Pressure = self.inertance*
+            (fluid_mass_flowrate - self.previous_mass_flowrate)/self.timestep;
+
+        return self.branch.get_pressure_change(fluid_mass_flowrate)
+            - inertial_pressure_change;
+    }
+
+    /// calculates the mass flowrate at the end of the timestep
+    /// from pressure change
+    ///
+    /// same as the branches, NaN is returned if brent fails
+    /// and the parallel branch solver reports it
+    fn get_mass_flowrate_from_pressure_change(
+        &self,
+        pressure_change: Pressure) -> MassRate {
+
+        return self.try_get_mass_flowrate_from_pressure_change(pressure_change)
+            .unwrap_or(MassRate::new::<kilogram_per_second>(f64::NAN));
+    }
+}
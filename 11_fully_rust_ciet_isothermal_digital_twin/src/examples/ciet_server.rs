@@ -1,13 +1,7 @@
-// these imports are for mutex locks and atomic reference counting (ARC)
+// these imports are for mutex locks 
 // which are for bringing mutable objects and references into multithread
 // operations safely
-//
-// probably use in future
-//use std::sync::Arc;
-//use std::sync::Mutex;
-//use std::sync::mpsc;
-//use std::thread;
-//use std::time;
+use std::sync::Mutex;
 use std::time::Instant;
 
 #[warn(missing_docs)]
@@ -17,15 +11,31 @@ use opcua::server::{config};
 
 use fluid_mechanics_rust::prelude::*;
 
-use crate::CIETIsothermalFacility;
-use crate::CTAHBranch;
-use crate::DHXBranch;
-use crate::HeaterBranch;
+use crate::ComponentCatalogue;
+use crate::FacilityTopology;
+use crate::{spawn_isothermal_ciet_solver_thread, IsothermalCIETSetpoints,
+    HydraulicTimestepping, PumpCurve, get_flow_diagnostic_warnings};
 
 /// in example 8,     
 /// we want to check if the server runs polling actions synchronously 
 /// or asynchronously
-pub fn construct_and_run_ciet_server(run_server: bool){
+///
+/// the ctah pump only follows a head against flowrate curve if
+/// use_placeholder_ctah_pump_curve is true, the only curve available
+/// (PumpCurve::ciet_ctah_pump) is a placeholder which is not fitted
+/// to manufacturer data, so the solver status says so whenever
+/// it is in use, otherwise the pump is only a pressure source and
+/// the pump speed node does nothing
+pub fn construct_and_run_ciet_server(run_server: bool,
+                                     use_placeholder_ctah_pump_curve: bool){
+
+    // building ciet, its lookup tables and the solver thread takes
+    // a while, and the solver thread would keep running with
+    // nothing to talk to, so none of it is done unless the
+    // server is going to run
+    if !run_server {
+        return;
+    }
 
     let mut server = build_standard_server();
 
@@ -40,15 +50,33 @@ pub fn construct_and_run_ciet_server(run_server: bool){
     // I'll have 4 variables here
     // note that each variable needs a separate node ID
     // this is how the user will interact with ciet: through these
-    // flowrates and the pump pressure
+    // flowrates, the pump pressure and the branch valves
 
     let ctah_branch_mass_flowrate_node = NodeId::new(ns, "ctah_branch_mass_flowrate");
     let heater_branch_mass_flowrate_node = NodeId::new(ns, "heater_branch_flowrate");
     let dhx_branch_mass_flowrate_node = NodeId::new(ns, "dhx_branch_flowrate");
     let ctah_pump_pressure_node = NodeId::new(ns, "ctah_pump_pressure");
+    let ctah_pump_speed_node = NodeId::new(ns, "ctah_pump_speed");
+    let heater_branch_valve_node = NodeId::new(ns, "heater_branch_valve_open");
+    let dhx_branch_valve_node = NodeId::new(ns, "dhx_branch_valve_open");
+    let ctah_branch_valve_node = NodeId::new(ns, "ctah_branch_valve_open");
+    let heater_branch_valve_opening_node = 
+        NodeId::new(ns, "heater_branch_valve_opening_percent");
+    let dhx_branch_valve_opening_node = 
+        NodeId::new(ns, "dhx_branch_valve_opening_percent");
+    let ctah_branch_valve_opening_node = 
+        NodeId::new(ns, "ctah_branch_valve_opening_percent");
+    let fluid_temperature_node = NodeId::new(ns, "fluid_temperature");
+    let transient_hydraulics_node = NodeId::new(ns, "transient_hydraulics");
+    let hydraulic_timestep_node = NodeId::new(ns, "hydraulic_timestep");
     let calculation_time_node = NodeId::new(ns, "calculation_time");
     let initiation_time_node = NodeId::new(ns, "ciet_obj_construction_time");
     let total_calc_time_node = NodeId::new(ns, "construction_time_plus_calc_time");
+    let solver_status_node = NodeId::new(ns, "solver_status");
+    let solver_iteration_count_node = NodeId::new(ns, "solver_iteration_count");
+    let solver_cache_hit_node = NodeId::new(ns, "solver_cache_hit");
+    let solver_cache_hit_count_node = NodeId::new(ns, "solver_cache_hit_count");
+    let solver_cache_miss_count_node = NodeId::new(ns, "solver_cache_miss_count");
 
     let address_space = server.address_space();
 
@@ -82,6 +110,24 @@ pub fn construct_and_run_ciet_server(run_server: bool){
                 Variable::new(&total_calc_time_node, 
                               "construction_time_plus_calc_time_ms", 
                               "construction_time_plus_calc_time_ms", 0 as f64),
+                Variable::new(&solver_status_node, 
+                              "solver_status", 
+                              "solver_status", "converged"),
+                Variable::new(&solver_iteration_count_node, 
+                              "solver_iteration_count", 
+                              "solver_iteration_count", 0 as f64),
+                Variable::new(&solver_cache_hit_node, 
+                              "solver_cache_hit", 
+                              "solver_cache_hit", false),
+                Variable::new(&solver_cache_hit_count_node, 
+                              "solver_cache_hit_count", 
+                              "solver_cache_hit_count", 0 as f64),
+                Variable::new(&solver_cache_miss_count_node, 
+                              "solver_cache_miss_count", 
+                              "solver_cache_miss_count", 0 as f64),
+                Variable::new(&hydraulic_timestep_node, 
+                              "hydraulic_timestep_s", 
+                              "hydraulic_timestep_s", 0 as f64),
             ],
             &sample_folder_id,
         );
@@ -103,6 +149,94 @@ pub fn construct_and_run_ciet_server(run_server: bool){
             .writable()
             .organized_by(&folder_id)
             .insert(&mut address_space);
+
+        // the ctah pump follows its pump curve at this speed,
+        // on top of whatever ctah branch pressure is set above
+        VariableBuilder::new(&ctah_pump_speed_node, 
+                             "ctah_pump_speed_rpm", "ctah_pump_speed_rpm")
+            .data_type(DataTypeId::Float)
+            .value(0 as f64)
+            .writable()
+            .organized_by(&folder_id)
+            .insert(&mut address_space);
+
+        VariableBuilder::new(&heater_branch_valve_node,
+                             "heater_branch_valve_open", "heater_branch_valve_open")
+            .data_type(DataTypeId::Boolean)
+            .value(true as bool)
+            .writable()
+            .organized_by(&folder_id)
+            .insert(&mut address_space);
+
+        VariableBuilder::new(&dhx_branch_valve_node,
+                             "dhx_branch_valve_open", "dhx_branch_valve_open")
+            .data_type(DataTypeId::Boolean)
+            .value(true as bool)
+            .writable()
+            .organized_by(&folder_id)
+            .insert(&mut address_space);
+
+        VariableBuilder::new(&ctah_branch_valve_node,
+                             "ctah_branch_valve_open", "ctah_branch_valve_open")
+            .data_type(DataTypeId::Boolean)
+            .value(true as bool)
+            .writable()
+            .organized_by(&folder_id)
+            .insert(&mut address_space);
+
+        // the branch valves can also be throttled, from 0% (same as
+        // closed) to 100% (fully open), for throttling studies
+        VariableBuilder::new(&heater_branch_valve_opening_node,
+                             "heater_branch_valve_opening_percent", 
+                             "heater_branch_valve_opening_percent")
+            .data_type(DataTypeId::Float)
+            .value(100 as f64)
+            .writable()
+            .organized_by(&folder_id)
+            .insert(&mut address_space);
+
+        VariableBuilder::new(&dhx_branch_valve_opening_node,
+                             "dhx_branch_valve_opening_percent", 
+                             "dhx_branch_valve_opening_percent")
+            .data_type(DataTypeId::Float)
+            .value(100 as f64)
+            .writable()
+            .organized_by(&folder_id)
+            .insert(&mut address_space);
+
+        VariableBuilder::new(&ctah_branch_valve_opening_node,
+                             "ctah_branch_valve_opening_percent", 
+                             "ctah_branch_valve_opening_percent")
+            .data_type(DataTypeId::Float)
+            .value(100 as f64)
+            .writable()
+            .organized_by(&folder_id)
+            .insert(&mut address_space);
+
+        // the whole facility is at this temperature, the therminol
+        // property correlations are good from 20 to 180 degC
+        VariableBuilder::new(&fluid_temperature_node,
+                             "fluid_temperature_degc", "fluid_temperature_degc")
+            .data_type(DataTypeId::Float)
+            .value(21 as f64)
+            .writable()
+            .organized_by(&folder_id)
+            .insert(&mut address_space);
+
+        // with transient hydraulics on, the flowrates coast up and
+        // down in real time when the pump pressure changes,
+        // with it off, they jump straight to the steady state
+        //
+        // it starts off, so that the server uses the solver cache
+        // and branch lookup tables, which are only for the 
+        // steady state
+        VariableBuilder::new(&transient_hydraulics_node,
+                             "transient_hydraulics_on", "transient_hydraulics_on")
+            .data_type(DataTypeId::Boolean)
+            .value(false as bool)
+            .writable()
+            .organized_by(&folder_id)
+            .insert(&mut address_space);
     }
 
 
@@ -124,234 +258,514 @@ pub fn construct_and_run_ciet_server(run_server: bool){
     server.add_polling_action(5000, print_endpoint_simple);
 
 
-    // we need to prepare transmitters and receivers for the
-    // ciet isothermal facility
-
-    //let (tx, rx) = mpsc::channel();
+    // the ciet facility object is built once and then moved into
+    // its own solver thread
+    //
+    // the server and the solver thread only talk through channels,
+    // every 500 ms the server sends the pump pressure over as a 
+    // setpoint and picks up whatever results the solver has finished,
+    // so a slow calculation never blocks the server
 
+    // construct CIET
+    let start_of_object_init = Instant::now();
 
-    // now this algorithm is REALLY inefficient, i am instantiating CIET at
-    // EVERY timestep in addition to calculation
+    // the branches are assembled from the component catalogue
+    // according to the facility topology, both ship with this crate,
+    // the ctah pump pressure starts at 0 Pa
     //
-    // but if it works, it works
-    let calculate_flowrate_and_pressure_loss = move || {
+    // if the topology is invalid (eg. a missing component), there
+    // is no facility to serve, so the error is printed and the
+    // server does not start
+    let component_catalogue = ComponentCatalogue::ciet_default();
+    let facility_topology = FacilityTopology::ciet_default();
+
+    let mut ciet_isothermal_facility = 
+    match facility_topology.build_isothermal_ciet_facility(
+        &component_catalogue) {
+        Ok(ciet_isothermal_facility) => ciet_isothermal_facility,
+        Err(topology_error) => {
+            println!("could not build ciet from its topology, {}, \
+                     the server will not start", topology_error);
+            return;
+        },
+    };
+
+    // the loss correlations of each custom component are shown 
+    // read only, so the user can see what ciet is using
+    {
+        let mut address_space = address_space.write();
+        let correlation_folder_id = address_space
+            .add_folder("component correlations", "component correlations", 
+                        &NodeId::objects_folder_id())
+            .unwrap();
 
-        // construct CIET
-        let start_of_object_init = Instant::now();
+        let correlation_variables: Vec<Variable> = component_catalogue
+            .custom_components.iter()
+            .map(|custom_component| {
+                let correlation_node = NodeId::new(
+                    ns, format!("{}_correlations", custom_component.name));
+                let correlation_description = format!("{}, {}",
+                    custom_component.custom_k, custom_component.custom_darcy);
+
+                Variable::new(&correlation_node, 
+                              custom_component.name.as_str(), 
+                              custom_component.name.as_str(), 
+                              correlation_description)
+            })
+            .collect();
 
-        // here i will start storing all of ciet's component objects
-        // and constructing them into vectors
-        //
-        // firstly, the ctah branch
-
-        let ctah_branch_factory = CTAHBranch::new();
-        let pipe6a = ctah_branch_factory.get_pipe6a();
-        let static_mixer_41 = ctah_branch_factory.get_static_mixer_41();
-        let ctah_vertical = ctah_branch_factory.get_ctah_vertical();
-        let ctah_horizontal = ctah_branch_factory.get_ctah_horizontal();
-        let pipe_8a = ctah_branch_factory.get_pipe_8a();
-        let static_mixer_40 = ctah_branch_factory.get_static_mixer_40();
-        let pipe_9 = ctah_branch_factory.get_pipe_9();
-        let pipe_10 = ctah_branch_factory.get_pipe_10();
-        let pipe_11 = ctah_branch_factory.get_pipe_11();
-        let pipe_12 = ctah_branch_factory.get_pipe_12();
-        let pipe_13 = ctah_branch_factory.get_pipe_13();
-        let pipe_14 = ctah_branch_factory.get_pipe_14();
-        let flowmeter_40_14a = ctah_branch_factory.get_flowmeter_40_14a();
-        let pipe_15 = ctah_branch_factory.get_pipe_15();
-        let pipe_16 = ctah_branch_factory.get_pipe_16();
-        let branch_17 = ctah_branch_factory.get_branch_17();
-
-        // now push them into a vector
-        // step 2, find the variable using this node object
-        // first let's get the address space
-        // i want to first set my ciet ctah branch pressure to the user specified
-        // value
+        let _ = address_space.add_variables(
+            correlation_variables, &correlation_folder_id);
+    }
+
+    // each manometer in the topology reads the static pressure
+    // difference between its two pressure taps, so it can be
+    // compared directly against the manometers on ciet
+    let manometer_nodes: Vec<NodeId> = ciet_isothermal_facility
+        .get_manometers().iter()
+        .map(|manometer| NodeId::new(
+                ns, format!("{}_pressure_difference", manometer.name)))
+        .collect();
+
+    {
         let mut address_space = address_space.write();
-        
-        // step 1, find the correct node object
-        let ctah_pump_node = ctah_pump_pressure_node.clone();
-        let pump_pressure_value = address_space.
-            get_variable_value(ctah_pump_node).unwrap();
+        let manometer_folder_id = address_space
+            .add_folder("manometers", "manometers", 
+                        &NodeId::objects_folder_id())
+            .unwrap();
 
-        // let's try setting ctah branch pressure to the user specified value
-        //
-        // This is basically how you set ctah pump pressure... kind of cumbersome
-        // but oh well
-
-        // step 3, convert variable value into f64
-        let pump_pressure_value: f64 = pump_pressure_value.
-            value.unwrap().as_f64().unwrap();
-
-        // step 4 convert f64 to Pressure
-        let user_specified_pump_pressure = 
-            Pressure::new::<pascal>(pump_pressure_value);
-
-        let mut mutable_ctah_pump = ctah_branch_factory.get_ctah_pump();
-        mutable_ctah_pump.set_internal_pressure_source(user_specified_pump_pressure);
-
-        let mut ctah_branch_vector :Vec<&dyn FluidComponent> = vec![];
-
-        // element number: 0 
-        ctah_branch_vector.push(&pipe6a); 
-        // 1
-        ctah_branch_vector.push(&static_mixer_41);
-        // 2
-        ctah_branch_vector.push(&ctah_vertical);
-        // 3
-        ctah_branch_vector.push(&ctah_horizontal);
-        // 4
-        ctah_branch_vector.push(&pipe_8a);
-        // 5
-        ctah_branch_vector.push(&static_mixer_40);
-        // 6
-        ctah_branch_vector.push(&pipe_9);
-        // 7
-        ctah_branch_vector.push(&pipe_10);
-        // 8
-        ctah_branch_vector.push(&pipe_11);
-        // 9
-        ctah_branch_vector.push(&pipe_12);
-        // 10
-        ctah_branch_vector.push(&mutable_ctah_pump);
-        // 11
-        ctah_branch_vector.push(&pipe_13);
-        // 12
-        ctah_branch_vector.push(&pipe_14);
-        //13
-        ctah_branch_vector.push(&flowmeter_40_14a);
-        //14
-        ctah_branch_vector.push(&pipe_15);
-        //15
-        ctah_branch_vector.push(&pipe_16);
-        //16
-        ctah_branch_vector.push(&branch_17);
-
-        // now set the vector in the CTAHBranch Object
-
-        let mut ctah_branch = CTAHBranch::new();
-        ctah_branch.set_fluid_component_vector(ctah_branch_vector);
-
-        // now for the heater branch
-
-        let heater_branch_factory = HeaterBranch::new();
-
-        let branch5 = heater_branch_factory.get_branch5();
-        let pipe4 = heater_branch_factory.get_pipe4();
-        let pipe3 = heater_branch_factory.get_pipe3();
-        let mixer10 = heater_branch_factory.get_mixer10();
-        let pipe2a = heater_branch_factory.get_pipe2a();
-        let heater_top_head_1a = heater_branch_factory.get_heater_top_head_1a();
-        let ciet_heater = heater_branch_factory.get_ciet_heater();
-        let heater_bottom_head_1b = heater_branch_factory.get_heater_bottom_head_1b();
-        let pipe18 = heater_branch_factory.get_pipe18();
-
-        let mut heater_branch_vector :Vec<&dyn FluidComponent> = vec![];
-
-        heater_branch_vector.push(&branch5);
-        heater_branch_vector.push(&pipe4);
-        heater_branch_vector.push(&pipe3);
-        heater_branch_vector.push(&mixer10);
-        heater_branch_vector.push(&pipe2a);
-        heater_branch_vector.push(&heater_top_head_1a);
-        heater_branch_vector.push(&ciet_heater);
-        heater_branch_vector.push(&heater_bottom_head_1b);
-        heater_branch_vector.push(&pipe18);
-
-        let mut heater_branch = HeaterBranch::new();
-        heater_branch.set_fluid_component_vector(heater_branch_vector);
-
-        // last but not least the dhx branch
-        let dhx_branch_factory = DHXBranch::new();
-
-        let pipe26 = dhx_branch_factory.get_pipe26();
-        // item 25
-        let static_mixer_21 = dhx_branch_factory.get_static_mixer_21();
-        let pipe25a = dhx_branch_factory.get_pipe25a();
-        // item 24
-        let dhx_shell_side_heat_exchanger = dhx_branch_factory.get_dhx_shell_side_heat_exchanger();
-        // item 23
-        let static_mixer_20 = dhx_branch_factory.get_static_mixer_20();
-        let pipe23a = dhx_branch_factory.get_pipe23a();
-        let pipe22 = dhx_branch_factory.get_pipe22();
-        // item 21a
-        let flowmeter20 = dhx_branch_factory.get_flowmeter20();
-        let pipe21 = dhx_branch_factory.get_pipe21();
-        let pipe20 = dhx_branch_factory.get_pipe20();
-        let pipe19 = dhx_branch_factory.get_pipe19();
-
-        let mut dhx_branch_vector :Vec<&dyn FluidComponent> = vec![];
-
-        dhx_branch_vector.push(&pipe26);
-        dhx_branch_vector.push(&static_mixer_21);
-        dhx_branch_vector.push(&pipe25a);
-        dhx_branch_vector.push(&dhx_shell_side_heat_exchanger);
-        dhx_branch_vector.push(&static_mixer_20);
-        dhx_branch_vector.push(&pipe23a);
-        dhx_branch_vector.push(&pipe22);
-        dhx_branch_vector.push(&flowmeter20);
-        dhx_branch_vector.push(&pipe21);
-        dhx_branch_vector.push(&pipe20);
-        dhx_branch_vector.push(&pipe19);
-
-        let mut dhx_branch = DHXBranch::new();
-        dhx_branch.set_fluid_component_vector(dhx_branch_vector);
-
-        // now we are ready for ciet
-
-        let mut ciet_isothermal_facility = 
-        CIETIsothermalFacility::new(ctah_branch, heater_branch, dhx_branch);
-
-        let initiation_duration = start_of_object_init.elapsed();
-
-
-
-        // step 5, set the pump pressure to the correct value
-        // and calculate everything
-        //
+        let manometer_variables: Vec<Variable> = ciet_isothermal_facility
+            .get_manometers().iter()
+            .zip(manometer_nodes.iter())
+            .map(|(manometer, manometer_node)| {
+                let manometer_variable_name = 
+                    format!("{}_pressure_difference_pa", manometer.name);
 
-        
-        // need to mutably borrow ciet and return the mutable borrow
-        let (calc_time,
-             ctah_branch_flowrate,
-             heater_branch_flowrate,
-             dhx_branch_flowrate)
-             = ciet_isothermal_facility.calculate();
+                Variable::new(manometer_node, 
+                              manometer_variable_name.as_str(), 
+                              manometer_variable_name.as_str(), 
+                              0 as f64)
+            })
+            .collect();
 
-        // this mutable ctah pump cannot be used safely
-        // as it cannot be shared between threads
+        let _ = address_space.add_variables(
+            manometer_variables, &manometer_folder_id);
+    }
 
+    // the pressure change across every component is broken down
+    // into friction, form loss, hydrostatic and pump pressure,
+    // with a folder for each branch and a folder for each component
+    // within it, so the user can see which components are
+    // responsible for the flowrates
+    //
+    // the nodes are in the same order as the components in the
+    // pressure breakdown results
+    let pressure_breakdown_quantities = ["friction_loss", "form_loss",
+        "hydrostatic_pressure_change", "pump_pressure"];
+    let mut pressure_breakdown_nodes: Vec<NodeId> = vec![];
 
+    {
+        let mut address_space = address_space.write();
+        let pressure_breakdown_folder_id = address_space
+            .add_folder("pressure breakdown", "pressure breakdown", 
+                        &NodeId::objects_folder_id())
+            .unwrap();
 
-        // step 6 set the time variable
+        for branch_pressure_breakdown in 
+            ciet_isothermal_facility.get_pressure_breakdown().iter() {
+
+            let branch_folder_name = 
+                branch_pressure_breakdown.branch_role.to_string();
+            let branch_folder_id = address_space
+                .add_folder(branch_folder_name.as_str(), 
+                            branch_folder_name.as_str(), 
+                            &pressure_breakdown_folder_id)
+                .unwrap();
+
+            for component in branch_pressure_breakdown.components.iter() {
+
+                let component_folder_id = address_space
+                    .add_folder(component.name.as_str(), 
+                                component.name.as_str(), 
+                                &branch_folder_id)
+                    .unwrap();
+
+                let component_variables: Vec<Variable> = 
+                    pressure_breakdown_quantities.iter()
+                    .map(|quantity| {
+                        let quantity_node = NodeId::new(
+                            ns, format!("{}_{}", component.name, quantity));
+                        let quantity_variable_name = format!("{}_pa", quantity);
+                        pressure_breakdown_nodes.push(quantity_node.clone());
+
+                        Variable::new(&quantity_node, 
+                                      quantity_variable_name.as_str(), 
+                                      quantity_variable_name.as_str(), 
+                                      0 as f64)
+                    })
+                    .collect();
+
+                let _ = address_space.add_variables(
+                    component_variables, &component_folder_id);
+            }
+        }
+    }
 
-        let calc_time_taken_milleseconds: u16 = 
-            calc_time.as_millis().try_into().unwrap();
+    // the reynolds number, mean velocity and flow regime in every
+    // component, laid out the same way as the pressure breakdown,
+    // along with warnings for any component whose loss correlations
+    // are used outside the reynolds numbers they were fitted over,
+    // and a running count of how often this has happened, so the 
+    // user can tell when the twin is outside validated conditions
+    //
+    // the nodes come in threes (Re, velocity and regime) in the same
+    // order as the components in the flow diagnostics results
+    let flow_diagnostic_warnings_node = 
+        NodeId::new(ns, "flow_diagnostic_warnings");
+    let flow_diagnostic_warning_count_node = 
+        NodeId::new(ns, "flow_diagnostic_warning_count");
+    let correlation_out_of_range_count_node = 
+        NodeId::new(ns, "correlation_out_of_range_count");
+    let mut flow_diagnostic_nodes: Vec<NodeId> = vec![];
+
+    {
+        let mut address_space = address_space.write();
+        let flow_diagnostics_folder_id = address_space
+            .add_folder("flow diagnostics", "flow diagnostics", 
+                        &NodeId::objects_folder_id())
+            .unwrap();
+
+        let _ = address_space.add_variables(
+            vec![Variable::new(&flow_diagnostic_warnings_node, 
+                               "flow_diagnostic_warnings", 
+                               "flow_diagnostic_warnings", 
+                               "none".to_string()),
+                 Variable::new(&flow_diagnostic_warning_count_node, 
+                               "flow_diagnostic_warning_count", 
+                               "flow_diagnostic_warning_count", 
+                               0 as f64),
+                 Variable::new(&correlation_out_of_range_count_node, 
+                               "correlation_out_of_range_count", 
+                               "correlation_out_of_range_count", 
+                               0 as f64),
+            ], &flow_diagnostics_folder_id);
+
+        for branch_flow_diagnostics in 
+            ciet_isothermal_facility.get_flow_diagnostics().iter() {
+
+            let branch_folder_name = 
+                branch_flow_diagnostics.branch_role.to_string();
+            let branch_folder_id = address_space
+                .add_folder(branch_folder_name.as_str(), 
+                            branch_folder_name.as_str(), 
+                            &flow_diagnostics_folder_id)
+                .unwrap();
+
+            for component in branch_flow_diagnostics.components.iter() {
+
+                let component_folder_id = address_space
+                    .add_folder(component.name.as_str(), 
+                                component.name.as_str(), 
+                                &branch_folder_id)
+                    .unwrap();
+
+                let reynolds_number_node = NodeId::new(
+                    ns, format!("{}_reynolds_number", component.name));
+                let mean_velocity_node = NodeId::new(
+                    ns, format!("{}_mean_velocity", component.name));
+                let flow_regime_node = NodeId::new(
+                    ns, format!("{}_flow_regime", component.name));
+
+                let _ = address_space.add_variables(
+                    vec![Variable::new(&reynolds_number_node, 
+                                       "reynolds_number", 
+                                       "reynolds_number", 
+                                       0 as f64),
+                         Variable::new(&mean_velocity_node, 
+                                       "mean_velocity_m_per_s", 
+                                       "mean_velocity_m_per_s", 
+                                       0 as f64),
+                         Variable::new(&flow_regime_node, 
+                                       "flow_regime", 
+                                       "flow_regime", 
+                                       component.flow_regime.to_string()),
+                    ], &component_folder_id);
+
+                flow_diagnostic_nodes.push(reynolds_number_node);
+                flow_diagnostic_nodes.push(mean_velocity_node);
+                flow_diagnostic_nodes.push(flow_regime_node);
+            }
+        }
+    }
+
+    // if asked to, the ctah pump follows a head against flowrate 
+    // curve, so the user can set its speed rather than the pump 
+    // pressure, at zero speed, the pump is only a small resistance
+    //
+    // this goes in before the lookup tables are built so they
+    // include the curve at zero speed, there are no tables to 
+    // rebuild yet, but if there were and they failed, ciet just
+    // goes through every component in every branch
+    if use_placeholder_ctah_pump_curve {
+
+        println!("using the PLACEHOLDER ctah pump curve, it is not \
+                 fitted to manufacturer data");
+
+        if let Err(pump_curve_error) = ciet_isothermal_facility.set_ctah_pump_curve(
+            Some(PumpCurve::ciet_ctah_pump())) {
+            println!("ctah pump curve set, but not using branch lookup tables, {}", 
+                     pump_curve_error);
+        }
+    }
+
+    // lookup tables make each calculation much faster, 
+    // the flowrates in ciet stay well within 0.5 kg/s
+    //
+    // if the tables are not accurate enough, ciet just goes 
+    // through every component in every branch as before
+    let lookup_table_result = 
+        ciet_isothermal_facility.build_branch_lookup_tables(
+            MassRate::new::<kilogram_per_second>(-0.5), 
+            MassRate::new::<kilogram_per_second>(0.5), 
+            1001, 
+            MassRate::new::<kilogram_per_second>(1e-4));
+
+    if let Err(lookup_table_error) = lookup_table_result {
+        println!("not using branch lookup tables, {}", lookup_table_error);
+    }
+
+    // this is a one off cost now, it is only incurred when
+    // the server starts
+    let initiation_duration = start_of_object_init.elapsed();
+
+    let initiation_time_taken_millseconds: f64 =
+        initiation_duration.as_secs_f64() * 1000.0;
+
+    let (setpoint_sender, result_receiver, _solver_thread) = 
+        spawn_isothermal_ciet_solver_thread(ciet_isothermal_facility);
+
+    // the receiver can only be used by one thread at a time,
+    // so it needs a mutex to go into the polling action
+    let result_receiver = Mutex::new(result_receiver);
+
+    // the setpoints last sent to the solver, these start at the 
+    // same values as the controller nodes, if a node cannot be 
+    // read (eg. a client wrote the wrong type to it), its 
+    // previous setpoint is sent again
+    let previous_setpoints = Mutex::new(IsothermalCIETSetpoints {
+        ctah_pump_pressure: Pressure::new::<pascal>(0.0),
+        ctah_pump_speed_rpm: 0.0,
+        heater_branch_valve_open: true,
+        dhx_branch_valve_open: true,
+        ctah_branch_valve_open: true,
+        heater_branch_valve_opening_percent: 100.0,
+        dhx_branch_valve_opening_percent: 100.0,
+        ctah_branch_valve_opening_percent: 100.0,
+        fluid_temperature: ThermodynamicTemperature::new::<degree_celsius>(21.0),
+        hydraulic_timestepping: HydraulicTimestepping::SteadyState,
+    });
+
+    let exchange_setpoints_and_results = move || {
+
+        // step 1, read the user specified pump pressure, pump speed,
+        // valve positions, valve openings, fluid temperature and
+        // timestepping from the controller nodes
+        //
+        // any node which is missing or does not hold the right type
+        // keeps its previous setpoint rather than stopping the server
+        let mut address_space = address_space.write();
+        let mut previous_setpoints = previous_setpoints.lock().unwrap();
+
+        let pump_pressure_value: f64 = 
+            read_f64_node(&address_space, &ctah_pump_pressure_node)
+            .unwrap_or(previous_setpoints.ctah_pump_pressure.get::<pascal>());
+
+        let pump_speed_value: f64 = 
+            read_f64_node(&address_space, &ctah_pump_speed_node)
+            .unwrap_or(previous_setpoints.ctah_pump_speed_rpm);
+
+        // step 2 now for heater valve, ctah valve and dhx valve
+        // control, these are opcua Variant::Boolean 
+        let heater_valve_open: bool = 
+            read_bool_node(&address_space, &heater_branch_valve_node)
+            .unwrap_or(previous_setpoints.heater_branch_valve_open);
+
+        let dhx_valve_open: bool = 
+            read_bool_node(&address_space, &dhx_branch_valve_node)
+            .unwrap_or(previous_setpoints.dhx_branch_valve_open);
+
+        let ctah_valve_open: bool = 
+            read_bool_node(&address_space, &ctah_branch_valve_node)
+            .unwrap_or(previous_setpoints.ctah_branch_valve_open);
+
+        let heater_valve_opening_value: f64 = 
+            read_f64_node(&address_space, &heater_branch_valve_opening_node)
+            .unwrap_or(previous_setpoints.heater_branch_valve_opening_percent);
+
+        let dhx_valve_opening_value: f64 = 
+            read_f64_node(&address_space, &dhx_branch_valve_opening_node)
+            .unwrap_or(previous_setpoints.dhx_branch_valve_opening_percent);
+
+        let ctah_valve_opening_value: f64 = 
+            read_f64_node(&address_space, &ctah_branch_valve_opening_node)
+            .unwrap_or(previous_setpoints.ctah_branch_valve_opening_percent);
+
+        let fluid_temperature_value: f64 = 
+            read_f64_node(&address_space, &fluid_temperature_node)
+            .unwrap_or(previous_setpoints.fluid_temperature
+                       .get::<degree_celsius>());
+
+        // in transient mode the timestep is however long it has been
+        // since the solver last ran, which is the polling interval
+        // unless the solver falls behind
+        let hydraulic_timestepping = 
+            match read_bool_node(&address_space, &transient_hydraulics_node) {
+                Some(true) => HydraulicTimestepping::RealTime,
+                Some(false) => HydraulicTimestepping::SteadyState,
+                None => previous_setpoints.hydraulic_timestepping,
+            };
+
+        // step 3 convert f64 to Pressure and send it to the solver
+        // along with the pump speed, valve positions, 
+        // fluid temperature and timestepping
+        //
+        // if the solver is too far behind, the channel is full and
+        // this setpoint is dropped, the next one will go through
+        let setpoints = IsothermalCIETSetpoints {
+            ctah_pump_pressure: Pressure::new::<pascal>(pump_pressure_value),
+            ctah_pump_speed_rpm: pump_speed_value,
+            heater_branch_valve_open: heater_valve_open,
+            dhx_branch_valve_open: dhx_valve_open,
+            ctah_branch_valve_open: ctah_valve_open,
+            heater_branch_valve_opening_percent: heater_valve_opening_value,
+            dhx_branch_valve_opening_percent: dhx_valve_opening_value,
+            ctah_branch_valve_opening_percent: ctah_valve_opening_value,
+            fluid_temperature: ThermodynamicTemperature::new::<degree_celsius>(
+                fluid_temperature_value),
+            hydraulic_timestepping,
+        };
+
+        *previous_setpoints = setpoints;
+
+        let _ = setpoint_sender.try_send(setpoints);
+
+        // step 4, pick up the latest results from the solver,
+        // if the solver has not finished anything new, 
+        // the nodes keep their old values
+        let latest_results = result_receiver.lock().unwrap()
+            .try_iter().last();
+
+        let results = match latest_results {
+            Some(results) => results,
+            None => return,
+        };
+
+        // step 5 set the time variables
+        // these are in f64 milliseconds since calculation 
+        // times are often less than a millisecond now
+
+        let calc_time_taken_milleseconds: f64 = 
+            results.calc_time.as_secs_f64() * 1000.0;
 
         let now = DateTime::now();
         let _ = address_space.set_variable_value(
             calculation_time_node.clone(), 
-            calc_time_taken_milleseconds as f64,
+            calc_time_taken_milleseconds,
             &now, 
             &now);
 
-        let initiation_time_taken_millseconds: u16 =
-            initiation_duration.as_millis().try_into().unwrap();
-
         let now = DateTime::now();
         let _ = address_space.set_variable_value(
             initiation_time_node.clone(), 
-            initiation_time_taken_millseconds as f64,
+            initiation_time_taken_millseconds,
             &now, 
             &now);
 
-        let total_time_taken: u16 =
+        let total_time_taken: f64 =
             calc_time_taken_milleseconds + initiation_time_taken_millseconds;
 
         let now = DateTime::now();
         let _ = address_space.set_variable_value(
             total_calc_time_node.clone(), 
-            total_time_taken as f64,
+            total_time_taken,
+            &now, 
+            &now);
+
+        // the brent iterations go alongside the calculation time
+        let now = DateTime::now();
+        let _ = address_space.set_variable_value(
+            solver_iteration_count_node.clone(), 
+            results.solver_iteration_count as f64,
+            &now, 
+            &now);
+
+        // if the pump pressure and valves have not changed,
+        // the solver skips the calculation (a cache hit),
+        // so the calculation time should be tiny
+        let now = DateTime::now();
+        let _ = address_space.set_variable_value(
+            solver_cache_hit_node.clone(), 
+            results.cache_hit,
+            &now, 
+            &now);
+
+        let now = DateTime::now();
+        let _ = address_space.set_variable_value(
+            solver_cache_hit_count_node.clone(), 
+            results.cache_hit_count as f64,
+            &now, 
+            &now);
+
+        let now = DateTime::now();
+        let _ = address_space.set_variable_value(
+            solver_cache_miss_count_node.clone(), 
+            results.cache_miss_count as f64,
+            &now, 
+            &now);
+
+        // zero means the flowrates are the steady state
+        let hydraulic_timestep_seconds: f64 = match results.timestep {
+            Some(timestep) => timestep.get::<second>(),
+            None => 0.0,
+        };
+
+        let now = DateTime::now();
+        let _ = address_space.set_variable_value(
+            hydraulic_timestep_node.clone(), 
+            hydraulic_timestep_seconds,
+            &now, 
+            &now);
+
+        // step 6 let the user know if the solver is healthy,
+        // if it failed, the flowrates below are the last converged ones
+        //
+        // the user should also know if the lookup tables were not
+        // used (eg. the pump speed or a valve opening has just
+        // changed), since the calculation is then much slower
+        let solver_status: String = match (results.solver_error, 
+                                           results.branch_lookup_tables_bypassed) {
+            (None, false) => "converged".to_string(),
+            (None, true) => 
+                "converged without branch lookup tables, \
+                they are rebuilt once the pump speed and \
+                valve openings settle".to_string(),
+            (Some(solver_error), _) => 
+                format!("failed, showing last converged state: {}", 
+                        solver_error),
+        };
+
+        // results from the placeholder pump curve should never be
+        // mistaken for the real pump
+        let solver_status: String = match use_placeholder_ctah_pump_curve {
+            true => format!("{} (using PLACEHOLDER ctah pump curve)", 
+                            solver_status),
+            false => solver_status,
+        };
+
+        let now = DateTime::now();
+        let _ = address_space.set_variable_value(
+            solver_status_node.clone(), 
+            solver_status,
             &now, 
             &now);
 
@@ -361,33 +775,123 @@ pub fn construct_and_run_ciet_server(run_server: bool){
         let now = DateTime::now();
         let _ = address_space.set_variable_value(
             ctah_branch_mass_flowrate_node.clone(), 
-            ctah_branch_flowrate.value as f64,
+            results.ctah_branch_mass_flowrate.value as f64,
             &now, 
             &now);
 
         let now = DateTime::now();
         let _ = address_space.set_variable_value(
             heater_branch_mass_flowrate_node.clone(), 
-            heater_branch_flowrate.value as f64,
+            results.heater_branch_mass_flowrate.value as f64,
             &now, 
             &now);
 
         let now = DateTime::now();
         let _ = address_space.set_variable_value(
             dhx_branch_mass_flowrate_node.clone(), 
-            dhx_branch_flowrate.value as f64,
+            results.dhx_branch_mass_flowrate.value as f64,
             &now, 
             &now);
 
+        // step 8 the manometer readings, these come in the same
+        // order as the manometer nodes
+        for (manometer_node, manometer_reading) in 
+            manometer_nodes.iter().zip(results.manometer_readings.iter()) {
+
+            let now = DateTime::now();
+            let _ = address_space.set_variable_value(
+                manometer_node.clone(), 
+                manometer_reading.pressure_difference.value as f64,
+                &now, 
+                &now);
+        }
+
+        // step 9 the pressure breakdown, four values per component
+        // in the same order as the pressure breakdown nodes
+        let pressure_breakdown_values: Vec<f64> = results.pressure_breakdown
+            .iter()
+            .flat_map(|branch_pressure_breakdown| 
+                      branch_pressure_breakdown.components.iter())
+            .flat_map(|component| [
+                      component.friction_loss.value,
+                      component.form_loss.value,
+                      component.hydrostatic_pressure_change.value,
+                      component.pump_pressure.value])
+            .collect();
+
+        for (pressure_breakdown_node, pressure_breakdown_value) in 
+            pressure_breakdown_nodes.iter()
+            .zip(pressure_breakdown_values) {
+
+            let now = DateTime::now();
+            let _ = address_space.set_variable_value(
+                pressure_breakdown_node.clone(), 
+                pressure_breakdown_value,
+                &now, 
+                &now);
+        }
+
+        // step 10 the flow diagnostics, three nodes per component
+        // in the same order as the flow diagnostic nodes, 
+        // and then the warnings
+        let component_flow_diagnostics = results.flow_diagnostics
+            .iter()
+            .flat_map(|branch_flow_diagnostics| 
+                      branch_flow_diagnostics.components.iter());
+
+        for (component_nodes, component) in 
+            flow_diagnostic_nodes.chunks(3).zip(component_flow_diagnostics) {
+
+            let now = DateTime::now();
+            let _ = address_space.set_variable_value(
+                component_nodes[0].clone(), 
+                component.reynolds_number,
+                &now, 
+                &now);
+
+            let _ = address_space.set_variable_value(
+                component_nodes[1].clone(), 
+                component.mean_velocity.value,
+                &now, 
+                &now);
+
+            let _ = address_space.set_variable_value(
+                component_nodes[2].clone(), 
+                component.flow_regime.to_string(),
+                &now, 
+                &now);
+        }
+
+        let flow_diagnostic_warnings = 
+            get_flow_diagnostic_warnings(&results.flow_diagnostics);
+
+        let flow_diagnostic_warnings_text = match flow_diagnostic_warnings.is_empty() {
+            true => "none".to_string(),
+            false => flow_diagnostic_warnings.join("; "),
+        };
 
-        // i think we are done!
-
+        let now = DateTime::now();
+        let _ = address_space.set_variable_value(
+            flow_diagnostic_warnings_node.clone(), 
+            flow_diagnostic_warnings_text,
+            &now, 
+            &now);
 
+        let _ = address_space.set_variable_value(
+            flow_diagnostic_warning_count_node.clone(), 
+            flow_diagnostic_warnings.len() as f64,
+            &now, 
+            &now);
 
+        let _ = address_space.set_variable_value(
+            correlation_out_of_range_count_node.clone(), 
+            results.correlation_out_of_range_count as f64,
+            &now, 
+            &now);
 
     };
 
-    server.add_polling_action(500, calculate_flowrate_and_pressure_loss);
+    server.add_polling_action(500, exchange_setpoints_and_results);
 
     // to check if polling server adds the polling time to
     // the execution time
@@ -398,9 +902,25 @@ pub fn construct_and_run_ciet_server(run_server: bool){
     // the endpoint prints (every 5s)
     // otherwise it will print twice as often
 
-    if run_server { server.run(); }
+    server.run();
+
 
+}
+
+/// reads a float controller node, or None if the node is missing
+/// or does not hold a number
+fn read_f64_node(address_space: &AddressSpace, node: &NodeId) -> Option<f64> {
+    return address_space.get_variable_value(node.clone()).ok()?
+        .value?.as_f64();
+}
 
+/// reads a boolean controller node, or None if the node is missing
+/// or does not hold true or false
+fn read_bool_node(address_space: &AddressSpace, node: &NodeId) -> Option<bool> {
+    match address_space.get_variable_value(node.clone()).ok()?.value? {
+        Variant::Boolean(value) => return Some(value),
+        _ => return None,
+    }
 }
 
 const CUSTOM_ENDPOINT_PATH: &str = "/rust_ciet_opcua_server";
@@ -26,6 +26,10 @@ fn main() {
     let run_server = true;
     let dont_run_server = false;
 
+    // the ctah pump curve shipped with this crate is a placeholder,
+    // not fitted to manufacturer data, so it is off unless asked for
+    let use_placeholder_ctah_pump_curve = false;
+
     example_3_timer_server_auto_ip_addr(dont_run_server);
     example_4_timer_server_auto_ip_addr(dont_run_server);
     //example_5_read_and_write_variables(run_server);
@@ -35,7 +39,7 @@ fn main() {
 
 
 
-    construct_and_run_ciet_server(run_server);
+    construct_and_run_ciet_server(run_server, use_placeholder_ctah_pump_curve);
 
 }
 
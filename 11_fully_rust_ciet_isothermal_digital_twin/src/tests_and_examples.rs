@@ -33,71 +33,13 @@ pub fn assert_ctah_behave_ok() {
 
         // get a test version of ctah, the one based on traits
 
-        let ctah_branch_factory = CTAHBranch::new();
-        let pipe6a = ctah_branch_factory.get_pipe6a();
-        let static_mixer_41 = ctah_branch_factory.get_static_mixer_41();
-        let ctah_vertical = ctah_branch_factory.get_ctah_vertical();
-        let ctah_horizontal = ctah_branch_factory.get_ctah_horizontal();
-        let pipe_8a = ctah_branch_factory.get_pipe_8a();
-        let static_mixer_40 = ctah_branch_factory.get_static_mixer_40();
-        let pipe_9 = ctah_branch_factory.get_pipe_9();
-        let pipe_10 = ctah_branch_factory.get_pipe_10();
-        let pipe_11 = ctah_branch_factory.get_pipe_11();
-        let pipe_12 = ctah_branch_factory.get_pipe_12();
-        let pipe_13 = ctah_branch_factory.get_pipe_13();
-        let pipe_14 = ctah_branch_factory.get_pipe_14();
-        let flowmeter_40_14a = ctah_branch_factory.get_flowmeter_40_14a();
-        let pipe_15 = ctah_branch_factory.get_pipe_15();
-        let pipe_16 = ctah_branch_factory.get_pipe_16();
-        let branch_17 = ctah_branch_factory.get_branch_17();
-
-
 
         let user_specified_pump_pressure = 
             Pressure::new::<pascal>(pump_pressure_pascals);
-        let mut mutable_ctah_pump = ctah_branch_factory.get_ctah_pump();
-        mutable_ctah_pump.set_internal_pressure_source(user_specified_pump_pressure);
-
-
-        let mut ctah_branch_vector :Vec<&dyn FluidComponent> = vec![];
-        // element number: 0 
-        ctah_branch_vector.push(&pipe6a); 
-        // 1
-        ctah_branch_vector.push(&static_mixer_41);
-        // 2
-        ctah_branch_vector.push(&ctah_vertical);
-        // 3
-        ctah_branch_vector.push(&ctah_horizontal);
-        // 4
-        ctah_branch_vector.push(&pipe_8a);
-        // 5
-        ctah_branch_vector.push(&static_mixer_40);
-        // 6
-        ctah_branch_vector.push(&pipe_9);
-        // 7
-        ctah_branch_vector.push(&pipe_10);
-        // 8
-        ctah_branch_vector.push(&pipe_11);
-        // 9
-        ctah_branch_vector.push(&pipe_12);
-        // 10
-        ctah_branch_vector.push(&mutable_ctah_pump);
-        // 11
-        ctah_branch_vector.push(&pipe_13);
-        // 12
-        ctah_branch_vector.push(&pipe_14);
-        //13
-        ctah_branch_vector.push(&flowmeter_40_14a);
-        //14
-        ctah_branch_vector.push(&pipe_15);
-        //15
-        ctah_branch_vector.push(&pipe_16);
-        //16
-        ctah_branch_vector.push(&branch_17);
 
 
         let mut ctah_branch = CTAHBranch::new();
-        ctah_branch.set_fluid_component_vector(ctah_branch_vector);
+        ctah_branch.set_ctah_pump_pressure(user_specified_pump_pressure);
 
         let test_ctah_pressure_change = 
             ctah_branch.get_pressure_change(
@@ -145,71 +87,13 @@ pub fn assert_ctah_behave_ok() {
 
         // get a test version of ctah, the one based on traits
 
-        let ctah_branch_factory = CTAHBranch::new();
-        let pipe6a = ctah_branch_factory.get_pipe6a();
-        let static_mixer_41 = ctah_branch_factory.get_static_mixer_41();
-        let ctah_vertical = ctah_branch_factory.get_ctah_vertical();
-        let ctah_horizontal = ctah_branch_factory.get_ctah_horizontal();
-        let pipe_8a = ctah_branch_factory.get_pipe_8a();
-        let static_mixer_40 = ctah_branch_factory.get_static_mixer_40();
-        let pipe_9 = ctah_branch_factory.get_pipe_9();
-        let pipe_10 = ctah_branch_factory.get_pipe_10();
-        let pipe_11 = ctah_branch_factory.get_pipe_11();
-        let pipe_12 = ctah_branch_factory.get_pipe_12();
-        let pipe_13 = ctah_branch_factory.get_pipe_13();
-        let pipe_14 = ctah_branch_factory.get_pipe_14();
-        let flowmeter_40_14a = ctah_branch_factory.get_flowmeter_40_14a();
-        let pipe_15 = ctah_branch_factory.get_pipe_15();
-        let pipe_16 = ctah_branch_factory.get_pipe_16();
-        let branch_17 = ctah_branch_factory.get_branch_17();
-
-
 
         let user_specified_pump_pressure = 
             Pressure::new::<pascal>(pump_pressure_pascals);
-        let mut mutable_ctah_pump = ctah_branch_factory.get_ctah_pump();
-        mutable_ctah_pump.set_internal_pressure_source(user_specified_pump_pressure);
-
-
-        let mut ctah_branch_vector :Vec<&dyn FluidComponent> = vec![];
-        // element number: 0 
-        ctah_branch_vector.push(&pipe6a); 
-        // 1
-        ctah_branch_vector.push(&static_mixer_41);
-        // 2
-        ctah_branch_vector.push(&ctah_vertical);
-        // 3
-        ctah_branch_vector.push(&ctah_horizontal);
-        // 4
-        ctah_branch_vector.push(&pipe_8a);
-        // 5
-        ctah_branch_vector.push(&static_mixer_40);
-        // 6
-        ctah_branch_vector.push(&pipe_9);
-        // 7
-        ctah_branch_vector.push(&pipe_10);
-        // 8
-        ctah_branch_vector.push(&pipe_11);
-        // 9
-        ctah_branch_vector.push(&pipe_12);
-        // 10
-        ctah_branch_vector.push(&mutable_ctah_pump);
-        // 11
-        ctah_branch_vector.push(&pipe_13);
-        // 12
-        ctah_branch_vector.push(&pipe_14);
-        //13
-        ctah_branch_vector.push(&flowmeter_40_14a);
-        //14
-        ctah_branch_vector.push(&pipe_15);
-        //15
-        ctah_branch_vector.push(&pipe_16);
-        //16
-        ctah_branch_vector.push(&branch_17);
 
 
         let mut ctah_branch = CTAHBranch::new();
-        ctah_branch.set_fluid_component_vector(ctah_branch_vector);
+        ctah_branch.set_ctah_pump_pressure(user_specified_pump_pressure);
 
         let test_ctah_mass_flowrate = 
             ctah_branch.get_mass_flowrate_from_pressure_change(
@@ -271,71 +155,13 @@ pub fn assert_ctah_behave_ok() {
 
         // get a test version of ctah, the one based on traits
 
-        let ctah_branch_factory = CTAHBranch::new();
-        let pipe6a = ctah_branch_factory.get_pipe6a();
-        let static_mixer_41 = ctah_branch_factory.get_static_mixer_41();
-        let ctah_vertical = ctah_branch_factory.get_ctah_vertical();
-        let ctah_horizontal = ctah_branch_factory.get_ctah_horizontal();
-        let pipe_8a = ctah_branch_factory.get_pipe_8a();
-        let static_mixer_40 = ctah_branch_factory.get_static_mixer_40();
-        let pipe_9 = ctah_branch_factory.get_pipe_9();
-        let pipe_10 = ctah_branch_factory.get_pipe_10();
-        let pipe_11 = ctah_branch_factory.get_pipe_11();
-        let pipe_12 = ctah_branch_factory.get_pipe_12();
-        let pipe_13 = ctah_branch_factory.get_pipe_13();
-        let pipe_14 = ctah_branch_factory.get_pipe_14();
-        let flowmeter_40_14a = ctah_branch_factory.get_flowmeter_40_14a();
-        let pipe_15 = ctah_branch_factory.get_pipe_15();
-        let pipe_16 = ctah_branch_factory.get_pipe_16();
-        let branch_17 = ctah_branch_factory.get_branch_17();
-
-
 
         let user_specified_pump_pressure = 
             Pressure::new::<pascal>(pump_pressure_pascals);
-        let mut mutable_ctah_pump = ctah_branch_factory.get_ctah_pump();
-        mutable_ctah_pump.set_internal_pressure_source(user_specified_pump_pressure);
-
-
-        let mut ctah_branch_vector :Vec<&dyn FluidComponent> = vec![];
-        // element number: 0 
-        ctah_branch_vector.push(&pipe6a); 
-        // 1
-        ctah_branch_vector.push(&static_mixer_41);
-        // 2
-        ctah_branch_vector.push(&ctah_vertical);
-        // 3
-        ctah_branch_vector.push(&ctah_horizontal);
-        // 4
-        ctah_branch_vector.push(&pipe_8a);
-        // 5
-        ctah_branch_vector.push(&static_mixer_40);
-        // 6
-        ctah_branch_vector.push(&pipe_9);
-        // 7
-        ctah_branch_vector.push(&pipe_10);
-        // 8
-        ctah_branch_vector.push(&pipe_11);
-        // 9
-        ctah_branch_vector.push(&pipe_12);
-        // 10
-        ctah_branch_vector.push(&mutable_ctah_pump);
-        // 11
-        ctah_branch_vector.push(&pipe_13);
-        // 12
-        ctah_branch_vector.push(&pipe_14);
-        //13
-        ctah_branch_vector.push(&flowmeter_40_14a);
-        //14
-        ctah_branch_vector.push(&pipe_15);
-        //15
-        ctah_branch_vector.push(&pipe_16);
-        //16
-        ctah_branch_vector.push(&branch_17);
 
 
         let mut ctah_branch = CTAHBranch::new();
-        ctah_branch.set_fluid_component_vector(ctah_branch_vector);
+        ctah_branch.set_ctah_pump_pressure(user_specified_pump_pressure);
 
         let test_ctah_mass_flowrate = 
             ctah_branch.get_mass_flowrate_from_pressure_change(
@@ -385,71 +211,13 @@ pub fn ctah_branch_manual_test(){
 
     // get a test version of ctah, the one based on traits
 
-    let ctah_branch_factory = CTAHBranch::new();
-    let pipe6a = ctah_branch_factory.get_pipe6a();
-    let static_mixer_41 = ctah_branch_factory.get_static_mixer_41();
-    let ctah_vertical = ctah_branch_factory.get_ctah_vertical();
-    let ctah_horizontal = ctah_branch_factory.get_ctah_horizontal();
-    let pipe_8a = ctah_branch_factory.get_pipe_8a();
-    let static_mixer_40 = ctah_branch_factory.get_static_mixer_40();
-    let pipe_9 = ctah_branch_factory.get_pipe_9();
-    let pipe_10 = ctah_branch_factory.get_pipe_10();
-    let pipe_11 = ctah_branch_factory.get_pipe_11();
-    let pipe_12 = ctah_branch_factory.get_pipe_12();
-    let pipe_13 = ctah_branch_factory.get_pipe_13();
-    let pipe_14 = ctah_branch_factory.get_pipe_14();
-    let flowmeter_40_14a = ctah_branch_factory.get_flowmeter_40_14a();
-    let pipe_15 = ctah_branch_factory.get_pipe_15();
-    let pipe_16 = ctah_branch_factory.get_pipe_16();
-    let branch_17 = ctah_branch_factory.get_branch_17();
-
-
 
     let user_specified_pump_pressure = 
         Pressure::new::<pascal>(pump_pressure_pascals);
-    let mut mutable_ctah_pump = ctah_branch_factory.get_ctah_pump();
-    mutable_ctah_pump.set_internal_pressure_source(user_specified_pump_pressure);
-
-
-    let mut ctah_branch_vector :Vec<&dyn FluidComponent> = vec![];
-    // element number: 0 
-    ctah_branch_vector.push(&pipe6a); 
-    // 1
-    ctah_branch_vector.push(&static_mixer_41);
-    // 2
-    ctah_branch_vector.push(&ctah_vertical);
-    // 3
-    ctah_branch_vector.push(&ctah_horizontal);
-    // 4
-    ctah_branch_vector.push(&pipe_8a);
-    // 5
-    ctah_branch_vector.push(&static_mixer_40);
-    // 6
-    ctah_branch_vector.push(&pipe_9);
-    // 7
-    ctah_branch_vector.push(&pipe_10);
-    // 8
-    ctah_branch_vector.push(&pipe_11);
-    // 9
-    ctah_branch_vector.push(&pipe_12);
-    // 10
-    ctah_branch_vector.push(&mutable_ctah_pump);
-    // 11
-    ctah_branch_vector.push(&pipe_13);
-    // 12
-    ctah_branch_vector.push(&pipe_14);
-    //13
-    ctah_branch_vector.push(&flowmeter_40_14a);
-    //14
-    ctah_branch_vector.push(&pipe_15);
-    //15
-    ctah_branch_vector.push(&pipe_16);
-    //16
-    ctah_branch_vector.push(&branch_17);
 
 
     let mut ctah_branch = CTAHBranch::new();
-    ctah_branch.set_fluid_component_vector(ctah_branch_vector);
+    ctah_branch.set_ctah_pump_pressure(user_specified_pump_pressure);
 
     let test_ctah_mass_flowrate = 
         ctah_branch.get_mass_flowrate_from_pressure_change(
@@ -513,41 +281,7 @@ pub fn assert_dhx_branch_ok(){
                 temperature_degrees_c);
 
         // get a test version of dhx, the one based on traits
-
-        let dhx_branch_factory = DHXBranch::new();
-
-        let pipe26 = dhx_branch_factory.get_pipe26();
-        // item 25
-        let static_mixer_21 = dhx_branch_factory.get_static_mixer_21();
-        let pipe25a = dhx_branch_factory.get_pipe25a();
-        // item 24
-        let dhx_shell_side_heat_exchanger = dhx_branch_factory.get_dhx_shell_side_heat_exchanger();
-        // item 23
-        let static_mixer_20 = dhx_branch_factory.get_static_mixer_20();
-        let pipe23a = dhx_branch_factory.get_pipe23a();
-        let pipe22 = dhx_branch_factory.get_pipe22();
-        // item 21a
-        let flowmeter20 = dhx_branch_factory.get_flowmeter20();
-        let pipe21 = dhx_branch_factory.get_pipe21();
-        let pipe20 = dhx_branch_factory.get_pipe20();
-        let pipe19 = dhx_branch_factory.get_pipe19();
-
-        let mut dhx_branch_vector :Vec<&dyn FluidComponent> = vec![];
-
-        dhx_branch_vector.push(&pipe26);
-        dhx_branch_vector.push(&static_mixer_21);
-        dhx_branch_vector.push(&pipe25a);
-        dhx_branch_vector.push(&dhx_shell_side_heat_exchanger);
-        dhx_branch_vector.push(&static_mixer_20);
-        dhx_branch_vector.push(&pipe23a);
-        dhx_branch_vector.push(&pipe22);
-        dhx_branch_vector.push(&flowmeter20);
-        dhx_branch_vector.push(&pipe21);
-        dhx_branch_vector.push(&pipe20);
-        dhx_branch_vector.push(&pipe19);
-
-        let mut dhx_branch = DHXBranch::new();
-        dhx_branch.set_fluid_component_vector(dhx_branch_vector);
+        let dhx_branch = DHXBranch::new();
 
         let test_dhx_pressure_change = 
             dhx_branch.get_pressure_change(
@@ -587,43 +321,8 @@ pub fn assert_dhx_branch_ok(){
         let temperature_degrees_c = 21.0;
 
 
-
         // get a test version of dhx, the one based on traits
-
-        let dhx_branch_factory = DHXBranch::new();
-
-        let pipe26 = dhx_branch_factory.get_pipe26();
-        // item 25
-        let static_mixer_21 = dhx_branch_factory.get_static_mixer_21();
-        let pipe25a = dhx_branch_factory.get_pipe25a();
-        // item 24
-        let dhx_shell_side_heat_exchanger = dhx_branch_factory.get_dhx_shell_side_heat_exchanger();
-        // item 23
-        let static_mixer_20 = dhx_branch_factory.get_static_mixer_20();
-        let pipe23a = dhx_branch_factory.get_pipe23a();
-        let pipe22 = dhx_branch_factory.get_pipe22();
-        // item 21a
-        let flowmeter20 = dhx_branch_factory.get_flowmeter20();
-        let pipe21 = dhx_branch_factory.get_pipe21();
-        let pipe20 = dhx_branch_factory.get_pipe20();
-        let pipe19 = dhx_branch_factory.get_pipe19();
-
-        let mut dhx_branch_vector :Vec<&dyn FluidComponent> = vec![];
-
-        dhx_branch_vector.push(&pipe26);
-        dhx_branch_vector.push(&static_mixer_21);
-        dhx_branch_vector.push(&pipe25a);
-        dhx_branch_vector.push(&dhx_shell_side_heat_exchanger);
-        dhx_branch_vector.push(&static_mixer_20);
-        dhx_branch_vector.push(&pipe23a);
-        dhx_branch_vector.push(&pipe22);
-        dhx_branch_vector.push(&flowmeter20);
-        dhx_branch_vector.push(&pipe21);
-        dhx_branch_vector.push(&pipe20);
-        dhx_branch_vector.push(&pipe19);
-
-        let mut dhx_branch = DHXBranch::new();
-        dhx_branch.set_fluid_component_vector(dhx_branch_vector);
+        let dhx_branch = DHXBranch::new();
 
         let test_dhx_mass_flowrate = 
             dhx_branch.
@@ -689,32 +388,8 @@ pub fn assert_heater_branch_ok(){
 
         // get a test version of heater, the one based on traits
 
-        let heater_branch_factory = HeaterBranch::new();
-
-        let branch5 = heater_branch_factory.get_branch5();
-        let pipe4 = heater_branch_factory.get_pipe4();
-        let pipe3 = heater_branch_factory.get_pipe3();
-        let mixer10 = heater_branch_factory.get_mixer10();
-        let pipe2a = heater_branch_factory.get_pipe2a();
-        let heater_top_head_1a = heater_branch_factory.get_heater_top_head_1a();
-        let ciet_heater = heater_branch_factory.get_ciet_heater();
-        let heater_bottom_head_1b = heater_branch_factory.get_heater_bottom_head_1b();
-        let pipe18 = heater_branch_factory.get_pipe18();
-
-        let mut heater_branch_vector :Vec<&dyn FluidComponent> = vec![];
-
-        heater_branch_vector.push(&branch5);
-        heater_branch_vector.push(&pipe4);
-        heater_branch_vector.push(&pipe3);
-        heater_branch_vector.push(&mixer10);
-        heater_branch_vector.push(&pipe2a);
-        heater_branch_vector.push(&heater_top_head_1a);
-        heater_branch_vector.push(&ciet_heater);
-        heater_branch_vector.push(&heater_bottom_head_1b);
-        heater_branch_vector.push(&pipe18);
-
-        let mut heater_branch = HeaterBranch::new();
-        heater_branch.set_fluid_component_vector(heater_branch_vector);
+
+        let heater_branch = HeaterBranch::new();
 
         let test_heater_pressure_change = 
             heater_branch.get_pressure_change(
@@ -754,35 +429,10 @@ pub fn assert_heater_branch_ok(){
         let temperature_degrees_c = 21.0;
 
 
-
         // get a test version of heater, the one based on traits
 
-        let heater_branch_factory = HeaterBranch::new();
-
-        let branch5 = heater_branch_factory.get_branch5();
-        let pipe4 = heater_branch_factory.get_pipe4();
-        let pipe3 = heater_branch_factory.get_pipe3();
-        let mixer10 = heater_branch_factory.get_mixer10();
-        let pipe2a = heater_branch_factory.get_pipe2a();
-        let heater_top_head_1a = heater_branch_factory.get_heater_top_head_1a();
-        let ciet_heater = heater_branch_factory.get_ciet_heater();
-        let heater_bottom_head_1b = heater_branch_factory.get_heater_bottom_head_1b();
-        let pipe18 = heater_branch_factory.get_pipe18();
-
-        let mut heater_branch_vector :Vec<&dyn FluidComponent> = vec![];
-
-        heater_branch_vector.push(&branch5);
-        heater_branch_vector.push(&pipe4);
-        heater_branch_vector.push(&pipe3);
-        heater_branch_vector.push(&mixer10);
-        heater_branch_vector.push(&pipe2a);
-        heater_branch_vector.push(&heater_top_head_1a);
-        heater_branch_vector.push(&ciet_heater);
-        heater_branch_vector.push(&heater_bottom_head_1b);
-        heater_branch_vector.push(&pipe18);
-
-        let mut heater_branch = HeaterBranch::new();
-        heater_branch.set_fluid_component_vector(heater_branch_vector);
+
+        let heater_branch = HeaterBranch::new();
 
         let test_heater_mass_flowrate = 
             heater_branch.
@@ -817,6 +467,3106 @@ pub fn assert_heater_branch_ok(){
 }
 
 
+#[test]
+pub fn ciet_facility_can_be_recalculated_with_new_pump_pressure(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, CIETIsothermalFacility};
+
+    // construct ciet once, the facility owns all its components
+    // so it can be built in a function and returned
+    fn build_ciet() -> CIETIsothermalFacility {
+        return CIETIsothermalFacility::new(
+            CTAHBranch::new(), 
+            HeaterBranch::new(), 
+            DHXBranch::new());
+    }
+
+    let mut ciet_isothermal_facility = build_ciet();
+
+    // now calculate a few times with different pump pressures
+    // without rebuilding ciet
+    let mut previous_ctah_flowrate_kg_per_s: f64 = 0.0;
+
+    for pump_pressure_pascals in [0.0, 2000.0, 4000.0] {
+
+        ciet_isothermal_facility.set_ctah_pump_pressure(
+            Pressure::new::<pascal>(pump_pressure_pascals)).unwrap();
+
+        let (_calc_time,
+             ctah_branch_flowrate,
+             heater_branch_flowrate,
+             dhx_branch_flowrate) = ciet_isothermal_facility.calculate();
+
+        // mass must be conserved
+        approx::assert_abs_diff_eq!(
+            ctah_branch_flowrate.value 
+            + heater_branch_flowrate.value 
+            + dhx_branch_flowrate.value,
+            0.0,
+            epsilon = 1e-6);
+
+        // and more pump pressure means more flow through the ctah
+        if pump_pressure_pascals > 0.0 {
+            assert!(ctah_branch_flowrate.value > previous_ctah_flowrate_kg_per_s);
+        }
+
+        previous_ctah_flowrate_kg_per_s = ctah_branch_flowrate.value;
+    }
+}
+
+
+#[test]
+pub fn ciet_facility_can_be_solved_on_its_own_thread(){
+
+    use std::time::Duration;
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, CIETIsothermalFacility,
+        spawn_isothermal_ciet_solver_thread, IsothermalCIETSetpoints,
+        HydraulicTimestepping, StaticMixer41, CTAHVertical};
+    use crate::therminol_component::TherminolCustomComponentTraits;
+
+    // the facility must be thread safe to be moved into the solver thread
+    fn assert_send_and_sync<T: Send + Sync>() {}
+    assert_send_and_sync::<CIETIsothermalFacility>();
+
+    // custom k and darcy set through the custom component trait
+    // must be Sync, so the component stays thread safe
+    let mut custom_component = StaticMixer41::new().get();
+    custom_component.set_custom_k(&CTAHVertical::custom_k);
+    custom_component.set_custom_darcy(&CTAHVertical::custom_darcy);
+
+    let custom_component = std::thread::spawn(move || custom_component)
+        .join().unwrap();
+    assert_eq!(custom_component.get_custom_k_immutable()(1000.0), 3.9);
+
+    let ciet_isothermal_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(), 
+        HeaterBranch::new(), 
+        DHXBranch::new());
+
+    let (setpoint_sender, result_receiver, solver_thread) = 
+        spawn_isothermal_ciet_solver_thread(ciet_isothermal_facility);
+
+    setpoint_sender.send(IsothermalCIETSetpoints { 
+        ctah_pump_pressure: Pressure::new::<pascal>(2000.0),
+        ctah_pump_speed_rpm: 0.0,
+        heater_branch_valve_open: true,
+        dhx_branch_valve_open: true,
+        ctah_branch_valve_open: true,
+        heater_branch_valve_opening_percent: 100.0,
+        dhx_branch_valve_opening_percent: 100.0,
+        ctah_branch_valve_opening_percent: 100.0,
+        fluid_temperature: ThermodynamicTemperature::new::<degree_celsius>(21.0),
+        hydraulic_timestepping: HydraulicTimestepping::SteadyState,
+    }).unwrap();
+
+    let results = result_receiver.recv_timeout(
+        Duration::from_secs(30)).unwrap();
+
+    assert!(results.timestep.is_none());
+
+    // mass must be conserved
+    approx::assert_abs_diff_eq!(
+        results.ctah_branch_mass_flowrate.value 
+        + results.heater_branch_mass_flowrate.value 
+        + results.dhx_branch_mass_flowrate.value,
+        0.0,
+        epsilon = 1e-6);
+
+    // and the pump should push flow through the ctah branch
+    assert!(results.ctah_branch_mass_flowrate.value > 0.0);
+
+    // when the pump is switched off in transient mode, the flow
+    // coasts down rather than stopping at once
+    let timestep = Time::new::<second>(0.5);
+
+    setpoint_sender.send(IsothermalCIETSetpoints { 
+        ctah_pump_pressure: Pressure::new::<pascal>(0.0),
+        ctah_pump_speed_rpm: 0.0,
+        heater_branch_valve_open: true,
+        dhx_branch_valve_open: true,
+        ctah_branch_valve_open: true,
+        heater_branch_valve_opening_percent: 100.0,
+        dhx_branch_valve_opening_percent: 100.0,
+        ctah_branch_valve_opening_percent: 100.0,
+        fluid_temperature: ThermodynamicTemperature::new::<degree_celsius>(21.0),
+        hydraulic_timestepping: HydraulicTimestepping::FixedTimestep(timestep),
+    }).unwrap();
+
+    let coast_down_results = result_receiver.recv_timeout(
+        Duration::from_secs(30)).unwrap();
+
+    assert_eq!(coast_down_results.timestep, Some(timestep));
+    assert!(coast_down_results.ctah_branch_mass_flowrate.value > 0.0);
+    assert!(coast_down_results.ctah_branch_mass_flowrate.value < 
+            results.ctah_branch_mass_flowrate.value);
+
+    // dropping the sender stops the solver thread
+    drop(setpoint_sender);
+    solver_thread.join().unwrap();
+}
+
+
+#[test]
+pub fn ciet_facility_handles_closed_branch_valves(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, CIETIsothermalFacility};
+
+    let mut ciet_isothermal_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(), 
+        HeaterBranch::new(), 
+        DHXBranch::new());
+
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(2000.0)).unwrap();
+
+    // (1) dhx branch closed, flow only goes through the ctah and heater
+    ciet_isothermal_facility.set_dhx_branch_valve_open(false);
+
+    let (_calc_time,
+         ctah_branch_flowrate,
+         heater_branch_flowrate,
+         dhx_branch_flowrate) = ciet_isothermal_facility.calculate();
+
+    assert_eq!(dhx_branch_flowrate.value, 0.0);
+    assert!(ctah_branch_flowrate.value > 0.0);
+    approx::assert_abs_diff_eq!(
+        ctah_branch_flowrate.value + heater_branch_flowrate.value,
+        0.0,
+        epsilon = 1e-6);
+
+    // (2) heater branch closed with positive pump pressure,
+    // the dhx check valve blocks the flow
+    ciet_isothermal_facility.set_dhx_branch_valve_open(true);
+    ciet_isothermal_facility.set_heater_branch_valve_open(false);
+
+    let (_calc_time,
+         ctah_branch_flowrate,
+         heater_branch_flowrate,
+         dhx_branch_flowrate) = ciet_isothermal_facility.calculate();
+
+    assert_eq!(ctah_branch_flowrate.value, 0.0);
+    assert_eq!(heater_branch_flowrate.value, 0.0);
+    assert_eq!(dhx_branch_flowrate.value, 0.0);
+
+    // (3) only the ctah branch open, there is no loop to flow through
+    ciet_isothermal_facility.set_dhx_branch_valve_open(false);
+
+    let (_calc_time,
+         ctah_branch_flowrate,
+         heater_branch_flowrate,
+         dhx_branch_flowrate) = ciet_isothermal_facility.calculate();
+
+    assert_eq!(ctah_branch_flowrate.value, 0.0);
+    assert_eq!(heater_branch_flowrate.value, 0.0);
+    assert_eq!(dhx_branch_flowrate.value, 0.0);
+
+    // (4) everything open again, the facility should recover
+    ciet_isothermal_facility.set_dhx_branch_valve_open(true);
+    ciet_isothermal_facility.set_heater_branch_valve_open(true);
+
+    let (_calc_time,
+         ctah_branch_flowrate,
+         heater_branch_flowrate,
+         dhx_branch_flowrate) = ciet_isothermal_facility.calculate();
+
+    assert!(ctah_branch_flowrate.value > 0.0);
+    approx::assert_abs_diff_eq!(
+        ctah_branch_flowrate.value 
+        + heater_branch_flowrate.value 
+        + dhx_branch_flowrate.value,
+        0.0,
+        epsilon = 1e-6);
+}
+
+
+#[test]
+pub fn heater_valve_closed_blocks_flow_only_if_the_total_pump_head_pushes_forward(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, CIETIsothermalFacility,
+        PumpCurve};
+
+    let mut ciet_isothermal_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(), 
+        HeaterBranch::new(), 
+        DHXBranch::new());
+
+    ciet_isothermal_facility.set_heater_branch_valve_open(false);
+
+    // (1) a pump pressure of -0 Pa is no different from 0 Pa,
+    // there is nothing to push the fluid, so there is no flow
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(-0.0)).unwrap();
+
+    let (_calc_time,
+         ctah_branch_flowrate,
+         heater_branch_flowrate,
+         dhx_branch_flowrate) = ciet_isothermal_facility.calculate();
+
+    assert!(ciet_isothermal_facility.get_solver_error().is_none());
+    assert_eq!(ctah_branch_flowrate.value, 0.0);
+    assert_eq!(heater_branch_flowrate.value, 0.0);
+    assert_eq!(dhx_branch_flowrate.value, 0.0);
+
+    // (2) a small negative pump pressure with the pump running at its
+    // rated speed, the pump curve outweighs the pump pressure,
+    // so the pump still pushes backwards through the dhx check valve
+    ciet_isothermal_facility.set_ctah_pump_curve(
+        Some(PumpCurve::ciet_ctah_pump())).unwrap();
+    ciet_isothermal_facility.set_ctah_pump_speed(3450.0).unwrap();
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(-100.0)).unwrap();
+
+    let (_calc_time,
+         ctah_branch_flowrate,
+         heater_branch_flowrate,
+         dhx_branch_flowrate) = ciet_isothermal_facility.calculate();
+
+    assert!(ciet_isothermal_facility.get_solver_error().is_none());
+    assert_eq!(ctah_branch_flowrate.value, 0.0);
+    assert_eq!(heater_branch_flowrate.value, 0.0);
+    assert_eq!(dhx_branch_flowrate.value, 0.0);
+
+    // the same goes for a timestep
+    let (_calc_time,
+         ctah_branch_flowrate,
+         heater_branch_flowrate,
+         dhx_branch_flowrate) = ciet_isothermal_facility
+        .advance_timestep(Time::new::<second>(0.1));
+
+    assert!(ciet_isothermal_facility.get_solver_error().is_none());
+    assert_eq!(ctah_branch_flowrate.value, 0.0);
+    assert_eq!(heater_branch_flowrate.value, 0.0);
+    assert_eq!(dhx_branch_flowrate.value, 0.0);
+
+    // (3) with the pump stopped, the negative pump pressure drives
+    // the fluid forward through the dhx check valve and back through
+    // the ctah branch, so the flow is solved for as usual
+    ciet_isothermal_facility.set_ctah_pump_speed(0.0).unwrap();
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(-2000.0)).unwrap();
+
+    let (_calc_time,
+         ctah_branch_flowrate,
+         heater_branch_flowrate,
+         dhx_branch_flowrate) = ciet_isothermal_facility.calculate();
+
+    assert!(ciet_isothermal_facility.get_solver_error().is_none());
+    assert!(ctah_branch_flowrate.value < 0.0);
+    assert!(dhx_branch_flowrate.value > 0.0);
+    assert_eq!(heater_branch_flowrate.value, 0.0);
+    approx::assert_abs_diff_eq!(
+        ctah_branch_flowrate.value + dhx_branch_flowrate.value,
+        0.0,
+        epsilon = 1e-6);
+}
+
+
+#[test]
+pub fn parallel_branch_solver_handles_any_number_of_branches(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, CIETIsothermalFacility,
+        ParallelBranchSolver};
+
+    let pump_pressure = Pressure::new::<pascal>(2000.0);
+
+    let mut ctah_branch = CTAHBranch::new();
+    ctah_branch.set_ctah_pump_pressure(pump_pressure);
+    let heater_branch = HeaterBranch::new();
+    let dhx_branch = DHXBranch::new();
+
+    let mut parallel_branch_solver = ParallelBranchSolver::new();
+
+    // (1) the three ciet branches should give the same answer 
+    // as the ciet facility
+    let (_pressure_change, branch_mass_flowrates) = 
+        parallel_branch_solver.solve(
+            &[&heater_branch, &dhx_branch, &ctah_branch]).unwrap();
+
+    let mut ciet_isothermal_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(), 
+        HeaterBranch::new(), 
+        DHXBranch::new());
+    ciet_isothermal_facility.set_ctah_pump_pressure(pump_pressure).unwrap();
+
+    let (_calc_time,
+         ctah_branch_flowrate,
+         heater_branch_flowrate,
+         dhx_branch_flowrate) = ciet_isothermal_facility.calculate();
+
+    approx::assert_abs_diff_eq!(
+        branch_mass_flowrates[0].value, heater_branch_flowrate.value,
+        epsilon = 1e-9);
+    approx::assert_abs_diff_eq!(
+        branch_mass_flowrates[1].value, dhx_branch_flowrate.value,
+        epsilon = 1e-9);
+    approx::assert_abs_diff_eq!(
+        branch_mass_flowrates[2].value, ctah_branch_flowrate.value,
+        epsilon = 1e-9);
+
+    // (2) a fourth branch (a second heater branch in parallel)
+    // should still conserve mass, and the two identical heater branches
+    // should carry the same flow
+    let second_heater_branch = HeaterBranch::new();
+
+    let (_pressure_change, branch_mass_flowrates) = 
+        parallel_branch_solver.solve(
+            &[&heater_branch, &dhx_branch, &ctah_branch, 
+            &second_heater_branch]).unwrap();
+
+    let total_mass_flowrate: f64 = branch_mass_flowrates.iter()
+        .map(|mass_flowrate| mass_flowrate.value).sum();
+
+    approx::assert_abs_diff_eq!(total_mass_flowrate, 0.0, epsilon = 1e-6);
+    approx::assert_abs_diff_eq!(
+        branch_mass_flowrates[0].value, branch_mass_flowrates[3].value,
+        epsilon = 1e-9);
+
+    // (3) a single branch has nowhere for the fluid to go
+    let (_pressure_change, branch_mass_flowrates) = 
+        parallel_branch_solver.solve(&[&ctah_branch]).unwrap();
+
+    assert_eq!(branch_mass_flowrates[0].value, 0.0);
+}
+
+
+#[test]
+pub fn hydraulic_network_matches_parallel_branch_solver(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, 
+        ParallelBranchSolver, HydraulicNetwork};
+
+    // ciet as a network, two nodes (top and bottom) with three
+    // branches between them
+    //
+    // the ctah pump is on the network edge rather than in the branch
+    let mut ciet_network = HydraulicNetwork::new();
+    let top = ciet_network.add_node("top");
+    let bottom = ciet_network.add_node("bottom");
+
+    let heater_edge = ciet_network.add_edge(
+        "heater_branch", top, bottom, Box::new(HeaterBranch::new()));
+    let dhx_edge = ciet_network.add_edge(
+        "dhx_branch", top, bottom, Box::new(DHXBranch::new()));
+    let ctah_edge = ciet_network.add_edge(
+        "ctah_branch", top, bottom, Box::new(CTAHBranch::new()));
+
+    let pump_pressure = Pressure::new::<pascal>(2000.0);
+    ciet_network.set_edge_pump_pressure(ctah_edge, pump_pressure);
+
+    let network_solution = ciet_network.solve();
+    assert!(network_solution.converged);
+
+    // the same branches in the parallel branch solver
+    let mut ctah_branch = CTAHBranch::new();
+    ctah_branch.set_ctah_pump_pressure(pump_pressure);
+    let heater_branch = HeaterBranch::new();
+    let dhx_branch = DHXBranch::new();
+
+    let (pressure_change, branch_mass_flowrates) = 
+        ParallelBranchSolver::new().solve(
+            &[&heater_branch, &dhx_branch, &ctah_branch]).unwrap();
+
+    approx::assert_abs_diff_eq!(
+        network_solution.node_pressures[bottom].value,
+        pressure_change.value,
+        epsilon = 1.0);
+
+    for (edge, branch) in [(heater_edge, 0), (dhx_edge, 1), (ctah_edge, 2)] {
+        approx::assert_abs_diff_eq!(
+            network_solution.edge_mass_flowrates[edge].value,
+            branch_mass_flowrates[branch].value,
+            epsilon = 1e-5);
+    }
+}
+
+#[test]
+pub fn hydraulic_network_solves_several_junctions(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, 
+        HydraulicNetwork, TherminolSeriesBranch, Pipe6a, StaticMixer41,
+        CTAHVertical, CTAHHorizontal, Pipe8a, StaticMixer40, Pipe9, Pipe10,
+        Pipe11, Pipe12, CTAHPump, Pipe13, Pipe14, Flowmeter40, Pipe15,
+        Pipe16, Branch17};
+
+    // the ctah branch is split in two at a middle junction,
+    // the first half is ctah branch components 0 to 9 and the
+    // second half is the ctah pump onwards (components 10 to 16)
+
+    let mut ctah_upper_half = TherminolSeriesBranch::new("ctah_upper_half");
+    ctah_upper_half.add_component(Box::new(Pipe6a::new().get()));
+    ctah_upper_half.add_component(Box::new(StaticMixer41::new().get()));
+    ctah_upper_half.add_component(Box::new(CTAHVertical::new().get()));
+    ctah_upper_half.add_component(Box::new(CTAHHorizontal::new().get()));
+    ctah_upper_half.add_component(Box::new(Pipe8a::new().get()));
+    ctah_upper_half.add_component(Box::new(StaticMixer40::new().get()));
+    ctah_upper_half.add_component(Box::new(Pipe9::new().get()));
+    ctah_upper_half.add_component(Box::new(Pipe10::new().get()));
+    ctah_upper_half.add_component(Box::new(Pipe11::new().get()));
+    ctah_upper_half.add_component(Box::new(Pipe12::new().get()));
+
+    let mut ctah_lower_half = TherminolSeriesBranch::new("ctah_lower_half");
+    let ctah_pump_index = ctah_lower_half.add_component(
+        Box::new(CTAHPump::new().get()));
+    ctah_lower_half.add_component(Box::new(Pipe13::new().get()));
+    ctah_lower_half.add_component(Box::new(Pipe14::new().get()));
+    ctah_lower_half.add_component(Box::new(Flowmeter40::new().get()));
+    ctah_lower_half.add_component(Box::new(Pipe15::new().get()));
+    ctah_lower_half.add_component(Box::new(Pipe16::new().get()));
+    ctah_lower_half.add_component(Box::new(Branch17::new().get()));
+
+    // the pump can sit inside the series branch too
+    let pump_pressure = Pressure::new::<pascal>(2000.0);
+    ctah_lower_half.set_component_internal_pressure_source(
+        ctah_pump_index, pump_pressure);
+
+    let mut ciet_network = HydraulicNetwork::new();
+    let top = ciet_network.add_node("top");
+    let bottom = ciet_network.add_node("bottom");
+    let ctah_middle = ciet_network.add_node("ctah_middle");
+
+    ciet_network.add_edge(
+        "heater_branch", top, bottom, Box::new(HeaterBranch::new()));
+    ciet_network.add_edge(
+        "dhx_branch", top, bottom, Box::new(DHXBranch::new()));
+    let upper_edge = ciet_network.add_edge(
+        "ctah_upper_half", top, ctah_middle, Box::new(ctah_upper_half));
+    let lower_edge = ciet_network.add_edge(
+        "ctah_lower_half", ctah_middle, bottom, Box::new(ctah_lower_half));
+
+    let network_solution = ciet_network.solve();
+    assert!(network_solution.converged);
+
+    // mass is conserved at the middle junction
+    approx::assert_abs_diff_eq!(
+        network_solution.edge_mass_flowrates[upper_edge].value,
+        network_solution.edge_mass_flowrates[lower_edge].value,
+        epsilon = 1e-6);
+
+    // and the split ctah branch behaves like the whole ctah branch
+    let mut ciet_facility_network = HydraulicNetwork::new();
+    let top = ciet_facility_network.add_node("top");
+    let bottom = ciet_facility_network.add_node("bottom");
+    ciet_facility_network.add_edge(
+        "heater_branch", top, bottom, Box::new(HeaterBranch::new()));
+    ciet_facility_network.add_edge(
+        "dhx_branch", top, bottom, Box::new(DHXBranch::new()));
+    let ctah_edge = ciet_facility_network.add_edge(
+        "ctah_branch", top, bottom, Box::new(CTAHBranch::new()));
+    ciet_facility_network.set_edge_pump_pressure(ctah_edge, pump_pressure);
+
+    let reference_solution = ciet_facility_network.solve();
+
+    approx::assert_abs_diff_eq!(
+        network_solution.edge_mass_flowrates[upper_edge].value,
+        reference_solution.edge_mass_flowrates[ctah_edge].value,
+        epsilon = 1e-5);
+
+    assert_eq!(ciet_network.get_node_index("ctah_middle"), Some(ctah_middle));
+}
+
+
+#[test]
+pub fn ciet_facility_keeps_last_converged_state_on_solver_failure(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, 
+        CIETIsothermalFacility, ParallelBranchSolver, CIETSolverError};
+
+    // (1) a bracket which is too narrow should give a bracket
+    // failure rather than a panic
+    let mut ctah_branch = CTAHBranch::new();
+    ctah_branch.set_ctah_pump_pressure(Pressure::new::<pascal>(2000.0));
+    let heater_branch = HeaterBranch::new();
+    let dhx_branch = DHXBranch::new();
+
+    let mut parallel_branch_solver = ParallelBranchSolver::new();
+    parallel_branch_solver.pressure_bracket_half_width = 
+        Pressure::new::<pascal>(1.0);
+    parallel_branch_solver.max_bracket_expansions = 0;
+
+    let solver_result = parallel_branch_solver.solve(
+        &[&heater_branch, &dhx_branch, &ctah_branch]);
+
+    match solver_result {
+        Err(CIETSolverError::BracketFailure { .. }) => (),
+        _ => panic!("expected a bracket failure, got {:?}", solver_result),
+    }
+
+    // (2) the facility should keep its last converged state 
+    // if the pump pressure is far too large to solve for
+    let mut ciet_isothermal_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(), 
+        HeaterBranch::new(), 
+        DHXBranch::new());
+
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(2000.0)).unwrap();
+
+    let (_calc_time,
+         converged_ctah_branch_flowrate,
+         converged_heater_branch_flowrate,
+         converged_dhx_branch_flowrate) = ciet_isothermal_facility.calculate();
+
+    assert_eq!(ciet_isothermal_facility.get_solver_error(), None);
+
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(1.0e7)).unwrap();
+
+    assert!(ciet_isothermal_facility.try_calculate().is_err());
+
+    let (_calc_time,
+         ctah_branch_flowrate,
+         heater_branch_flowrate,
+         dhx_branch_flowrate) = ciet_isothermal_facility.calculate();
+
+    assert!(ciet_isothermal_facility.get_solver_error().is_some());
+    assert_eq!(ctah_branch_flowrate, converged_ctah_branch_flowrate);
+    assert_eq!(heater_branch_flowrate, converged_heater_branch_flowrate);
+    assert_eq!(dhx_branch_flowrate, converged_dhx_branch_flowrate);
+
+    // (3) and recover once the pump pressure is reasonable again
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(2000.0)).unwrap();
+
+    assert!(ciet_isothermal_facility.try_calculate().is_ok());
+    assert_eq!(ciet_isothermal_facility.get_solver_error(), None);
+}
+
+
+#[test]
+pub fn parallel_branch_solver_expands_and_seeds_its_bracket(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, 
+        CIETIsothermalFacility, ParallelBranchSolver,
+        SeriesBranchSolverSettings, CIETSolverError};
+
+    let mut ctah_branch = CTAHBranch::new();
+    ctah_branch.set_ctah_pump_pressure(Pressure::new::<pascal>(2000.0));
+    let heater_branch = HeaterBranch::new();
+    let dhx_branch = DHXBranch::new();
+
+    // (1) reference solution with the usual 50 kPa bracket
+    let (reference_pressure_change, reference_mass_flowrates) = 
+        ParallelBranchSolver::new().solve(
+            &[&heater_branch, &dhx_branch, &ctah_branch]).unwrap();
+
+    // (2) a 1 Pa bracket should be expanded until it
+    // contains the root, and give the same answer
+    let mut parallel_branch_solver = ParallelBranchSolver::new();
+    parallel_branch_solver.pressure_bracket_half_width = 
+        Pressure::new::<pascal>(1.0);
+    parallel_branch_solver.max_bracket_expansions = 30;
+
+    let (pressure_change, branch_mass_flowrates) = 
+        parallel_branch_solver.solve(
+            &[&heater_branch, &dhx_branch, &ctah_branch]).unwrap();
+
+    assert!(parallel_branch_solver.get_last_bracket_expansion_count() > 0);
+    approx::assert_abs_diff_eq!(
+        pressure_change.value, reference_pressure_change.value,
+        epsilon = 1e-3);
+    approx::assert_abs_diff_eq!(
+        branch_mass_flowrates[2].value, reference_mass_flowrates[2].value,
+        epsilon = 1e-6);
+
+    // (3) the next solve starts from the converged pressure change,
+    // so it needs no expansion at all
+    assert_eq!(parallel_branch_solver.get_previous_pressure_change(),
+        Some(pressure_change));
+
+    parallel_branch_solver.solve(
+        &[&heater_branch, &dhx_branch, &ctah_branch]).unwrap();
+
+    assert_eq!(parallel_branch_solver.get_last_bracket_expansion_count(), 0);
+
+    // (4) the facility reports its iterations, and a tight
+    // iteration limit is honoured
+    let mut ciet_isothermal_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(), 
+        HeaterBranch::new(), 
+        DHXBranch::new());
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(2000.0)).unwrap();
+
+    ciet_isothermal_facility.try_calculate().unwrap();
+    let iteration_count = ciet_isothermal_facility.get_solver_iteration_count();
+
+    assert!(iteration_count > 0);
+    assert!(iteration_count <= 30);
+
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(3000.0)).unwrap();
+    ciet_isothermal_facility.set_solver_tolerance(1e-12);
+    ciet_isothermal_facility.set_solver_max_iterations(1);
+
+    assert!(ciet_isothermal_facility.try_calculate().is_err());
+    assert!(ciet_isothermal_facility.get_solver_iteration_count() <= 1);
+
+    // (5) each branch expands its own mass flowrate bracket too,
+    // a pressure change needing 3 kg/s is found even though the
+    // bracket starts at plus or minus 1 kg/s
+    let mut heater_branch = HeaterBranch::new();
+    let fast_flowrate = MassRate::new::<kilogram_per_second>(3.0);
+    let fast_pressure_change = heater_branch.get_pressure_change(fast_flowrate);
+
+    approx::assert_abs_diff_eq!(
+        heater_branch.try_get_mass_flowrate_from_pressure_change(
+            fast_pressure_change).unwrap().value,
+        fast_flowrate.value,
+        epsilon = 1e-6);
+
+    // without room to expand, the branch says so rather than
+    // quietly returning NaN
+    let mut solver_settings = SeriesBranchSolverSettings::new();
+    solver_settings.max_bracket_expansions = 0;
+    heater_branch.set_solver_settings(solver_settings);
+
+    match heater_branch.try_get_mass_flowrate_from_pressure_change(
+        fast_pressure_change) {
+        Err(CIETSolverError::MassFlowrateBracketFailure { .. }) => (),
+        other => panic!("expected a bracket failure, got {:?}", other),
+    }
+    assert!(heater_branch.get_mass_flowrate_from_pressure_change(
+            fast_pressure_change).value.is_nan());
+
+    // and the iteration limit is honoured
+    let mut solver_settings = SeriesBranchSolverSettings::new();
+    solver_settings.tolerance = 1e-15;
+    solver_settings.max_iter = 1;
+    heater_branch.set_solver_settings(solver_settings);
+
+    assert_eq!(heater_branch.try_get_mass_flowrate_from_pressure_change(
+            fast_pressure_change),
+        Err(CIETSolverError::NonConvergence { max_iter: 1 }));
+}
+
+
+#[test]
+pub fn ciet_facility_skips_solve_when_inputs_are_unchanged(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, 
+        CIETIsothermalFacility, CIETSolverError};
+
+    let mut ciet_isothermal_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(), 
+        HeaterBranch::new(), 
+        DHXBranch::new());
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(2000.0)).unwrap();
+
+    // (1) the first calculation has to be solved
+    let (_calc_time,
+         first_ctah_branch_flowrate, _, _) = ciet_isothermal_facility.calculate();
+
+    assert!(!ciet_isothermal_facility.get_last_calculation_was_cache_hit());
+    assert!(ciet_isothermal_facility.get_converged_pressure_change().is_some());
+
+    // (2) nothing changed, so the second one is a cache hit
+    let (_calc_time,
+         second_ctah_branch_flowrate, _, _) = ciet_isothermal_facility.calculate();
+
+    assert!(ciet_isothermal_facility.get_last_calculation_was_cache_hit());
+    assert_eq!(ciet_isothermal_facility.get_solver_iteration_count(), 0);
+    assert_eq!(first_ctah_branch_flowrate, second_ctah_branch_flowrate);
+
+    // (3) changing the pump pressure means solving again
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(2500.0)).unwrap();
+    ciet_isothermal_facility.calculate();
+
+    assert!(!ciet_isothermal_facility.get_last_calculation_was_cache_hit());
+
+    // so does closing a valve
+    ciet_isothermal_facility.set_dhx_branch_valve_open(false);
+    ciet_isothermal_facility.calculate();
+
+    assert!(!ciet_isothermal_facility.get_last_calculation_was_cache_hit());
+    assert_eq!(ciet_isothermal_facility.get_cache_hit_count(), 1);
+    assert_eq!(ciet_isothermal_facility.get_cache_miss_count(), 3);
+
+    // (4) setpoints which are not numbers are rejected before they
+    // reach the cache (NaN never equals itself, so it would miss
+    // every time), the facility stays where it was and hits the cache
+    assert!(matches!(
+        ciet_isothermal_facility.set_ctah_pump_pressure(
+            Pressure::new::<pascal>(f64::NAN)),
+        Err(CIETSolverError::InvalidCTAHPumpPressure { .. })));
+    assert!(matches!(
+        ciet_isothermal_facility.set_ctah_pump_speed(f64::INFINITY),
+        Err(CIETSolverError::InvalidCTAHPumpSpeed { .. })));
+    assert!(matches!(
+        ciet_isothermal_facility.set_heater_branch_valve_opening(f64::NAN),
+        Err(CIETSolverError::InvalidValveOpening { .. })));
+
+    assert_eq!(ciet_isothermal_facility.get_ctah_pump_pressure(),
+        Pressure::new::<pascal>(2500.0));
+
+    for _ in 0..2 {
+        ciet_isothermal_facility.calculate();
+        assert!(ciet_isothermal_facility.get_last_calculation_was_cache_hit());
+    }
+
+    assert_eq!(ciet_isothermal_facility.get_cache_hit_count(), 3);
+    assert_eq!(ciet_isothermal_facility.get_cache_miss_count(), 3);
+}
+
+
+#[test]
+pub fn branch_lookup_tables_match_exact_branches(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, 
+        CIETIsothermalFacility, BranchLookupTable};
+
+    let lower_mass_flowrate = MassRate::new::<kilogram_per_second>(-0.5);
+    let upper_mass_flowrate = MassRate::new::<kilogram_per_second>(0.5);
+    let fluid_temperature = ThermodynamicTemperature::new::<degree_celsius>(21.0);
+
+    // (1) a table on its own should be close to the branch
+    // and give NaN outside its range
+    let heater_branch = HeaterBranch::new();
+    let heater_branch_lookup_table = BranchLookupTable::new(
+        &heater_branch, lower_mass_flowrate, upper_mass_flowrate,
+        401, fluid_temperature);
+
+    let test_mass_flowrate = MassRate::new::<kilogram_per_second>(0.18);
+    approx::assert_relative_eq!(
+        heater_branch_lookup_table.get_pressure_change(test_mass_flowrate).value,
+        heater_branch.get_pressure_change(test_mass_flowrate).value,
+        max_relative = 1e-3);
+
+    let test_pressure_change = 
+        heater_branch.get_pressure_change(test_mass_flowrate);
+    approx::assert_abs_diff_eq!(
+        heater_branch_lookup_table.get_mass_flowrate_from_pressure_change(
+            test_pressure_change).value,
+        test_mass_flowrate.value,
+        epsilon = 1e-4);
+
+    let (_lowest_pressure_change, highest_pressure_change) = 
+        heater_branch_lookup_table.get_pressure_change_range();
+    assert!(heater_branch_lookup_table.get_mass_flowrate_from_pressure_change(
+            highest_pressure_change + Pressure::new::<pascal>(1.0))
+        .value.is_nan());
+
+    // (2) the facility with tables should match the facility without,
+    // including after the pump pressure changes
+    let mut exact_ciet_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(), HeaterBranch::new(), DHXBranch::new());
+    let mut tabulated_ciet_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(), HeaterBranch::new(), DHXBranch::new());
+
+    tabulated_ciet_facility.build_branch_lookup_tables(
+        lower_mass_flowrate, upper_mass_flowrate, 1001,
+        MassRate::new::<kilogram_per_second>(1e-4)).unwrap();
+
+    assert!(tabulated_ciet_facility.get_using_branch_lookup_tables());
+
+    for pump_pressure_pascals in [1000.0, 4000.0] {
+
+        let pump_pressure = Pressure::new::<pascal>(pump_pressure_pascals);
+        exact_ciet_facility.set_ctah_pump_pressure(pump_pressure).unwrap();
+        tabulated_ciet_facility.set_ctah_pump_pressure(pump_pressure).unwrap();
+
+        let (_, exact_ctah_flowrate, exact_heater_flowrate, exact_dhx_flowrate)
+            = exact_ciet_facility.try_calculate().unwrap();
+        let (_, ctah_flowrate, heater_flowrate, dhx_flowrate)
+            = tabulated_ciet_facility.try_calculate().unwrap();
+
+        assert!(tabulated_ciet_facility
+            .get_last_calculation_used_branch_lookup_tables());
+
+        approx::assert_abs_diff_eq!(
+            ctah_flowrate.value, exact_ctah_flowrate.value, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(
+            heater_flowrate.value, exact_heater_flowrate.value, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(
+            dhx_flowrate.value, exact_dhx_flowrate.value, epsilon = 1e-4);
+    }
+
+    // (3) a tolerance which is far too tight is rejected
+    // and the facility goes back to the exact branches
+    let tolerance_result = tabulated_ciet_facility.build_branch_lookup_tables(
+        lower_mass_flowrate, upper_mass_flowrate, 3,
+        MassRate::new::<kilogram_per_second>(1e-12));
+
+    assert!(tolerance_result.is_err());
+    assert!(!tabulated_ciet_facility.get_using_branch_lookup_tables());
+}
+
+#[test]
+pub fn branch_lookup_tables_are_rebuilt_once_pump_speed_and_valves_settle(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, 
+        CIETIsothermalFacility, PumpCurve, BRANCH_LOOKUP_TABLE_SETTLE_COUNT};
+
+    let mut exact_ciet_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(), HeaterBranch::new(), DHXBranch::new());
+    let mut tabulated_ciet_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(), HeaterBranch::new(), DHXBranch::new());
+
+    exact_ciet_facility.set_ctah_pump_curve(
+        Some(PumpCurve::ciet_ctah_pump())).unwrap();
+    tabulated_ciet_facility.set_ctah_pump_curve(
+        Some(PumpCurve::ciet_ctah_pump())).unwrap();
+
+    // the tables are built at 0 rpm and fully open valves,
+    // same as the server
+    tabulated_ciet_facility.build_branch_lookup_tables(
+        MassRate::new::<kilogram_per_second>(-0.5), 
+        MassRate::new::<kilogram_per_second>(0.5), 
+        1001,
+        MassRate::new::<kilogram_per_second>(1e-4)).unwrap();
+
+    tabulated_ciet_facility.try_calculate().unwrap();
+    assert!(tabulated_ciet_facility.get_last_calculation_used_branch_lookup_tables());
+    assert!(!tabulated_ciet_facility.get_branch_lookup_tables_bypassed());
+
+    // (1) a new pump speed bypasses the tables at first, and says so,
+    // (2) once the speed has stayed put, the tables are rebuilt
+    // there and used again, and match the exact branches
+    //
+    // the same goes for a valve opening
+    for (pump_speed_rpm, heater_branch_valve_opening) in 
+        [(3000.0, 100.0), (3000.0, 60.0)] {
+
+        for ciet_facility in [&mut exact_ciet_facility, 
+                              &mut tabulated_ciet_facility] {
+            ciet_facility.set_ctah_pump_speed(pump_speed_rpm).unwrap();
+            ciet_facility.set_heater_branch_valve_opening(
+                heater_branch_valve_opening).unwrap();
+        }
+
+        tabulated_ciet_facility.try_calculate().unwrap();
+
+        assert!(!tabulated_ciet_facility.get_last_calculation_used_branch_lookup_tables());
+        assert!(tabulated_ciet_facility.get_branch_lookup_tables_bypassed());
+
+        for _ in 1..BRANCH_LOOKUP_TABLE_SETTLE_COUNT {
+            tabulated_ciet_facility.try_calculate().unwrap();
+        }
+
+        let (_, ctah_flowrate, heater_flowrate, dhx_flowrate)
+            = tabulated_ciet_facility.try_calculate().unwrap();
+
+        assert!(tabulated_ciet_facility.get_last_calculation_used_branch_lookup_tables());
+        assert!(!tabulated_ciet_facility.get_branch_lookup_tables_bypassed());
+
+        let (_, exact_ctah_flowrate, exact_heater_flowrate, exact_dhx_flowrate)
+            = exact_ciet_facility.try_calculate().unwrap();
+
+        assert!(exact_ctah_flowrate.value > 0.0);
+        approx::assert_abs_diff_eq!(
+            ctah_flowrate.value, exact_ctah_flowrate.value, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(
+            heater_flowrate.value, exact_heater_flowrate.value, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(
+            dhx_flowrate.value, exact_dhx_flowrate.value, epsilon = 1e-4);
+    }
+}
+
+
+#[test]
+pub fn component_catalogue_matches_component_libraries(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{ComponentCatalogue, ComponentCatalogueError, 
+        CTAHBranch, HeaterBranch, DHXBranch, FacilityTopology,
+        FacilityTopologyError};
+
+    // (1) the ctah, heater and dhx branches built from the catalogue
+    // should have the same pressure change as the hard coded ones
+    let ciet_component_catalogue = ComponentCatalogue::ciet_default();
+
+    let catalogue_ctah_branch = ciet_component_catalogue.build_series_branch(
+        "ctah_branch",
+        &["pipe_6a", "static_mixer_41_label_6", "ctah_vertical_label_7a",
+        "ctah_horizontal_label_7b", "static_mixer_pipe_8a", 
+        "static_mixer_40_label_8", "pipe_9", "pipe_10", "pipe_11", "pipe_12",
+        "ctah_pump", "pipe_13", "pipe_14", "flowmeter_40_14a", "pipe_15",
+        "pipe_16", "branch_17"]).unwrap();
+
+    let catalogue_heater_branch = ciet_component_catalogue.build_series_branch(
+        "heater_branch",
+        &["branch_5", "pipe_4", "pipe_3", "static_mixer_10_label_2",
+        "pipe_2a_static_mixer", "heater_top_head_label_1a", 
+        "heater_version_1_label_1", "heater_bottom_head_label_1b",
+        "pipe_18"]).unwrap();
+
+    let catalogue_dhx_branch = ciet_component_catalogue.build_series_branch(
+        "dhx_branch",
+        &["pipe_26", "static_mixer_21_label_25", "static_mixer_pipe_25a",
+        "dhx_shell_side_label_24", "static_mixer_20_label_23", 
+        "static_mixer_pipe_23a", "static_mixer_pipe_22", 
+        "flowmeter_20_label_21a", "static_mixer_pipe_21", 
+        "static_mixer_pipe_20", "static_mixer_pipe_19"]).unwrap();
+
+    let ctah_branch = CTAHBranch::new();
+    let heater_branch = HeaterBranch::new();
+    let dhx_branch = DHXBranch::new();
+
+    for mass_flowrate_kg_per_s in [-0.15, 0.01, 0.18] {
+
+        let mass_flowrate = 
+            MassRate::new::<kilogram_per_second>(mass_flowrate_kg_per_s);
+
+        approx::assert_relative_eq!(
+            catalogue_ctah_branch.get_pressure_change(mass_flowrate).value,
+            ctah_branch.get_pressure_change(mass_flowrate).value,
+            max_relative = 1e-9);
+        approx::assert_relative_eq!(
+            catalogue_heater_branch.get_pressure_change(mass_flowrate).value,
+            heater_branch.get_pressure_change(mass_flowrate).value,
+            max_relative = 1e-9);
+        approx::assert_relative_eq!(
+            catalogue_dhx_branch.get_pressure_change(mass_flowrate).value,
+            dhx_branch.get_pressure_change(mass_flowrate).value,
+            max_relative = 1e-9);
+    }
+
+    assert!(ciet_component_catalogue.get_component_names()
+        .contains(&"flowmeter_60_label_37a"));
+
+    // (2) the catalogue can go to json and back (eg. for python),
+    // and toml and back
+    let catalogue_json = ciet_component_catalogue.to_json_string().unwrap();
+    assert_eq!(ComponentCatalogue::from_json_str(&catalogue_json).unwrap(),
+        ciet_component_catalogue);
+
+    let catalogue_toml = ciet_component_catalogue.to_toml_string().unwrap();
+    assert_eq!(ComponentCatalogue::from_toml_str(&catalogue_toml).unwrap(),
+        ciet_component_catalogue);
+
+    // (3) new components can be added without recompiling
+    let test_section_catalogue = ComponentCatalogue::from_toml_str(r#"
+        fluid_temperature_degrees_celsius = 21.0
+
+        [[pipe]]
+        name = "test_section_pipe"
+        hydraulic_diameter_meters = 0.0279
+        component_length_meters = 1.0
+        absolute_roughness_millimeters = 0.015
+        incline_angle_degrees = 0.0
+        form_loss_k = 1.5
+
+        [[custom_component]]
+        name = "test_section_mixer"
+        hydraulic_diameter_meters = 0.0279
+        component_length_meters = 0.33
+        cross_sectional_area_square_meters = 6.11e-4
+        absolute_roughness_millimeters = 0.015
+        incline_angle_degrees = 0.0
+        custom_k = "static_mixer_41"
+        custom_darcy = "static_mixer_41"
+
+        [[custom_component]]
+        name = "mystery_component"
+        hydraulic_diameter_meters = 0.0279
+        component_length_meters = 0.33
+        cross_sectional_area_square_meters = 6.11e-4
+        absolute_roughness_millimeters = 0.015
+        incline_angle_degrees = 0.0
+        custom_k = { table = { reynolds_numbers = [100.0, 1000.0], k_values = [61.0] } }
+        custom_darcy = "static_mixer_41"
+        "#).unwrap();
+
+    let test_section = test_section_catalogue.build_series_branch(
+        "test_section", &["test_section_pipe", "test_section_mixer"]).unwrap();
+
+    assert_eq!(test_section.get_fluid_component_vector().len(), 2);
+
+    assert!(matches!(
+        test_section_catalogue.get_fluid_component("mystery_component"),
+        Err(ComponentCatalogueError::InvalidCorrelation(_))));
+
+    // unknown correlation names are caught when the catalogue is parsed
+    let unknown_correlation_catalogue = ComponentCatalogue::from_toml_str(r#"
+        fluid_temperature_degrees_celsius = 21.0
+
+        [[custom_component]]
+        name = "mystery_component"
+        hydraulic_diameter_meters = 0.0279
+        component_length_meters = 0.33
+        cross_sectional_area_square_meters = 6.11e-4
+        absolute_roughness_millimeters = 0.015
+        incline_angle_degrees = 0.0
+        custom_k = "no_such_correlation"
+        custom_darcy = "static_mixer_41"
+        "#);
+
+    assert!(matches!(unknown_correlation_catalogue,
+        Err(ComponentCatalogueError::Parse(_))));
+    assert!(matches!(
+        test_section_catalogue.get_fluid_component("pipe_6a"),
+        Err(ComponentCatalogueError::ComponentNotFound(_))));
+
+    // (4) the fluid temperature must be within the therminol
+    // property range, and the geometry must be physical, 
+    // otherwise the components are not built
+    let mut hot_catalogue = ciet_component_catalogue.clone();
+    hot_catalogue.fluid_temperature_degrees_celsius = 500.0;
+
+    assert_eq!(hot_catalogue.get_fluid_component("pipe_6a").err(),
+        Some(ComponentCatalogueError::FluidTemperatureOutOfRange(500.0)));
+    assert!(matches!(
+        hot_catalogue.get_fluid_component("static_mixer_41_label_6"),
+        Err(ComponentCatalogueError::FluidTemperatureOutOfRange(_))));
+
+    let mut nan_temperature_catalogue = ciet_component_catalogue.clone();
+    nan_temperature_catalogue.fluid_temperature_degrees_celsius = f64::NAN;
+
+    assert!(matches!(
+        nan_temperature_catalogue.get_fluid_component("ctah_branch_valve"),
+        Err(ComponentCatalogueError::FluidTemperatureOutOfRange(_))));
+
+    let mut bad_geometry_catalogue = test_section_catalogue.clone();
+    bad_geometry_catalogue.pipes[0].hydraulic_diameter_meters = 0.0;
+
+    assert!(matches!(
+        bad_geometry_catalogue.get_fluid_component("test_section_pipe"),
+        Err(ComponentCatalogueError::InvalidGeometry(_))));
+
+    let mut bad_geometry_catalogue = test_section_catalogue.clone();
+    bad_geometry_catalogue.pipes[0].absolute_roughness_millimeters = -0.015;
+
+    assert!(matches!(
+        bad_geometry_catalogue.get_fluid_component("test_section_pipe"),
+        Err(ComponentCatalogueError::InvalidGeometry(_))));
+
+    let mut bad_geometry_catalogue = test_section_catalogue.clone();
+    bad_geometry_catalogue.custom_components[0]
+        .cross_sectional_area_square_meters = -6.11e-4;
+
+    assert!(matches!(
+        bad_geometry_catalogue.get_fluid_component("test_section_mixer"),
+        Err(ComponentCatalogueError::InvalidGeometry(_))));
+
+    let mut bad_geometry_catalogue = test_section_catalogue.clone();
+    bad_geometry_catalogue.custom_components[0]
+        .component_length_meters = f64::INFINITY;
+
+    assert!(matches!(
+        bad_geometry_catalogue.get_fluid_component("test_section_mixer"),
+        Err(ComponentCatalogueError::InvalidGeometry(_))));
+
+    let mut bad_geometry_catalogue = ciet_component_catalogue.clone();
+    bad_geometry_catalogue.valves[0].component_length_meters = 0.0;
+    let bad_valve_name = bad_geometry_catalogue.valves[0].name.clone();
+
+    assert!(matches!(
+        bad_geometry_catalogue.get_fluid_component(&bad_valve_name),
+        Err(ComponentCatalogueError::InvalidGeometry(_))));
+
+    let mut bad_geometry_catalogue = ciet_component_catalogue.clone();
+    bad_geometry_catalogue.check_valves[0].hydraulic_diameter_meters = f64::NAN;
+    let bad_check_valve_name = bad_geometry_catalogue.check_valves[0].name.clone();
+
+    assert!(matches!(
+        bad_geometry_catalogue.get_fluid_component(&bad_check_valve_name),
+        Err(ComponentCatalogueError::InvalidGeometry(_))));
+
+    // ciet cannot be built from a bad catalogue either,
+    // the error comes back rather than a panic in the solver
+    assert!(matches!(
+        FacilityTopology::ciet_default().build_isothermal_ciet_facility(
+            &hot_catalogue),
+        Err(FacilityTopologyError::Catalogue(
+                ComponentCatalogueError::FluidTemperatureOutOfRange(_)))));
+}
+
+
+#[test]
+pub fn facility_topology_builds_ciet_and_rejects_bad_topologies(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, CIETIsothermalFacility,
+        ComponentCatalogue, FacilityTopology, FacilityTopologyError};
+
+    let ciet_component_catalogue = ComponentCatalogue::ciet_default();
+    let ciet_facility_topology = FacilityTopology::ciet_default();
+
+    // (1) ciet built from the topology file should give the same
+    // flowrates as ciet built from the hard coded branches,
+    // this also checks the pump is found in the ctah branch
+    let mut topology_ciet = ciet_facility_topology
+        .build_isothermal_ciet_facility(&ciet_component_catalogue)
+        .unwrap();
+
+    let mut hard_coded_ciet = CIETIsothermalFacility::new(
+        CTAHBranch::new(),
+        HeaterBranch::new(),
+        DHXBranch::new());
+
+    let pump_pressure = Pressure::new::<pascal>(2000.0);
+    topology_ciet.set_ctah_pump_pressure(pump_pressure).unwrap();
+    hard_coded_ciet.set_ctah_pump_pressure(pump_pressure).unwrap();
+
+    let (_, topology_ctah_flowrate, topology_heater_flowrate,
+         topology_dhx_flowrate) = topology_ciet.calculate();
+    let (_, ctah_flowrate, heater_flowrate, dhx_flowrate) =
+        hard_coded_ciet.calculate();
+
+    assert!(topology_ciet.get_solver_error().is_none());
+
+    approx::assert_relative_eq!(
+        topology_ctah_flowrate.value, ctah_flowrate.value,
+        max_relative = 1e-6);
+    approx::assert_relative_eq!(
+        topology_heater_flowrate.value, heater_flowrate.value,
+        max_relative = 1e-6);
+    approx::assert_abs_diff_eq!(
+        topology_dhx_flowrate.value, dhx_flowrate.value,
+        epsilon = 1e-9);
+
+    // (2) the topology can go to json and back, and toml and back
+    let topology_json = ciet_facility_topology.to_json_string().unwrap();
+    assert_eq!(FacilityTopology::from_json_str(&topology_json).unwrap(),
+        ciet_facility_topology);
+
+    let topology_toml = ciet_facility_topology.to_toml_string().unwrap();
+    assert_eq!(FacilityTopology::from_toml_str(&topology_toml).unwrap(),
+        ciet_facility_topology);
+
+    // (3) a component which is not in the catalogue
+    let mut bad_topology = ciet_facility_topology.clone();
+    bad_topology.branches[0].components.push("pipe_999".to_string());
+
+    assert_eq!(bad_topology.validate(&ciet_component_catalogue),
+        Err(FacilityTopologyError::MissingComponent {
+            branch: "heater_branch".to_string(),
+            component: "pipe_999".to_string() }));
+
+    // (4) two branches with the same name
+    let mut bad_topology = ciet_facility_topology.clone();
+    bad_topology.branches[1].name = "heater_branch".to_string();
+
+    assert_eq!(bad_topology.validate(&ciet_component_catalogue),
+        Err(FacilityTopologyError::DuplicateBranchName(
+                "heater_branch".to_string())));
+
+    // (5) the same component in two places
+    let mut bad_topology = ciet_facility_topology.clone();
+    bad_topology.branches[1].components.push("pipe_18".to_string());
+
+    assert_eq!(bad_topology.validate(&ciet_component_catalogue),
+        Err(FacilityTopologyError::ComponentUsedTwice(
+                "pipe_18".to_string())));
+
+    // (6) leaving out pipe 6a means the ctah branch no longer
+    // rises as high as the heater branch
+    let mut bad_topology = ciet_facility_topology.clone();
+    bad_topology.branches[2].components.retain(
+        |component_name| component_name != "pipe_6a");
+
+    match bad_topology.build_isothermal_ciet_facility(&ciet_component_catalogue) {
+        Err(FacilityTopologyError::InconsistentElevation { branch, .. }) =>
+            assert_eq!(branch, "ctah_branch"),
+        _ => panic!("expected an inconsistent elevation error"),
+    }
+
+    // (7) the ctah pump must be in the ctah branch
+    let mut bad_topology = ciet_facility_topology.clone();
+    bad_topology.branches[2].pump = Some("pipe_13".to_string());
+    bad_topology.branches[2].components.retain(
+        |component_name| component_name != "pipe_13");
+
+    assert_eq!(bad_topology.validate(&ciet_component_catalogue),
+        Err(FacilityTopologyError::InvalidPump {
+            branch: "ctah_branch".to_string(),
+            pump: Some("pipe_13".to_string()) }));
+
+    // and a ctah branch built by hand cannot have its
+    // pump past the end of the branch
+    let ctah_components = vec![
+        ciet_component_catalogue.get_fluid_component("pipe_13").unwrap()];
+
+    assert!(matches!(CTAHBranch::new_from_components(ctah_components, 1),
+        Err(FacilityTopologyError::PumpIndexOutOfRange {
+            pump_index: 1, component_count: 1 })));
+}
+
+#[test]
+pub fn custom_correlations_match_component_libraries(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::therminol_component::TherminolCustomComponent;
+    use crate::{CustomKCorrelation, CustomDarcyCorrelation,
+        StaticMixer41, CTAHVertical,
+        Flowmeter20WithHighKCheckValve, DHXShellSideHeatExchanger,
+        ComponentCatalogue, ComponentCatalogueError, TherminolSeriesBranch,
+        ParallelBranchSolver, CIETSolverError};
+
+    let reynolds_numbers = [-5000.0, -150.0, 80.0, 2300.0, 40000.0];
+    let roughness_ratio = 0.015e-3/2.79e-2;
+
+    // (1) the ciet fits give the same K and darcy as the
+    // functions in component_libraries, including reverse flow
+    for reynolds_number in reynolds_numbers {
+
+        assert_eq!(
+            CustomKCorrelation::StaticMixer41.custom_k(reynolds_number),
+            StaticMixer41::custom_k(reynolds_number));
+        assert_eq!(
+            CustomKCorrelation::Flowmeter20WithHighKCheckValve
+            .custom_k(reynolds_number),
+            Flowmeter20WithHighKCheckValve::custom_k(reynolds_number));
+        assert_eq!(
+            CustomDarcyCorrelation::CTAHVertical
+            .custom_darcy(reynolds_number, roughness_ratio),
+            CTAHVertical::custom_darcy(reynolds_number, roughness_ratio));
+
+        // (2) the general forms can express the same fits
+        approx::assert_relative_eq!(
+            CustomKCorrelation::PowerLaw { a: 21.0, b: 4000.0, c: -1.0 }
+            .custom_k(reynolds_number),
+            StaticMixer41::custom_k(reynolds_number),
+            max_relative = 1e-12);
+        approx::assert_relative_eq!(
+            CustomKCorrelation::Constant { k: 3.9 }
+            .custom_k(reynolds_number),
+            CTAHVertical::custom_k(reynolds_number),
+            max_relative = 1e-12);
+        approx::assert_relative_eq!(
+            CustomDarcyCorrelation::Churchill { multiplier: 1.0 }
+            .custom_darcy(reynolds_number, roughness_ratio),
+            DHXShellSideHeatExchanger::custom_darcy(
+                reynolds_number, roughness_ratio),
+            max_relative = 1e-12);
+    }
+
+    // (3) tables interpolate linearly in Re, hold their end values,
+    // and take the sign of Re
+    let k_table = CustomKCorrelation::Table {
+        reynolds_numbers: vec![100.0, 1000.0],
+        k_values: vec![60.0, 24.0] };
+
+    approx::assert_relative_eq!(k_table.custom_k(550.0), 42.0,
+        max_relative = 1e-12);
+    approx::assert_relative_eq!(k_table.custom_k(-550.0), -42.0,
+        max_relative = 1e-12);
+    approx::assert_relative_eq!(k_table.custom_k(1.0e5), 24.0,
+        max_relative = 1e-12);
+
+    let bad_k_table = CustomKCorrelation::Table {
+        reynolds_numbers: vec![1000.0, 100.0],
+        k_values: vec![60.0, 24.0] };
+
+    assert!(bad_k_table.validate().is_err());
+    assert!(bad_k_table.custom_k(550.0).is_nan());
+
+    // churchill needs a roughness of zero or more, a negative one
+    // is rejected when checked and gives NaN rather than a panic
+    let churchill = CustomDarcyCorrelation::Churchill { multiplier: 1.0 };
+
+    assert!(churchill.validate_roughness_ratio(roughness_ratio).is_ok());
+    assert!(churchill.validate_roughness_ratio(-roughness_ratio).is_err());
+    assert!(CustomDarcyCorrelation::StaticMixer41
+            .validate_roughness_ratio(-roughness_ratio).is_ok());
+    assert!(churchill.custom_darcy(2300.0, -roughness_ratio).is_nan());
+
+    let mut rough_catalogue = ComponentCatalogue::ciet_default();
+    let rough_component = rough_catalogue.custom_components.iter_mut()
+        .find(|custom_component| 
+              custom_component.name == "dhx_shell_side_label_24").unwrap();
+    rough_component.custom_darcy = churchill.clone();
+    rough_component.absolute_roughness_millimeters = -0.015;
+
+    assert!(matches!(
+        rough_catalogue.get_fluid_component("dhx_shell_side_label_24"),
+        Err(ComponentCatalogueError::InvalidCorrelation(_))));
+
+    // if such a component is built anyway, the solver
+    // reports NaN instead of the solver thread panicking
+    let negative_roughness_component = || {
+        Box::new(TherminolCustomComponent::new_from_correlations(
+            "negative_roughness_component",
+            ThermodynamicTemperature::new::<degree_celsius>(21.0),
+            Angle::new::<degree>(0.0),
+            Length::new::<meter>(1.0),
+            Area::new::<square_meter>(6.11e-4),
+            Length::new::<meter>(2.79e-2),
+            Length::new::<millimeter>(-0.015),
+            CustomKCorrelation::Constant { k: 1.0 },
+            churchill.clone()))
+    };
+
+    let mut first_branch = TherminolSeriesBranch::new("first_branch");
+    first_branch.add_component(negative_roughness_component());
+    let mut second_branch = TherminolSeriesBranch::new("second_branch");
+    second_branch.add_component(negative_roughness_component());
+
+    assert_eq!(
+        ParallelBranchSolver::new().solve(&[&first_branch, &second_branch]).err(),
+        Some(CIETSolverError::NotANumber));
+
+    // (4) correlations can be written to and read from config files,
+    // ciet fits are just their names
+    assert_eq!(serde_json::to_string(&CustomKCorrelation::StaticMixer41)
+        .unwrap(), "\"static_mixer_41\"");
+
+    for correlation in [CustomKCorrelation::StaticMixer41,
+        CustomKCorrelation::PowerLaw { a: 18.0, b: 93000.0, c: -1.35 },
+        k_table.clone()] {
+
+        let correlation_json = serde_json::to_string(&correlation).unwrap();
+        let correlation_from_json: CustomKCorrelation =
+            serde_json::from_str(&correlation_json).unwrap();
+
+        assert_eq!(correlation_from_json, correlation);
+    }
+
+    assert_eq!(CustomKCorrelation::StaticMixer41.to_string(),
+        "static_mixer_41: K = 21 + 4000/Re");
+    assert_eq!(k_table.get_name(), "table");
+
+    // (5) a component built from correlations behaves the same as
+    // one built from functions, and keeps its correlations
+    let static_mixer_41 = StaticMixer41::new().get();
+    let static_mixer_41_from_correlations =
+        TherminolCustomComponent::new_from_correlations(
+            "static_mixer_41_label_6",
+            ThermodynamicTemperature::new::<degree_celsius>(21.0),
+            Angle::new::<degree>(51.526384),
+            Length::new::<meter>(0.33),
+            Area::new::<square_meter>(6.11e-4),
+            Length::new::<meter>(2.79e-2),
+            Length::new::<millimeter>(0.015),
+            CustomKCorrelation::StaticMixer41,
+            CustomDarcyCorrelation::StaticMixer41);
+
+    for mass_flowrate_kg_per_s in [-0.2, 0.05, 0.2] {
+
+        let mass_flowrate =
+            MassRate::new::<kilogram_per_second>(mass_flowrate_kg_per_s);
+
+        approx::assert_relative_eq!(
+            static_mixer_41_from_correlations
+            .get_pressure_loss_immutable(mass_flowrate).value,
+            static_mixer_41.get_pressure_loss_immutable(mass_flowrate).value,
+            max_relative = 1e-12);
+    }
+
+    assert_eq!(static_mixer_41_from_correlations.get_custom_k_correlation(),
+        Some(&CustomKCorrelation::StaticMixer41));
+    assert_eq!(static_mixer_41.get_custom_k_correlation(), None);
+}
+
+#[test]
+pub fn ciet_facility_can_be_set_to_a_new_fluid_temperature(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, CIETIsothermalFacility,
+        CIETSolverError, ComponentCatalogue, FacilityTopology};
+
+    let pump_pressure = Pressure::new::<pascal>(2000.0);
+    let fluid_temperature = ThermodynamicTemperature::new::<degree_celsius>(80.0);
+
+    let mut ciet_isothermal_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(),
+        HeaterBranch::new(),
+        DHXBranch::new());
+
+    approx::assert_relative_eq!(
+        ciet_isothermal_facility.get_fluid_temperature()
+        .get::<degree_celsius>(), 21.0, max_relative = 1e-12);
+
+    ciet_isothermal_facility.set_ctah_pump_pressure(pump_pressure).unwrap();
+    let (_, ctah_flowrate_at_21_degc, _, _) =
+        ciet_isothermal_facility.calculate();
+
+    // (1) therminol is less viscous when hot, so the same pump
+    // pressure should push more flow round ciet, and the cached
+    // 21 degC solution must not be reused
+    ciet_isothermal_facility.set_fluid_temperature(fluid_temperature).unwrap();
+
+    let (_, ctah_flowrate_at_80_degc, heater_flowrate_at_80_degc, _) =
+        ciet_isothermal_facility.calculate();
+
+    assert!(!ciet_isothermal_facility.get_last_calculation_was_cache_hit());
+    assert!(ctah_flowrate_at_80_degc.value > ctah_flowrate_at_21_degc.value);
+
+    // (2) this should be the same as building ciet at 80 degC
+    // in the first place, ie. every component got the new temperature
+    let mut hot_catalogue = ComponentCatalogue::ciet_default();
+    hot_catalogue.fluid_temperature_degrees_celsius = 80.0;
+
+    let mut hot_ciet = FacilityTopology::ciet_default()
+        .build_isothermal_ciet_facility(&hot_catalogue).unwrap();
+
+    approx::assert_relative_eq!(
+        hot_ciet.get_fluid_temperature().get::<degree_celsius>(), 80.0,
+        max_relative = 1e-12);
+
+    hot_ciet.set_ctah_pump_pressure(pump_pressure).unwrap();
+    let (_, hot_ctah_flowrate, hot_heater_flowrate, _) = hot_ciet.calculate();
+
+    approx::assert_relative_eq!(
+        ctah_flowrate_at_80_degc.value, hot_ctah_flowrate.value,
+        max_relative = 1e-6);
+    approx::assert_relative_eq!(
+        heater_flowrate_at_80_degc.value, hot_heater_flowrate.value,
+        max_relative = 1e-6);
+
+    // (3) lookup tables are rebuilt at the new temperature
+    ciet_isothermal_facility.build_branch_lookup_tables(
+        MassRate::new::<kilogram_per_second>(-0.5),
+        MassRate::new::<kilogram_per_second>(0.5),
+        1001,
+        MassRate::new::<kilogram_per_second>(1e-4)).unwrap();
+
+    ciet_isothermal_facility.set_fluid_temperature(
+        ThermodynamicTemperature::new::<degree_celsius>(21.0)).unwrap();
+
+    let (_, ctah_flowrate_from_tables, _, _) =
+        ciet_isothermal_facility.calculate();
+
+    assert!(ciet_isothermal_facility
+        .get_last_calculation_used_branch_lookup_tables());
+    approx::assert_abs_diff_eq!(
+        ctah_flowrate_from_tables.value, ctah_flowrate_at_21_degc.value,
+        epsilon = 1e-4);
+
+    // (4) temperatures outside the therminol correlations are refused
+    let too_hot = ThermodynamicTemperature::new::<degree_celsius>(250.0);
+
+    assert_eq!(ciet_isothermal_facility.set_fluid_temperature(too_hot),
+        Err(CIETSolverError::FluidTemperatureOutOfRange {
+            fluid_temperature: too_hot }));
+    approx::assert_relative_eq!(
+        ciet_isothermal_facility.get_fluid_temperature()
+        .get::<degree_celsius>(), 21.0, max_relative = 1e-12);
+}
+
+#[test]
+pub fn non_isothermal_ciet_reaches_an_energy_balance_in_natural_and_forced_circulation(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, CIETNonIsothermalFacility,
+        FacilityTopology};
+
+    let heater_power = Power::new::<watt>(1500.0);
+    let timestep = Time::new::<second>(20.0);
+    let therminol_properties = TherminolVP1Properties::new();
+
+    // the heater, ctah and dhx shell side are named in the topology
+    let heat_transfer_component_names = FacilityTopology::ciet_default()
+        .get_heat_transfer_component_names().unwrap();
+    let heater_component_name = heat_transfer_component_names
+        .heater_branch[0].clone();
+
+    let mut ciet = CIETNonIsothermalFacility::new(
+        CTAHBranch::new(),
+        HeaterBranch::new(),
+        DHXBranch::new(),
+        heat_transfer_component_names,
+        4).unwrap();
+
+    // without DRACS the dhx has no heat removal, so only the heater
+    // and ctah branches are open
+    ciet.set_dhx_branch_valve_open(false);
+    ciet.set_ctah_cooling(
+        ThermalConductance::new::<watt_per_kelvin>(100.0),
+        ThermodynamicTemperature::new::<degree_celsius>(20.0));
+
+    // (1) with the heater off and no pump, ciet is isothermal,
+    // so there is no buoyancy and no flow
+    let (ctah_flowrate, heater_flowrate, _) =
+        ciet.advance_timestep(timestep).unwrap();
+
+    approx::assert_abs_diff_eq!(ctah_flowrate.value, 0.0, epsilon = 1e-6);
+    approx::assert_abs_diff_eq!(heater_flowrate.value, 0.0, epsilon = 1e-6);
+
+    // (2) natural circulation, the heated fluid rises through the
+    // heater (negative flow) and sinks through the ctah (positive flow)
+    // until the ctah removes as much heat as the heater puts in
+    ciet.set_heater_power(heater_power);
+
+    for _ in 0..150 {
+        ciet.advance_timestep(timestep).unwrap();
+    }
+
+    let ctah_flowrate = ciet.get_ctah_branch_mass_flowrate();
+    let heater_flowrate = ciet.get_heater_branch_mass_flowrate();
+
+    assert!(ctah_flowrate.value > 0.0);
+    approx::assert_relative_eq!(
+        heater_flowrate.value, -ctah_flowrate.value, max_relative = 1e-6);
+    approx::assert_relative_eq!(
+        ciet.get_ctah_heat_removal_rate().get::<watt>(),
+        heater_power.get::<watt>(), max_relative = 1e-3);
+
+    // the heater nodes get hotter in the direction of flow,
+    // ie. from the last node to the first
+    let heater_node_temperatures = ciet
+        .get_component_thermal_nodes(&heater_component_name).unwrap()
+        .node_temperatures.clone();
+
+    assert!(heater_node_temperatures.first().unwrap().value >
+            heater_node_temperatures.last().unwrap().value);
+
+    // and the temperature rise round the loop is Q/(mdot cp)
+    let assert_temperature_rise_matches_heater_power = 
+        |ciet: &CIETNonIsothermalFacility| {
+
+        let top_temperature = ciet.get_top_node_temperature();
+        let bottom_temperature = ciet.get_bottom_node_temperature();
+        let average_temperature = ThermodynamicTemperature::new::<kelvin>(
+            0.5*(top_temperature.get::<kelvin>() + bottom_temperature.get::<kelvin>()));
+        let specific_heat_capacity = therminol_properties
+            .specific_heat_capacity(average_temperature);
+
+        let expected_temperature_rise = heater_power.get::<watt>()/
+            ciet.get_ctah_branch_mass_flowrate().get::<kilogram_per_second>()/
+            specific_heat_capacity.get::<joule_per_kilogram_kelvin>();
+
+        approx::assert_relative_eq!(
+            top_temperature.get::<kelvin>() - bottom_temperature.get::<kelvin>(),
+            expected_temperature_rise, max_relative = 0.01);
+    };
+
+    assert_temperature_rise_matches_heater_power(&ciet);
+
+    let natural_circulation_flowrate = ctah_flowrate;
+
+    // (3) heated forced circulation, the pump pushes much more flow
+    // so the temperature rise across the heater is smaller
+    ciet.set_ctah_pump_pressure(Pressure::new::<pascal>(2000.0)).unwrap();
+
+    for _ in 0..150 {
+        ciet.advance_timestep(timestep).unwrap();
+    }
+
+    assert!(ciet.get_ctah_branch_mass_flowrate().value > 
+            natural_circulation_flowrate.value);
+    approx::assert_relative_eq!(
+        ciet.get_ctah_heat_removal_rate().get::<watt>(),
+        heater_power.get::<watt>(), max_relative = 1e-3);
+
+    assert_temperature_rise_matches_heater_power(&ciet);
+
+    approx::assert_relative_eq!(
+        ciet.get_simulation_time().get::<second>(), 301.0*20.0,
+        max_relative = 1e-12);
+}
+
+#[test]
+pub fn ciet_flowrates_coast_up_and_down_with_fluid_inertia(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, CIETIsothermalFacility,
+        InertialBranch, TherminolSeriesComponents};
+
+    let pump_pressure = Pressure::new::<pascal>(2000.0);
+    let timestep = Time::new::<second>(0.1);
+
+    let mut ciet_isothermal_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(),
+        HeaterBranch::new(),
+        DHXBranch::new());
+
+    // every branch has some fluid inertia
+    let (heater_branch_inertance, dhx_branch_inertance, ctah_branch_inertance) =
+        ciet_isothermal_facility.get_branch_inertances();
+
+    assert!(heater_branch_inertance.value > 0.0);
+    assert!(dhx_branch_inertance.value > 0.0);
+    assert!(ctah_branch_inertance.value > 0.0);
+
+    ciet_isothermal_facility.set_ctah_pump_pressure(pump_pressure).unwrap();
+    let (_, steady_ctah_flowrate, steady_heater_flowrate, _) =
+        ciet_isothermal_facility.calculate();
+
+    // (1) coast up, starting from rest, the flow rises steadily
+    // towards the steady state rather than jumping there
+    let mut ciet_isothermal_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(),
+        HeaterBranch::new(),
+        DHXBranch::new());
+    ciet_isothermal_facility.set_ctah_pump_pressure(pump_pressure).unwrap();
+
+    let (_, first_ctah_flowrate, _, _) =
+        ciet_isothermal_facility.advance_timestep(timestep);
+
+    assert!(first_ctah_flowrate.value > 0.0);
+    assert!(first_ctah_flowrate.value < 0.5*steady_ctah_flowrate.value);
+
+    // a timestep is never skipped, so it counts as a cache miss
+    assert!(!ciet_isothermal_facility.get_last_calculation_was_cache_hit());
+    assert_eq!(ciet_isothermal_facility.get_cache_hit_count(), 0);
+    assert_eq!(ciet_isothermal_facility.get_cache_miss_count(), 1);
+
+    let mut previous_ctah_flowrate = first_ctah_flowrate;
+
+    // (once the steady state is reached, the flowrates only
+    // change by round off, hence the small allowance)
+    for _ in 0..150 {
+        let (_, ctah_flowrate, heater_flowrate, dhx_flowrate) =
+            ciet_isothermal_facility.advance_timestep(timestep);
+
+        assert!(ciet_isothermal_facility.get_solver_error().is_none());
+        assert!(ctah_flowrate.value >= previous_ctah_flowrate.value - 1e-9);
+
+        // mass is conserved at every timestep
+        approx::assert_abs_diff_eq!(
+            ctah_flowrate.value + heater_flowrate.value + dhx_flowrate.value,
+            0.0, epsilon = 1e-6);
+
+        previous_ctah_flowrate = ctah_flowrate;
+    }
+
+    approx::assert_relative_eq!(
+        previous_ctah_flowrate.value, steady_ctah_flowrate.value,
+        max_relative = 1e-6);
+    approx::assert_relative_eq!(
+        ciet_isothermal_facility.get_heater_branch_mass_flowrate().value,
+        steady_heater_flowrate.value, max_relative = 1e-6);
+
+    // (2) coast down, with the pump off the flow decays
+    // without reversing
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(0.0)).unwrap();
+
+    for _ in 0..50 {
+        let (_, ctah_flowrate, _, _) =
+            ciet_isothermal_facility.advance_timestep(timestep);
+
+        assert!(ctah_flowrate.value <= previous_ctah_flowrate.value + 1e-9);
+        assert!(ctah_flowrate.value >= 0.0);
+
+        previous_ctah_flowrate = ctah_flowrate;
+    }
+
+    assert!(previous_ctah_flowrate.value < 0.5*steady_ctah_flowrate.value);
+
+    // (3) a very long timestep goes straight to the steady state
+    ciet_isothermal_facility.set_ctah_pump_pressure(pump_pressure).unwrap();
+    let (_, ctah_flowrate, _, _) = ciet_isothermal_facility
+        .advance_timestep(Time::new::<second>(1e9));
+
+    approx::assert_relative_eq!(
+        ctah_flowrate.value, steady_ctah_flowrate.value,
+        max_relative = 1e-6);
+
+    // and calculate solves the steady state again
+    // rather than reusing a cached one
+    let (_, ctah_flowrate, _, _) = ciet_isothermal_facility.calculate();
+
+    assert!(!ciet_isothermal_facility.get_last_calculation_was_cache_hit());
+    approx::assert_relative_eq!(
+        ctah_flowrate.value, steady_ctah_flowrate.value,
+        max_relative = 1e-6);
+
+    // 1 + 150 + 50 + 1 timesteps, and the calculation above
+    assert_eq!(ciet_isothermal_facility.get_cache_hit_count(), 0);
+    assert_eq!(ciet_isothermal_facility.get_cache_miss_count(), 203);
+
+    // (4) the dhx check valve still holds with fluid inertia,
+    // the shut valve holds back pressure changes around the zero
+    // flow pressure change (inertial term included), and
+    // reverse flow is never given
+    let dhx_branch = DHXBranch::new();
+    let dhx_inertial_branch = InertialBranch::new(
+        &dhx_branch,
+        dhx_branch.get_zero_flow_pressure_loss_range(),
+        dhx_branch.get_solver_settings(),
+        dhx_branch_inertance,
+        MassRate::new::<kilogram_per_second>(0.05),
+        timestep);
+
+    let zero_flow_pressure_change = dhx_inertial_branch.get_pressure_change(
+        MassRate::new::<kilogram_per_second>(0.0));
+
+    assert_eq!(dhx_inertial_branch.try_get_mass_flowrate_from_pressure_change(
+            zero_flow_pressure_change + Pressure::new::<pascal>(1000.0)),
+        Ok(MassRate::new::<kilogram_per_second>(0.0)));
+    assert!(dhx_inertial_branch.try_get_mass_flowrate_from_pressure_change(
+            zero_flow_pressure_change - Pressure::new::<pascal>(1000.0))
+        .unwrap().value > 0.0);
+}
+
+#[test]
+pub fn dracs_removes_heater_power_through_the_dhx_in_natural_circulation(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, CIETNonIsothermalFacility,
+        DRACSLoop, ComponentHeatTransfer, DRACS_TCHX_COMPONENT_NAME,
+        FacilityTopology};
+
+    let heater_power = Power::new::<watt>(1000.0);
+    let timestep = Time::new::<second>(20.0);
+
+    let heat_transfer_component_names = FacilityTopology::ciet_default()
+        .get_heat_transfer_component_names().unwrap();
+    let dhx_shell_side_component_name = heat_transfer_component_names
+        .dhx_branch[0].clone();
+
+    let mut ciet = CIETNonIsothermalFacility::new(
+        CTAHBranch::new(),
+        HeaterBranch::new(),
+        DHXBranch::new(),
+        heat_transfer_component_names,
+        4).unwrap();
+
+    // the ctah is closed and the pump is off, so the only way
+    // for the heater power to leave the primary loop is through
+    // the dhx into DRACS, and then out of the tchx
+    ciet.set_ctah_branch_valve_open(false);
+    ciet.set_heater_power(heater_power);
+
+    let mut dracs_loop = DRACSLoop::ciet_default(4);
+    dracs_loop.set_tchx_cooling(
+        ThermalConductance::new::<watt_per_kelvin>(100.0),
+        ThermodynamicTemperature::new::<degree_celsius>(20.0));
+
+    // both DRACS branches drop by the same height, so when DRACS
+    // is isothermal there is nothing to drive flow
+    let (dracs_dhx_flowrate, dracs_tchx_flowrate) = dracs_loop
+        .advance_timestep(timestep, ComponentHeatTransfer::heat_source(
+                Power::new::<watt>(0.0)))
+        .unwrap();
+
+    approx::assert_abs_diff_eq!(dracs_dhx_flowrate.value, 0.0, epsilon = 1e-6);
+    approx::assert_abs_diff_eq!(dracs_tchx_flowrate.value, 0.0, epsilon = 1e-6);
+
+    ciet.set_dracs_loop(dracs_loop,
+                        ThermalConductance::new::<watt_per_kelvin>(200.0));
+
+    for _ in 0..400 {
+        ciet.advance_timestep(timestep).unwrap();
+    }
+
+    // in the primary loop, the fluid rises through the heater
+    // and sinks through the dhx shell side
+    assert!(ciet.get_dhx_branch_mass_flowrate().value > 0.0);
+    approx::assert_relative_eq!(
+        ciet.get_heater_branch_mass_flowrate().value,
+        -ciet.get_dhx_branch_mass_flowrate().value, max_relative = 1e-6);
+    approx::assert_abs_diff_eq!(
+        ciet.get_ctah_branch_mass_flowrate().value, 0.0);
+
+    // in DRACS, the fluid rises through the dhx tube side
+    // and sinks through the tchx
+    let dracs_loop = ciet.get_dracs_loop().unwrap();
+
+    assert!(dracs_loop.get_tchx_branch_mass_flowrate().value > 0.0);
+    approx::assert_relative_eq!(
+        dracs_loop.get_dhx_branch_mass_flowrate().value,
+        -dracs_loop.get_tchx_branch_mass_flowrate().value, max_relative = 1e-6);
+
+    // at steady state, the heater power goes through the dhx
+    // and out of the tchx
+    approx::assert_relative_eq!(
+        ciet.get_dhx_heat_transfer_rate().get::<watt>(),
+        heater_power.get::<watt>(), max_relative = 1e-3);
+    approx::assert_relative_eq!(
+        dracs_loop.get_dhx_heat_gain_rate().get::<watt>(),
+        heater_power.get::<watt>(), max_relative = 1e-3);
+    approx::assert_relative_eq!(
+        dracs_loop.get_tchx_heat_removal_rate().get::<watt>(),
+        heater_power.get::<watt>(), max_relative = 1e-3);
+
+    // and heat flows from hot to cold, primary loop to DRACS to air
+    let dhx_tube_side_temperature = dracs_loop.get_dhx_tube_side_temperature();
+    let dhx_shell_side_temperature = ciet
+        .get_component_thermal_nodes(&dhx_shell_side_component_name)
+        .unwrap().get_average_temperature();
+    let tchx_temperature = dracs_loop
+        .get_component_thermal_nodes(DRACS_TCHX_COMPONENT_NAME)
+        .unwrap().get_average_temperature();
+
+    assert!(dhx_shell_side_temperature > dhx_tube_side_temperature);
+    assert!(dhx_tube_side_temperature > tchx_temperature);
+    assert!(tchx_temperature.get::<degree_celsius>() > 20.0);
+}
+
+#[test]
+pub fn non_isothermal_ciet_takes_its_heat_transfer_components_from_the_topology(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, CIETNonIsothermalFacility,
+        ComponentCatalogue, FacilityTopology, FacilityTopologyError, 
+        BranchRole, HeatTransferComponentNames};
+
+    let ciet_component_catalogue = ComponentCatalogue::ciet_default();
+    let ciet_facility_topology = FacilityTopology::ciet_default();
+
+    // (1) the shipped topology names the heater, the two ctah
+    // sections and the dhx shell side
+    let heat_transfer_component_names = ciet_facility_topology
+        .get_heat_transfer_component_names().unwrap();
+
+    assert_eq!(heat_transfer_component_names, HeatTransferComponentNames {
+        heater_branch: vec!["heater_version_1_label_1".to_string()],
+        dhx_branch: vec!["dhx_shell_side_label_24".to_string()],
+        ctah_branch: vec!["ctah_vertical_label_7a".to_string(),
+                          "ctah_horizontal_label_7b".to_string()],
+    });
+
+    // (2) ciet built from the topology heats up in the heater
+    // and cools down in the ctah
+    let mut ciet = ciet_facility_topology
+        .build_non_isothermal_ciet_facility(&ciet_component_catalogue, 4)
+        .unwrap();
+
+    let initial_temperature = ciet.get_top_node_temperature();
+
+    ciet.set_heater_power(Power::new::<watt>(1500.0));
+    ciet.set_ctah_cooling(
+        ThermalConductance::new::<watt_per_kelvin>(50.0),
+        initial_temperature);
+
+    for _ in 0..20 {
+        ciet.advance_timestep(Time::new::<second>(20.0)).unwrap();
+    }
+
+    let heater_temperature = ciet
+        .get_component_thermal_nodes("heater_version_1_label_1").unwrap()
+        .get_average_temperature();
+
+    assert!(heater_temperature.value > initial_temperature.value);
+    assert!(ciet.get_ctah_heat_removal_rate().value > 0.0);
+    assert_eq!(ciet.get_heat_transfer_component_names(), 
+               &heat_transfer_component_names);
+
+    // (3) a branch without heat transfer components cannot be built
+    let mut bad_topology = ciet_facility_topology.clone();
+    bad_topology.branches.iter_mut()
+        .find(|branch| branch.role == BranchRole::CTAH).unwrap()
+        .heat_transfer_components.clear();
+
+    assert_eq!(
+        bad_topology.get_heat_transfer_component_names(),
+        Err(FacilityTopologyError::MissingHeatTransferComponents(BranchRole::CTAH)));
+    assert!(matches!(
+        bad_topology.build_non_isothermal_ciet_facility(&ciet_component_catalogue, 4),
+        Err(FacilityTopologyError::MissingHeatTransferComponents(BranchRole::CTAH))));
+
+    // the isothermal facility does not need them
+    assert!(bad_topology
+            .build_isothermal_ciet_facility(&ciet_component_catalogue).is_ok());
+
+    // (4) heat transfer components must be in their own branch
+    let mut bad_topology = ciet_facility_topology.clone();
+    bad_topology.branches.iter_mut()
+        .find(|branch| branch.role == BranchRole::Heater).unwrap()
+        .heat_transfer_components = vec!["pipe_6a".to_string()];
+
+    assert_eq!(
+        bad_topology.validate(&ciet_component_catalogue),
+        Err(FacilityTopologyError::InvalidHeatTransferComponent {
+            branch: "heater_branch".to_string(),
+            component: "pipe_6a".to_string() }));
+
+    // (5) the same goes for the names given to the facility directly
+    let mut bad_names = heat_transfer_component_names.clone();
+    bad_names.dhx_branch.clear();
+
+    assert!(matches!(
+        CIETNonIsothermalFacility::new(
+            CTAHBranch::new(), HeaterBranch::new(), DHXBranch::new(),
+            bad_names, 4),
+        Err(FacilityTopologyError::MissingHeatTransferComponents(BranchRole::DHX))));
+
+    let mut bad_names = heat_transfer_component_names.clone();
+    bad_names.ctah_branch.push("no_such_component".to_string());
+
+    assert!(matches!(
+        CIETNonIsothermalFacility::new(
+            CTAHBranch::new(), HeaterBranch::new(), DHXBranch::new(),
+            bad_names, 4),
+        Err(FacilityTopologyError::InvalidHeatTransferComponent { .. })));
+}
+
+#[test]
+pub fn ctah_pump_curve_follows_affinity_laws_and_meets_the_system_curve(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, CIETIsothermalFacility,
+        PumpCurve, PumpCurveError};
+
+    let ctah_pump_curve = PumpCurve::ciet_ctah_pump();
+    let rated_speed_rpm = ctah_pump_curve.get_rated_speed_rpm();
+
+    // (0) the placeholder curve is the same as building it 
+    // from its shutoff head and runout flowrate, and curves 
+    // with a rated speed of zero or non finite coefficients
+    // are rejected rather than dividing by zero later
+    assert_eq!(PumpCurve::from_shutoff_head_and_runout_flowrate(
+            3450.0,
+            Length::new::<meter>(0.5),
+            Volume::new::<cubic_meter>(8.0e-4)/Time::new::<second>(1.0)),
+        Ok(ctah_pump_curve));
+
+    for rated_speed_rpm in [0.0, -3450.0, f64::NAN, f64::INFINITY] {
+        assert!(matches!(PumpCurve::new(rated_speed_rpm, 0.5, 0.0, -1.0e6),
+            Err(PumpCurveError::InvalidRatedSpeed(_))));
+    }
+
+    assert_eq!(PumpCurve::new(3450.0, f64::NAN, 0.0, -1.0e6),
+        Err(PumpCurveError::NonFiniteHeadCoefficient));
+    assert_eq!(PumpCurve::from_shutoff_head_and_runout_flowrate(
+            3450.0,
+            Length::new::<meter>(0.5),
+            Volume::new::<cubic_meter>(0.0)/Time::new::<second>(1.0)),
+        Err(PumpCurveError::NonFiniteHeadCoefficient));
+
+    // a curve read from a file is checked in the same way
+    let pump_curve_toml = |rated_speed_rpm: f64| format!(
+        "rated_speed_rpm = {:?}\n\
+        shutoff_head_meters = 0.5\n\
+        linear_head_coefficient = 0.0\n\
+        quadratic_head_coefficient = -1.0e6\n", rated_speed_rpm);
+
+    assert!(toml::from_str::<PumpCurve>(&pump_curve_toml(3450.0)).is_ok());
+    assert!(toml::from_str::<PumpCurve>(&pump_curve_toml(0.0)).is_err());
+
+    // (1) affinity laws, at half the speed, half the flowrate
+    // gives a quarter of the head
+    let volumetric_flowrate = Volume::new::<cubic_meter>(3.0e-4)/
+        Time::new::<second>(1.0);
+
+    approx::assert_relative_eq!(
+        ctah_pump_curve.get_head(volumetric_flowrate*0.5, 0.5*rated_speed_rpm)
+        .get::<meter>(),
+        0.25*ctah_pump_curve.get_head(volumetric_flowrate, rated_speed_rpm)
+        .get::<meter>(),
+        max_relative = 1e-12);
+
+    // at zero speed, the pump only resists flow in either direction
+    let zero_speed_head_forward = ctah_pump_curve.get_head(
+        volumetric_flowrate, 0.0);
+    let zero_speed_head_reverse = ctah_pump_curve.get_head(
+        -volumetric_flowrate, 0.0);
+
+    assert!(zero_speed_head_forward.value < 0.0);
+    approx::assert_relative_eq!(
+        zero_speed_head_forward.value, -zero_speed_head_reverse.value,
+        max_relative = 1e-12);
+
+    let mut ciet_isothermal_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(),
+        HeaterBranch::new(),
+        DHXBranch::new());
+
+    ciet_isothermal_facility.set_ctah_pump_curve(Some(ctah_pump_curve)).unwrap();
+
+    // (2) with the pump stopped, isothermal ciet has no flow
+    let (_, ctah_flowrate, heater_flowrate, dhx_flowrate) = 
+        ciet_isothermal_facility.try_calculate().unwrap();
+
+    approx::assert_abs_diff_eq!(ctah_flowrate.value, 0.0, epsilon = 1e-6);
+    approx::assert_abs_diff_eq!(heater_flowrate.value, 0.0, epsilon = 1e-6);
+    approx::assert_abs_diff_eq!(dhx_flowrate.value, 0.0, epsilon = 1e-6);
+
+    // (3) at the rated speed, the pump pushes flow round the ctah
+    // branch and back up through the heater
+    ciet_isothermal_facility.set_ctah_pump_speed(rated_speed_rpm).unwrap();
+
+    let (_, ctah_flowrate, heater_flowrate, dhx_flowrate) = 
+        ciet_isothermal_facility.try_calculate().unwrap();
+
+    assert!(!ciet_isothermal_facility.get_last_calculation_was_cache_hit());
+    assert!(ctah_flowrate.value > 0.0);
+    approx::assert_abs_diff_eq!(
+        ctah_flowrate.value + heater_flowrate.value + dhx_flowrate.value, 
+        0.0, epsilon = 1e-6);
+
+    // the pump gives whatever pressure its curve gives at this flowrate,
+    // so a pressure source at that pressure gives the same flowrates
+    let fluid_density = TherminolVP1Properties::new().density(
+        ciet_isothermal_facility.get_fluid_temperature());
+    let pump_curve_pressure = ctah_pump_curve.get_pressure_change(
+        ctah_flowrate, rated_speed_rpm, fluid_density);
+
+    let mut pressure_source_facility = CIETIsothermalFacility::new(
+        CTAHBranch::new(),
+        HeaterBranch::new(),
+        DHXBranch::new());
+
+    pressure_source_facility.set_ctah_pump_pressure(pump_curve_pressure).unwrap();
+
+    let (_, pressure_source_ctah_flowrate, pressure_source_heater_flowrate, _) =
+        pressure_source_facility.try_calculate().unwrap();
+
+    approx::assert_relative_eq!(
+        ctah_flowrate.value, pressure_source_ctah_flowrate.value, 
+        max_relative = 1e-4);
+    approx::assert_relative_eq!(
+        heater_flowrate.value, pressure_source_heater_flowrate.value, 
+        max_relative = 1e-4);
+
+    // (4) running faster gives more flow
+    ciet_isothermal_facility.set_ctah_pump_speed(1.2*rated_speed_rpm).unwrap();
+
+    let (_, faster_ctah_flowrate, _, _) = 
+        ciet_isothermal_facility.try_calculate().unwrap();
+
+    assert!(faster_ctah_flowrate > ctah_flowrate);
+}
+#[test]
+pub fn branch_valves_throttle_the_flow_with_their_characteristics(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{CTAHBranch, HeaterBranch, DHXBranch, CIETIsothermalFacility,
+        ComponentCatalogue, ComponentCatalogueError, FacilityTopology,
+        ValveCharacteristic, SHUT_VALVE_RELATIVE_FLOW_COEFFICIENT,
+        CIETSolverError, BranchRole};
+
+    // (1) the characteristics at half open
+    let equal_percentage = ValveCharacteristic::EqualPercentage { 
+        rangeability: 50.0 };
+
+    approx::assert_relative_eq!(
+        ValveCharacteristic::Linear.get_relative_flow_coefficient(50.0),
+        0.5, max_relative = 1e-12);
+    approx::assert_relative_eq!(
+        equal_percentage.get_relative_flow_coefficient(50.0),
+        1.0/50.0_f64.sqrt(), max_relative = 1e-12);
+    approx::assert_relative_eq!(
+        ValveCharacteristic::QuickOpening.get_relative_flow_coefficient(50.0),
+        0.5_f64.sqrt(), max_relative = 1e-12);
+
+    // fully open is 1 for all of them, and a shut valve
+    // still leaks a little
+    approx::assert_relative_eq!(
+        equal_percentage.get_relative_flow_coefficient(100.0),
+        1.0, max_relative = 1e-12);
+    approx::assert_relative_eq!(
+        ValveCharacteristic::Linear.get_relative_flow_coefficient(0.0),
+        SHUT_VALVE_RELATIVE_FLOW_COEFFICIENT, max_relative = 1e-12);
+
+    // (2) ciet built from its topology has a valve in each branch,
+    // fully open they add no loss, so the flowrates are the same
+    // as the hard coded ciet without valves
+    let ciet_component_catalogue = ComponentCatalogue::ciet_default();
+
+    let mut topology_ciet = FacilityTopology::ciet_default()
+        .build_isothermal_ciet_facility(&ciet_component_catalogue)
+        .unwrap();
+
+    let mut hard_coded_ciet = CIETIsothermalFacility::new(
+        CTAHBranch::new(),
+        HeaterBranch::new(),
+        DHXBranch::new());
+
+    let pump_pressure = Pressure::new::<pascal>(2000.0);
+    topology_ciet.set_ctah_pump_pressure(pump_pressure).unwrap();
+    hard_coded_ciet.set_ctah_pump_pressure(pump_pressure).unwrap();
+
+    let (_, fully_open_ctah_flowrate, _, _) = topology_ciet.calculate();
+    let (_, ctah_flowrate, _, _) = hard_coded_ciet.calculate();
+
+    assert!(topology_ciet.get_solver_error().is_none());
+    approx::assert_relative_eq!(
+        fully_open_ctah_flowrate.value, ctah_flowrate.value,
+        max_relative = 1e-6);
+
+    // (3) throttling the ctah valve cuts the ctah flow,
+    // more so the further it is closed
+    let mut previous_ctah_flowrate = fully_open_ctah_flowrate;
+
+    for opening_percent in [80.0, 50.0, 20.0, 5.0] {
+
+        topology_ciet.set_ctah_branch_valve_opening(opening_percent).unwrap();
+
+        let (_, ctah_flowrate, heater_flowrate, dhx_flowrate) = 
+            topology_ciet.calculate();
+
+        assert!(topology_ciet.get_solver_error().is_none());
+        assert!(!topology_ciet.get_last_calculation_was_cache_hit());
+        assert!(ctah_flowrate.value > 0.0);
+        assert!(ctah_flowrate < previous_ctah_flowrate);
+
+        approx::assert_abs_diff_eq!(
+            ctah_flowrate.value + heater_flowrate.value + dhx_flowrate.value,
+            0.0,
+            epsilon = 1e-6);
+
+        previous_ctah_flowrate = ctah_flowrate;
+    }
+
+    // (4) 0% open is the same as closing the ctah branch
+    topology_ciet.set_ctah_branch_valve_opening(0.0).unwrap();
+    let (_, shut_ctah_flowrate, shut_heater_flowrate, _) = 
+        topology_ciet.calculate();
+
+    hard_coded_ciet.set_ctah_branch_valve_open(false);
+    let (_, closed_ctah_flowrate, closed_heater_flowrate, _) = 
+        hard_coded_ciet.calculate();
+
+    assert_eq!(shut_ctah_flowrate.value, 0.0);
+    assert_eq!(closed_ctah_flowrate.value, 0.0);
+    approx::assert_abs_diff_eq!(
+        shut_heater_flowrate.value, closed_heater_flowrate.value,
+        epsilon = 1e-6);
+
+    // (5) valve openings which are not numbers are rejected and
+    // leave the valve where it was (so the next calculation is
+    // a cache hit), openings outside 0-100% are clamped to it
+    for bad_opening_percent in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+
+        let valve_opening_error = topology_ciet
+            .set_ctah_branch_valve_opening(bad_opening_percent);
+
+        assert!(matches!(
+            valve_opening_error,
+            Err(CIETSolverError::InvalidValveOpening { 
+                branch_role: BranchRole::CTAH, .. })));
+
+        let (_, ctah_flowrate, _, _) = topology_ciet.calculate();
+
+        assert!(topology_ciet.get_last_calculation_was_cache_hit());
+        assert_eq!(ctah_flowrate.value, 0.0);
+    }
+
+    topology_ciet.set_ctah_branch_valve_opening(150.0).unwrap();
+    let (_, over_open_ctah_flowrate, _, _) = topology_ciet.calculate();
+
+    approx::assert_relative_eq!(
+        over_open_ctah_flowrate.value, fully_open_ctah_flowrate.value,
+        max_relative = 1e-9);
+
+    topology_ciet.set_ctah_branch_valve_opening(-5.0).unwrap();
+    let (_, under_shut_ctah_flowrate, _, _) = topology_ciet.calculate();
+
+    assert_eq!(under_shut_ctah_flowrate.value, 0.0);
+
+    // (6) equal percentage valves need a rangeability above 1
+    let mut bad_catalogue = ciet_component_catalogue.clone();
+    bad_catalogue.valves[0].characteristic = 
+        ValveCharacteristic::EqualPercentage { rangeability: 0.0 };
+
+    let bad_valve_name = bad_catalogue.valves[0].name.clone();
+
+    assert!(matches!(
+        bad_catalogue.get_fluid_component(&bad_valve_name),
+        Err(ComponentCatalogueError::InvalidValveCharacteristic(_))));
+}
+
+#[test]
+pub fn check_valves_hold_back_flow_until_they_crack_and_can_leak(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{ComponentCatalogue, ComponentCatalogueError, DHXBranch,
+        TherminolSeriesComponents};
+
+    let catalogue_toml = r#"
+    fluid_temperature_degrees_celsius = 21.0
+
+    [[pipe]]
+    name = "horizontal_pipe"
+    hydraulic_diameter_meters = 0.0279
+    component_length_meters = 1.0
+    absolute_roughness_millimeters = 0.015
+    incline_angle_degrees = 0.0
+    form_loss_k = 1.0
+
+    [[check_valve]]
+    name = "leaky_check_valve"
+    hydraulic_diameter_meters = 0.0279
+    component_length_meters = 0.05
+    incline_angle_degrees = 0.0
+    fully_open_k = 2.0
+    cracking_pressure_pascals = 500.0
+    reverse_leakage_k = 1000.0
+
+    [[check_valve]]
+    name = "tight_check_valve"
+    hydraulic_diameter_meters = 0.0279
+    component_length_meters = 0.05
+    incline_angle_degrees = 0.0
+    fully_open_k = 2.0
+    cracking_pressure_pascals = 500.0
+    "#;
+
+    let catalogue = ComponentCatalogue::from_toml_str(catalogue_toml).unwrap();
+
+    // (1) the check valve on its own, forward flow has the cracking
+    // pressure on top of the form loss, and inverting gives the
+    // same flowrate back
+    let leaky_check_valve = catalogue
+        .get_therminol_check_valve("leaky_check_valve").unwrap();
+
+    let forward_flowrate = MassRate::new::<kilogram_per_second>(0.1);
+    let forward_pressure_loss = 
+        leaky_check_valve.get_pressure_loss_immutable(forward_flowrate);
+
+    let cross_sectional_area = 
+        leaky_check_valve.get_cross_sectional_area_immutable();
+    let fluid_density = leaky_check_valve.get_fluid_density_immutable();
+    let expected_pressure_loss: Pressure = Pressure::new::<pascal>(500.0)
+        + 0.5*2.0*forward_flowrate*forward_flowrate/
+        (fluid_density*cross_sectional_area*cross_sectional_area);
+
+    approx::assert_relative_eq!(
+        forward_pressure_loss.value, expected_pressure_loss.value,
+        max_relative = 1e-12);
+    approx::assert_relative_eq!(
+        leaky_check_valve.get_mass_flowrate_from_pressure_loss_immutable(
+            forward_pressure_loss).value,
+        forward_flowrate.value,
+        max_relative = 1e-9);
+
+    // below the cracking pressure it stays shut
+    assert_eq!(leaky_check_valve.get_mass_flowrate_from_pressure_loss_immutable(
+            Pressure::new::<pascal>(300.0)).value, 0.0);
+
+    // (2) a branch with the leaky valve, a pressure drop smaller
+    // than the cracking pressure gives no flow, a bigger one
+    // gives forward flow, and a back pressure leaks backwards
+    let leaky_branch = catalogue.build_series_branch(
+        "leaky_branch", &["horizontal_pipe", "leaky_check_valve"]).unwrap();
+
+    let (lowest_shut_pressure_loss, highest_shut_pressure_loss) = 
+        leaky_branch.get_zero_flow_pressure_loss_range();
+    assert_eq!(lowest_shut_pressure_loss.value, 0.0);
+    assert_eq!(highest_shut_pressure_loss.value, 500.0);
+
+    assert_eq!(leaky_branch.get_mass_flowrate_from_pressure_change(
+            Pressure::new::<pascal>(-300.0)).value, 0.0);
+
+    for pressure_change_pascals in [-2000.0, -600.0, 200.0, 2000.0] {
+
+        let pressure_change = Pressure::new::<pascal>(pressure_change_pascals);
+        let mass_flowrate = 
+            leaky_branch.get_mass_flowrate_from_pressure_change(pressure_change);
+
+        assert!(mass_flowrate.value != 0.0);
+        assert_eq!(mass_flowrate.value < 0.0, pressure_change_pascals > 0.0);
+
+        approx::assert_relative_eq!(
+            leaky_branch.get_pressure_change(mass_flowrate).value,
+            pressure_change_pascals,
+            max_relative = 1e-6);
+    }
+
+    // the leak is much smaller than the forward flow
+    // for the same pressure difference past the valve
+    let leakage_flowrate = leaky_branch.get_mass_flowrate_from_pressure_change(
+        Pressure::new::<pascal>(2000.0));
+    let forward_branch_flowrate = 
+        leaky_branch.get_mass_flowrate_from_pressure_change(
+            Pressure::new::<pascal>(-2500.0));
+
+    assert!(leakage_flowrate.abs() < 0.2*forward_branch_flowrate);
+
+    // (3) the tight valve does not let anything back
+    let tight_branch = catalogue.build_series_branch(
+        "tight_branch", &["horizontal_pipe", "tight_check_valve"]).unwrap();
+
+    for pressure_change_pascals in [-300.0, 0.0, 200.0, 1.0e5] {
+        assert_eq!(tight_branch.get_mass_flowrate_from_pressure_change(
+                Pressure::new::<pascal>(pressure_change_pascals)).value, 0.0);
+    }
+
+    assert!(tight_branch.get_mass_flowrate_from_pressure_change(
+            Pressure::new::<pascal>(-2000.0)).value > 0.0);
+
+    // (4) the dhx branch uses the same check valve,
+    // it never flows backwards
+    let dhx_branch = DHXBranch::new();
+    let zero_flow_pressure_change = dhx_branch.get_pressure_change(
+        MassRate::new::<kilogram_per_second>(0.0));
+
+    // the check valve does not leak, so when shut it can hold
+    // any back pressure at all
+    let check_valve_index = dhx_branch.get_dhx_branch_check_valve_index()
+        .unwrap();
+    let (lowest_shut_pressure_loss, _) = dhx_branch
+        .get_therminol_components()[check_valve_index]
+        .get_zero_flow_pressure_loss_range();
+    assert_eq!(lowest_shut_pressure_loss.value, f64::NEG_INFINITY);
+    assert_eq!(dhx_branch.get_mass_flowrate_from_pressure_change(
+            zero_flow_pressure_change + Pressure::new::<pascal>(1000.0))
+        .value, 0.0);
+    assert!(dhx_branch.get_mass_flowrate_from_pressure_change(
+            zero_flow_pressure_change - Pressure::new::<pascal>(1000.0))
+        .value > 0.0);
+
+    // (5) check valves cannot have negative cracking pressures
+    let mut bad_catalogue = catalogue.clone();
+    bad_catalogue.check_valves[0].cracking_pressure_pascals = -1.0;
+
+    assert!(matches!(
+        bad_catalogue.get_fluid_component("leaky_check_valve"),
+        Err(ComponentCatalogueError::InvalidCheckValve(_))));
+}
+
+#[test]
+pub fn manometers_read_the_static_pressure_difference_between_taps(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{ComponentCatalogue, FacilityTopology, FacilityTopologyError,
+        BranchRole, BranchPressureTap, Manometer, TherminolSeriesComponents,
+        CTAHBranch};
+
+    let ciet_component_catalogue = ComponentCatalogue::ciet_default();
+    let ciet_facility_topology = FacilityTopology::ciet_default();
+
+    let mut ciet_isothermal_facility = ciet_facility_topology
+        .build_isothermal_ciet_facility(&ciet_component_catalogue)
+        .unwrap();
+
+    // (1) the taps and manometers in the topology are in the facility
+    assert_eq!(ciet_isothermal_facility.get_pressure_taps().len(), 4);
+    assert_eq!(ciet_isothermal_facility.get_manometers().len(), 2);
+
+    // a tap at the bottom of each branch as well
+    for (branch_name, branch_role) in [("heater_branch", BranchRole::Heater),
+                                       ("dhx_branch", BranchRole::DHX),
+                                       ("ctah_branch", BranchRole::CTAH)] {
+
+        let number_of_components = ciet_facility_topology
+            .get_branch(branch_name).unwrap().components.len();
+
+        ciet_isothermal_facility.add_pressure_tap(BranchPressureTap {
+            name: format!("{}_bottom_tap", branch_name),
+            branch_role,
+            components_upstream: number_of_components,
+        }).unwrap();
+    }
+
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(2000.0)).unwrap();
+    ciet_isothermal_facility.try_calculate().unwrap();
+
+    // (2) the bottom of ciet is at the same pressure whichever branch
+    // the fluid goes down, this includes the dhx branch, whose check
+    // valve is shut with the pump running
+    assert_eq!(ciet_isothermal_facility.get_dhx_branch_mass_flowrate().value, 0.0);
+
+    let converged_pressure_change = 
+        ciet_isothermal_facility.get_converged_pressure_change().unwrap();
+
+    for branch_name in ["heater_branch", "dhx_branch", "ctah_branch"] {
+        let bottom_static_pressure = ciet_isothermal_facility
+            .get_pressure_tap_static_pressure(
+                &format!("{}_bottom_tap", branch_name))
+            .unwrap();
+
+        approx::assert_abs_diff_eq!(
+            bottom_static_pressure.value, converged_pressure_change.value,
+            epsilon = 1e-3);
+    }
+
+    // (3) the ctah manometer reads the pressure drop across the
+    // vertical and horizontal parts of the ctah (components 2 and 3)
+    let ctah_branch = CTAHBranch::new();
+    let ctah_branch_mass_flowrate = 
+        ciet_isothermal_facility.get_ctah_branch_mass_flowrate();
+    let ctah_component_pressure_changes = ctah_branch
+        .get_component_pressure_changes(ctah_branch_mass_flowrate);
+
+    let expected_ctah_pressure_drop = 
+        -(ctah_component_pressure_changes[2] + ctah_component_pressure_changes[3]);
+
+    let manometer_readings = ciet_isothermal_facility.get_manometer_readings();
+
+    assert_eq!(manometer_readings[0].name, "ctah_manometer");
+    approx::assert_relative_eq!(
+        manometer_readings[0].pressure_difference.value,
+        expected_ctah_pressure_drop.value,
+        max_relative = 1e-9);
+
+    // the component pressure changes add up to the branch
+    // pressure change (the pump pressure is in the pump)
+    let mut ctah_branch_with_pump = CTAHBranch::new();
+    ctah_branch_with_pump.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(2000.0));
+
+    let summed_pressure_change = ctah_branch_with_pump
+        .get_component_pressure_changes(ctah_branch_mass_flowrate)
+        .into_iter()
+        .fold(Pressure::new::<pascal>(0.0), 
+              |pressure_change_sum, pressure_change| 
+              pressure_change_sum + pressure_change);
+
+    approx::assert_relative_eq!(
+        summed_pressure_change.value,
+        ctah_branch_with_pump.get_pressure_change(ctah_branch_mass_flowrate).value,
+        max_relative = 1e-9);
+
+    // (4) swapping the taps swaps the sign
+    let heater_pressure_difference = ciet_isothermal_facility
+        .get_static_pressure_difference("heater_inlet_tap", "heater_outlet_tap")
+        .unwrap();
+    let reversed_heater_pressure_difference = ciet_isothermal_facility
+        .get_static_pressure_difference("heater_outlet_tap", "heater_inlet_tap")
+        .unwrap();
+
+    assert_eq!(manometer_readings[1].pressure_difference, 
+               heater_pressure_difference);
+    assert_eq!(heater_pressure_difference, -reversed_heater_pressure_difference);
+    assert!(ciet_isothermal_facility
+            .get_static_pressure_difference("heater_inlet_tap", "no_such_tap")
+            .is_none());
+
+    // (5) taps must be on a branch after one of its components,
+    // and manometers must use taps which exist
+    let mut bad_topology = ciet_facility_topology.clone();
+    bad_topology.pressure_taps[0].after_component = 
+        Some("heater_version_1_label_1".to_string());
+
+    assert!(matches!(
+        bad_topology.build_isothermal_ciet_facility(&ciet_component_catalogue),
+        Err(FacilityTopologyError::InvalidPressureTap { .. })));
+
+    let mut bad_topology = ciet_facility_topology.clone();
+    bad_topology.manometers.push(Manometer {
+        name: "dhx_manometer".to_string(),
+        high_pressure_tap: "dhx_inlet_tap".to_string(),
+        low_pressure_tap: "ctah_outlet_tap".to_string(),
+    });
+
+    assert!(matches!(
+        bad_topology.build_isothermal_ciet_facility(&ciet_component_catalogue),
+        Err(FacilityTopologyError::UnknownPressureTap { .. })));
+}
+
+
+#[test]
+pub fn pressure_breakdown_adds_up_to_the_branch_pressure_change(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{ComponentCatalogue, FacilityTopology, BranchRole,
+        PRESSURE_BREAKDOWN_CSV_HEADER};
+
+    let ciet_component_catalogue = ComponentCatalogue::ciet_default();
+    let ciet_facility_topology = FacilityTopology::ciet_default();
+
+    let mut ciet_isothermal_facility = ciet_facility_topology
+        .build_isothermal_ciet_facility(&ciet_component_catalogue)
+        .unwrap();
+
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(2000.0)).unwrap();
+    ciet_isothermal_facility.try_calculate().unwrap();
+
+    let pressure_breakdown = ciet_isothermal_facility.get_pressure_breakdown();
+
+    // (1) the breakdown has every component of every branch, by name,
+    // in the order of the topology
+    for (branch_breakdown, branch_name) in pressure_breakdown.iter()
+        .zip(["heater_branch", "dhx_branch", "ctah_branch"]) {
+
+        let component_names: Vec<&str> = branch_breakdown.components.iter()
+            .map(|component| component.name.as_str())
+            .collect();
+
+        assert_eq!(component_names, ciet_facility_topology
+                   .get_branch(branch_name).unwrap().components);
+    }
+
+    // (2) with flow through them, the heater and ctah branch 
+    // breakdowns add up to the converged pressure change, 
+    // the ctah pump pressure included
+    let converged_pressure_change = 
+        ciet_isothermal_facility.get_converged_pressure_change().unwrap();
+
+    for branch_breakdown in pressure_breakdown.iter()
+        .filter(|branch_breakdown| branch_breakdown.branch_role != BranchRole::DHX) {
+
+        approx::assert_abs_diff_eq!(
+            branch_breakdown.get_pressure_change().value, 
+            converged_pressure_change.value,
+            epsilon = 1e-3);
+    }
+
+    let ctah_branch_breakdown = &pressure_breakdown[2];
+    let ctah_pump_breakdown = ctah_branch_breakdown.components.iter()
+        .find(|component| component.name == "ctah_pump").unwrap();
+
+    assert_eq!(ctah_pump_breakdown.pump_pressure.value, 2000.0);
+
+    // (3) the form loss of a pipe is K m|m|/(2 rho A^2), 
+    // and the rest of its loss is friction
+    let pipe_12 = ciet_component_catalogue.get_therminol_pipe("pipe_12").unwrap();
+    let pipe_12_breakdown = ctah_branch_breakdown.components.iter()
+        .find(|component| component.name == "pipe_12").unwrap();
+
+    let ctah_branch_mass_flowrate = ctah_branch_breakdown.mass_flowrate;
+    let pipe_area = pipe_12.get_cross_sectional_area_immutable();
+    let expected_form_loss: Pressure = 0.5*21.65*
+        ctah_branch_mass_flowrate*ctah_branch_mass_flowrate.abs()/
+        (pipe_12.get_fluid_density_immutable()*pipe_area*pipe_area);
+
+    approx::assert_relative_eq!(
+        pipe_12_breakdown.form_loss.value, expected_form_loss.value,
+        max_relative = 1e-9);
+    approx::assert_relative_eq!(
+        (pipe_12_breakdown.friction_loss + pipe_12_breakdown.form_loss).value, 
+        pipe_12.get_pressure_loss_immutable(ctah_branch_mass_flowrate).value,
+        max_relative = 1e-9);
+
+    // (4) a check valve loss is all form loss, the cracking
+    // pressure included (the ciet check valve cracks at 0 Pa,
+    // so it is given a cracking pressure here)
+    let mut check_valve = ciet_component_catalogue
+        .get_therminol_check_valve("dhx_branch_check_valve").unwrap();
+    check_valve.set_cracking_pressure(Pressure::new::<pascal>(500.0));
+
+    let check_valve_breakdown = crate::ComponentPressureBreakdown::new(
+        &check_valve, MassRate::new::<kilogram_per_second>(0.1));
+
+    assert_eq!(check_valve_breakdown.friction_loss.value, 0.0);
+    assert_eq!(check_valve_breakdown.form_loss.value, 500.0);
+
+    // (5) the csv has a header and a line for each component
+    let csv_path = std::env::temp_dir().join("ciet_pressure_breakdown.csv");
+    ciet_isothermal_facility.write_pressure_breakdown_csv(&csv_path).unwrap();
+    let csv_string = std::fs::read_to_string(&csv_path).unwrap();
+    let _ = std::fs::remove_file(&csv_path);
+
+    let number_of_components: usize = pressure_breakdown.iter()
+        .map(|branch_breakdown| branch_breakdown.components.len())
+        .sum();
+
+    let csv_lines: Vec<&str> = csv_string.lines().collect();
+    assert_eq!(csv_lines[0], PRESSURE_BREAKDOWN_CSV_HEADER);
+    assert_eq!(csv_lines.len(), number_of_components + 1);
+    assert!(csv_lines[1].starts_with(&format!("heater,{},", 
+        pressure_breakdown[0].components[0].name)));
+}
+
+#[test]
+pub fn flow_diagnostics_give_reynolds_numbers_and_warn_outside_fitted_range(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{ComponentCatalogue, FacilityTopology, FlowRegime,
+        CustomKCorrelation, ComponentCatalogueError};
+
+    // (1) the flow regime only depends on the magnitude of Re
+    assert_eq!(FlowRegime::from_reynolds_number(1000.0), FlowRegime::Laminar);
+    assert_eq!(FlowRegime::from_reynolds_number(-3000.0), FlowRegime::Transition);
+    assert_eq!(FlowRegime::from_reynolds_number(1.0e4), FlowRegime::Turbulent);
+
+    // (2) a table knows the range it was fitted over
+    let custom_k_table = CustomKCorrelation::Table {
+        reynolds_numbers: vec![100.0, 1000.0],
+        k_values: vec![61.0, 25.0],
+    };
+    assert_eq!(custom_k_table.get_fitted_reynolds_number_range(), 
+               Some((100.0, 1000.0)));
+    assert_eq!(CustomKCorrelation::StaticMixer41
+               .get_fitted_reynolds_number_range(), None);
+
+    // (3) Re = m D/(A mu) and u = m/(rho A) in each component
+    let mut ciet_component_catalogue = ComponentCatalogue::ciet_default();
+    let ciet_facility_topology = FacilityTopology::ciet_default();
+
+    let mut ciet_isothermal_facility = ciet_facility_topology
+        .build_isothermal_ciet_facility(&ciet_component_catalogue)
+        .unwrap();
+
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(2000.0)).unwrap();
+    ciet_isothermal_facility.try_calculate().unwrap();
+
+    let flow_diagnostics = ciet_isothermal_facility.get_flow_diagnostics();
+    let ctah_branch_diagnostics = &flow_diagnostics[2];
+    let ctah_branch_mass_flowrate = ctah_branch_diagnostics.mass_flowrate;
+
+    let pipe_12 = ciet_component_catalogue.get_therminol_pipe("pipe_12").unwrap();
+    let pipe_12_diagnostics = ctah_branch_diagnostics.components.iter()
+        .find(|component| component.name == "pipe_12").unwrap();
+
+    let pipe_area = pipe_12.get_cross_sectional_area_immutable();
+    let expected_reynolds_number: f64 = (ctah_branch_mass_flowrate/pipe_area*
+        pipe_12.get_hydraulic_diameter_immutable()/
+        pipe_12.get_fluid_viscosity_immutable()).value;
+    let expected_mean_velocity: Velocity = ctah_branch_mass_flowrate/
+        (pipe_12.get_fluid_density_immutable()*pipe_area);
+
+    approx::assert_relative_eq!(
+        pipe_12_diagnostics.reynolds_number, expected_reynolds_number,
+        max_relative = 1e-9);
+    approx::assert_relative_eq!(
+        pipe_12_diagnostics.mean_velocity.value, expected_mean_velocity.value,
+        max_relative = 1e-9);
+    assert_eq!(pipe_12_diagnostics.flow_regime, 
+               FlowRegime::from_reynolds_number(expected_reynolds_number));
+
+    // the dhx check valve is shut, so there is no flow there
+    assert!(flow_diagnostics[1].components.iter()
+            .all(|component| component.reynolds_number == 0.0));
+
+    // (4) the ciet fits are given the range they were fitted over
+    // in the catalogue, static mixer 41 is well within it here
+    let static_mixer_41_diagnostics = ctah_branch_diagnostics.components.iter()
+        .find(|component| component.name == "static_mixer_41_label_6")
+        .unwrap();
+
+    assert_eq!(static_mixer_41_diagnostics.fitted_reynolds_number_range, 
+               Some((100.0, 2300.0)));
+    assert!(!static_mixer_41_diagnostics.is_outside_fitted_range());
+    assert!(ciet_isothermal_facility.get_flow_diagnostic_warnings().iter()
+            .all(|warning| !warning.starts_with("static_mixer_41_label_6")));
+
+    // the pump has no loss of its own, so it has no range
+    assert!(ctah_branch_diagnostics.components.iter()
+            .find(|component| component.name == "ctah_pump")
+            .unwrap().fitted_reynolds_number_range.is_none());
+
+    // at a trickle, every ciet fit in the ctah branch is used 
+    // below its range, and each of them is warned about
+    let mut ciet_isothermal_facility = ciet_facility_topology
+        .build_isothermal_ciet_facility(&ciet_component_catalogue)
+        .unwrap();
+
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(50.0)).unwrap();
+    ciet_isothermal_facility.try_calculate().unwrap();
+
+    let flow_diagnostic_warnings = 
+        ciet_isothermal_facility.get_flow_diagnostic_warnings();
+    let ctah_branch_fitted_components: Vec<String> = 
+        ciet_isothermal_facility.get_flow_diagnostics()[2].components.iter()
+        .filter(|component| component.fitted_reynolds_number_range.is_some())
+        .map(|component| component.name.clone())
+        .collect();
+
+    assert!(!ctah_branch_fitted_components.is_empty());
+
+    for component_name in ctah_branch_fitted_components.iter() {
+        assert!(flow_diagnostic_warnings.iter()
+                .any(|warning| warning.starts_with(component_name.as_str())));
+    }
+
+    // a range set in the catalogue replaces the shipped one
+    let static_mixer_41_reynolds_number = 
+        static_mixer_41_diagnostics.reynolds_number;
+
+    let static_mixer_41_specification = ciet_component_catalogue
+        .custom_components.iter_mut()
+        .find(|custom_component| custom_component.name == "static_mixer_41_label_6")
+        .unwrap();
+    static_mixer_41_specification.fitted_reynolds_number_range = 
+        Some((2.0*static_mixer_41_reynolds_number, 
+              4.0*static_mixer_41_reynolds_number));
+
+    let mut ciet_isothermal_facility = ciet_facility_topology
+        .build_isothermal_ciet_facility(&ciet_component_catalogue)
+        .unwrap();
+
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(2000.0)).unwrap();
+    ciet_isothermal_facility.try_calculate().unwrap();
+
+    assert!(ciet_isothermal_facility.get_flow_diagnostic_warnings().iter()
+            .any(|warning| warning.starts_with("static_mixer_41_label_6")));
+
+    // a range which goes backwards is not accepted
+    let ctah_vertical_specification = ciet_component_catalogue
+        .custom_components.iter_mut()
+        .find(|custom_component| custom_component.name == "ctah_vertical_label_7a")
+        .unwrap();
+    ctah_vertical_specification.fitted_reynolds_number_range = 
+        Some((1000.0, 100.0));
+
+    assert!(matches!(
+            ciet_component_catalogue
+            .get_therminol_custom_component("ctah_vertical_label_7a"),
+            Err(ComponentCatalogueError::InvalidCorrelation(_))));
+}
+
+#[test]
+pub fn out_of_range_policies_clamp_extrapolate_or_stop_the_calculation(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{ComponentCatalogue, FacilityTopology, CustomKCorrelation,
+        CustomDarcyCorrelation, OutOfRangePolicy, CIETSolverError,
+        ComponentCatalogueError, StaticMixer41};
+    use crate::therminol_component::{TherminolCustomComponent, 
+        TherminolCustomComponentTraits};
+
+    // (1) clamping keeps the magnitude of Re within the fitted 
+    // range and keeps its sign, the other policies leave Re alone
+    let fitted_reynolds_number_range = Some((100.0, 1000.0));
+    let clamp = OutOfRangePolicy::Clamp;
+
+    assert_eq!(clamp.get_correlation_reynolds_number(
+            10.0, fitted_reynolds_number_range), 100.0);
+    assert_eq!(clamp.get_correlation_reynolds_number(
+            -5000.0, fitted_reynolds_number_range), -1000.0);
+    assert_eq!(clamp.get_correlation_reynolds_number(
+            500.0, fitted_reynolds_number_range), 500.0);
+    assert_eq!(clamp.get_correlation_reynolds_number(
+            0.0, fitted_reynolds_number_range), 0.0);
+    assert_eq!(clamp.get_correlation_reynolds_number(10.0, None), 10.0);
+    assert_eq!(OutOfRangePolicy::Extrapolate.get_correlation_reynolds_number(
+            10.0, fitted_reynolds_number_range), 10.0);
+
+    // extrapolation is the default, and the policies are
+    // lower case in config files
+    assert_eq!(OutOfRangePolicy::default(), OutOfRangePolicy::Extrapolate);
+    assert_eq!(serde_json::from_str::<OutOfRangePolicy>("\"error\"").unwrap(),
+               OutOfRangePolicy::Error);
+
+    // (2) a clamped component evaluates its correlations 
+    // at the edge of the fitted range
+    let mut custom_component = TherminolCustomComponent::new_from_correlations(
+        "power_law_component",
+        ThermodynamicTemperature::new::<degree_celsius>(21.0),
+        Angle::new::<degree>(0.0),
+        Length::new::<meter>(0.5),
+        Area::new::<square_meter>(6.11e-4),
+        Length::new::<meter>(0.0279),
+        Length::new::<millimeter>(0.015),
+        CustomKCorrelation::PowerLaw { a: 0.0, b: 1000.0, c: -1.0 },
+        CustomDarcyCorrelation::Constant { darcy: 0.0 });
+
+    custom_component.set_fitted_reynolds_number_range(fitted_reynolds_number_range);
+
+    approx::assert_relative_eq!(
+        custom_component.get_custom_k_immutable()(10.0), 100.0,
+        max_relative = 1e-12);
+
+    custom_component.set_out_of_range_policy(OutOfRangePolicy::Clamp);
+
+    approx::assert_relative_eq!(
+        custom_component.get_custom_k_immutable()(10.0), 10.0,
+        max_relative = 1e-12);
+    approx::assert_relative_eq!(
+        custom_component.get_custom_k_immutable()(-10.0), -10.0,
+        max_relative = 1e-12);
+
+    // (3) the ciet fits in the catalogue ship with the range they were
+    // fitted over, and a range starting at zero is not accepted, 
+    // clamping there would give an infinite K for the a + b/Re fits
+    let mut ciet_component_catalogue = ComponentCatalogue::ciet_default();
+    let ciet_facility_topology = FacilityTopology::ciet_default();
+
+    let mut static_mixer_41 = ciet_component_catalogue
+        .get_therminol_custom_component("static_mixer_41_label_6").unwrap();
+
+    assert_eq!(static_mixer_41.get_fitted_reynolds_number_range(), 
+               Some((100.0, 2300.0)));
+
+    let static_mixer_41_specification = ciet_component_catalogue
+        .custom_components.iter_mut()
+        .find(|custom_component| custom_component.name == "static_mixer_41_label_6")
+        .unwrap();
+    static_mixer_41_specification.fitted_reynolds_number_range = 
+        Some((0.0, 2300.0));
+
+    assert!(matches!(
+            ciet_component_catalogue
+            .get_therminol_custom_component("static_mixer_41_label_6"),
+            Err(ComponentCatalogueError::InvalidCorrelation(_))));
+
+    // a clamped ciet fit is evaluated at the edge of its range
+    static_mixer_41.set_out_of_range_policy(OutOfRangePolicy::Clamp);
+
+    approx::assert_relative_eq!(
+        static_mixer_41.get_custom_k_immutable()(10.0), 
+        StaticMixer41::custom_k(100.0),
+        max_relative = 1e-12);
+    approx::assert_relative_eq!(
+        static_mixer_41.get_custom_k_immutable()(5000.0), 
+        StaticMixer41::custom_k(2300.0),
+        max_relative = 1e-12);
+    approx::assert_relative_eq!(
+        static_mixer_41.get_custom_k_immutable()(500.0), 
+        StaticMixer41::custom_k(500.0),
+        max_relative = 1e-12);
+
+    // (4) extrapolating (the default) counts each component out of 
+    // range in each calculation, but still gives an answer
+    //
+    // at a trickle, the ciet fits in the ctah branch are all
+    // used below their range
+    let ciet_component_catalogue = ComponentCatalogue::ciet_default();
+    let trickle_pump_pressure = Pressure::new::<pascal>(50.0);
+
+    let mut ciet_isothermal_facility = ciet_facility_topology
+        .build_isothermal_ciet_facility(&ciet_component_catalogue)
+        .unwrap();
+
+    ciet_isothermal_facility.set_ctah_pump_pressure(trickle_pump_pressure).unwrap();
+    let (_, extrapolated_ctah_branch_mass_flowrate, _, _) = 
+        ciet_isothermal_facility.try_calculate().unwrap();
+
+    let out_of_range_count = 
+        ciet_isothermal_facility.get_correlation_out_of_range_count();
+
+    assert!(out_of_range_count > 0);
+    assert_eq!(out_of_range_count as usize, 
+               ciet_isothermal_facility.get_flow_diagnostic_warnings().len());
+
+    // the same inputs are not solved again, so not counted again
+    ciet_isothermal_facility.try_calculate().unwrap();
+    assert_eq!(ciet_isothermal_facility.get_correlation_out_of_range_count(), 
+               out_of_range_count);
+
+    // (5) clamping holds K at its value at the bottom of the range, 
+    // which is a smaller loss than extrapolating to lower Re, 
+    // so more flows
+    let mut clamped_component_catalogue = ComponentCatalogue::ciet_default();
+
+    for custom_component in clamped_component_catalogue.custom_components.iter_mut() {
+        custom_component.out_of_range_policy = OutOfRangePolicy::Clamp;
+    }
+
+    let mut ciet_isothermal_facility = ciet_facility_topology
+        .build_isothermal_ciet_facility(&clamped_component_catalogue)
+        .unwrap();
+
+    ciet_isothermal_facility.set_ctah_pump_pressure(trickle_pump_pressure).unwrap();
+    let (_, clamped_ctah_branch_mass_flowrate, _, _) = 
+        ciet_isothermal_facility.try_calculate().unwrap();
+
+    assert!(clamped_ctah_branch_mass_flowrate > extrapolated_ctah_branch_mass_flowrate);
+    assert!(ciet_isothermal_facility.get_correlation_out_of_range_count() > 0);
+
+    // (6) with the error policy, the calculation fails and
+    // the facility keeps its last converged flowrates
+    let mut stopping_component_catalogue = ComponentCatalogue::ciet_default();
+
+    let static_mixer_41_specification = stopping_component_catalogue
+        .custom_components.iter_mut()
+        .find(|custom_component| custom_component.name == "static_mixer_41_label_6")
+        .unwrap();
+    static_mixer_41_specification.out_of_range_policy = OutOfRangePolicy::Error;
+
+    let mut ciet_isothermal_facility = ciet_facility_topology
+        .build_isothermal_ciet_facility(&stopping_component_catalogue)
+        .unwrap();
+
+    ciet_isothermal_facility.set_ctah_pump_pressure(trickle_pump_pressure).unwrap();
+
+    assert!(matches!(ciet_isothermal_facility.try_calculate(),
+                     Err(CIETSolverError::CorrelationOutOfRange { 
+                         lowest_reynolds_number, 
+                         highest_reynolds_number, .. })
+                     if lowest_reynolds_number == 100.0 
+                     && highest_reynolds_number == 2300.0));
+    assert_eq!(ciet_isothermal_facility.get_ctah_branch_mass_flowrate().value, 0.0);
+    assert!(ciet_isothermal_facility.get_correlation_out_of_range_count() > 0);
+
+    // within the range, it solves as usual
+    ciet_isothermal_facility.set_ctah_pump_pressure(
+        Pressure::new::<pascal>(2000.0)).unwrap();
+
+    assert!(ciet_isothermal_facility.try_calculate().is_ok());
+    assert!(ciet_isothermal_facility.get_ctah_branch_mass_flowrate().value > 0.0);
+}
+#[test]
+pub fn pressure_change_is_odd_about_zero_flow_apart_from_hydrostatics(){
+
+    use fluid_mechanics_rust::prelude::*;
+    use crate::{ComponentCatalogue, TherminolFluidComponent, 
+        Flowmeter20WithHighKCheckValve};
+    use crate::therminol_component::{TherminolCustomComponent, 
+        TherminolCustomComponentTraits};
+
+    // (1) every pipe and custom component in the catalogue has a
+    // pressure loss which opposes the flow, and is the same size
+    // in both directions, apart from the flowmeter with the 
+    // artificial check valve
+    let ciet_component_catalogue = ComponentCatalogue::ciet_default();
+
+    let pipe_names: Vec<&str> = ciet_component_catalogue.pipes.iter()
+        .map(|pipe| pipe.name.as_str()).collect();
+    let custom_component_names: Vec<&str> = ciet_component_catalogue
+        .custom_components.iter()
+        .map(|custom_component| custom_component.name.as_str())
+        .filter(|name| *name != "flowmeter_20_label_21a_with_check_valve")
+        .collect();
+
+    for mass_flowrate_kg_per_s in [0.01, 0.18] {
+
+        let mass_flowrate = 
+            MassRate::new::<kilogram_per_second>(mass_flowrate_kg_per_s);
+
+        for pipe_name in pipe_names.iter() {
+            let pipe = ciet_component_catalogue
+                .get_therminol_pipe(pipe_name).unwrap();
+
+            let forward_pressure_loss = 
+                pipe.get_pressure_loss_immutable(mass_flowrate);
+
+            assert!(forward_pressure_loss.value > 0.0);
+            approx::assert_relative_eq!(
+                pipe.get_pressure_loss_immutable(-mass_flowrate).value,
+                -forward_pressure_loss.value,
+                max_relative = 1e-12);
+        }
+
+        for custom_component_name in custom_component_names.iter() {
+            let custom_component = ciet_component_catalogue
+                .get_therminol_custom_component(custom_component_name).unwrap();
+
+            let forward_pressure_loss = 
+                custom_component.get_pressure_loss_immutable(mass_flowrate);
+
+            assert!(forward_pressure_loss.value >= 0.0);
+            approx::assert_relative_eq!(
+                custom_component.get_pressure_loss_immutable(-mass_flowrate).value,
+                -forward_pressure_loss.value,
+                max_relative = 1e-12);
+        }
+    }
+
+    // (2) so the pressure change across the heater, ctah and dhx
+    // branches (without the check valve) is odd about its value 
+    // at zero flow, which is just the hydrostatic pressure change
+    let catalogue_branches = [
+        ciet_component_catalogue.build_series_branch(
+            "heater_branch",
+            &["branch_5", "pipe_4", "pipe_3", "static_mixer_10_label_2",
+            "pipe_2a_static_mixer", "heater_top_head_label_1a", 
+            "heater_version_1_label_1", "heater_bottom_head_label_1b",
+            "pipe_18"]).unwrap(),
+        ciet_component_catalogue.build_series_branch(
+            "ctah_branch",
+            &["pipe_6a", "static_mixer_41_label_6", "ctah_vertical_label_7a",
+            "ctah_horizontal_label_7b", "static_mixer_pipe_8a", 
+            "static_mixer_40_label_8", "pipe_9", "pipe_10", "pipe_11", "pipe_12",
+            "ctah_pump", "pipe_13", "pipe_14", "flowmeter_40_14a", "pipe_15",
+            "pipe_16", "branch_17"]).unwrap(),
+        ciet_component_catalogue.build_series_branch(
+            "dhx_branch",
+            &["pipe_26", "static_mixer_21_label_25", "static_mixer_pipe_25a",
+            "dhx_shell_side_label_24", "static_mixer_20_label_23", 
+            "static_mixer_pipe_23a", "static_mixer_pipe_22", 
+            "flowmeter_20_label_21a", "static_mixer_pipe_21", 
+            "static_mixer_pipe_20", "static_mixer_pipe_19"]).unwrap(),
+    ];
+
+    for catalogue_branch in catalogue_branches.iter() {
+
+        let hydrostatic_pressure_change = catalogue_branch.get_pressure_change(
+            MassRate::new::<kilogram_per_second>(0.0));
+
+        for mass_flowrate_kg_per_s in [0.01, 0.05, 0.18] {
+
+            let mass_flowrate = 
+                MassRate::new::<kilogram_per_second>(mass_flowrate_kg_per_s);
+
+            let forward_pressure_change = 
+                catalogue_branch.get_pressure_change(mass_flowrate);
+            let reverse_pressure_change = 
+                catalogue_branch.get_pressure_change(-mass_flowrate);
+
+            assert!(forward_pressure_change < hydrostatic_pressure_change);
+            approx::assert_relative_eq!(
+                (reverse_pressure_change - hydrostatic_pressure_change).value,
+                -(forward_pressure_change - hydrostatic_pressure_change).value,
+                max_relative = 1e-9);
+        }
+    }
+
+    // (3) a custom K which forgets to change sign in reverse flow
+    // still gives a loss which opposes the flow
+    fn custom_k_without_reverse_flow(_reynolds_number: f64) -> f64 {
+        return 5.0;
+    }
+
+    fn no_darcy(_reynolds_number: f64, _roughness_ratio: f64) -> f64 {
+        return 0.0;
+    }
+
+    let custom_component = TherminolCustomComponent::new(
+        "positive_k_component",
+        ThermodynamicTemperature::new::<degree_celsius>(21.0),
+        Angle::new::<degree>(0.0),
+        Length::new::<meter>(0.5),
+        Area::new::<square_meter>(6.11e-4),
+        Length::new::<meter>(0.0279),
+        Length::new::<millimeter>(0.015),
+        &custom_k_without_reverse_flow,
+        &no_darcy);
+
+    let mass_flowrate = MassRate::new::<kilogram_per_second>(0.1);
+
+    assert_eq!(custom_component.get_custom_k_immutable()(-1000.0), -5.0);
+    approx::assert_relative_eq!(
+        custom_component.get_pressure_loss_immutable(-mass_flowrate).value,
+        -custom_component.get_pressure_loss_immutable(mass_flowrate).value,
+        max_relative = 1e-12);
+
+    // (4) pipes and custom components can have a different 
+    // K in reverse flow, the loss still opposes the flow
+    let asymmetric_catalogue = ComponentCatalogue::from_toml_str(r#"
+        fluid_temperature_degrees_celsius = 21.0
+
+        [[pipe]]
+        name = "pipe_with_reverse_k"
+        hydraulic_diameter_meters = 0.0279
+        component_length_meters = 0.5
+        absolute_roughness_millimeters = 0.015
+        incline_angle_degrees = 0.0
+        form_loss_k = 1.5
+        reverse_form_loss_k = 15.0
+
+        [[custom_component]]
+        name = "flowmeter_20_with_reverse_k"
+        hydraulic_diameter_meters = 0.0279
+        component_length_meters = 0.36
+        cross_sectional_area_square_meters = 0.000611
+        absolute_roughness_millimeters = 0.015
+        incline_angle_degrees = -90.0
+        custom_k = "flowmeter_20"
+        custom_darcy = "flowmeter_20"
+        reverse_custom_k = { power_law = { a = 1.0e10, b = 1.0e10, c = -1.0 } }
+        "#).unwrap();
+
+    let pipe = asymmetric_catalogue
+        .get_therminol_pipe("pipe_with_reverse_k").unwrap();
+
+    assert_eq!(pipe.get_reverse_form_loss_k(), Some(15.0));
+
+    let forward_form_loss = pipe.get_form_loss_immutable(mass_flowrate);
+    let reverse_form_loss = pipe.get_form_loss_immutable(-mass_flowrate);
+
+    assert!(forward_form_loss.value > 0.0);
+    approx::assert_relative_eq!(
+        reverse_form_loss.value, -10.0*forward_form_loss.value,
+        max_relative = 1e-12);
+    assert!(pipe.get_pressure_loss_immutable(-mass_flowrate).value < 
+            -pipe.get_pressure_loss_immutable(mass_flowrate).value);
+
+    // the pressure loss still inverts back to the mass flowrate
+    approx::assert_relative_eq!(
+        pipe.get_mass_flowrate_from_pressure_loss_immutable(
+            pipe.get_pressure_loss_immutable(-mass_flowrate)).value,
+        -mass_flowrate.value,
+        max_relative = 1e-6);
+
+    // a reverse K of 1e10 + 1e10/Re on the flowmeter is the
+    // same as the flowmeter with the artificial check valve
+    let flowmeter_with_reverse_k = asymmetric_catalogue
+        .get_therminol_custom_component("flowmeter_20_with_reverse_k").unwrap();
+    let flowmeter_with_check_valve = Flowmeter20WithHighKCheckValve::new().get();
+
+    for mass_flowrate_kg_per_s in [-0.18, -0.01, 0.01, 0.18] {
+
+        let mass_flowrate = 
+            MassRate::new::<kilogram_per_second>(mass_flowrate_kg_per_s);
+
+        approx::assert_relative_eq!(
+            flowmeter_with_reverse_k.get_pressure_loss_immutable(mass_flowrate).value,
+            flowmeter_with_check_valve.get_pressure_loss_immutable(mass_flowrate).value,
+            max_relative = 1e-9);
+    }
+
+    // and the reverse K goes to toml and back
+    let asymmetric_catalogue_toml = asymmetric_catalogue.to_toml_string().unwrap();
+    assert_eq!(ComponentCatalogue::from_toml_str(&asymmetric_catalogue_toml).unwrap(),
+        asymmetric_catalogue);
+}
+
+
 extern crate fluid_mechanics_rust;
 use fluid_mechanics_rust::prelude::*;
 